@@ -216,7 +216,7 @@ fn main() -> color_eyre::Result<()> {
     }
 
     let state = serde_json::from_str::<komorebi_client::State>(&komorebi_client::send_query(
-        &SocketMessage::State,
+        &SocketMessage::CompactState,
     )?)?;
 
     MONITOR_RIGHT.store(
@@ -331,6 +331,7 @@ fn main() -> color_eyre::Result<()> {
 
                 let listener = komorebi_client::subscribe_with_options(&subscriber_name, SubscribeOptions {
                     filter_state_changes: true,
+                    ..Default::default()
                 })
                     .expect("could not subscribe to komorebi notifications");
 
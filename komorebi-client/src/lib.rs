@@ -14,46 +14,71 @@ pub use komorebi::core::Arrangement;
 pub use komorebi::core::Axis;
 pub use komorebi::core::BorderImplementation;
 pub use komorebi::core::BorderStyle;
+pub use komorebi::core::CommandFailure;
+pub use komorebi::core::CornerPreference;
 pub use komorebi::core::CustomLayout;
 pub use komorebi::core::CycleDirection;
 pub use komorebi::core::DefaultLayout;
 pub use komorebi::core::Direction;
+pub use komorebi::core::DragModifier;
+pub use komorebi::core::DropBehaviour;
 pub use komorebi::core::FocusFollowsMouseImplementation;
+pub use komorebi::core::Health;
 pub use komorebi::core::HidingBehaviour;
+pub use komorebi::core::KomorebiError;
 pub use komorebi::core::Layout;
+pub use komorebi::core::LogLevel;
 pub use komorebi::core::MoveBehaviour;
 pub use komorebi::core::OperationBehaviour;
 pub use komorebi::core::OperationDirection;
 pub use komorebi::core::Rect;
 pub use komorebi::core::Sizing;
+pub use komorebi::core::SnapBehaviour;
 pub use komorebi::core::SocketMessage;
 pub use komorebi::core::StackbarLabel;
 pub use komorebi::core::StackbarMode;
 pub use komorebi::core::StateQuery;
+pub use komorebi::core::VersionInformation;
+pub use komorebi::core::VersionedSocketMessage;
 pub use komorebi::core::WindowKind;
+pub use komorebi::core::INSTANCE_MUTEX;
+pub use komorebi::core::PROTOCOL_VERSION;
 pub use komorebi::monitor::Monitor;
 pub use komorebi::ring::Ring;
 pub use komorebi::window::Window;
 pub use komorebi::window_manager_event::WindowManagerEvent;
 pub use komorebi::workspace::Workspace;
 pub use komorebi::BorderColours;
+pub use komorebi::Change;
 pub use komorebi::GlobalState;
 pub use komorebi::KomorebiTheme;
+pub use komorebi::MonitorDelta;
+pub use komorebi::MonitorQuery;
+pub use komorebi::MonitorStatus;
 pub use komorebi::Notification;
 pub use komorebi::NotificationEvent;
 pub use komorebi::RuleDebug;
 pub use komorebi::StackbarConfig;
 pub use komorebi::State;
+pub use komorebi::StateDelta;
 pub use komorebi::StaticConfig;
 pub use komorebi::SubscribeOptions;
 pub use komorebi::TabsConfig;
+pub use komorebi::WindowQuery;
+pub use komorebi::WorkspaceDelta;
+pub use komorebi::WorkspaceQuery;
+pub use komorebi::WorkspaceStatus;
 
 use komorebi::DATA_DIR;
 
 use std::io::BufReader;
+use std::io::Error;
+use std::io::ErrorKind;
 use std::io::Read;
 use std::io::Write;
 use std::net::Shutdown;
+use std::sync::OnceLock;
+use std::time::Duration;
 pub use uds_windows::UnixListener;
 use uds_windows::UnixStream;
 
@@ -62,14 +87,39 @@ const KOMOREBI: &str = "komorebi.sock";
 pub fn send_message(message: &SocketMessage) -> std::io::Result<()> {
     let socket = DATA_DIR.join(KOMOREBI);
     let mut stream = UnixStream::connect(socket)?;
-    stream.write_all(serde_json::to_string(message)?.as_bytes())
+    let message = VersionedSocketMessage::from(message.clone());
+    stream.write_all(serde_json::to_string(&message)?.as_bytes())
+}
+
+/// Sends a message and blocks until komorebi has finished processing it, including any relayout
+///
+/// If the command fails, the returned error wraps the [`KomorebiError`] describing the failure,
+/// which can be retrieved with `error.get_ref()` and `downcast_ref`
+pub fn send_message_and_wait(message: &SocketMessage) -> std::io::Result<()> {
+    // komorebi closes the connection once it has processed every message sent on it
+    let reply = send_query(message)?;
+
+    match CommandFailure::from_reply(&reply) {
+        Some(failure) => Err(Error::other(failure.error)),
+        None => Ok(()),
+    }
 }
 
 pub fn send_query(message: &SocketMessage) -> std::io::Result<String> {
+    send_query_with_timeout(message, None)
+}
+
+/// Sends a query, giving up with a TimedOut or WouldBlock error if komorebi does not reply in time
+pub fn send_query_with_timeout(
+    message: &SocketMessage,
+    timeout: Option<Duration>,
+) -> std::io::Result<String> {
     let socket = DATA_DIR.join(KOMOREBI);
 
     let mut stream = UnixStream::connect(socket)?;
-    stream.write_all(serde_json::to_string(message)?.as_bytes())?;
+    stream.set_read_timeout(timeout)?;
+    let message = VersionedSocketMessage::from(message.clone());
+    stream.write_all(serde_json::to_string(&message)?.as_bytes())?;
     stream.shutdown(Shutdown::Write)?;
 
     let mut reader = BufReader::new(stream);
@@ -124,3 +174,37 @@ pub fn subscribe_with_options(
 
     Ok(listener)
 }
+
+/// The version information of the running komorebi process, once it has been found to be
+/// compatible with this client
+static HANDSHAKE: OnceLock<VersionInformation> = OnceLock::new();
+
+/// Query the running komorebi process for its version information, without checking whether it
+/// speaks the same version of the IPC protocol as this client
+pub fn query_version() -> std::io::Result<VersionInformation> {
+    let response = send_query(&SocketMessage::Version)?;
+
+    serde_json::from_str::<VersionInformation>(&response).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            "komorebi did not respond to the version handshake; it is likely older than this client, please make sure that all komorebi binaries are from the same release",
+        )
+    })
+}
+
+/// Query the running komorebi process for its version information, returning an error if it
+/// speaks a different version of the IPC protocol to this client; once this has succeeded, the
+/// same version information is returned for the rest of the process without asking again
+pub fn handshake() -> std::io::Result<VersionInformation> {
+    if let Some(version) = HANDSHAKE.get() {
+        return Ok(version.clone());
+    }
+
+    let version = query_version()?;
+
+    version
+        .ensure_compatible()
+        .map_err(|error| Error::new(ErrorKind::Unsupported, error.to_string()))?;
+
+    Ok(HANDSHAKE.get_or_init(|| version).clone())
+}
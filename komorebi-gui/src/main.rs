@@ -41,6 +41,7 @@ struct BorderColours {
     single: Color32,
     stack: Color32,
     monocle: Color32,
+    floating: Color32,
     unfocused: Color32,
 }
 
@@ -49,6 +50,7 @@ struct BorderConfig {
     border_colours: BorderColours,
     border_style: BorderStyle,
     border_offset: i32,
+    border_radius: i32,
     border_width: i32,
 }
 
@@ -62,6 +64,16 @@ struct StackbarConfig {
     background_colour: Color32,
 }
 
+struct TransparencyConfig {
+    enabled: bool,
+    alpha: u8,
+}
+
+struct AnimationConfig {
+    enabled: bool,
+    duration: u64,
+}
+
 struct MonitorConfig {
     size: Rect,
     work_area_offset: Rect,
@@ -116,6 +128,8 @@ impl From<&komorebi_client::Workspace> for WorkspaceConfig {
 struct KomorebiGui {
     border_config: BorderConfig,
     stackbar_config: StackbarConfig,
+    transparency_config: TransparencyConfig,
+    animation_config: AnimationConfig,
     mouse_follows_focus: bool,
     monitors: Vec<MonitorConfig>,
     workspace_names: HashMap<usize, Vec<String>>,
@@ -146,14 +160,16 @@ impl KomorebiGui {
         )
         .unwrap();
 
-        let state: State =
-            serde_json::from_str(&komorebi_client::send_query(&SocketMessage::State).unwrap())
-                .unwrap();
+        let state: State = serde_json::from_str(
+            &komorebi_client::send_query(&SocketMessage::CompactState).unwrap(),
+        )
+        .unwrap();
 
         let border_colours = BorderColours {
             single: colour32(global_state.border_colours.single),
             stack: colour32(global_state.border_colours.stack),
             monocle: colour32(global_state.border_colours.monocle),
+            floating: colour32(global_state.border_colours.floating),
             unfocused: colour32(global_state.border_colours.unfocused),
         };
 
@@ -162,6 +178,7 @@ impl KomorebiGui {
             border_colours,
             border_style: global_state.border_style,
             border_offset: global_state.border_offset,
+            border_radius: global_state.border_radius,
             border_width: global_state.border_width,
         };
 
@@ -189,6 +206,16 @@ impl KomorebiGui {
             background_colour: colour32(Some(global_state.stackbar_tab_background_colour)),
         };
 
+        let transparency_config = TransparencyConfig {
+            enabled: global_state.transparency_enabled,
+            alpha: global_state.transparency_alpha,
+        };
+
+        let animation_config = AnimationConfig {
+            enabled: global_state.animation_enabled,
+            duration: global_state.animation_duration,
+        };
+
         let mut debug_windows = vec![];
 
         unsafe {
@@ -207,6 +234,8 @@ impl KomorebiGui {
             debug_hwnd: 0,
             debug_windows,
             stackbar_config,
+            transparency_config,
+            animation_config,
             debug_rule: None,
         }
     }
@@ -377,6 +406,22 @@ impl eframe::App for KomorebiGui {
                             }
                         });
 
+                        ui.collapsing("Floating", |ui| {
+                            if egui::color_picker::color_picker_color32(
+                                ui,
+                                &mut self.border_config.border_colours.floating,
+                                Alpha::Opaque,
+                            ) {
+                                komorebi_client::send_message(&SocketMessage::BorderColour(
+                                    WindowKind::Floating,
+                                    self.border_config.border_colours.floating.r() as u32,
+                                    self.border_config.border_colours.floating.g() as u32,
+                                    self.border_config.border_colours.floating.b() as u32,
+                                ))
+                                .unwrap();
+                            }
+                        });
+
                         ui.collapsing("Unfocused", |ui| {
                             if egui::color_picker::color_picker_color32(
                                 ui,
@@ -449,6 +494,75 @@ impl eframe::App for KomorebiGui {
                             .unwrap();
                         };
                     });
+
+                    ui.collapsing("Radius", |ui| {
+                        if ui
+                            .add(egui::Slider::new(
+                                &mut self.border_config.border_radius,
+                                0..=50,
+                            ))
+                            .changed()
+                        {
+                            komorebi_client::send_message(&SocketMessage::BorderRadius(
+                                self.border_config.border_radius,
+                            ))
+                            .unwrap();
+                        };
+                    });
+                });
+
+                ui.collapsing("Transparency", |ui| {
+                    if ui
+                        .toggle_value(&mut self.transparency_config.enabled, "Transparency")
+                        .changed()
+                    {
+                        komorebi_client::send_message(&SocketMessage::Transparency(
+                            self.transparency_config.enabled,
+                        ))
+                        .unwrap();
+                    }
+
+                    ui.collapsing("Alpha", |ui| {
+                        if ui
+                            .add(egui::Slider::new(
+                                &mut self.transparency_config.alpha,
+                                0..=255,
+                            ))
+                            .changed()
+                        {
+                            komorebi_client::send_message(&SocketMessage::TransparencyAlpha(
+                                self.transparency_config.alpha,
+                            ))
+                            .unwrap();
+                        };
+                    });
+                });
+
+                ui.collapsing("Animation", |ui| {
+                    if ui
+                        .toggle_value(&mut self.animation_config.enabled, "Animation")
+                        .changed()
+                    {
+                        komorebi_client::send_message(&SocketMessage::Animation(
+                            self.animation_config.enabled,
+                        ))
+                        .unwrap();
+                    }
+
+                    ui.collapsing("Duration", |ui| {
+                        if ui
+                            .add(egui::Slider::new(
+                                &mut self.animation_config.duration,
+                                0..=1000,
+                            ))
+                            .changed()
+                        {
+                            komorebi_client::send_message(&SocketMessage::AnimationDuration(
+                                self.animation_config.duration,
+                            ))
+                            .unwrap();
+                        };
+                    });
                 });
 
                 ui.collapsing("Stackbar", |ui| {
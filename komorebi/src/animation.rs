@@ -6,17 +6,54 @@ use schemars::JsonSchema;
 
 use serde::Deserialize;
 use serde::Serialize;
+use std::cell::Cell;
 use std::f64::consts::PI;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 
+use crate::system_api;
 use crate::ANIMATION_DURATION;
+use crate::ANIMATION_ENABLED;
 use crate::ANIMATION_MANAGER;
 use crate::ANIMATION_STYLE;
+use crate::FULLSCREEN_MONITORS;
 
 pub static ANIMATION_FPS: AtomicU64 = AtomicU64::new(60);
+pub static ANIMATE_LAYOUT_CHANGES: AtomicBool = AtomicBool::new(true);
+pub static ANIMATE_MOUSE_DRAG_SNAPS: AtomicBool = AtomicBool::new(true);
+
+thread_local! {
+    static MOUSE_DRAG_SNAP: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs the given function with the windows that it moves on this thread being treated as windows
+/// snapping into place after having been dragged or resized with the mouse
+pub fn as_mouse_drag_snap<T>(f: impl FnOnce() -> T) -> T {
+    let previous = MOUSE_DRAG_SNAP.replace(true);
+    let result = f();
+    MOUSE_DRAG_SNAP.set(previous);
+
+    result
+}
+
+/// Whether the windows being moved on this thread should be animated into place
+pub fn should_animate() -> bool {
+    if !ANIMATION_ENABLED.load(Ordering::SeqCst) || system_api::is_dry_run() {
+        return false;
+    }
+
+    let operation_enabled = if MOUSE_DRAG_SNAP.get() {
+        ANIMATE_MOUSE_DRAG_SNAPS.load(Ordering::SeqCst)
+    } else {
+        ANIMATE_LAYOUT_CHANGES.load(Ordering::SeqCst)
+    };
+
+    // Animating windows behind a focused fullscreen window such as a game only costs it frames
+    operation_enabled && FULLSCREEN_MONITORS.lock().is_empty()
+}
 
 pub trait Ease {
     fn evaluate(t: f64) -> f64;
@@ -478,7 +515,8 @@ impl Animation {
 
         ANIMATION_MANAGER.lock().start(self.hwnd);
 
-        let target_frame_time = Duration::from_millis(1000 / ANIMATION_FPS.load(Ordering::Relaxed));
+        let target_frame_time =
+            Duration::from_millis(1000 / ANIMATION_FPS.load(Ordering::Relaxed).max(1));
         let mut progress = 0.0;
         let animation_start = Instant::now();
 
@@ -1,6 +1,7 @@
 use crate::border_manager::window_kind_colour;
 use crate::border_manager::WindowKind;
 use crate::border_manager::BORDER_OFFSET;
+use crate::border_manager::BORDER_RADIUS;
 use crate::border_manager::BORDER_WIDTH;
 use crate::border_manager::FOCUS_STATE;
 use crate::border_manager::STYLE;
@@ -186,18 +187,27 @@ impl Border {
                             // Draw the border
                             SelectObject(hdc, hpen);
                             SelectObject(hdc, hbrush);
-                            // TODO(raggi): this is approximately the correct curvature for
-                            // the top left of a Windows 11 window (DWMWCP_DEFAULT), but
-                            // often the bottom right has a different shape. Furthermore if
+                            // TODO(raggi): the default radius is approximately the correct
+                            // curvature for the top left of a Windows 11 window (DWMWCP_DEFAULT),
+                            // but often the bottom right has a different shape. Furthermore if
                             // the window was made with DWMWCP_ROUNDSMALL then this is the
                             // wrong size.  In the future we should read the DWM properties
                             // of windows and attempt to match appropriately.
+                            let radius = BORDER_RADIUS.load(Ordering::SeqCst);
+
                             match STYLE.load() {
                                 BorderStyle::System => {
                                     if *WINDOWS_11 {
                                         // TODO: error handling
-                                        let _ =
-                                            RoundRect(hdc, 0, 0, rect.right, rect.bottom, 20, 20);
+                                        let _ = RoundRect(
+                                            hdc,
+                                            0,
+                                            0,
+                                            rect.right,
+                                            rect.bottom,
+                                            radius,
+                                            radius,
+                                        );
                                     } else {
                                         // TODO: error handling
                                         let _ = Rectangle(hdc, 0, 0, rect.right, rect.bottom);
@@ -205,7 +215,15 @@ impl Border {
                                 }
                                 BorderStyle::Rounded => {
                                     // TODO: error handling
-                                    let _ = RoundRect(hdc, 0, 0, rect.right, rect.bottom, 20, 20);
+                                    let _ = RoundRect(
+                                        hdc,
+                                        0,
+                                        0,
+                                        rect.right,
+                                        rect.bottom,
+                                        radius,
+                                        radius,
+                                    );
                                 }
                                 BorderStyle::Square => {
                                     // TODO: error handling
@@ -11,6 +11,7 @@ use crate::Colour;
 use crate::Rgb;
 use crate::WindowManager;
 use crate::WindowsApi;
+use crate::WINDOW_OPERATIONS_APPLIED;
 use border::border_hwnds;
 use border::Border;
 use crossbeam_channel::Receiver;
@@ -33,6 +34,7 @@ use std::sync::OnceLock;
 
 pub static BORDER_WIDTH: AtomicI32 = AtomicI32::new(8);
 pub static BORDER_OFFSET: AtomicI32 = AtomicI32::new(-1);
+pub static BORDER_RADIUS: AtomicI32 = AtomicI32::new(20);
 
 pub static BORDER_ENABLED: AtomicBool = AtomicBool::new(true);
 pub static BORDER_TEMPORARILY_DISABLED: AtomicBool = AtomicBool::new(false);
@@ -146,6 +148,7 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
 
     let mut previous_snapshot = Ring::default();
     let mut previous_pending_move_op = None;
+    let mut previous_operations_applied = 0;
     let mut previous_is_paused = false;
     let mut previous_notification: Option<Notification> = None;
 
@@ -158,6 +161,7 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
             state.monitors.elements()[focused_monitor_idx].focused_workspace_idx();
         let monitors = state.monitors.clone();
         let pending_move_op = state.pending_move_op;
+        let operations_applied = WINDOW_OPERATIONS_APPLIED.load(Ordering::SeqCst);
         let floating_window_hwnds = state.monitors.elements()[focused_monitor_idx].workspaces()
             [focused_workspace_idx]
             .floating_windows()
@@ -214,6 +218,8 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
                 if monitors == previous_snapshot
                     // handle the window dragging edge case
                     && pending_move_op == previous_pending_move_op
+                    // handle windows having been moved since the last snapshot
+                    && operations_applied == previous_operations_applied
                 {
                     should_process_notification = false;
                 }
@@ -586,6 +592,7 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
 
         previous_snapshot = monitors;
         previous_pending_move_op = pending_move_op;
+        previous_operations_applied = operations_applied;
         previous_is_paused = is_paused;
         previous_notification = Some(notification);
     }
@@ -104,7 +104,7 @@ impl Container {
 
     pub fn remove_focused_window(&mut self) -> Option<Window> {
         let focused_idx = self.focused_window_idx();
-        self.remove_window_by_idx(focused_idx)
+        self.windows.remove(focused_idx)
     }
 
     pub fn add_window(&mut self, window: Window) {
@@ -1,13 +1,9 @@
 use clap::ValueEnum;
 use schemars::JsonSchema;
-use serde::Deserialize;
 use serde::Serialize;
 use strum::Display;
-use strum::EnumString;
 
-#[derive(
-    Copy, Clone, Debug, Serialize, Deserialize, Display, EnumString, ValueEnum, JsonSchema,
-)]
+#[derive(Copy, Clone, Debug, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum AnimationStyle {
     Linear,
     EaseInSine,
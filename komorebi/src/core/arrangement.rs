@@ -2,10 +2,8 @@ use std::num::NonZeroUsize;
 
 use clap::ValueEnum;
 use schemars::JsonSchema;
-use serde::Deserialize;
 use serde::Serialize;
 use strum::Display;
-use strum::EnumString;
 
 use super::custom_layout::Column;
 use super::custom_layout::ColumnSplit;
@@ -603,18 +601,7 @@ impl Arrangement for CustomLayout {
     }
 }
 
-#[derive(
-    Clone,
-    Copy,
-    Debug,
-    Serialize,
-    Deserialize,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-    PartialEq,
-)]
+#[derive(Clone, Copy, Debug, Serialize, Display, ValueEnum, JsonSchema, PartialEq)]
 pub enum Axis {
     Horizontal,
     Vertical,
@@ -1143,3 +1130,198 @@ fn resize_top(rect: &mut Rect, resize: i32) {
 fn resize_bottom(rect: &mut Rect, resize: i32) {
     rect.bottom += resize / 2;
 }
+
+/// Layouts produced by integer division can be off by a pixel from their neighbours
+const EDGE_TOLERANCE: i32 = 2;
+
+/// Grows any layout which is smaller than the minimum size of the window which it will hold by
+/// moving the edges it shares with its neighbours, as far as those neighbours can give up space
+/// without dropping below the minimum sizes of their own windows
+pub fn respect_minimum_sizes(
+    layouts: &mut [Rect],
+    minimum_sizes: &[Option<(i32, i32)>],
+    container_padding: i32,
+) {
+    // Neighbouring layouts are separated by the padding of both containers
+    let gap = container_padding * 2;
+
+    for idx in 0..layouts.len() {
+        let Some((minimum_width, minimum_height)) = minimum_sizes.get(idx).copied().flatten()
+        else {
+            continue;
+        };
+
+        for (horizontal, minimum) in [(true, minimum_width), (false, minimum_height)] {
+            let (start, size) = span(&layouts[idx], horizontal);
+            let deficit = minimum - size;
+
+            if deficit > 0 {
+                // Prefer taking space from the neighbours after the layout, and fall back to the
+                // neighbours before it for whatever they could not give up
+                let moved = move_edge(
+                    layouts,
+                    minimum_sizes,
+                    horizontal,
+                    start + size,
+                    gap,
+                    deficit,
+                );
+
+                if moved < deficit {
+                    move_edge(
+                        layouts,
+                        minimum_sizes,
+                        horizontal,
+                        start - gap,
+                        gap,
+                        moved - deficit,
+                    );
+                }
+            }
+        }
+    }
+}
+
+// Moves the edge between the layouts ending at `edge` and the layouts starting `gap` after it by
+// up to `delta`, returning how far the edge could actually be moved
+fn move_edge(
+    layouts: &mut [Rect],
+    minimum_sizes: &[Option<(i32, i32)>],
+    horizontal: bool,
+    edge: i32,
+    gap: i32,
+    delta: i32,
+) -> i32 {
+    let ends_at_edge = |rect: &Rect| {
+        let (start, size) = span(rect, horizontal);
+        (start + size - edge).abs() <= EDGE_TOLERANCE
+    };
+
+    let starts_after_edge = |rect: &Rect| {
+        let (start, _) = span(rect, horizontal);
+        (start - (edge + gap)).abs() <= EDGE_TOLERANCE
+    };
+
+    // An edge on the border of the work area has nothing on the other side to take space from
+    if !layouts.iter().any(ends_at_edge) || !layouts.iter().any(starts_after_edge) {
+        return 0;
+    }
+
+    let mut available = delta.abs();
+
+    for (idx, rect) in layouts.iter().enumerate() {
+        let shrinks = if delta > 0 {
+            starts_after_edge(rect)
+        } else {
+            ends_at_edge(rect)
+        };
+
+        if shrinks {
+            let minimum = minimum_sizes
+                .get(idx)
+                .copied()
+                .flatten()
+                .map_or(0, |(width, height)| if horizontal { width } else { height });
+
+            let (_, size) = span(rect, horizontal);
+            available = available.min((size - minimum).max(0));
+        }
+    }
+
+    let moved = available * delta.signum();
+
+    for rect in layouts.iter_mut() {
+        if ends_at_edge(rect) {
+            let (_, size) = span_mut(rect, horizontal);
+            *size += moved;
+        } else if starts_after_edge(rect) {
+            let (start, size) = span_mut(rect, horizontal);
+            *start += moved;
+            *size -= moved;
+        }
+    }
+
+    moved
+}
+
+fn span(rect: &Rect, horizontal: bool) -> (i32, i32) {
+    if horizontal {
+        (rect.left, rect.right)
+    } else {
+        (rect.top, rect.bottom)
+    }
+}
+
+fn span_mut(rect: &mut Rect, horizontal: bool) -> (&mut i32, &mut i32) {
+    if horizontal {
+        (&mut rect.left, &mut rect.right)
+    } else {
+        (&mut rect.top, &mut rect.bottom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rect::rect;
+
+    #[test]
+    fn layouts_which_fit_their_windows_are_unchanged() {
+        let mut layouts = vec![rect(0, 0, 500, 500), rect(500, 0, 500, 500)];
+        respect_minimum_sizes(&mut layouts, &[None, Some((400, 400))], 0);
+
+        assert_eq!(layouts, vec![rect(0, 0, 500, 500), rect(500, 0, 500, 500)]);
+    }
+
+    #[test]
+    fn space_is_taken_from_the_following_neighbour_first() {
+        let mut layouts = vec![rect(0, 0, 500, 500), rect(500, 0, 500, 500)];
+        respect_minimum_sizes(&mut layouts, &[Some((700, 0)), None], 0);
+
+        assert_eq!(layouts, vec![rect(0, 0, 700, 500), rect(700, 0, 300, 500)]);
+    }
+
+    #[test]
+    fn space_is_taken_from_the_preceding_neighbour_at_the_work_area_border() {
+        let mut layouts = vec![rect(0, 0, 500, 500), rect(500, 0, 500, 500)];
+        respect_minimum_sizes(&mut layouts, &[None, Some((700, 0))], 0);
+
+        assert_eq!(layouts, vec![rect(0, 0, 300, 500), rect(300, 0, 700, 500)]);
+    }
+
+    #[test]
+    fn neighbours_do_not_shrink_below_their_own_minimum_sizes() {
+        let mut layouts = vec![rect(0, 0, 500, 500), rect(500, 0, 500, 500)];
+        respect_minimum_sizes(&mut layouts, &[Some((400, 0)), Some((700, 0))], 0);
+
+        assert_eq!(layouts, vec![rect(0, 0, 400, 500), rect(400, 0, 600, 500)]);
+    }
+
+    #[test]
+    fn container_padding_is_kept_between_neighbours() {
+        let mut layouts = vec![rect(0, 0, 490, 500), rect(510, 0, 490, 500)];
+        respect_minimum_sizes(&mut layouts, &[None, Some((590, 0))], 10);
+
+        assert_eq!(layouts, vec![rect(0, 0, 390, 500), rect(410, 0, 590, 500)]);
+    }
+
+    #[test]
+    fn rows_share_the_edge_with_every_layout_beside_them() {
+        // A column on the left, and a column on the right which is split into two rows
+        let mut layouts = vec![
+            rect(0, 0, 500, 1000),
+            rect(500, 0, 500, 500),
+            rect(500, 500, 500, 500),
+        ];
+        respect_minimum_sizes(&mut layouts, &[None, Some((0, 800)), None], 0);
+
+        assert_eq!(
+            layouts,
+            vec![
+                rect(0, 0, 500, 1000),
+                rect(500, 0, 500, 800),
+                rect(500, 800, 500, 200),
+            ]
+        );
+    }
+}
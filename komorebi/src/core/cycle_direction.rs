@@ -2,14 +2,10 @@ use std::num::NonZeroUsize;
 
 use clap::ValueEnum;
 use schemars::JsonSchema;
-use serde::Deserialize;
 use serde::Serialize;
 use strum::Display;
-use strum::EnumString;
 
-#[derive(
-    Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ValueEnum, JsonSchema,
-)]
+#[derive(Clone, Copy, Debug, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum CycleDirection {
     Previous,
     Next,
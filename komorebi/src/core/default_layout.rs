@@ -1,27 +1,13 @@
 use clap::ValueEnum;
 use schemars::JsonSchema;
-use serde::Deserialize;
 use serde::Serialize;
 use strum::Display;
-use strum::EnumString;
 
 use super::OperationDirection;
 use super::Rect;
 use super::Sizing;
 
-#[derive(
-    Clone,
-    Copy,
-    Debug,
-    Serialize,
-    Deserialize,
-    Eq,
-    PartialEq,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-)]
+#[derive(Clone, Copy, Debug, Serialize, Eq, PartialEq, Display, ValueEnum, JsonSchema)]
 pub enum DefaultLayout {
     BSP,
     Columns,
@@ -0,0 +1,106 @@
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use super::OperationDirection;
+
+/// The ways in which a command can fail that clients are expected to want to react to, which are
+/// sent back over the socket so that scripts can branch on them without matching error messages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "kind", content = "content")]
+pub enum KomorebiError {
+    /// There is no monitor at this index
+    NoSuchMonitor(usize),
+    /// There is no workspace at this index
+    NoSuchWorkspace(usize),
+    /// There is no workspace with this name, along with the names of the workspaces that do exist
+    NoSuchNamedWorkspace(String, Vec<String>),
+    /// The window with this hwnd no longer exists
+    WindowGone(isize),
+    /// The foreground window with this hwnd is not managed, and commands on unmanaged windows are
+    /// set to be ignored
+    NotManaged(isize),
+    /// The command cannot be run while the focused window is in monocle mode or maximized
+    MonocleOrMaximized,
+    /// There is no container or monitor in this direction
+    NothingInDirection(OperationDirection),
+    /// The command cannot be previewed with a dry run
+    DryRunUnsupported,
+    /// Any failure which has not been given a kind of its own
+    Other(String),
+}
+
+impl Display for KomorebiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoSuchMonitor(idx) => write!(f, "there is no monitor at index {idx}"),
+            Self::NoSuchWorkspace(idx) => write!(f, "there is no workspace at index {idx}"),
+            Self::NoSuchNamedWorkspace(name, known) => {
+                if known.is_empty() {
+                    write!(
+                        f,
+                        "there is no workspace named {name}; no workspaces have names"
+                    )
+                } else {
+                    write!(
+                        f,
+                        "there is no workspace named {name}; the named workspaces are {}",
+                        known.join(", ")
+                    )
+                }
+            }
+            Self::WindowGone(hwnd) => write!(f, "the window with hwnd {hwnd} no longer exists"),
+            Self::NotManaged(hwnd) => write!(
+                f,
+                "ignoring commands while active window ({hwnd}) is not managed by komorebi"
+            ),
+            Self::MonocleOrMaximized => write!(
+                f,
+                "ignoring command while active window is in monocle mode or maximized"
+            ),
+            Self::NothingInDirection(direction) => {
+                write!(
+                    f,
+                    "there is no container or monitor in direction {direction}"
+                )
+            }
+            Self::DryRunUnsupported => write!(f, "this command cannot be run as a dry run"),
+            Self::Other(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for KomorebiError {}
+
+/// Written back to the client in place of the usual reply when a command fails
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct CommandFailure {
+    pub error: KomorebiError,
+    pub message: String,
+}
+
+impl CommandFailure {
+    /// Returns the failure contained in a reply from komorebi, if the command failed
+    #[must_use]
+    pub fn from_reply(reply: &str) -> Option<Self> {
+        serde_json::from_str(reply).ok()
+    }
+}
+
+impl From<&color_eyre::Report> for CommandFailure {
+    fn from(report: &color_eyre::Report) -> Self {
+        let error = report
+            .chain()
+            .find_map(|error| error.downcast_ref::<KomorebiError>())
+            .cloned()
+            .unwrap_or_else(|| KomorebiError::Other(report.to_string()));
+
+        Self {
+            message: report.to_string(),
+            error,
+        }
+    }
+}
@@ -12,7 +12,7 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use strum::Display;
-use strum::EnumString;
+use strum::VariantNames;
 
 use crate::KomorebiTheme;
 pub use animation::AnimationStyle;
@@ -22,6 +22,8 @@ pub use custom_layout::CustomLayout;
 pub use cycle_direction::CycleDirection;
 pub use default_layout::DefaultLayout;
 pub use direction::Direction;
+pub use error::CommandFailure;
+pub use error::KomorebiError;
 pub use layout::Layout;
 pub use operation_direction::OperationDirection;
 pub use rect::Rect;
@@ -34,11 +36,27 @@ pub mod custom_layout;
 pub mod cycle_direction;
 pub mod default_layout;
 pub mod direction;
+pub mod error;
 pub mod layout;
 pub mod operation_direction;
 pub mod rect;
+#[macro_use]
+pub mod value_enum;
 
-#[derive(Clone, Debug, Serialize, Deserialize, Display, JsonSchema)]
+/// The version of the IPC protocol spoken over komorebi.sock; this must be incremented whenever
+/// a change is made to SocketMessage which would break compatibility with older clients
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The name of the mutex held by komorebi for as long as it is running; named objects in the
+/// Local namespace are scoped to the current session, so komorebi can run independently in each
+/// RDP or fast user switching session
+pub const INSTANCE_MUTEX: &str = "Local\\komorebi";
+
+/// The name of the event signalled by a new instance started with --replace to ask the running
+/// instance in the same session to exit
+pub const REPLACE_EVENT: &str = "Local\\komorebi-replace";
+
+#[derive(Clone, Debug, Serialize, Deserialize, Display, VariantNames, JsonSchema)]
 #[serde(tag = "type", content = "content")]
 pub enum SocketMessage {
     // Window / Container Commands
@@ -46,6 +64,7 @@ pub enum SocketMessage {
     MoveWindow(OperationDirection),
     CycleFocusWindow(CycleDirection),
     CycleMoveWindow(CycleDirection),
+    RotateContainers(CycleDirection),
     StackWindow(OperationDirection),
     UnstackWindow,
     CycleStack(CycleDirection),
@@ -54,6 +73,7 @@ pub enum SocketMessage {
     UnstackAll,
     ResizeWindowEdge(OperationDirection, Sizing),
     ResizeWindowAxis(Axis, Sizing),
+    CycleWidth(CycleDirection),
     MoveContainerToMonitorNumber(usize),
     CycleMoveContainerToMonitor(CycleDirection),
     MoveContainerToWorkspaceNumber(usize),
@@ -70,12 +90,20 @@ pub enum SocketMessage {
     MoveWorkspaceToMonitorNumber(usize),
     SwapWorkspacesToMonitorNumber(usize),
     ForceFocus,
+    Hint,
+    Switcher,
+    FocusLast,
+    JumpToExe(String, bool),
     Close,
+    CloseWorkspaceWindows(bool),
     Minimize,
     Promote,
     PromoteFocus,
     PromoteWindow(OperationDirection),
     ToggleFloat,
+    ToggleFloatLayer,
+    SetFloatRect(Rect, bool),
+    CenterFloat(Option<u8>),
     ToggleMonocle,
     ToggleMaximize,
     ToggleWindowContainerBehaviour,
@@ -157,6 +185,7 @@ pub enum SocketMessage {
     BorderStyle(BorderStyle),
     BorderWidth(i32),
     BorderOffset(i32),
+    BorderRadius(i32),
     BorderImplementation(BorderImplementation),
     Transparency(bool),
     ToggleTransparency,
@@ -173,6 +202,8 @@ pub enum SocketMessage {
     StackbarFontFamily(Option<String>),
     WorkAreaOffset(Rect),
     MonitorWorkAreaOffset(usize, Rect),
+    HideTaskbar(usize),
+    ShowTaskbar(usize),
     ResizeDelta(i32),
     InitialWorkspaceRule(ApplicationIdentifier, String, usize, usize),
     InitialNamedWorkspaceRule(ApplicationIdentifier, String, String),
@@ -189,8 +220,13 @@ pub enum SocketMessage {
     IdentifyLayeredApplication(ApplicationIdentifier, String),
     IdentifyBorderOverflowApplication(ApplicationIdentifier, String),
     State,
+    CompactState,
     GlobalState,
     VisibleWindows,
+    KnownHwnds,
+    Stats,
+    CheckHealth,
+    BarStatus,
     MonitorInformation,
     Query(StateQuery),
     FocusFollowsMouse(FocusFollowsMouseImplementation, bool),
@@ -210,12 +246,82 @@ pub enum SocketMessage {
     StaticConfigSchema,
     GenerateStaticConfig,
     DebugWindow(isize),
+    LogLevel(LogLevel),
+    Version,
+    Batch(Vec<SocketMessage>),
+    DryRun(Box<SocketMessage>),
+    SaveSession,
 }
 
 impl SocketMessage {
     pub fn as_bytes(&self) -> Result<Vec<u8>> {
         Ok(serde_json::to_string(self)?.as_bytes().to_vec())
     }
+
+    /// Whether this command only changes the arrangement of windows, which can be previewed with
+    /// a dry run; commands which change configuration, global settings or files cannot be
+    #[must_use]
+    pub fn supports_dry_run(&self) -> bool {
+        match self {
+            Self::Batch(messages) => messages.iter().all(Self::supports_dry_run),
+            _ => matches!(
+                self,
+                Self::FocusWindow(_)
+                    | Self::MoveWindow(_)
+                    | Self::CycleFocusWindow(_)
+                    | Self::CycleMoveWindow(_)
+                    | Self::RotateContainers(_)
+                    | Self::StackWindow(_)
+                    | Self::UnstackWindow
+                    | Self::CycleStack(_)
+                    | Self::FocusStackWindow(_)
+                    | Self::StackAll
+                    | Self::UnstackAll
+                    | Self::ResizeWindowEdge(_, _)
+                    | Self::ResizeWindowAxis(_, _)
+                    | Self::CycleWidth(_)
+                    | Self::MoveContainerToMonitorNumber(_)
+                    | Self::CycleMoveContainerToMonitor(_)
+                    | Self::MoveContainerToWorkspaceNumber(_)
+                    | Self::MoveContainerToNamedWorkspace(_)
+                    | Self::CycleMoveContainerToWorkspace(_)
+                    | Self::SendContainerToMonitorNumber(_)
+                    | Self::CycleSendContainerToMonitor(_)
+                    | Self::SendContainerToWorkspaceNumber(_)
+                    | Self::CycleSendContainerToWorkspace(_)
+                    | Self::SendContainerToMonitorWorkspaceNumber(_, _)
+                    | Self::MoveContainerToMonitorWorkspaceNumber(_, _)
+                    | Self::SendContainerToNamedWorkspace(_)
+                    | Self::CycleMoveWorkspaceToMonitor(_)
+                    | Self::MoveWorkspaceToMonitorNumber(_)
+                    | Self::SwapWorkspacesToMonitorNumber(_)
+                    | Self::Promote
+                    | Self::PromoteFocus
+                    | Self::PromoteWindow(_)
+                    | Self::ToggleFloat
+                    | Self::SetFloatRect(_, _)
+                    | Self::CenterFloat(_)
+                    | Self::ToggleMonocle
+                    | Self::ToggleMaximize
+                    | Self::ChangeLayout(_)
+                    | Self::CycleLayout(_)
+                    | Self::FlipLayout(_)
+                    | Self::ToggleTiling
+                    | Self::Retile
+                    | Self::RetileWithResizeDimensions
+                    | Self::CycleFocusMonitor(_)
+                    | Self::CycleFocusWorkspace(_)
+                    | Self::FocusMonitorNumber(_)
+                    | Self::FocusLastWorkspace
+                    | Self::FocusWorkspaceNumber(_)
+                    | Self::FocusWorkspaceNumbers(_)
+                    | Self::FocusMonitorWorkspaceNumber(_, _)
+                    | Self::FocusNamedWorkspace(_)
+                    | Self::WorkspaceLayout(_, _, _)
+                    | Self::NamedWorkspaceLayout(_, _)
+            ),
+        }
+    }
 }
 
 impl FromStr for SocketMessage {
@@ -226,10 +332,139 @@ impl FromStr for SocketMessage {
     }
 }
 
-#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+/// A SocketMessage tagged with the IPC protocol version of the client that sent it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VersionedSocketMessage {
+    #[serde(flatten)]
+    pub message: SocketMessage,
+    /// This will be None for legacy clients which do not send a protocol version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_version: Option<u32>,
+}
+
+impl From<SocketMessage> for VersionedSocketMessage {
+    fn from(message: SocketMessage) -> Self {
+        Self {
+            message,
+            protocol_version: Option::from(PROTOCOL_VERSION),
+        }
+    }
+}
+
+impl FromStr for VersionedSocketMessage {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VersionInformation {
+    /// The version of the komorebi binary
+    pub version: String,
+    /// The version of the IPC protocol spoken by the komorebi binary
+    pub protocol_version: u32,
+    /// The SocketMessage types supported by the komorebi binary
+    pub capabilities: Vec<String>,
+}
+
+impl Default for VersionInformation {
+    fn default() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: SocketMessage::VARIANTS
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Health {
+    /// Whether the hook which receives window events from the system is installed
+    pub event_hook_installed: bool,
+    /// Whether the thread which handles window events and commands has been active recently
+    pub event_loop_responsive: bool,
+    /// Unix timestamp in milliseconds of the last window event received from the system
+    pub last_event_timestamp: Option<u64>,
+}
+
+impl Health {
+    /// Receiving a reply on komorebi.sock already shows that the command listener is alive, so the
+    /// health of komorebi comes down to whether it is receiving and handling window events
+    #[must_use]
+    pub const fn is_healthy(&self) -> bool {
+        self.event_hook_installed && self.event_loop_responsive
+    }
+}
+
+impl VersionInformation {
+    /// Returns an error describing the mismatch if this does not match the local protocol version
+    pub fn ensure_compatible(&self) -> Result<()> {
+        if self.protocol_version != PROTOCOL_VERSION {
+            return Err(anyhow!(
+                "komorebi v{} speaks IPC protocol version {}, but this client speaks IPC protocol version {}; please make sure that all komorebi binaries are from the same release",
+                self.version,
+                self.protocol_version,
+                PROTOCOL_VERSION
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+}
+
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct SubscribeOptions {
-    /// Only emit notifications when the window manager state has changed
+    /// Only emit notifications when the window manager state or focused window title changes
     pub filter_state_changes: bool,
+    /// Only emit notifications for these event types (eg. FocusChange, FocusWorkspaceNumber)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter_event_types: Option<Vec<String>>,
+    /// Send a compact per-monitor workspace status for status bars instead of the notification
+    #[serde(default)]
+    pub bar_status: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Display, Serialize, Deserialize, JsonSchema)]
+pub enum HookEvent {
+    /// A workspace was focused on any monitor
+    WorkspaceFocused,
+    /// A window started being managed
+    WindowManaged,
+    /// A window stopped being managed
+    WindowUnmanaged,
+    /// A monitor was connected
+    MonitorConnected,
+    /// A monitor was disconnected
+    MonitorDisconnected,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Display, Serialize, Deserialize, JsonSchema)]
+pub enum ScreenEdge {
+    /// The top edge of a monitor
+    Top,
+    /// The bottom edge of a monitor
+    Bottom,
+    /// The left edge of a monitor
+    Left,
+    /// The right edge of a monitor
+    Right,
+    /// The top left corner of a monitor
+    TopLeft,
+    /// The top right corner of a monitor
+    TopRight,
+    /// The bottom left corner of a monitor
+    BottomLeft,
+    /// The bottom right corner of a monitor
+    BottomRight,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Display, Serialize, Deserialize, JsonSchema)]
@@ -248,19 +483,7 @@ pub enum StackbarLabel {
     Title,
 }
 
-#[derive(
-    Default,
-    Copy,
-    Clone,
-    Debug,
-    Eq,
-    PartialEq,
-    Display,
-    Serialize,
-    Deserialize,
-    JsonSchema,
-    ValueEnum,
-)]
+#[derive(Default, Copy, Clone, Debug, Eq, PartialEq, Display, Serialize, JsonSchema, ValueEnum)]
 pub enum BorderStyle {
     #[default]
     /// Use the system border style
@@ -271,19 +494,7 @@ pub enum BorderStyle {
     Square,
 }
 
-#[derive(
-    Default,
-    Copy,
-    Clone,
-    Debug,
-    Eq,
-    PartialEq,
-    Display,
-    Serialize,
-    Deserialize,
-    JsonSchema,
-    ValueEnum,
-)]
+#[derive(Default, Copy, Clone, Debug, Eq, PartialEq, Display, Serialize, JsonSchema, ValueEnum)]
 pub enum BorderImplementation {
     #[default]
     /// Use the adjustable komorebi border implementation
@@ -292,18 +503,7 @@ pub enum BorderImplementation {
     Windows,
 }
 
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    Serialize,
-    Deserialize,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-    PartialEq,
-)]
+#[derive(Copy, Clone, Debug, Serialize, Display, ValueEnum, JsonSchema, PartialEq)]
 pub enum WindowKind {
     Single,
     Stack,
@@ -312,52 +512,31 @@ pub enum WindowKind {
     Floating,
 }
 
-#[derive(
-    Copy, Clone, Debug, Serialize, Deserialize, Display, EnumString, ValueEnum, JsonSchema,
-)]
+#[derive(Copy, Clone, Debug, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum StateQuery {
     FocusedMonitorIndex,
     FocusedWorkspaceIndex,
     FocusedContainerIndex,
     FocusedWindowIndex,
+    /// A JSON summary of the focused window and its location
+    FocusedWindow,
+    /// A JSON summary of the focused workspace
+    FocusedWorkspace,
+    /// A JSON summary of the focused monitor
+    FocusedMonitor,
+    /// A JSON summary of the window under the cursor and its location
+    WindowAtCursor,
 }
 
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    Eq,
-    PartialEq,
-    Serialize,
-    Deserialize,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum ApplicationIdentifier {
-    #[serde(alias = "exe")]
     Exe,
-    #[serde(alias = "class")]
     Class,
-    #[serde(alias = "title")]
     Title,
-    #[serde(alias = "path")]
     Path,
 }
 
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    PartialEq,
-    Serialize,
-    Deserialize,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum FocusFollowsMouseImplementation {
     /// A custom FFM implementation (slightly more CPU-intensive)
     Komorebi,
@@ -375,19 +554,7 @@ pub struct WindowManagementBehaviour {
     pub float_override: bool,
 }
 
-#[derive(
-    Clone,
-    Copy,
-    Debug,
-    Default,
-    Serialize,
-    Deserialize,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-    PartialEq,
-)]
+#[derive(Clone, Copy, Debug, Default, Serialize, Display, ValueEnum, JsonSchema, PartialEq)]
 pub enum WindowContainerBehaviour {
     /// Create a new container for each new window
     #[default]
@@ -396,18 +563,7 @@ pub enum WindowContainerBehaviour {
     Append,
 }
 
-#[derive(
-    Clone,
-    Copy,
-    Debug,
-    PartialEq,
-    Serialize,
-    Deserialize,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum MoveBehaviour {
     /// Swap the window container with the window container at the edge of the adjacent monitor
     Swap,
@@ -417,9 +573,21 @@ pub enum MoveBehaviour {
     NoOp,
 }
 
-#[derive(
-    Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ValueEnum, JsonSchema,
-)]
+#[derive(Clone, Copy, Debug, Serialize, Display, ValueEnum, JsonSchema)]
+pub enum LogLevel {
+    /// Only log errors
+    Error,
+    /// Log warnings and errors
+    Warn,
+    /// Log general information about what komorebi is doing (default)
+    Info,
+    /// Log details which are useful for diagnosing issues
+    Debug,
+    /// Log everything, including every event received from the system
+    Trace,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum CrossBoundaryBehaviour {
     /// Attempt to perform actions across a workspace boundary
     Workspace,
@@ -427,9 +595,46 @@ pub enum CrossBoundaryBehaviour {
     Monitor,
 }
 
-#[derive(
-    Copy, Clone, Debug, Serialize, Deserialize, Display, EnumString, ValueEnum, JsonSchema,
-)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Display, ValueEnum, JsonSchema)]
+pub enum SnapBehaviour {
+    /// Put windows which have been snapped by the system back into their place in the layout
+    Revert,
+    /// Treat the new position of windows which have been snapped by the system as a resize
+    Adopt,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Display, ValueEnum, JsonSchema)]
+pub enum DropBehaviour {
+    /// Swap the dragged window container with the window container it is dropped on
+    Swap,
+    /// Remove the dragged window container and insert it where it is dropped, shifting the window
+    /// containers in between
+    Insert,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Display, ValueEnum, JsonSchema)]
+pub enum DragModifier {
+    /// Either of the Alt keys
+    Alt,
+    /// Either of the Ctrl keys
+    Ctrl,
+    /// Either of the Windows keys
+    Win,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Display, ValueEnum, JsonSchema)]
+pub enum CornerPreference {
+    /// Let the system decide whether or not to round the corners of windows
+    Default,
+    /// Never round the corners of windows
+    DoNotRound,
+    /// Round the corners of windows
+    Round,
+    /// Round the corners of windows with a small radius
+    RoundSmall,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum HidingBehaviour {
     /// Use the SW_HIDE flag to hide windows when switching workspaces (has issues with Electron apps)
     Hide,
@@ -439,18 +644,7 @@ pub enum HidingBehaviour {
     Cloak,
 }
 
-#[derive(
-    Clone,
-    Copy,
-    Debug,
-    PartialEq,
-    Serialize,
-    Deserialize,
-    Display,
-    EnumString,
-    ValueEnum,
-    JsonSchema,
-)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum OperationBehaviour {
     /// Process komorebic commands on temporarily unmanaged/floated windows
     Op,
@@ -458,9 +652,7 @@ pub enum OperationBehaviour {
     NoOp,
 }
 
-#[derive(
-    Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ValueEnum, JsonSchema,
-)]
+#[derive(Clone, Copy, Debug, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum Sizing {
     Increase,
     Decrease,
@@ -482,6 +674,32 @@ impl Sizing {
     }
 }
 
+impl_value_enum_parsing!(
+    AnimationStyle,
+    ApplicationIdentifier,
+    config_generation::ApplicationOptions,
+    Axis,
+    BorderImplementation,
+    BorderStyle,
+    CornerPreference,
+    CrossBoundaryBehaviour,
+    CycleDirection,
+    DefaultLayout,
+    DragModifier,
+    DropBehaviour,
+    FocusFollowsMouseImplementation,
+    HidingBehaviour,
+    LogLevel,
+    MoveBehaviour,
+    OperationBehaviour,
+    OperationDirection,
+    Sizing,
+    SnapBehaviour,
+    StateQuery,
+    WindowContainerBehaviour,
+    WindowKind,
+);
+
 pub fn resolve_home_path<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
     let mut resolved_path = PathBuf::new();
     let mut resolved = false;
@@ -2,17 +2,13 @@ use std::num::NonZeroUsize;
 
 use clap::ValueEnum;
 use schemars::JsonSchema;
-use serde::Deserialize;
 use serde::Serialize;
 use strum::Display;
-use strum::EnumString;
 
 use super::direction::Direction;
 use super::Axis;
 
-#[derive(
-    Clone, Copy, Debug, Serialize, Deserialize, Display, EnumString, ValueEnum, JsonSchema,
-)]
+#[derive(Clone, Copy, Debug, Serialize, Display, ValueEnum, JsonSchema)]
 pub enum OperationDirection {
     Left,
     Right,
@@ -37,6 +37,17 @@ impl From<Rect> for RECT {
     }
 }
 
+/// Builds a rect from its position and dimensions, for tests
+#[cfg(test)]
+pub const fn rect(left: i32, top: i32, width: i32, height: i32) -> Rect {
+    Rect {
+        left,
+        top,
+        right: width,
+        bottom: height,
+    }
+}
+
 impl Rect {
     /// decrease the size of self by the padding amount.
     pub fn add_padding<T>(&mut self, padding: T)
@@ -0,0 +1,122 @@
+use std::ffi::OsStr;
+use std::marker::PhantomData;
+
+use clap::builder::EnumValueParser;
+use clap::builder::PossibleValue;
+use clap::builder::TypedValueParser;
+use clap::ValueEnum;
+
+/// Parses a variant of a unit enum without regard to case, hyphens or underscores, so that
+/// "DoNotRound", "do-not-round", "do_not_round" and "donotround" all refer to the same variant
+pub fn parse_value_enum<E: ValueEnum>(value: &str) -> Option<E> {
+    let value = normalize(value);
+
+    E::value_variants()
+        .iter()
+        .find(|variant| {
+            variant.to_possible_value().is_some_and(|possible_value| {
+                possible_value
+                    .get_name_and_aliases()
+                    .any(|name| normalize(name) == value)
+            })
+        })
+        .cloned()
+}
+
+fn normalize(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// A command line parser for unit enums which accepts everything that [`parse_value_enum`] does,
+/// while still showing the kebab-case possible values in help and error messages
+#[derive(Debug, Clone)]
+pub struct ValueEnumParser<E>(PhantomData<E>);
+
+impl<E> ValueEnumParser<E> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<E> Default for ValueEnumParser<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: ValueEnum + Send + Sync + 'static> TypedValueParser for ValueEnumParser<E> {
+    type Value = E;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        match value.to_str().and_then(parse_value_enum) {
+            Some(variant) => Ok(variant),
+            // clap already knows how to explain which values would have been accepted
+            None => EnumValueParser::<E>::new().parse_ref(cmd, arg, value),
+        }
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            E::value_variants()
+                .iter()
+                .filter_map(ValueEnum::to_possible_value),
+        ))
+    }
+}
+
+/// Implements `FromStr`, `Deserialize` and clap's `ValueParserFactory` for unit enums on top of
+/// [`parse_value_enum`], so that the command line, the configuration file and the socket all
+/// accept the same forms of each variant
+macro_rules! impl_value_enum_parsing {
+    ($($name:ty),+ $(,)?) => {
+        $(
+            impl std::str::FromStr for $name {
+                type Err = strum::ParseError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    $crate::core::value_enum::parse_value_enum(s)
+                        .ok_or(strum::ParseError::VariantNotFound)
+                }
+            }
+
+            impl<'de> serde::Deserialize<'de> for $name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+                    $crate::core::value_enum::parse_value_enum(&value).ok_or_else(|| {
+                        let expected = <Self as clap::ValueEnum>::value_variants()
+                            .iter()
+                            .map(|variant| format!("`{variant}`"))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+
+                        serde::de::Error::custom(format!(
+                            "unknown variant `{value}`, expected one of {expected}"
+                        ))
+                    })
+                }
+            }
+
+            impl clap::builder::ValueParserFactory for $name {
+                type Parser = $crate::core::value_enum::ValueEnumParser<Self>;
+
+                fn value_parser() -> Self::Parser {
+                    $crate::core::value_enum::ValueEnumParser::new()
+                }
+            }
+        )+
+    };
+}
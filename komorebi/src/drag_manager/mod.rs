@@ -0,0 +1,241 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::sync::atomic::AtomicIsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use windows::Win32::Foundation::POINT;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_CONTROL;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_LWIN;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_MENU;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_RWIN;
+use windows::Win32::UI::WindowsAndMessaging::MSLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::WM_LBUTTONDOWN;
+use windows::Win32::UI::WindowsAndMessaging::WM_LBUTTONUP;
+use windows::Win32::UI::WindowsAndMessaging::WM_MOUSEMOVE;
+
+use crate::core::DragModifier;
+use crate::core::Rect;
+use crate::window_manager_event::WindowManagerEvent;
+use crate::winevent::WinEvent;
+use crate::winevent_listener;
+use crate::State;
+use crate::Window;
+use crate::WindowManager;
+use crate::WindowsApi;
+
+pub static DRAG_MODIFIER: Mutex<Option<DragModifier>> = Mutex::new(None);
+
+/// The focused windows of the layouts on screen, which are the only windows that can be dragged
+/// from anywhere; the hook can't wait on the window manager, so this is kept up to date from the
+/// state instead
+static TILED_HWNDS: Mutex<Vec<isize>> = Mutex::new(Vec::new());
+
+/// The window which is being dragged by the hook, or 0 when there is no drag in progress
+static DRAGGING_HWND: AtomicIsize = AtomicIsize::new(0);
+
+pub enum Notification {
+    Start(isize, POINT),
+    Move(POINT),
+    End,
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(100))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+fn send_notification(notification: Notification) {
+    if event_tx().try_send(notification).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn update_tiled_windows(state: &State) {
+    let mut hwnds = vec![];
+
+    for monitor in state.monitors.elements() {
+        let Some(workspace) = monitor.focused_workspace() else {
+            continue;
+        };
+
+        if !*workspace.tile()
+            || workspace.monocle_container().is_some()
+            || workspace.maximized_window().is_some()
+        {
+            continue;
+        }
+
+        for container in workspace.containers() {
+            if let Some(window) = container.focused_window() {
+                hwnds.push(window.hwnd);
+            }
+        }
+    }
+
+    *TILED_HWNDS.lock() = hwnds;
+}
+
+pub fn listen_for_notifications(wm: Arc<Mutex<WindowManager>>) {
+    std::thread::spawn(move || loop {
+        match handle_notifications(wm.clone()) {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    // The window being dragged, and where the cursor and the window were when the drag started
+    let mut drag: Option<(isize, POINT, Rect)> = None;
+
+    for notification in receiver {
+        match notification {
+            Notification::Start(hwnd, origin) => {
+                let rect = match WindowsApi::window_rect(hwnd) {
+                    Ok(rect) => rect,
+                    Err(error) => {
+                        tracing::warn!("could not start dragging window {hwnd}: {error}");
+                        continue;
+                    }
+                };
+
+                // The click which started the drag never reached the window, so it has to be
+                // focused here for its container to be the one picked up by the event loop
+                if let Err(error) = focus_container(&wm, hwnd) {
+                    tracing::warn!("could not focus the container of window {hwnd}: {error}");
+                }
+
+                if let Err(error) = Window::from(hwnd).focus(false) {
+                    tracing::warn!("could not focus window {hwnd}: {error}");
+                }
+
+                // Releasing the modifier without having pressed anything else would otherwise
+                // open the start menu or the menu bar of the window
+                WindowsApi::press_unassigned_key();
+
+                send_event(WindowManagerEvent::MoveResizeStart(
+                    WinEvent::SystemMoveSizeStart,
+                    Window::from(hwnd),
+                ));
+
+                drag = Some((hwnd, origin, rect));
+            }
+            Notification::Move(point) => {
+                let Some((hwnd, origin, rect)) = drag else {
+                    continue;
+                };
+
+                let moved = Rect {
+                    left: rect.left + point.x - origin.x,
+                    top: rect.top + point.y - origin.y,
+                    ..rect
+                };
+
+                if let Err(error) = WindowsApi::position_window(hwnd, &moved, false) {
+                    tracing::warn!("could not move dragged window {hwnd}: {error}");
+                }
+            }
+            Notification::End => {
+                // From here on, the drop is handled exactly like a window which has been dragged
+                // by its title bar
+                if let Some((hwnd, _, _)) = drag.take() {
+                    send_event(WindowManagerEvent::MoveResizeEnd(
+                        WinEvent::SystemMoveSizeEnd,
+                        Window::from(hwnd),
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn focus_container(wm: &Arc<Mutex<WindowManager>>, hwnd: isize) -> color_eyre::Result<()> {
+    let mut wm = wm.lock();
+
+    let Some(monitor_idx) = wm.monitors().iter().position(|monitor| {
+        monitor
+            .focused_workspace()
+            .is_some_and(|workspace| workspace.contains_managed_window(hwnd))
+    }) else {
+        return Ok(());
+    };
+
+    if wm.focused_monitor_idx() != monitor_idx {
+        wm.focus_monitor(monitor_idx)?;
+    }
+
+    wm.focused_workspace_mut()?.focus_container_by_window(hwnd)
+}
+
+fn send_event(event: WindowManagerEvent) {
+    if let Err(error) = winevent_listener::event_tx().send(event) {
+        tracing::error!("could not send drag event: {error}");
+    }
+}
+
+fn modifier_is_held(modifier: DragModifier) -> bool {
+    match modifier {
+        DragModifier::Alt => WindowsApi::key_is_held(VK_MENU),
+        DragModifier::Ctrl => WindowsApi::key_is_held(VK_CONTROL),
+        DragModifier::Win => WindowsApi::key_is_held(VK_LWIN) || WindowsApi::key_is_held(VK_RWIN),
+    }
+}
+
+/// The tiled window under the point, if the drag modifier is being held
+fn draggable_window(point: POINT) -> Option<isize> {
+    let modifier = (*DRAG_MODIFIER.lock())?;
+
+    if !modifier_is_held(modifier) {
+        return None;
+    }
+
+    let hwnd = WindowsApi::root_window(WindowsApi::window_from_point(point).ok()?).ok()?;
+    TILED_HWNDS.lock().contains(&hwnd).then_some(hwnd)
+}
+
+/// Called from the low-level mouse hook; swallows the left button presses and releases which start
+/// and end a drag, so that the window being dragged never sees them
+pub fn handle_mouse_input(message: u32, event: &MSLLHOOKSTRUCT) -> bool {
+    let dragging = DRAGGING_HWND.load(Ordering::SeqCst) != 0;
+
+    if message == WM_LBUTTONDOWN && !dragging {
+        if let Some(hwnd) = draggable_window(event.pt) {
+            DRAGGING_HWND.store(hwnd, Ordering::SeqCst);
+            send_notification(Notification::Start(hwnd, event.pt));
+
+            return true;
+        }
+    } else if message == WM_MOUSEMOVE && dragging {
+        send_notification(Notification::Move(event.pt));
+    } else if message == WM_LBUTTONUP && dragging {
+        DRAGGING_HWND.store(0, Ordering::SeqCst);
+        send_notification(Notification::End);
+
+        return true;
+    }
+
+    false
+}
@@ -0,0 +1,194 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+mod drop_zone;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use crossbeam_utils::atomic::AtomicConsume;
+use parking_lot::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::core::Rect;
+use crate::drop_zone_manager::drop_zone::DropZone;
+use crate::WindowManager;
+use crate::WindowsApi;
+
+pub static DROP_ZONE_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// How often the cursor is checked against the layout while a window is being dragged
+const POLL_INTERVAL: Duration = Duration::from_millis(30);
+
+pub enum Notification {
+    /// A managed window has started being moved or resized with the mouse
+    Start(isize),
+    /// The window has been let go of
+    End,
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification(notification: Notification) {
+    if !DROP_ZONE_ENABLED.load_consume() {
+        return;
+    }
+
+    if event_tx().try_send(notification).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications(wm: Arc<Mutex<WindowManager>>) {
+    std::thread::spawn(move || loop {
+        match handle_notifications(wm.clone()) {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+    let drop_zone = DropZone::create()?;
+
+    // The window being dragged and its rect when the drag started
+    let mut dragging: Option<(isize, Rect)> = None;
+    let mut shown: Option<Rect> = None;
+
+    loop {
+        // There is no event for the cursor moving during a drag, so the position is polled until
+        // the window has been let go of
+        let notification = if dragging.is_some() {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(notification) => Some(notification),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            match receiver.recv() {
+                Ok(notification) => Some(notification),
+                Err(_) => break,
+            }
+        };
+
+        match notification {
+            Some(Notification::Start(hwnd)) => {
+                dragging = WindowsApi::window_rect(hwnd).ok().map(|rect| (hwnd, rect));
+            }
+            Some(Notification::End) => dragging = None,
+            None => {}
+        }
+
+        let target = match dragging {
+            Some((hwnd, initial)) => {
+                let wm = wm.lock();
+
+                // The end of a drag can be missed if the window was closed while it was held
+                if wm.pending_move_op.is_none() {
+                    dragging = None;
+                    None
+                } else {
+                    target_rect(&wm, hwnd, &initial)
+                }
+            }
+            None => None,
+        };
+
+        if target != shown {
+            match target {
+                Some(rect) => {
+                    if let Err(error) = drop_zone.show(&rect) {
+                        tracing::error!("could not show drop zone: {error}");
+                    }
+                }
+                None => drop_zone.hide(),
+            }
+
+            shown = target;
+        }
+    }
+
+    drop_zone.destroy()
+}
+
+/// Works out the layout slot that the dragged window will be placed in if it is let go of now,
+/// following the same rules as the handling of the end of the drag in the event loop
+fn target_rect(wm: &WindowManager, hwnd: isize, initial: &Rect) -> Option<Rect> {
+    let (origin_monitor_idx, origin_workspace_idx, _) = wm.pending_move_op?;
+
+    // A window which has changed size is being resized rather than moved
+    let current = WindowsApi::window_rect(hwnd).ok()?;
+    if current.right != initial.right || current.bottom != initial.bottom {
+        return None;
+    }
+
+    let origin_workspace = wm
+        .monitors()
+        .get(origin_monitor_idx)?
+        .workspaces()
+        .get(origin_workspace_idx)?;
+
+    if !origin_workspace.contains_managed_window(hwnd) {
+        return None;
+    }
+
+    let hmonitor = WindowsApi::monitor_from_point(WindowsApi::cursor_pos().ok()?);
+    let (monitor_idx, monitor) = wm
+        .monitors()
+        .iter()
+        .enumerate()
+        .find(|(_, monitor)| monitor.id() == hmonitor)?;
+
+    let workspace = monitor.focused_workspace()?;
+
+    if !*workspace.tile() {
+        return None;
+    }
+
+    if monitor_idx == origin_monitor_idx {
+        let behaviour =
+            wm.window_management_behaviour(monitor_idx, monitor.focused_workspace_idx());
+
+        if behaviour.float_override {
+            return None;
+        }
+
+        let container_idx = workspace.container_idx_from_current_point()?;
+        workspace.latest_layout().get(container_idx).copied()
+    } else {
+        // Windows dropped on another monitor are inserted at the container under the cursor, or
+        // fill the work area of an empty workspace
+        let container_idx = workspace.container_idx_from_current_point().unwrap_or(0);
+
+        workspace
+            .latest_layout()
+            .get(container_idx)
+            .copied()
+            .or_else(|| {
+                let mut work_area = *monitor.work_area_size();
+                work_area.add_padding(workspace.workspace_padding());
+                Some(work_area)
+            })
+    }
+}
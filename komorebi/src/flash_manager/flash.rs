@@ -0,0 +1,158 @@
+use crate::border_manager;
+use crate::flash_manager::FLASH_ALPHA;
+use crate::windows_api;
+use crate::WindowsApi;
+use crossbeam_utils::atomic::AtomicConsume;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::time::Duration;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::COLORREF;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::Graphics::Gdi::BeginPaint;
+use windows::Win32::Graphics::Gdi::EndPaint;
+use windows::Win32::Graphics::Gdi::GetStockObject;
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::Graphics::Gdi::Rectangle;
+use windows::Win32::Graphics::Gdi::SelectObject;
+use windows::Win32::Graphics::Gdi::SetDCBrushColor;
+use windows::Win32::Graphics::Gdi::SetDCPenColor;
+use windows::Win32::Graphics::Gdi::DC_BRUSH;
+use windows::Win32::Graphics::Gdi::DC_PEN;
+use windows::Win32::Graphics::Gdi::PAINTSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::DefWindowProcW;
+use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
+use windows::Win32::UI::WindowsAndMessaging::GetClientRect;
+use windows::Win32::UI::WindowsAndMessaging::GetMessageW;
+use windows::Win32::UI::WindowsAndMessaging::PostQuitMessage;
+use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
+use windows::Win32::UI::WindowsAndMessaging::CS_HREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::CS_VREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::WM_DESTROY;
+use windows::Win32::UI::WindowsAndMessaging::WM_PAINT;
+use windows::Win32::UI::WindowsAndMessaging::WNDCLASSW;
+
+#[derive(Debug)]
+pub struct Flash {
+    pub hwnd: isize,
+}
+
+impl Flash {
+    pub const fn hwnd(&self) -> HWND {
+        HWND(windows_api::as_ptr!(self.hwnd))
+    }
+
+    pub fn create() -> color_eyre::Result<Self> {
+        let name: Vec<u16> = "komoflash\0".encode_utf16().collect();
+        let class_name = PCWSTR(name.as_ptr());
+
+        let h_module = WindowsApi::module_handle_w()?;
+
+        let window_class = WNDCLASSW {
+            hInstance: h_module.into(),
+            lpszClassName: class_name,
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(Self::callback),
+            hbrBackground: WindowsApi::create_solid_brush(0),
+            ..Default::default()
+        };
+
+        let _ = WindowsApi::register_class_w(&window_class);
+
+        let (hwnd_sender, hwnd_receiver) = mpsc::channel();
+
+        let instance = h_module.0 as isize;
+        std::thread::spawn(move || -> color_eyre::Result<()> {
+            let hwnd = WindowsApi::create_border_window(PCWSTR(name.as_ptr()), instance)?;
+            hwnd_sender.send(hwnd)?;
+
+            let mut msg: MSG = MSG::default();
+
+            loop {
+                unsafe {
+                    if !GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {
+                        tracing::debug!("flash window event processing thread shutdown");
+                        break;
+                    };
+                    // TODO: error handling
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+
+                std::thread::sleep(Duration::from_millis(10))
+            }
+
+            Ok(())
+        });
+
+        Ok(Self {
+            hwnd: hwnd_receiver.recv()?,
+        })
+    }
+
+    pub fn destroy(&self) -> color_eyre::Result<()> {
+        WindowsApi::close_window(self.hwnd)
+    }
+
+    /// Covers the target window with a translucent fill of the focused border colour
+    pub fn show(&self, target: isize) -> color_eyre::Result<()> {
+        let rect = WindowsApi::window_rect(target)?;
+
+        // Unlike borders, the whole of the flash is see-through rather than just the parts which
+        // have not been drawn on, so the alpha replaces the colour key every time it is shown
+        WindowsApi::set_transparent(self.hwnd, FLASH_ALPHA.load_consume())?;
+        WindowsApi::position_window(self.hwnd, &rect, true)?;
+        WindowsApi::restore_window(self.hwnd);
+
+        let _ = unsafe { InvalidateRect(self.hwnd(), None, true) };
+
+        Ok(())
+    }
+
+    pub fn hide(&self) {
+        WindowsApi::hide_window(self.hwnd);
+    }
+
+    pub extern "system" fn callback(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        unsafe {
+            match message {
+                WM_PAINT => {
+                    let mut ps = PAINTSTRUCT::default();
+                    let hdc = BeginPaint(window, &mut ps);
+
+                    let mut rect = RECT::default();
+                    // TODO: error handling
+                    let _ = GetClientRect(window, &mut rect);
+
+                    let colour = border_manager::FOCUSED.load(Ordering::SeqCst);
+
+                    SelectObject(hdc, GetStockObject(DC_PEN));
+                    SelectObject(hdc, GetStockObject(DC_BRUSH));
+                    SetDCPenColor(hdc, COLORREF(colour));
+                    SetDCBrushColor(hdc, COLORREF(colour));
+                    // TODO: error handling
+                    let _ = Rectangle(hdc, rect.left, rect.top, rect.right, rect.bottom);
+
+                    // TODO: error handling
+                    let _ = EndPaint(window, &ps);
+                    LRESULT(0)
+                }
+                WM_DESTROY => {
+                    PostQuitMessage(0);
+                    LRESULT(0)
+                }
+                _ => DefWindowProcW(window, message, wparam, lparam),
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+mod flash;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use crossbeam_utils::atomic::AtomicConsume;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicU8;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::flash_manager::flash::Flash;
+use crate::WindowsApi;
+
+pub static FLASH_ENABLED: AtomicBool = AtomicBool::new(false);
+pub static FLASH_DURATION: AtomicU64 = AtomicU64::new(200);
+pub static FLASH_ALPHA: AtomicU8 = AtomicU8::new(64);
+
+pub struct Notification(pub isize);
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+/// Flashes a highlight over the window which has just been focused by a command
+pub fn send_notification(hwnd: isize) {
+    if !FLASH_ENABLED.load_consume() {
+        return;
+    }
+
+    if event_tx().try_send(Notification(hwnd)).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+    let flash = Flash::create()?;
+    let mut hide_at = None;
+
+    loop {
+        // A window which is focused while the previous one is still highlighted takes over the
+        // highlight straight away
+        let notification = match hide_at {
+            Some(deadline) => match receiver.recv_deadline(deadline) {
+                Ok(notification) => notification,
+                Err(RecvTimeoutError::Timeout) => {
+                    flash.hide();
+                    hide_at = None;
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match receiver.recv() {
+                Ok(notification) => notification,
+                Err(_) => break,
+            },
+        };
+
+        let Notification(hwnd) = notification;
+
+        if !WindowsApi::is_window(hwnd) {
+            continue;
+        }
+
+        if let Err(error) = flash.show(hwnd) {
+            tracing::error!("could not flash window {hwnd}: {error}");
+            continue;
+        }
+
+        hide_at = Some(Instant::now() + Duration::from_millis(FLASH_DURATION.load_consume()));
+    }
+
+    flash.destroy()
+}
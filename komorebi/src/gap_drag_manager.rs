@@ -0,0 +1,296 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use crossbeam_utils::atomic::AtomicConsume;
+use parking_lot::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+use windows::Win32::Foundation::POINT;
+use windows::Win32::UI::WindowsAndMessaging::MSLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::WM_LBUTTONDOWN;
+use windows::Win32::UI::WindowsAndMessaging::WM_LBUTTONUP;
+use windows::Win32::UI::WindowsAndMessaging::WM_MOUSEMOVE;
+
+use crate::core::Layout;
+use crate::core::OperationDirection;
+use crate::core::Rect;
+use crate::core::Sizing;
+use crate::scroll_manager::DESKTOP_CLASSES;
+use crate::State;
+use crate::WindowManager;
+use crate::WindowsApi;
+
+pub static GAP_DRAG_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Containers further apart than this are not treated as being next to each other
+const MAX_GAP: i32 = 64;
+
+/// The layout is only recalculated this often while a gap is being dragged
+const RESIZE_INTERVAL: Duration = Duration::from_millis(16);
+
+/// The gap between a container and the containers after it in the layout, which moves the right
+/// or the bottom edge of the container when it is dragged
+#[derive(Debug, Clone, Copy)]
+pub struct Gap {
+    monitor_idx: usize,
+    container_idx: usize,
+    /// Either `Right` for a gap between two columns, or `Down` for a gap between two rows
+    edge: OperationDirection,
+    rect: Rect,
+}
+
+impl Gap {
+    fn contains(&self, point: POINT) -> bool {
+        point.x >= self.rect.left
+            && point.x < self.rect.left + self.rect.right
+            && point.y >= self.rect.top
+            && point.y < self.rect.top + self.rect.bottom
+    }
+}
+
+/// The gaps between the containers of the layouts on screen; the hook can't wait on the window
+/// manager, so this is kept up to date from the state instead
+static GAPS: Mutex<Vec<Gap>> = Mutex::new(Vec::new());
+
+/// The gap which is being dragged by the hook, if any
+static DRAGGING_GAP: Mutex<Option<Gap>> = Mutex::new(None);
+
+pub enum Notification {
+    Start(Gap, POINT),
+    Move(POINT),
+    End(POINT),
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(100))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+fn send_notification(notification: Notification) {
+    if event_tx().try_send(notification).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn update_gaps(state: &State) {
+    let mut gaps = vec![];
+
+    for (monitor_idx, monitor) in state.monitors.elements().iter().enumerate() {
+        let Some(workspace) = monitor.focused_workspace() else {
+            continue;
+        };
+
+        if !*workspace.tile()
+            || !matches!(workspace.layout(), Layout::Default(_))
+            || workspace.monocle_container().is_some()
+            || workspace.maximized_window().is_some()
+        {
+            continue;
+        }
+
+        let layout = workspace.latest_layout();
+
+        for (container_idx, a) in layout.iter().enumerate() {
+            for b in layout.iter() {
+                let a_right = a.left + a.right;
+                let a_bottom = a.top + a.bottom;
+
+                // Side by side, with the rows that they share
+                let top = a.top.max(b.top);
+                let bottom = a_bottom.min(b.top + b.bottom);
+                let width = b.left - a_right;
+
+                if width > 0 && width <= MAX_GAP && bottom > top {
+                    gaps.push(Gap {
+                        monitor_idx,
+                        container_idx,
+                        edge: OperationDirection::Right,
+                        rect: Rect {
+                            left: a_right,
+                            top,
+                            right: width,
+                            bottom: bottom - top,
+                        },
+                    });
+                }
+
+                // One above the other, with the columns that they share
+                let left = a.left.max(b.left);
+                let right = a_right.min(b.left + b.right);
+                let height = b.top - a_bottom;
+
+                if height > 0 && height <= MAX_GAP && right > left {
+                    gaps.push(Gap {
+                        monitor_idx,
+                        container_idx,
+                        edge: OperationDirection::Down,
+                        rect: Rect {
+                            left,
+                            top: a_bottom,
+                            right: right - left,
+                            bottom: height,
+                        },
+                    });
+                }
+            }
+        }
+    }
+
+    *GAPS.lock() = gaps;
+}
+
+pub fn listen_for_notifications(wm: Arc<Mutex<WindowManager>>) {
+    std::thread::spawn(move || loop {
+        match handle_notifications(wm.clone()) {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    // The gap being dragged, where the cursor was when the layout was last resized to follow it,
+    // and when that was
+    let mut drag: Option<(Gap, POINT, Instant)> = None;
+
+    for notification in receiver {
+        match notification {
+            Notification::Start(gap, point) => {
+                drag = Some((gap, point, Instant::now()));
+            }
+            Notification::Move(point) => {
+                let Some((gap, last, resized_at)) = drag else {
+                    continue;
+                };
+
+                if resized_at.elapsed() < RESIZE_INTERVAL {
+                    continue;
+                }
+
+                if let Err(error) = resize(&wm, &gap, last, point) {
+                    tracing::warn!("could not resize containers by dragging a gap: {error}");
+                }
+
+                drag = Some((gap, point, Instant::now()));
+            }
+            Notification::End(point) => {
+                if let Some((gap, last, _)) = drag.take() {
+                    if let Err(error) = resize(&wm, &gap, last, point) {
+                        tracing::warn!("could not resize containers by dragging a gap: {error}");
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves the edge of the container next to the gap by however far the cursor has moved, which
+/// the layout makes up for by resizing the containers on the other side of the gap
+fn resize(
+    wm: &Arc<Mutex<WindowManager>>,
+    gap: &Gap,
+    from: POINT,
+    to: POINT,
+) -> color_eyre::Result<()> {
+    let distance = match gap.edge {
+        OperationDirection::Down => to.y - from.y,
+        _ => to.x - from.x,
+    };
+
+    if distance == 0 {
+        return Ok(());
+    }
+
+    // This matches the adjustment made when windows are resized by their borders
+    let adjusted = distance * 2;
+    let sizing = if adjusted < 0 {
+        Sizing::Decrease
+    } else {
+        Sizing::Increase
+    };
+
+    let mut wm = wm.lock();
+
+    if wm.focused_monitor_idx() != gap.monitor_idx {
+        wm.focus_monitor(gap.monitor_idx)?;
+    }
+
+    // Resizing works on the focused container, which is put back afterwards so that dragging a
+    // gap doesn't change focus
+    let workspace = wm.focused_workspace_mut()?;
+    let focused_idx = workspace.focused_container_idx();
+    workspace.focus_container(gap.container_idx);
+
+    let result = wm.resize_window(gap.edge, sizing, adjusted.abs(), false);
+    wm.focused_workspace_mut()?.focus_container(focused_idx);
+    result?;
+
+    wm.update_focused_workspace(false, false)
+}
+
+/// The gap under the point, as long as nothing else is on top of it
+fn draggable_gap(point: POINT) -> Option<Gap> {
+    let gap = GAPS
+        .lock()
+        .iter()
+        .find(|gap| gap.contains(point))
+        .copied()?;
+
+    // Only the desktop and komorebi's own borders are expected to show through the gaps; anything
+    // else, like a floating window, should get the click as usual
+    let hwnd = WindowsApi::root_window(WindowsApi::window_from_point(point).ok()?).ok()?;
+    let class = WindowsApi::real_window_class_w(hwnd).ok()?;
+
+    (DESKTOP_CLASSES.contains(&class.as_str()) || class.starts_with("komoborder")).then_some(gap)
+}
+
+/// Called from the low-level mouse hook; swallows the left button presses and releases which start
+/// and end dragging a gap
+pub fn handle_mouse_input(message: u32, event: &MSLLHOOKSTRUCT) -> bool {
+    if !GAP_DRAG_ENABLED.load_consume() {
+        return false;
+    }
+
+    let mut dragging = DRAGGING_GAP.lock();
+
+    if message == WM_LBUTTONDOWN && dragging.is_none() {
+        if let Some(gap) = draggable_gap(event.pt) {
+            *dragging = Some(gap);
+            send_notification(Notification::Start(gap, event.pt));
+
+            return true;
+        }
+    } else if message == WM_MOUSEMOVE && dragging.is_some() {
+        send_notification(Notification::Move(event.pt));
+    } else if message == WM_LBUTTONUP && dragging.is_some() {
+        *dragging = None;
+        send_notification(Notification::End(event.pt));
+
+        return true;
+    }
+
+    false
+}
@@ -0,0 +1,191 @@
+use crate::border_manager;
+use crate::core::Rect;
+use crate::stackbar_manager::STACKBAR_FOCUSED_TEXT_COLOUR;
+use crate::windows_api;
+use crate::WindowsApi;
+use crossbeam_utils::atomic::AtomicConsume;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::COLORREF;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::Graphics::Gdi::BeginPaint;
+use windows::Win32::Graphics::Gdi::CreateFontIndirectW;
+use windows::Win32::Graphics::Gdi::DeleteObject;
+use windows::Win32::Graphics::Gdi::DrawTextW;
+use windows::Win32::Graphics::Gdi::EndPaint;
+use windows::Win32::Graphics::Gdi::GetDeviceCaps;
+use windows::Win32::Graphics::Gdi::GetStockObject;
+use windows::Win32::Graphics::Gdi::RoundRect;
+use windows::Win32::Graphics::Gdi::SelectObject;
+use windows::Win32::Graphics::Gdi::SetBkColor;
+use windows::Win32::Graphics::Gdi::SetDCBrushColor;
+use windows::Win32::Graphics::Gdi::SetDCPenColor;
+use windows::Win32::Graphics::Gdi::SetTextColor;
+use windows::Win32::Graphics::Gdi::DC_BRUSH;
+use windows::Win32::Graphics::Gdi::DC_PEN;
+use windows::Win32::Graphics::Gdi::DT_CENTER;
+use windows::Win32::Graphics::Gdi::DT_SINGLELINE;
+use windows::Win32::Graphics::Gdi::DT_VCENTER;
+use windows::Win32::Graphics::Gdi::FONT_QUALITY;
+use windows::Win32::Graphics::Gdi::FW_BOLD;
+use windows::Win32::Graphics::Gdi::LOGFONTW;
+use windows::Win32::Graphics::Gdi::LOGPIXELSY;
+use windows::Win32::Graphics::Gdi::PAINTSTRUCT;
+use windows::Win32::Graphics::Gdi::PROOF_QUALITY;
+use windows::Win32::System::WindowsProgramming::MulDiv;
+use windows::Win32::UI::WindowsAndMessaging::DefWindowProcW;
+use windows::Win32::UI::WindowsAndMessaging::DestroyWindow;
+use windows::Win32::UI::WindowsAndMessaging::CS_HREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::CS_VREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::WM_PAINT;
+use windows::Win32::UI::WindowsAndMessaging::WNDCLASSW;
+
+const SIZE: i32 = 48;
+const FONT_SIZE: i32 = 20;
+
+lazy_static! {
+    /// The label drawn by each hint window
+    static ref HINT_LABELS: Mutex<HashMap<isize, char>> = Mutex::new(HashMap::new());
+}
+
+/// A label drawn in the middle of a window, which is created on the thread that dispatches its
+/// messages
+#[derive(Debug)]
+pub struct Hint {
+    pub hwnd: isize,
+    pub label: char,
+    /// The window which is focused when the label is typed
+    pub target: isize,
+}
+
+impl Hint {
+    pub const fn hwnd(&self) -> HWND {
+        HWND(windows_api::as_ptr!(self.hwnd))
+    }
+
+    pub fn create(label: char, target: isize) -> color_eyre::Result<Self> {
+        let window_rect = WindowsApi::window_rect(target)?;
+        let rect = Rect {
+            left: window_rect.left + (window_rect.right - SIZE) / 2,
+            top: window_rect.top + (window_rect.bottom - SIZE) / 2,
+            right: SIZE,
+            bottom: SIZE,
+        };
+
+        let name: Vec<u16> = "komohint\0".encode_utf16().collect();
+        let class_name = PCWSTR(name.as_ptr());
+
+        let h_module = WindowsApi::module_handle_w()?;
+
+        let window_class = WNDCLASSW {
+            hInstance: h_module.into(),
+            lpszClassName: class_name,
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(Self::callback),
+            hbrBackground: WindowsApi::create_solid_brush(0),
+            ..Default::default()
+        };
+
+        let _ = WindowsApi::register_class_w(&window_class);
+
+        let hwnd = WindowsApi::create_border_window(class_name, h_module.0 as isize)?;
+        HINT_LABELS.lock().insert(hwnd, label);
+
+        let hint = Self {
+            hwnd,
+            label,
+            target,
+        };
+
+        if let Err(error) = WindowsApi::position_window(hwnd, &rect, true) {
+            hint.destroy();
+            return Err(error);
+        }
+
+        WindowsApi::restore_window(hwnd);
+
+        Ok(hint)
+    }
+
+    pub fn destroy(self) {
+        HINT_LABELS.lock().remove(&self.hwnd);
+        // TODO: error handling
+        let _ = unsafe { DestroyWindow(self.hwnd()) };
+    }
+
+    pub extern "system" fn callback(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        unsafe {
+            match message {
+                WM_PAINT => {
+                    let mut ps = PAINTSTRUCT::default();
+                    let hdc = BeginPaint(window, &mut ps);
+
+                    if let Some(label) = HINT_LABELS.lock().get(&(window.0 as isize)) {
+                        let background = border_manager::FOCUSED.load(Ordering::SeqCst);
+                        let text_colour = STACKBAR_FOCUSED_TEXT_COLOUR.load_consume();
+
+                        let logfont = LOGFONTW {
+                            lfWeight: FW_BOLD.0 as i32,
+                            lfQuality: FONT_QUALITY(PROOF_QUALITY.0),
+                            lfHeight: -MulDiv(FONT_SIZE, GetDeviceCaps(hdc, LOGPIXELSY), 72),
+                            ..Default::default()
+                        };
+
+                        let hfont = CreateFontIndirectW(&logfont);
+                        let previous_font = SelectObject(hdc, hfont);
+
+                        SelectObject(hdc, GetStockObject(DC_PEN));
+                        SelectObject(hdc, GetStockObject(DC_BRUSH));
+                        SetDCPenColor(hdc, COLORREF(background));
+                        SetDCBrushColor(hdc, COLORREF(background));
+                        // TODO: error handling
+                        let _ = RoundRect(hdc, 0, 0, SIZE, SIZE, 12, 12);
+
+                        SetBkColor(hdc, COLORREF(background));
+                        SetTextColor(hdc, COLORREF(text_colour));
+
+                        let mut text: Vec<u16> = label
+                            .to_ascii_uppercase()
+                            .to_string()
+                            .encode_utf16()
+                            .collect();
+                        let mut rect = RECT {
+                            left: 0,
+                            top: 0,
+                            right: SIZE,
+                            bottom: SIZE,
+                        };
+
+                        DrawTextW(
+                            hdc,
+                            &mut text,
+                            &mut rect,
+                            DT_SINGLELINE | DT_CENTER | DT_VCENTER,
+                        );
+
+                        SelectObject(hdc, previous_font);
+                        // TODO: error handling
+                        let _ = DeleteObject(hfont);
+                    }
+
+                    // TODO: error handling
+                    let _ = EndPaint(window, &ps);
+                    LRESULT(0)
+                }
+                _ => DefWindowProcW(window, message, wparam, lparam),
+            }
+        }
+    }
+}
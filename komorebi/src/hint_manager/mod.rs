@@ -0,0 +1,188 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+mod hint;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::sync::OnceLock;
+use windows::Win32::Foundation::HINSTANCE;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_ESCAPE;
+use windows::Win32::UI::WindowsAndMessaging::CallNextHookEx;
+use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
+use windows::Win32::UI::WindowsAndMessaging::GetMessageW;
+use windows::Win32::UI::WindowsAndMessaging::KillTimer;
+use windows::Win32::UI::WindowsAndMessaging::PostQuitMessage;
+use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+use windows::Win32::UI::WindowsAndMessaging::SetWindowsHookExW;
+use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
+use windows::Win32::UI::WindowsAndMessaging::UnhookWindowsHookEx;
+use windows::Win32::UI::WindowsAndMessaging::HHOOK;
+use windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::WH_KEYBOARD_LL;
+use windows::Win32::UI::WindowsAndMessaging::WM_KEYDOWN;
+use windows::Win32::UI::WindowsAndMessaging::WM_SYSKEYDOWN;
+use windows::Win32::UI::WindowsAndMessaging::WM_TIMER;
+
+use crate::flash_manager;
+use crate::hint_manager::hint::Hint;
+use crate::Window;
+use crate::WindowsApi;
+
+/// Labels are handed out in this order, so that the first windows get the keys under the fingers
+const LABELS: &str = "asdfghjklqwertyuiopzxcvbnm";
+
+/// Hints which have not been used are taken down after this long
+const TIMEOUT_MS: u32 = 5000;
+
+/// The labels on screen and the windows that they belong to, which the keyboard hook matches keys
+/// against
+static ACTIVE_HINTS: Mutex<Vec<(char, isize)>> = Mutex::new(Vec::new());
+static SELECTED_HWND: Mutex<Option<isize>> = Mutex::new(None);
+
+pub struct Notification {
+    hwnds: Vec<isize>,
+    mouse_follows_focus: bool,
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(1))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification(hwnds: Vec<isize>, mouse_follows_focus: bool) {
+    if event_tx()
+        .try_send(Notification {
+            hwnds,
+            mouse_follows_focus,
+        })
+        .is_err()
+    {
+        tracing::warn!("hints are already being shown; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    for notification in receiver {
+        let mut hints = vec![];
+
+        for (label, hwnd) in LABELS.chars().zip(notification.hwnds) {
+            match Hint::create(label, hwnd) {
+                Ok(hint) => hints.push(hint),
+                Err(error) => tracing::warn!("could not show hint for window {hwnd}: {error}"),
+            }
+        }
+
+        if hints.is_empty() {
+            continue;
+        }
+
+        *ACTIVE_HINTS.lock() = hints.iter().map(|hint| (hint.label, hint.target)).collect();
+        *SELECTED_HWND.lock() = None;
+
+        let hook = unsafe {
+            SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook), HINSTANCE::default(), 0)
+        };
+
+        match hook {
+            Ok(hook) => unsafe {
+                // The hook, the timer and the hint windows all belong to this thread, so their
+                // messages are dispatched here until a key has been pressed or the timer has fired
+                let timer = SetTimer(HWND::default(), 0, TIMEOUT_MS, None);
+                let mut msg = MSG::default();
+
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {
+                    if msg.message == WM_TIMER && msg.wParam.0 == timer {
+                        break;
+                    }
+
+                    // TODO: error handling
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+
+                // TODO: error handling
+                let _ = KillTimer(HWND::default(), timer);
+                // TODO: error handling
+                let _ = UnhookWindowsHookEx(hook);
+            },
+            Err(error) => tracing::error!("could not install keyboard hook for hints: {error}"),
+        }
+
+        ACTIVE_HINTS.lock().clear();
+
+        for hint in hints {
+            hint.destroy();
+        }
+
+        if let Some(hwnd) = SELECTED_HWND.lock().take() {
+            // The focus change is picked up by the event loop, which updates the focused
+            // monitor, workspace and container to match
+            if WindowsApi::is_window(hwnd) {
+                Window::from(hwnd).focus(notification.mouse_follows_focus)?;
+                flash_manager::send_notification(hwnd);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Swallows letters and escape while hints are on screen, taking them down as soon as one of them
+/// has been pressed; any other key is passed through
+unsafe extern "system" fn keyboard_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let is_key_down = wparam.0 == WM_KEYDOWN as usize || wparam.0 == WM_SYSKEYDOWN as usize;
+
+    if code >= 0 && is_key_down {
+        let event = unsafe { &*(lparam.0 as *const KBDLLHOOKSTRUCT) };
+
+        // The virtual key codes of letters are the same as their uppercase ASCII codes
+        let key = char::from_u32(event.vkCode)
+            .filter(char::is_ascii_uppercase)
+            .map(|key| key.to_ascii_lowercase());
+
+        if key.is_some() || event.vkCode == u32::from(VK_ESCAPE.0) {
+            *SELECTED_HWND.lock() = ACTIVE_HINTS
+                .lock()
+                .iter()
+                .find(|(label, _)| Some(*label) == key)
+                .map(|(_, hwnd)| *hwnd);
+
+            unsafe { PostQuitMessage(0) };
+            return LRESULT(1);
+        }
+    }
+
+    unsafe { CallNextHookEx(HHOOK::default(), code, wparam, lparam) }
+}
@@ -0,0 +1,123 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::io::Write;
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::OnceLock;
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+use crate::Hook;
+use crate::HookEvent;
+
+static HOOKS: OnceLock<Mutex<Vec<Hook>>> = OnceLock::new();
+
+pub struct Notification {
+    event: HookEvent,
+    payload: String,
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn hooks() -> Vec<Hook> {
+    HOOKS.get_or_init(|| Mutex::new(Vec::new())).lock().clone()
+}
+
+pub fn set_hooks(hooks: Vec<Hook>) {
+    *HOOKS.get_or_init(|| Mutex::new(Vec::new())).lock() = hooks;
+}
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification(event: HookEvent, payload: String) {
+    if !HOOKS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .iter()
+        .any(|hook| hook.event == event)
+    {
+        return;
+    }
+
+    if event_tx()
+        .try_send(Notification { event, payload })
+        .is_err()
+    {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    for notification in receiver {
+        for hook in hooks() {
+            if hook.event != notification.event {
+                continue;
+            }
+
+            tracing::info!("running {} hook: {}", notification.event, hook.command);
+
+            let mut child = match Command::new(&hook.command)
+                .args(hook.args.as_deref().unwrap_or_default())
+                .env("KOMOREBI_HOOK", notification.event.to_string())
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .creation_flags(CREATE_NO_WINDOW.0)
+                .spawn()
+            {
+                Ok(child) => child,
+                Err(error) => {
+                    tracing::error!("could not run {} hook: {error}", notification.event);
+                    continue;
+                }
+            };
+
+            let payload = notification.payload.clone();
+
+            // Hooks should never be able to hold up the handling of subsequent events
+            std::thread::spawn(move || {
+                if let Some(mut stdin) = child.stdin.take() {
+                    if let Err(error) = stdin.write_all(payload.as_bytes()) {
+                        tracing::warn!("could not write event to hook stdin: {error}");
+                    }
+                }
+
+                if let Err(error) = child.wait() {
+                    tracing::warn!("could not wait for hook to exit: {error}");
+                }
+            });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,175 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+use windows::Win32::Foundation::POINT;
+use windows::Win32::Graphics::Gdi::MonitorFromPoint;
+use windows::Win32::Graphics::Gdi::MONITOR_DEFAULTTONEAREST;
+use windows::Win32::UI::WindowsAndMessaging::MSLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::WM_MOUSEMOVE;
+
+use crate::core::ScreenEdge;
+use crate::process_command;
+use crate::HotEdge;
+use crate::WindowsApi;
+
+/// How long in ms the cursor has to rest on a hot edge if no dwell time has been configured for it
+const DEFAULT_DWELL: u64 = 500;
+
+static HOT_EDGES: Mutex<Vec<HotEdge>> = Mutex::new(Vec::new());
+
+/// The edge or corner that the cursor was on when it last moved, so that the hook only has to
+/// send a notification when the cursor arrives at or leaves an edge
+static CURSOR_EDGE: Mutex<Option<ScreenEdge>> = Mutex::new(None);
+
+/// The cursor has arrived at an edge or corner of a monitor, or left it if there is none
+pub struct Notification(Option<ScreenEdge>);
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn hot_edges() -> Vec<HotEdge> {
+    HOT_EDGES.lock().clone()
+}
+
+pub fn set_hot_edges(hot_edges: Vec<HotEdge>) {
+    *HOT_EDGES.lock() = hot_edges;
+}
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+fn send_notification(notification: Notification) {
+    if event_tx().try_send(notification).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    // The hot edge that the cursor is resting on, and when its command is due to be run
+    let mut resting: Option<(HotEdge, Instant)> = None;
+
+    loop {
+        let notification = match &resting {
+            Some((_, due)) => {
+                match receiver.recv_timeout(due.saturating_duration_since(Instant::now())) {
+                    Ok(notification) => Some(notification),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            None => match receiver.recv() {
+                Ok(notification) => Some(notification),
+                Err(_) => break,
+            },
+        };
+
+        match notification {
+            Some(Notification(edge)) => {
+                // Only the first hot edge configured for an edge or corner is used
+                resting = edge.and_then(|edge| {
+                    hot_edges()
+                        .into_iter()
+                        .find(|hot_edge| hot_edge.edge == edge)
+                        .map(|hot_edge| {
+                            let dwell =
+                                Duration::from_millis(hot_edge.dwell.unwrap_or(DEFAULT_DWELL));
+
+                            (hot_edge, Instant::now() + dwell)
+                        })
+                });
+            }
+            None => {
+                // The command is only run once for every time that the cursor arrives at the edge
+                if let Some((hot_edge, _)) = resting.take() {
+                    tracing::info!("running {} hot edge command", hot_edge.edge);
+
+                    match process_command::submit_command(hot_edge.command) {
+                        Ok((_, Ok(()))) => {}
+                        Ok((_, Err(error))) | Err(error) => {
+                            tracing::error!(
+                                "could not run {} hot edge command: {error}",
+                                hot_edge.edge
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The edge or corner of its monitor that the point is on, if any; points can be past the edge of
+/// the monitor when the cursor is being pushed against it
+fn edge_at_point(point: POINT) -> Option<ScreenEdge> {
+    let hmonitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) };
+    let rect = WindowsApi::monitor_info_w(hmonitor)
+        .ok()?
+        .monitorInfo
+        .rcMonitor;
+
+    let top = point.y <= rect.top;
+    let bottom = point.y >= rect.bottom - 1;
+    let left = point.x <= rect.left;
+    let right = point.x >= rect.right - 1;
+
+    match (top, bottom, left, right) {
+        (true, _, true, _) => Some(ScreenEdge::TopLeft),
+        (true, _, _, true) => Some(ScreenEdge::TopRight),
+        (_, true, true, _) => Some(ScreenEdge::BottomLeft),
+        (_, true, _, true) => Some(ScreenEdge::BottomRight),
+        (true, _, _, _) => Some(ScreenEdge::Top),
+        (_, true, _, _) => Some(ScreenEdge::Bottom),
+        (_, _, true, _) => Some(ScreenEdge::Left),
+        (_, _, _, true) => Some(ScreenEdge::Right),
+        _ => None,
+    }
+}
+
+/// Called from the low-level mouse hook; watches the cursor arriving at and leaving the edges and
+/// corners of monitors, and never swallows any mouse input
+pub fn handle_mouse_input(message: u32, event: &MSLLHOOKSTRUCT) -> bool {
+    if message == WM_MOUSEMOVE && !HOT_EDGES.lock().is_empty() {
+        let edge = edge_at_point(event.pt);
+
+        let mut cursor_edge = CURSOR_EDGE.lock();
+        if *cursor_edge != edge {
+            *cursor_edge = edge;
+            send_notification(Notification(edge));
+        }
+    }
+
+    false
+}
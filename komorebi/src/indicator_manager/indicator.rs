@@ -0,0 +1,257 @@
+use crate::border_manager;
+use crate::core::Rect;
+use crate::indicator_manager::IndicatorContent;
+use crate::indicator_manager::IndicatorKind;
+use crate::stackbar_manager::STACKBAR_FOCUSED_TEXT_COLOUR;
+use crate::stackbar_manager::STACKBAR_FONT_FAMILY;
+use crate::styles::ExtendedWindowStyle;
+use crate::windows_api;
+use crate::Window;
+use crate::WindowsApi;
+use crossbeam_utils::atomic::AtomicConsume;
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::time::Duration;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::COLORREF;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::Graphics::Gdi::BeginPaint;
+use windows::Win32::Graphics::Gdi::CreateFontIndirectW;
+use windows::Win32::Graphics::Gdi::DeleteObject;
+use windows::Win32::Graphics::Gdi::DrawTextW;
+use windows::Win32::Graphics::Gdi::EndPaint;
+use windows::Win32::Graphics::Gdi::GetDeviceCaps;
+use windows::Win32::Graphics::Gdi::GetStockObject;
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::Graphics::Gdi::RoundRect;
+use windows::Win32::Graphics::Gdi::SelectObject;
+use windows::Win32::Graphics::Gdi::SetBkColor;
+use windows::Win32::Graphics::Gdi::SetDCBrushColor;
+use windows::Win32::Graphics::Gdi::SetDCPenColor;
+use windows::Win32::Graphics::Gdi::SetTextColor;
+use windows::Win32::Graphics::Gdi::DC_BRUSH;
+use windows::Win32::Graphics::Gdi::DC_PEN;
+use windows::Win32::Graphics::Gdi::DT_CENTER;
+use windows::Win32::Graphics::Gdi::DT_SINGLELINE;
+use windows::Win32::Graphics::Gdi::DT_VCENTER;
+use windows::Win32::Graphics::Gdi::FONT_QUALITY;
+use windows::Win32::Graphics::Gdi::FW_BOLD;
+use windows::Win32::Graphics::Gdi::LOGFONTW;
+use windows::Win32::Graphics::Gdi::LOGPIXELSY;
+use windows::Win32::Graphics::Gdi::PAINTSTRUCT;
+use windows::Win32::Graphics::Gdi::PROOF_QUALITY;
+use windows::Win32::System::WindowsProgramming::MulDiv;
+use windows::Win32::UI::WindowsAndMessaging::DefWindowProcW;
+use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
+use windows::Win32::UI::WindowsAndMessaging::GetMessageW;
+use windows::Win32::UI::WindowsAndMessaging::PostQuitMessage;
+use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
+use windows::Win32::UI::WindowsAndMessaging::CS_HREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::CS_VREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::WM_DESTROY;
+use windows::Win32::UI::WindowsAndMessaging::WM_PAINT;
+use windows::Win32::UI::WindowsAndMessaging::WNDCLASSW;
+
+const WIDTH: i32 = 120;
+const HEIGHT: i32 = 28;
+const MARGIN: i32 = 8;
+const FONT_SIZE: i32 = 10;
+
+lazy_static! {
+    /// The label and fill colour drawn by each indicator window
+    static ref INDICATOR_LABELS: Mutex<HashMap<isize, (String, u32)>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug)]
+pub struct Indicator {
+    pub hwnd: isize,
+}
+
+impl Indicator {
+    pub const fn hwnd(&self) -> HWND {
+        HWND(windows_api::as_ptr!(self.hwnd))
+    }
+
+    pub fn create() -> color_eyre::Result<Self> {
+        let name: Vec<u16> = "komoindicator\0".encode_utf16().collect();
+        let class_name = PCWSTR(name.as_ptr());
+
+        let h_module = WindowsApi::module_handle_w()?;
+
+        let window_class = WNDCLASSW {
+            hInstance: h_module.into(),
+            lpszClassName: class_name,
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(Self::callback),
+            hbrBackground: WindowsApi::create_solid_brush(0),
+            ..Default::default()
+        };
+
+        let _ = WindowsApi::register_class_w(&window_class);
+
+        let (hwnd_sender, hwnd_receiver) = mpsc::channel();
+
+        let instance = h_module.0 as isize;
+        std::thread::spawn(move || -> color_eyre::Result<()> {
+            let hwnd = WindowsApi::create_border_window(PCWSTR(name.as_ptr()), instance)?;
+
+            // The indicator sits over the title bar of the monocle or maximized window, which
+            // should still receive clicks made on it
+            let window = Window::from(hwnd);
+            let mut ex_style = window.ex_style()?;
+            ex_style.insert(ExtendedWindowStyle::TRANSPARENT);
+            window.update_ex_style(&ex_style)?;
+
+            hwnd_sender.send(hwnd)?;
+
+            let mut msg: MSG = MSG::default();
+
+            loop {
+                unsafe {
+                    if !GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {
+                        tracing::debug!("indicator window event processing thread shutdown");
+                        break;
+                    };
+                    // TODO: error handling
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+
+                std::thread::sleep(Duration::from_millis(10))
+            }
+
+            Ok(())
+        });
+
+        Ok(Self {
+            hwnd: hwnd_receiver.recv()?,
+        })
+    }
+
+    pub fn destroy(&self) -> color_eyre::Result<()> {
+        INDICATOR_LABELS.lock().remove(&self.hwnd);
+        WindowsApi::close_window(self.hwnd)
+    }
+
+    /// Shows the indicator at the top of the work area in the content, filled with
+    /// the monocle border colour for monocle containers and the focused border colour for
+    /// maximized windows
+    pub fn show(&self, content: &IndicatorContent) -> color_eyre::Result<()> {
+        let (kind, fill) = match content.kind {
+            IndicatorKind::Monocle => ("monocle", border_manager::MONOCLE.load(Ordering::SeqCst)),
+            IndicatorKind::Maximized => {
+                ("maximized", border_manager::FOCUSED.load(Ordering::SeqCst))
+            }
+        };
+
+        let label = if content.hidden == 0 {
+            kind.to_string()
+        } else {
+            format!("{kind} +{}", content.hidden)
+        };
+
+        INDICATOR_LABELS.lock().insert(self.hwnd, (label, fill));
+
+        let work_area = content.work_area;
+        let rect = Rect {
+            left: work_area.left + (work_area.right - WIDTH) / 2,
+            top: work_area.top + MARGIN,
+            right: WIDTH,
+            bottom: HEIGHT,
+        };
+
+        WindowsApi::position_window(self.hwnd, &rect, true)?;
+        WindowsApi::restore_window(self.hwnd);
+
+        let _ = unsafe { InvalidateRect(self.hwnd(), None, true) };
+
+        Ok(())
+    }
+
+    pub fn hide(&self) {
+        WindowsApi::hide_window(self.hwnd);
+    }
+
+    pub extern "system" fn callback(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        unsafe {
+            match message {
+                WM_PAINT => {
+                    let mut ps = PAINTSTRUCT::default();
+                    let hdc = BeginPaint(window, &mut ps);
+
+                    if let Some((label, fill)) = INDICATOR_LABELS.lock().get(&(window.0 as isize)) {
+                        let text_colour = STACKBAR_FOCUSED_TEXT_COLOUR.load_consume();
+
+                        let mut logfont = LOGFONTW {
+                            lfWeight: FW_BOLD.0 as i32,
+                            lfQuality: FONT_QUALITY(PROOF_QUALITY.0),
+                            lfHeight: -MulDiv(FONT_SIZE, GetDeviceCaps(hdc, LOGPIXELSY), 72),
+                            ..Default::default()
+                        };
+
+                        if let Some(font_name) = &*STACKBAR_FONT_FAMILY.lock() {
+                            // The last element is left as the nul terminator
+                            for (i, c) in font_name.encode_utf16().take(31).enumerate() {
+                                logfont.lfFaceName[i] = c;
+                            }
+                        }
+
+                        let hfont = CreateFontIndirectW(&logfont);
+                        let previous_font = SelectObject(hdc, hfont);
+
+                        SelectObject(hdc, GetStockObject(DC_PEN));
+                        SelectObject(hdc, GetStockObject(DC_BRUSH));
+                        SetDCPenColor(hdc, COLORREF(*fill));
+                        SetDCBrushColor(hdc, COLORREF(*fill));
+                        // TODO: error handling
+                        let _ = RoundRect(hdc, 0, 0, WIDTH, HEIGHT, 12, 12);
+
+                        SetBkColor(hdc, COLORREF(*fill));
+                        SetTextColor(hdc, COLORREF(text_colour));
+
+                        let mut text: Vec<u16> = label.encode_utf16().collect();
+                        let mut rect = RECT {
+                            left: 0,
+                            top: 0,
+                            right: WIDTH,
+                            bottom: HEIGHT,
+                        };
+
+                        DrawTextW(
+                            hdc,
+                            &mut text,
+                            &mut rect,
+                            DT_SINGLELINE | DT_CENTER | DT_VCENTER,
+                        );
+
+                        SelectObject(hdc, previous_font);
+                        // TODO: error handling
+                        let _ = DeleteObject(hfont);
+                    }
+
+                    // TODO: error handling
+                    let _ = EndPaint(window, &ps);
+                    LRESULT(0)
+                }
+                WM_DESTROY => {
+                    PostQuitMessage(0);
+                    LRESULT(0)
+                }
+                _ => DefWindowProcW(window, message, wparam, lparam),
+            }
+        }
+    }
+}
@@ -0,0 +1,141 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+mod indicator;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use crossbeam_utils::atomic::AtomicConsume;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::OnceLock;
+
+use crate::core::Rect;
+use crate::indicator_manager::indicator::Indicator;
+use crate::State;
+
+pub static INDICATOR_ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndicatorKind {
+    Monocle,
+    Maximized,
+}
+
+/// What the indicator of a single monitor shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndicatorContent {
+    work_area: Rect,
+    kind: IndicatorKind,
+    /// The number of tiled containers hidden behind the monocle or maximized window
+    hidden: usize,
+}
+
+/// The indicator content for each monitor whose focused workspace has a monocle container or a
+/// maximized window
+pub struct Notification(HashMap<usize, IndicatorContent>);
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_state_notifications(state: &State) {
+    // An empty notification is still sent when the indicators have been disabled, so that any
+    // which are on screen are taken down
+    let enabled = INDICATOR_ENABLED.load_consume();
+    let mut contents = HashMap::new();
+
+    for (monitor_idx, monitor) in state.monitors.elements().iter().enumerate() {
+        let Some(workspace) = monitor.focused_workspace() else {
+            continue;
+        };
+
+        let kind = if workspace.monocle_container().is_some() {
+            IndicatorKind::Monocle
+        } else if workspace.maximized_window().is_some() {
+            IndicatorKind::Maximized
+        } else {
+            continue;
+        };
+
+        if enabled {
+            contents.insert(
+                monitor_idx,
+                IndicatorContent {
+                    work_area: *monitor.work_area_size(),
+                    kind,
+                    hidden: workspace.containers().len(),
+                },
+            );
+        }
+    }
+
+    if event_tx().try_send(Notification(contents)).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+    let mut indicators: HashMap<usize, Indicator> = HashMap::new();
+    let mut shown: HashMap<usize, IndicatorContent> = HashMap::new();
+
+    for Notification(contents) in receiver {
+        for (monitor_idx, indicator) in &indicators {
+            if !contents.contains_key(monitor_idx) && shown.remove(monitor_idx).is_some() {
+                indicator.hide();
+            }
+        }
+
+        for (monitor_idx, content) in contents {
+            // Most notifications don't change what an indicator shows, so the window is only
+            // touched when they do
+            if shown.get(&monitor_idx) == Some(&content) {
+                continue;
+            }
+
+            let indicator = match indicators.entry(monitor_idx) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(Indicator::create()?),
+            };
+
+            if let Err(error) = indicator.show(&content) {
+                tracing::error!("could not show indicator on monitor {monitor_idx}: {error}");
+                continue;
+            }
+
+            shown.insert(monitor_idx, content);
+        }
+    }
+
+    for indicator in indicators.values() {
+        indicator.destroy()?;
+    }
+
+    Ok(())
+}
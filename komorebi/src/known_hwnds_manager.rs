@@ -0,0 +1,105 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::DATA_DIR;
+
+/// The minimum interval between two writes of komorebi.hwnd.json
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+pub struct Notification(Vec<isize>);
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification(known_hwnds: Vec<isize>) {
+    if event_tx().try_send(Notification(known_hwnds)).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+    let mut last_written = None;
+
+    while let Ok(notification) = receiver.recv() {
+        let mut known_hwnds = notification.0;
+
+        // Bursts of events (eg. focus changes) are collapsed into a single write of the most
+        // recent list once the debounce interval has elapsed
+        let deadline = Instant::now() + DEBOUNCE;
+
+        loop {
+            match receiver.recv_deadline(deadline) {
+                Ok(notification) => known_hwnds = notification.0,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        if last_written.as_ref() == Some(&known_hwnds) {
+            continue;
+        }
+
+        tracing::trace!("updating list of known hwnds");
+        write_atomically(&known_hwnds)?;
+        last_written = Some(known_hwnds);
+    }
+
+    Ok(())
+}
+
+// Readers should never be able to observe a truncated or partially written file, so the list is
+// written to a temporary file first which then replaces komorebi.hwnd.json in a single rename
+fn write_atomically(known_hwnds: &[isize]) -> color_eyre::Result<()> {
+    let hwnd_json = DATA_DIR.join("komorebi.hwnd.json");
+    let tmp_json = DATA_DIR.join("komorebi.hwnd.json.tmp");
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .create(true)
+        .open(&tmp_json)?;
+
+    serde_json::to_writer_pretty(&file, known_hwnds)?;
+    file.flush()?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(tmp_json, hwnd_json)?;
+
+    Ok(())
+}
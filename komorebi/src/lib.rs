@@ -9,18 +9,39 @@ pub mod ring;
 pub mod colour;
 pub mod container;
 pub mod core;
+pub mod drag_manager;
+pub mod drop_zone_manager;
+pub mod flash_manager;
 pub mod focus_manager;
+pub mod gap_drag_manager;
+pub mod hint_manager;
+pub mod hook_manager;
+pub mod hot_edge_manager;
+pub mod indicator_manager;
+pub mod known_hwnds_manager;
+pub mod location_change_manager;
+pub mod metrics;
 pub mod monitor;
 pub mod monitor_reconciliator;
+pub mod mouse_listener;
+pub mod osd_manager;
 pub mod process_command;
 pub mod process_event;
 pub mod process_movement;
 pub mod reaper;
+pub mod scroll_manager;
+pub mod session;
+pub mod session_manager;
 pub mod set_window_position;
+pub mod shutdown;
+pub mod splash_screen_manager;
 pub mod stackbar_manager;
 pub mod static_config;
 pub mod styles;
+pub mod switcher_manager;
+pub mod system_api;
 pub mod theme_manager;
+pub mod toast_manager;
 pub mod transparency_manager;
 pub mod window;
 pub mod window_manager;
@@ -34,6 +55,7 @@ pub mod workspace_reconciliator;
 
 use lazy_static::lazy_static;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::fs::File;
 use std::io::Write;
@@ -46,6 +68,10 @@ use std::sync::atomic::AtomicU32;
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
 
 pub use animation::*;
 pub use animation_manager::*;
@@ -71,6 +97,9 @@ use regex::Regex;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
+use tracing_subscriber::reload;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Registry;
 use uds_windows::UnixStream;
 use which::which;
 use winreg::enums::HKEY_CURRENT_USER;
@@ -78,6 +107,26 @@ use winreg::RegKey;
 
 lazy_static! {
     static ref HIDDEN_HWNDS: Arc<Mutex<Vec<isize>>> = Arc::new(Mutex::new(vec![]));
+    // Windows which have flashed to request attention and have not been focused since
+    static ref URGENT_HWNDS: Arc<Mutex<HashSet<isize>>> = Arc::new(Mutex::new(HashSet::new()));
+    // Windows of elevated processes which cannot be managed while komorebi is running unelevated
+    static ref ELEVATED_HWNDS: Arc<Mutex<HashSet<isize>>> = Arc::new(Mutex::new(HashSet::new()));
+    // Consecutive layout updates in which a tiled window has refused the size assigned to it
+    static ref LAYOUT_REJECTIONS: Arc<Mutex<HashMap<isize, usize>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // Layouts which have been applied to tiled windows and are yet to be compared against where
+    // the windows ended up once they have settled
+    static ref PENDING_LAYOUT_CHECKS: Arc<Mutex<HashMap<isize, Rect>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    static ref RUNNING_ELEVATED: bool =
+        WindowsApi::is_process_elevated(WindowsApi::current_process_id())
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+    // Whether the processes of candidate windows are running elevated, or None if their token
+    // cannot be opened, which are forgotten once the last known window of a process is destroyed
+    static ref PROCESS_ELEVATION: Arc<Mutex<HashMap<u32, Option<bool>>>> =
+        Arc::new(Mutex::new(HashMap::new()));
     static ref LAYERED_WHITELIST: Arc<Mutex<Vec<MatchingRule>>> = Arc::new(Mutex::new(vec![
         MatchingRule::Simple(IdWithIdentifier {
             kind: ApplicationIdentifier::Exe,
@@ -112,6 +161,12 @@ lazy_static! {
                 id: String::from("ApplicationFrameHost.exe"),
                 matching_strategy: Option::from(MatchingStrategy::Equals),
             }),
+            // UWP frames resolve to the hosted application exe while their content is attached
+            MatchingRule::Simple(IdWithIdentifier {
+                kind: ApplicationIdentifier::Class,
+                id: String::from("ApplicationFrameWindow"),
+                matching_strategy: Option::from(MatchingStrategy::Equals),
+            }),
             MatchingRule::Simple(IdWithIdentifier {
                 kind: ApplicationIdentifier::Exe,
                 id: String::from("steam.exe"),
@@ -129,6 +184,12 @@ lazy_static! {
             id: String::from("idea64.exe"),
             matching_strategy: Option::from(MatchingStrategy::Equals),
         }),
+        // UWP frames are only managed once their content is attached, which is when they are titled
+        MatchingRule::Simple(IdWithIdentifier {
+            kind: ApplicationIdentifier::Class,
+            id: String::from("ApplicationFrameWindow"),
+            matching_strategy: Option::from(MatchingStrategy::Equals),
+        }),
     ]));
     static ref TRANSPARENCY_BLACKLIST: Arc<Mutex<Vec<MatchingRule>>> = Arc::new(Mutex::new(Vec::new()));
     static ref MONITOR_INDEX_PREFERENCES: Arc<Mutex<HashMap<usize, Rect>>> =
@@ -160,6 +221,20 @@ lazy_static! {
         })
     ]));
     static ref FLOATING_APPLICATIONS: Arc<Mutex<Vec<MatchingRule>>> = Arc::new(Mutex::new(Vec::new()));
+    static ref PICTURE_IN_PICTURE_IDENTIFIERS: Arc<Mutex<Vec<MatchingRule>>> = Arc::new(Mutex::new(vec![
+        // Chromium based browsers, including Chrome and Edge
+        MatchingRule::Simple(IdWithIdentifier {
+            kind: ApplicationIdentifier::Title,
+            id: String::from("Picture in picture"),
+            matching_strategy: Option::from(MatchingStrategy::Equals),
+        }),
+        // Firefox
+        MatchingRule::Simple(IdWithIdentifier {
+            kind: ApplicationIdentifier::Title,
+            id: String::from("Picture-in-Picture"),
+            matching_strategy: Option::from(MatchingStrategy::Equals),
+        }),
+    ]));
     static ref PERMAIGNORE_CLASSES: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![
         "Chrome_RenderWidgetHostHWND".to_string(),
     ]));
@@ -174,6 +249,18 @@ lazy_static! {
             matching_strategy: Option::from(MatchingStrategy::Equals),
         }),
     ]));
+    static ref SPLASH_SCREEN_IDENTIFIERS: Arc<Mutex<Vec<MatchingRule>>> =
+        Arc::new(Mutex::new(Vec::new()));
+    // Windows which looked like splash screens when they were shown, and when that happened
+    static ref PENDING_SPLASH_SCREENS: Arc<Mutex<HashMap<isize, Instant>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // Windows which were managed recently, when that happened and whether they matched a floating
+    // application rule at the time
+    static ref SETTLING_WINDOWS: Arc<Mutex<HashMap<isize, (Instant, bool)>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // Monitors with a focused fullscreen window, and that window
+    static ref FULLSCREEN_MONITORS: Arc<Mutex<HashMap<isize, isize>>> =
+        Arc::new(Mutex::new(HashMap::new()));
     static ref SUBSCRIPTION_PIPES: Arc<Mutex<HashMap<String, File>>> =
         Arc::new(Mutex::new(HashMap::new()));
     pub static ref SUBSCRIPTION_SOCKETS: Arc<Mutex<HashMap<String, PathBuf>>> =
@@ -184,6 +271,42 @@ lazy_static! {
         Arc::new(Mutex::new(HashMap::new()));
     static ref HIDING_BEHAVIOUR: Arc<Mutex<HidingBehaviour>> =
         Arc::new(Mutex::new(HidingBehaviour::Minimize));
+    static ref HIDING_BEHAVIOUR_RULES: Arc<Mutex<Vec<HidingBehaviourRule>>> =
+        Arc::new(Mutex::new(vec![]));
+    // Windows which were hidden using a per-application behaviour must be restored the same way
+    static ref HIDING_BEHAVIOUR_OVERRIDES: Arc<Mutex<HashMap<isize, HidingBehaviour>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // The device ids of monitors on which the taskbar has been hidden
+    static ref HIDDEN_TASKBARS: Arc<Mutex<HashSet<String>>> =
+        Arc::new(Mutex::new(HashSet::new()));
+    // The percentages of the width of the workspace which cycle-width moves containers through
+    static ref WIDTH_PRESETS: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(vec![33, 50, 67]));
+    static ref SNAP_BEHAVIOUR: Arc<Mutex<SnapBehaviour>> =
+        Arc::new(Mutex::new(SnapBehaviour::Revert));
+    static ref DROP_BEHAVIOUR: Arc<Mutex<DropBehaviour>> =
+        Arc::new(Mutex::new(DropBehaviour::Swap));
+    static ref CORNER_PREFERENCE: Arc<Mutex<Option<CornerPreference>>> =
+        Arc::new(Mutex::new(None));
+    static ref CORNER_PREFERENCE_RULES: Arc<Mutex<Vec<CornerPreferenceRule>>> =
+        Arc::new(Mutex::new(vec![]));
+    // Corner rounding preferences which have been applied to windows, along with whether the
+    // window was floating when they were resolved, so that they are only resolved again when the
+    // window is floated or tiled, retitled, or the preferences are reloaded (when this is None)
+    static ref APPLIED_CORNER_PREFERENCES: Arc<Mutex<HashMap<isize, (Option<bool>, CornerPreference)>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // The positions of managed windows from before komorebi first moved them, which are restored
+    // when komorebi exits
+    static ref ORIGINAL_RECTS: Arc<Mutex<HashMap<isize, Rect>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // Where windows ended up the last time they were positioned, which is only known once the
+    // position has actually been applied rather than recorded, and can differ from the layout
+    // that they were given when they enforce a minimum size or snap to a grid
+    static ref POSITIONED_RECTS: Arc<Mutex<HashMap<isize, Rect>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // The exe, path, class and title of windows which have already been looked up, which are
+    // forgotten when a window is destroyed or, for the title, when its name changes
+    static ref WINDOW_METADATA: Arc<Mutex<HashMap<isize, WindowMetadata>>> =
+        Arc::new(Mutex::new(HashMap::new()));
     pub static ref HOME_DIR: PathBuf = {
         std::env::var("KOMOREBI_CONFIG_HOME").map_or_else(|_| dirs::home_dir().expect("there is no home directory"), |home_path| {
             let home = PathBuf::from(&home_path);
@@ -224,7 +347,7 @@ lazy_static! {
 
     // Use app-specific titlebar removal options where possible
     // eg. Windows Terminal, IntelliJ IDEA, Firefox
-    static ref NO_TITLEBAR: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(vec![]));
+    static ref NO_TITLEBAR: Arc<Mutex<Vec<MatchingRule>>> = Arc::new(Mutex::new(vec![]));
 
     static ref WINDOWS_BY_BAR_HWNDS: Arc<Mutex<HashMap<isize, VecDeque<isize>>>> =
         Arc::new(Mutex::new(HashMap::new()));
@@ -235,6 +358,7 @@ pub static DEFAULT_CONTAINER_PADDING: AtomicI32 = AtomicI32::new(10);
 
 pub static INITIAL_CONFIGURATION_LOADED: AtomicBool = AtomicBool::new(false);
 pub static CUSTOM_FFM: AtomicBool = AtomicBool::new(false);
+pub static FOCUS_FOLLOWS_MOUSE_DELAY: AtomicU64 = AtomicU64::new(0);
 pub static SESSION_ID: AtomicU32 = AtomicU32::new(0);
 
 pub static REMOVE_TITLEBARS: AtomicBool = AtomicBool::new(false);
@@ -242,6 +366,33 @@ pub static ANIMATION_ENABLED: AtomicBool = AtomicBool::new(false);
 pub static ANIMATION_DURATION: AtomicU64 = AtomicU64::new(250);
 
 pub static SLOW_APPLICATION_COMPENSATION_TIME: AtomicU64 = AtomicU64::new(20);
+pub static SPLASH_SCREEN_GRACE_PERIOD: AtomicU64 = AtomicU64::new(1000);
+pub static RULE_SETTLE_PERIOD: AtomicU64 = AtomicU64::new(2000);
+pub static PAUSE_ON_FULLSCREEN: AtomicBool = AtomicBool::new(true);
+
+pub static KNOWN_HWNDS_FILE: AtomicBool = AtomicBool::new(false);
+pub static RESTORE_SESSION: AtomicBool = AtomicBool::new(false);
+pub static FLOAT_ELEVATED_WINDOWS: AtomicBool = AtomicBool::new(false);
+pub static FLOAT_LAYOUT_REJECTING_WINDOWS: AtomicBool = AtomicBool::new(true);
+pub static FLOAT_SNAPPING_ENABLED: AtomicBool = AtomicBool::new(false);
+pub static FLOAT_SNAPPING_GRID: AtomicI32 = AtomicI32::new(0);
+pub static FLOAT_SNAPPING_DISTANCE: AtomicI32 = AtomicI32::new(10);
+
+/// Incremented whenever deferred changes to windows have been applied, so that managers which
+/// skip work when the window manager state has not changed know that windows may have moved
+pub static WINDOW_OPERATIONS_APPLIED: AtomicU64 = AtomicU64::new(0);
+
+/// Handle to the filter of the global tracing subscriber, which allows the log level to be changed
+/// without restarting komorebi
+pub static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+pub(crate) fn unix_timestamp_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| {
+            u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+        })
+}
 
 #[must_use]
 pub fn current_virtual_desktop() -> Option<Vec<u8>> {
@@ -298,6 +449,31 @@ pub enum NotificationEvent {
 pub struct Notification {
     pub event: NotificationEvent,
     pub state: State,
+    /// The parts of the state which were changed by the event
+    #[serde(default)]
+    pub delta: StateDelta,
+}
+
+/// A notification which is held back until the changes to windows made by the event or command
+/// that produced it have been applied, so that neither subscribers nor the managers which draw on
+/// top of windows ever see windows before they have been moved into place
+pub struct PendingNotification {
+    pub notification: Notification,
+    pub state_has_been_modified: bool,
+    /// The window which the border manager is notified about
+    pub hwnd: Option<isize>,
+}
+
+impl PendingNotification {
+    pub fn send(self) -> Result<()> {
+        let result = notify_subscribers(self.notification, self.state_has_been_modified);
+
+        border_manager::send_notification(self.hwnd);
+        transparency_manager::send_notification();
+        stackbar_manager::send_notification();
+
+        result
+    }
 }
 
 pub fn notify_subscribers(notification: Notification, state_has_been_modified: bool) -> Result<()> {
@@ -309,23 +485,84 @@ pub fn notify_subscribers(notification: Notification, state_has_been_modified: b
             | NotificationEvent::Socket(SocketMessage::ReloadStaticConfiguration(_))
     );
 
+    // Both WindowManagerEvent and SocketMessage are serialized with their variant name as "type"
+    let event_type = serde_json::to_value(&notification.event)?
+        .get("type")
+        .and_then(|event_type| event_type.as_str())
+        .map(String::from);
+
+    toast_manager::send_state_notifications(&notification.state, &notification.delta);
+    osd_manager::send_state_notifications(&notification.state, &notification.delta);
+    indicator_manager::send_state_notifications(&notification.state);
+    drag_manager::update_tiled_windows(&notification.state);
+    gap_drag_manager::update_gaps(&notification.state);
+
+    let delta = &notification.delta;
+    let mut hook_events = vec![];
+
+    if delta.focused_monitor_idx.is_some()
+        || delta
+            .monitors
+            .iter()
+            .any(|monitor| monitor.focused_workspace_idx.is_some())
+    {
+        hook_events.push(HookEvent::WorkspaceFocused);
+    }
+
+    if !delta.managed_windows.is_empty() {
+        hook_events.push(HookEvent::WindowManaged);
+    }
+
+    if !delta.unmanaged_windows.is_empty() {
+        hook_events.push(HookEvent::WindowUnmanaged);
+    }
+
+    // Only summarize the state for status bars when there is a subscriber that wants it
+    let bar_status = if SUBSCRIPTION_SOCKET_OPTIONS
+        .lock()
+        .values()
+        .any(|options| options.bar_status)
+    {
+        Option::from(serde_json::to_string(&notification.state.bar_status())?)
+    } else {
+        None
+    };
+
     let notification = &serde_json::to_string(&notification)?;
+
+    for event in hook_events {
+        hook_manager::send_notification(event, notification.clone());
+    }
+
     let mut stale_sockets = vec![];
     let mut sockets = SUBSCRIPTION_SOCKETS.lock();
     let options = SUBSCRIPTION_SOCKET_OPTIONS.lock();
 
     for (socket, path) in &mut *sockets {
-        let apply_state_filter = (*options)
-            .get(socket)
-            .copied()
-            .unwrap_or_default()
-            .filter_state_changes;
+        let socket_options = (*options).get(socket);
+
+        let apply_state_filter = socket_options.is_some_and(|options| options.filter_state_changes);
+
+        let is_filtered_event_type = socket_options
+            .and_then(|options| options.filter_event_types.as_ref())
+            .is_some_and(|event_types| {
+                event_type
+                    .as_ref()
+                    .map_or(true, |event_type| !event_types.contains(event_type))
+            });
+
+        let payload = match (&bar_status, socket_options) {
+            (Some(bar_status), Some(options)) if options.bar_status => bar_status,
+            _ => notification,
+        };
 
-        if !apply_state_filter || state_has_been_modified || is_override_event {
+        if (!apply_state_filter || state_has_been_modified || is_override_event)
+            && (!is_filtered_event_type || is_override_event)
+        {
             match UnixStream::connect(path) {
                 Ok(mut stream) => {
                     tracing::debug!("pushed notification to subscriber: {socket}");
-                    stream.write_all(notification.as_bytes())?;
+                    stream.write_all(payload.as_bytes())?;
                 }
                 Err(_) => {
                     stale_sockets.push(socket.clone());
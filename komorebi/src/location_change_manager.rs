@@ -0,0 +1,165 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::core::SnapBehaviour;
+use crate::window::Window;
+use crate::window_manager_event::WindowManagerEvent;
+use crate::winevent::WinEvent;
+use crate::winevent_listener;
+use crate::WindowManager;
+use crate::ANIMATIONS_IN_PROGRESS;
+use crate::FULLSCREEN_MONITORS;
+use crate::SNAP_BEHAVIOUR;
+
+/// Windows which resize themselves often do so in several steps, so the layout is only enforced
+/// once a burst of location changes has settled
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+pub struct Notification(isize);
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(100))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification(hwnd: isize) {
+    // Location changes arrive in floods whenever anything on the screen moves, and any of them
+    // being dropped is of no consequence as long as the last few of a burst make it through
+    if event_tx().try_send(Notification(hwnd)).is_err() {
+        tracing::trace!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications(wm: Arc<Mutex<WindowManager>>) {
+    std::thread::spawn(move || loop {
+        match handle_notifications(wm.clone()) {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    while let Ok(notification) = receiver.recv() {
+        let mut hwnds = HashSet::from([notification.0]);
+        let deadline = Instant::now() + DEBOUNCE;
+
+        loop {
+            match receiver.recv_deadline(deadline) {
+                Ok(notification) => {
+                    hwnds.insert(notification.0);
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        let mut wm = wm.lock();
+
+        // Windows are expected to be away from their layout while the user is dragging them
+        // around or while they are being animated into place
+        if wm.is_paused
+            || wm.pending_move_op.is_some()
+            || ANIMATIONS_IN_PROGRESS.load(Ordering::Acquire) > 0
+        {
+            continue;
+        }
+
+        let mut displaced_monitors = vec![];
+        let mut snapped_windows = vec![];
+        let focused_monitor_idx = wm.focused_monitor_idx();
+        let snap_behaviour = *SNAP_BEHAVIOUR.lock();
+
+        for (monitor_idx, monitor) in wm.monitors().iter().enumerate() {
+            let Some(workspace) = monitor.focused_workspace() else {
+                continue;
+            };
+
+            if !*workspace.tile()
+                || FULLSCREEN_MONITORS.lock().contains_key(&monitor.id())
+                || workspace.monocle_container().is_some()
+                || workspace.maximized_window().is_some()
+            {
+                continue;
+            }
+
+            for (container_idx, container) in workspace.containers().iter().enumerate() {
+                let Some(window) = container.focused_window() else {
+                    continue;
+                };
+
+                if !hwnds.contains(&window.hwnd) || window.is_miminized() || window.is_maximized() {
+                    continue;
+                }
+
+                if let Some(layout) = workspace.latest_layout().get(container_idx) {
+                    if window.deviates_from_layout(layout) {
+                        // Only the focused window can be snapped with the keyboard, and adopting
+                        // its new position is handled the same way as a resize with the mouse
+                        if matches!(snap_behaviour, SnapBehaviour::Adopt)
+                            && monitor_idx == focused_monitor_idx
+                            && container_idx == workspace.focused_container_idx()
+                            && window.is_snapped()
+                        {
+                            snapped_windows.push(*window);
+                            continue;
+                        }
+
+                        displaced_monitors.push(monitor_idx);
+                        break;
+                    }
+                }
+            }
+        }
+
+        for monitor_idx in displaced_monitors {
+            tracing::info!("restoring the layout of windows which have resized themselves");
+            wm.update_focused_workspace_by_monitor_idx(monitor_idx)?;
+        }
+
+        // The event loop needs the lock to handle these, and it is never waited on while holding it
+        drop(wm);
+
+        // Windows which apply their own minimum size or snapping do so a moment after they have
+        // been positioned, so whether they have accepted their layout is only known by now
+        Window::check_layout_rejections(&hwnds);
+
+        for window in snapped_windows {
+            tracing::info!("adopting the position of window snapped by the system: {window}");
+            if let Err(error) = winevent_listener::event_tx().try_send(
+                WindowManagerEvent::MoveResizeEnd(WinEvent::SystemMoveSizeEnd, window),
+            ) {
+                tracing::warn!("could not adopt the position of snapped window {window}: {error}");
+            }
+        }
+    }
+
+    Ok(())
+}
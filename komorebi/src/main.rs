@@ -7,52 +7,78 @@
     clippy::doc_markdown
 )]
 
-use std::net::Shutdown;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-#[cfg(feature = "deadlock_detection")]
 use std::time::Duration;
 
 use clap::Parser;
+use clap::ValueEnum;
 use color_eyre::Result;
 use crossbeam_utils::Backoff;
 #[cfg(feature = "deadlock_detection")]
 use parking_lot::deadlock;
 use parking_lot::Mutex;
-use sysinfo::Process;
-use sysinfo::ProcessesToUpdate;
 use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::RollingFileAppender;
+use tracing_appender::rolling::Rotation;
 use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::reload;
 use tracing_subscriber::EnvFilter;
-use uds_windows::UnixStream;
 
 use komorebi::border_manager;
+use komorebi::drag_manager;
+use komorebi::flash_manager;
 use komorebi::focus_manager;
+use komorebi::gap_drag_manager;
+use komorebi::hint_manager;
+use komorebi::hook_manager;
+use komorebi::hot_edge_manager;
+use komorebi::indicator_manager;
+use komorebi::known_hwnds_manager;
 use komorebi::load_configuration;
+use komorebi::location_change_manager;
 use komorebi::monitor_reconciliator;
+use komorebi::osd_manager;
 use komorebi::process_command::listen_for_commands;
 use komorebi::process_command::listen_for_commands_tcp;
 use komorebi::process_event::listen_for_events;
+use komorebi::process_event::start_processing_events;
 use komorebi::process_movement::listen_for_movements;
 use komorebi::reaper;
+use komorebi::scroll_manager;
+use komorebi::session::Session;
+use komorebi::session_manager;
+use komorebi::shutdown;
+use komorebi::splash_screen_manager;
 use komorebi::stackbar_manager;
 use komorebi::static_config::StaticConfig;
+use komorebi::switcher_manager;
 use komorebi::theme_manager;
+use komorebi::toast_manager;
 use komorebi::transparency_manager;
 use komorebi::window_manager::WindowManager;
 use komorebi::windows_api::WindowsApi;
+use komorebi::windows_callbacks;
 use komorebi::winevent_listener;
 use komorebi::workspace_reconciliator;
 use komorebi::CUSTOM_FFM;
 use komorebi::DATA_DIR;
 use komorebi::HOME_DIR;
 use komorebi::INITIAL_CONFIGURATION_LOADED;
+use komorebi::INSTANCE_MUTEX;
+use komorebi::LOG_FILTER;
+use komorebi::REPLACE_EVENT;
+use komorebi::RESTORE_SESSION;
 use komorebi::SESSION_ID;
 
 shadow_rs::shadow!(build);
 
-fn setup() -> Result<(WorkerGuard, WorkerGuard)> {
+/// The number of daily log files of each kind which are kept before the oldest is deleted
+const LOG_RETENTION: usize = 7;
+
+fn setup(log_format: LogFormat) -> Result<(WorkerGuard, WorkerGuard)> {
     if std::env::var("RUST_LIB_BACKTRACE").is_err() {
         std::env::set_var("RUST_LIB_BACKTRACE", "1");
     }
@@ -63,20 +89,42 @@ fn setup() -> Result<(WorkerGuard, WorkerGuard)> {
         std::env::set_var("RUST_LOG", "info");
     }
 
-    let appender = tracing_appender::rolling::daily(std::env::temp_dir(), "komorebi_plaintext.log");
-    let color_appender = tracing_appender::rolling::daily(std::env::temp_dir(), "komorebi.log");
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("komorebi_plaintext.log")
+        .max_log_files(LOG_RETENTION)
+        .build(&*DATA_DIR)?;
+    let color_appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("komorebi.log")
+        .max_log_files(LOG_RETENTION)
+        .build(&*DATA_DIR)?;
     let (non_blocking, guard) = tracing_appender::non_blocking(appender);
     let (color_non_blocking, color_guard) = tracing_appender::non_blocking(color_appender);
 
+    // The filter can be swapped out at runtime with 'komorebic log-level'
+    let (filter, filter_handle) = reload::Layer::new(EnvFilter::from_default_env());
+    LOG_FILTER.get_or_init(|| filter_handle);
+
     tracing::subscriber::set_global_default(
-        tracing_subscriber::fmt::Subscriber::builder()
-            .with_env_filter(EnvFilter::from_default_env())
-            .finish()
-            .with(
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::Layer::default())
+            .with(matches!(log_format, LogFormat::Text).then(|| {
                 tracing_subscriber::fmt::Layer::default()
+                    .with_writer(non_blocking.clone())
+                    .with_ansi(false)
+            }))
+            // Each line is a JSON object with the timestamp, level and message of the entry, along
+            // with the event, hwnd, monitor and workspace fields of the span it was logged in
+            .with(matches!(log_format, LogFormat::Json).then(|| {
+                tracing_subscriber::fmt::Layer::default()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(true)
+                    .with_span_list(false)
                     .with_writer(non_blocking)
-                    .with_ansi(false),
-            )
+            }))
             .with(
                 tracing_subscriber::fmt::Layer::default()
                     .with_writer(color_non_blocking)
@@ -138,6 +186,15 @@ fn detect_deadlocks() {
     });
 }
 
+#[derive(Copy, Clone, Debug, Default, ValueEnum)]
+enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// One JSON object per line
+    Json,
+}
+
 #[derive(Parser)]
 #[clap(author, about, version = build::CLAP_LONG_VERSION)]
 struct Opts {
@@ -148,11 +205,27 @@ struct Opts {
     #[clap(short, long)]
     await_configuration: bool,
     /// Start a TCP server on the given port to allow the direct sending of SocketMessages
-    #[clap(short, long)]
-    tcp_port: Option<usize>,
+    #[clap(short, long, visible_alias = "tcp")]
+    tcp_port: Option<u16>,
+    /// Address for the TCP server to bind to (use 0.0.0.0 to accept connections from other machines)
+    #[clap(long, default_value = "127.0.0.1", requires = "tcp_port")]
+    tcp_address: IpAddr,
     /// Path to a static configuration JSON file
     #[clap(short, long)]
     config: Option<PathBuf>,
+    /// Place windows back into the workspaces they were on when the session was last saved
+    #[clap(long)]
+    restore_session: bool,
+    /// Ask an already running instance of komorebi to exit cleanly and take over from it
+    #[clap(long)]
+    replace: bool,
+    /// Detach from the console so that no console window is left open (eg. when started at logon
+    /// by a scheduled task)
+    #[clap(long)]
+    headless: bool,
+    /// Format of the entries written to komorebi_plaintext.log
+    #[clap(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
 }
 
 #[tracing::instrument]
@@ -161,6 +234,11 @@ fn main() -> Result<()> {
     let opts: Opts = Opts::parse();
     CUSTOM_FFM.store(opts.focus_follows_mouse, Ordering::SeqCst);
 
+    // Everything is logged to files, so nothing is lost by not having a console
+    if opts.headless {
+        WindowsApi::free_console()?;
+    }
+
     let process_id = WindowsApi::current_process_id();
     WindowsApi::allow_set_foreground_window(process_id)?;
     WindowsApi::set_process_dpi_awareness_context()?;
@@ -168,29 +246,29 @@ fn main() -> Result<()> {
     let session_id = WindowsApi::process_id_to_session_id()?;
     SESSION_ID.store(session_id, Ordering::SeqCst);
 
-    let mut system = sysinfo::System::new_all();
-    system.refresh_processes(ProcessesToUpdate::All);
-
-    let matched_procs: Vec<&Process> = system.processes_by_name("komorebi.exe".as_ref()).collect();
+    let (instance_mutex, already_running) = WindowsApi::create_mutex(INSTANCE_MUTEX)?;
 
-    if matched_procs.len() > 1 {
-        let mut len = matched_procs.len();
-        for proc in matched_procs {
-            if let Some(executable_path) = proc.exe() {
-                if executable_path.to_string_lossy().contains("shims") {
-                    len -= 1;
-                }
-            }
+    if already_running {
+        if !opts.replace {
+            tracing::error!("komorebi.exe is already running, please exit the existing process before starting a new one or start with --replace");
+            std::process::exit(1);
         }
 
-        if len > 1 {
-            tracing::error!("komorebi.exe is already running, please exit the existing process before starting a new one");
+        // The running instance releases the mutex when it exits after restoring all windows
+        WindowsApi::set_event(REPLACE_EVENT)?;
+
+        if !WindowsApi::wait_for_object(instance_mutex, Some(Duration::from_secs(10))) {
+            tracing::error!(
+                "komorebi.exe did not exit after being asked to make way for this process"
+            );
             std::process::exit(1);
         }
     }
 
+    let replace_event = WindowsApi::create_event(REPLACE_EVENT)?;
+
     // File logging worker guard has to have an assignment in the main fn to work
-    let (_guard, _color_guard) = setup()?;
+    let (_guard, _color_guard) = setup(opts.log_format)?;
 
     WindowsApi::foreground_lock_timeout()?;
 
@@ -236,6 +314,7 @@ fn main() -> Result<()> {
         StaticConfig::postload(config, &wm)?;
     }
 
+    listen_for_events(wm.clone());
     listen_for_commands(wm.clone());
 
     if !opts.await_configuration && !INITIAL_CONFIGURATION_LOADED.load(Ordering::SeqCst) {
@@ -243,7 +322,7 @@ fn main() -> Result<()> {
     };
 
     if let Some(port) = opts.tcp_port {
-        listen_for_commands_tcp(wm.clone(), port);
+        listen_for_commands_tcp(opts.tcp_address, port);
     }
 
     if static_config.is_none() {
@@ -257,9 +336,16 @@ fn main() -> Result<()> {
         }
     }
 
+    if opts.restore_session || RESTORE_SESSION.load(Ordering::SeqCst) {
+        match Session::load() {
+            Ok(session) => wm.lock().restore_session(&session)?,
+            Err(error) => tracing::warn!("could not load session: {error}"),
+        }
+    }
+
     wm.lock().retile_all(false)?;
 
-    listen_for_events(wm.clone());
+    start_processing_events();
 
     if CUSTOM_FFM.load(Ordering::SeqCst) {
         listen_for_movements(wm.clone());
@@ -272,36 +358,41 @@ fn main() -> Result<()> {
     monitor_reconciliator::listen_for_notifications(wm.clone())?;
     reaper::watch_for_orphans(wm.clone());
     focus_manager::listen_for_notifications(wm.clone());
+    location_change_manager::listen_for_notifications(wm.clone());
     theme_manager::listen_for_notifications();
+    known_hwnds_manager::listen_for_notifications();
+    session_manager::listen_for_notifications(wm.clone());
+    splash_screen_manager::listen_for_notifications();
+    hook_manager::listen_for_notifications();
+    toast_manager::listen_for_notifications();
+    osd_manager::listen_for_notifications();
+    flash_manager::listen_for_notifications();
+    drop_zone_manager::listen_for_notifications(wm.clone());
+    drag_manager::listen_for_notifications(wm.clone());
+    gap_drag_manager::listen_for_notifications(wm.clone());
+    indicator_manager::listen_for_notifications();
+    hint_manager::listen_for_notifications();
+    switcher_manager::listen_for_notifications(wm.clone());
+    scroll_manager::listen_for_notifications();
+    hot_edge_manager::listen_for_notifications();
+
+    ctrlc::set_handler(|| shutdown::send_notification("ctrl-c"))?;
+
+    std::thread::spawn(move || {
+        if WindowsApi::wait_for_object(replace_event, None) {
+            shutdown::send_notification("replace request");
+        }
+    });
 
-    let (ctrlc_sender, ctrlc_receiver) = crossbeam_channel::bounded(1);
-    ctrlc::set_handler(move || {
-        ctrlc_sender
-            .send(())
-            .expect("could not send signal on ctrl-c channel");
-    })?;
-
-    ctrlc_receiver
-        .recv()
-        .expect("could not receive signal on ctrl-c channel");
-
-    tracing::error!("received ctrl-c, restoring all hidden windows and terminating process");
+    // Handlers are called in reverse order of registration, so this one gets to see console close,
+    // logoff and shutdown events before the ctrl-c handler does
+    WindowsApi::set_console_ctrl_handler(Some(windows_callbacks::console_ctrl_handler))?;
 
-    wm.lock().restore_all_windows()?;
+    let reason = shutdown::wait_for_notification()?;
 
-    if WindowsApi::focus_follows_mouse()? {
-        WindowsApi::disable_focus_follows_mouse()?;
-    }
-
-    let sockets = komorebi::SUBSCRIPTION_SOCKETS.lock();
-    for path in (*sockets).values() {
-        if let Ok(stream) = UnixStream::connect(path) {
-            stream.shutdown(Shutdown::Both)?;
-        }
-    }
+    tracing::error!("received {reason}, restoring all hidden windows and terminating process");
 
-    let socket = DATA_DIR.join("komorebi.sock");
-    let _ = std::fs::remove_file(socket);
+    wm.lock().shutdown()?;
 
     std::process::exit(130);
 }
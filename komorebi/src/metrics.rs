@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Counters which are kept for as long as komorebi is running, so that performance regressions and
+/// applications which flood komorebi with events can be identified with 'komorebic stats'
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Stats {
+    /// All events handled by the event loop
+    pub events: DurationStats,
+    /// Events handled by the event loop, by type
+    pub events_by_type: BTreeMap<String, DurationStats>,
+    /// Events which were dropped because they were identical to an event which was still pending
+    pub coalesced_events: u64,
+    /// Commands handled by the event loop
+    pub commands: DurationStats,
+    /// The number of times that the layout of a workspace has been applied
+    pub retiles: u64,
+    /// The time spent waiting for the window manager lock before handling events and commands
+    pub lock_wait: DurationStats,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DurationStats {
+    pub count: u64,
+    pub total_micros: u64,
+    pub average_micros: u64,
+    pub max_micros: u64,
+}
+
+impl DurationStats {
+    const fn new() -> Self {
+        Self {
+            count: 0,
+            total_micros: 0,
+            average_micros: 0,
+            max_micros: 0,
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        let micros = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+
+        self.count += 1;
+        self.total_micros = self.total_micros.saturating_add(micros);
+        self.average_micros = self.total_micros / self.count;
+        self.max_micros = self.max_micros.max(micros);
+    }
+}
+
+static STATS: Mutex<Stats> = Mutex::new(Stats {
+    events: DurationStats::new(),
+    events_by_type: BTreeMap::new(),
+    coalesced_events: 0,
+    commands: DurationStats::new(),
+    retiles: 0,
+    lock_wait: DurationStats::new(),
+});
+
+pub fn stats() -> Stats {
+    STATS.lock().clone()
+}
+
+pub fn record_event(title: &str, duration: Duration) {
+    let mut stats = STATS.lock();
+    stats.events.record(duration);
+    stats
+        .events_by_type
+        .entry(title.to_string())
+        .or_default()
+        .record(duration);
+}
+
+pub fn record_coalesced_event() {
+    STATS.lock().coalesced_events += 1;
+}
+
+pub fn record_command(duration: Duration) {
+    STATS.lock().commands.record(duration);
+}
+
+pub fn record_retile() {
+    STATS.lock().retiles += 1;
+}
+
+pub fn record_lock_wait(duration: Duration) {
+    STATS.lock().lock_wait.record(duration);
+}
@@ -12,15 +12,17 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::core::KomorebiError;
 use crate::core::Rect;
 
 use crate::container::Container;
 use crate::ring::Ring;
+use crate::system_api::system_api;
 use crate::workspace::Workspace;
 use crate::DefaultLayout;
 use crate::Layout;
 use crate::OperationDirection;
-use crate::WindowsApi;
+use crate::HIDDEN_TASKBARS;
 
 #[derive(
     Debug,
@@ -74,6 +76,13 @@ pub fn new(
     let mut workspaces = Ring::default();
     workspaces.elements_mut().push_back(Workspace::default());
 
+    // A hidden taskbar keeps its space reserved in the work area reported by the system
+    let work_area_size = if HIDDEN_TASKBARS.lock().contains(&device_id) {
+        size
+    } else {
+        work_area_size
+    };
+
     Monitor {
         id,
         name,
@@ -125,10 +134,11 @@ impl Monitor {
         container: Container,
         workspace_idx: Option<usize>,
     ) -> Result<()> {
+        let focused_workspace_idx = self.focused_workspace_idx();
         let workspace = if let Some(idx) = workspace_idx {
             self.workspaces_mut()
                 .get_mut(idx)
-                .ok_or_else(|| anyhow!("there is no workspace at index {}", idx))?
+                .ok_or(KomorebiError::NoSuchWorkspace(idx))?
         } else {
             self.focused_workspace_mut()
                 .ok_or_else(|| anyhow!("there is no workspace"))?
@@ -136,6 +146,10 @@ impl Monitor {
 
         workspace.add_container_to_back(container);
 
+        if workspace_idx.is_some_and(|idx| idx != focused_workspace_idx) {
+            workspace.set_dirty(true);
+        }
+
         Ok(())
     }
 
@@ -179,7 +193,7 @@ impl Monitor {
             bail!("cannot move native maximized window to another monitor or workspace");
         }
 
-        let foreground_hwnd = WindowsApi::foreground_window()?;
+        let foreground_hwnd = system_api().foreground_window()?;
         let floating_window_index = workspace
             .floating_windows()
             .iter()
@@ -266,6 +280,10 @@ impl Monitor {
             }
         }
 
+        if let Some(workspace) = self.workspaces_mut().get_mut(target_workspace_idx) {
+            workspace.set_dirty(true);
+        }
+
         if follow {
             self.focus_workspace(target_workspace_idx)?;
         }
@@ -293,7 +311,7 @@ impl Monitor {
             if name.is_some() {
                 self.workspaces_mut()
                     .get_mut(idx)
-                    .ok_or_else(|| anyhow!("there is no workspace"))?
+                    .ok_or(KomorebiError::NoSuchWorkspace(idx))?
                     .set_name(name);
             }
         }
@@ -306,6 +324,20 @@ impl Monitor {
     }
 
     pub fn update_focused_workspace(&mut self, offset: Option<Rect>) -> Result<()> {
+        self.update_workspace(self.focused_workspace_idx(), offset)
+    }
+
+    /// Updates the workspace at the given index if it is the focused workspace, and otherwise
+    /// leaves it to be updated once it is shown again
+    pub fn update_workspace(&mut self, idx: usize, offset: Option<Rect>) -> Result<()> {
+        if idx != self.focused_workspace_idx() {
+            if let Some(workspace) = self.workspaces_mut().get_mut(idx) {
+                workspace.set_dirty(true);
+            }
+
+            return Ok(());
+        }
+
         let work_area = *self.work_area_size();
         let window_based_work_area_offset = (
             self.window_based_work_area_offset_limit(),
@@ -1,3 +1,5 @@
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
 use std::sync::mpsc;
 use std::time::Duration;
 
@@ -13,6 +15,7 @@ use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
 use windows::Win32::UI::WindowsAndMessaging::CS_HREDRAW;
 use windows::Win32::UI::WindowsAndMessaging::CS_VREDRAW;
 use windows::Win32::UI::WindowsAndMessaging::DBT_DEVNODES_CHANGED;
+use windows::Win32::UI::WindowsAndMessaging::HSHELL_FLASH;
 use windows::Win32::UI::WindowsAndMessaging::MSG;
 use windows::Win32::UI::WindowsAndMessaging::PBT_APMRESUMEAUTOMATIC;
 use windows::Win32::UI::WindowsAndMessaging::PBT_APMRESUMESUSPEND;
@@ -20,6 +23,7 @@ use windows::Win32::UI::WindowsAndMessaging::PBT_APMSUSPEND;
 use windows::Win32::UI::WindowsAndMessaging::SPI_SETWORKAREA;
 use windows::Win32::UI::WindowsAndMessaging::WM_DEVICECHANGE;
 use windows::Win32::UI::WindowsAndMessaging::WM_DISPLAYCHANGE;
+use windows::Win32::UI::WindowsAndMessaging::WM_ENDSESSION;
 use windows::Win32::UI::WindowsAndMessaging::WM_POWERBROADCAST;
 use windows::Win32::UI::WindowsAndMessaging::WM_SETTINGCHANGE;
 use windows::Win32::UI::WindowsAndMessaging::WM_WTSSESSION_CHANGE;
@@ -28,9 +32,19 @@ use windows::Win32::UI::WindowsAndMessaging::WTS_SESSION_LOCK;
 use windows::Win32::UI::WindowsAndMessaging::WTS_SESSION_UNLOCK;
 
 use crate::monitor_reconciliator;
+use crate::shutdown;
+use crate::window::Window;
+use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api;
+use crate::winevent_listener;
 use crate::WindowsApi;
 
+// The message id which the shell uses to send shell hook messages to this window
+static SHELL_HOOK_MESSAGE: AtomicU32 = AtomicU32::new(0);
+
+// The message id which explorer broadcasts to top-level windows whenever it creates the taskbar
+static TASKBAR_CREATED_MESSAGE: AtomicU32 = AtomicU32::new(0);
+
 // This is a hidden window specifically spawned to listen to system-wide events related to monitors
 #[derive(Debug, Clone, Copy)]
 pub struct Hidden {
@@ -94,6 +108,19 @@ impl Hidden {
 
         WindowsApi::wts_register_session_notification(hwnd)?;
 
+        // Shell hook messages tell us when a window flashes in the taskbar to request attention
+        SHELL_HOOK_MESSAGE.store(
+            WindowsApi::register_window_message_w("SHELLHOOK")?,
+            Ordering::SeqCst,
+        );
+        WindowsApi::register_shell_hook_window(hwnd)?;
+
+        // Explorer shows the taskbar again when it restarts, even if komorebi had hidden it
+        TASKBAR_CREATED_MESSAGE.store(
+            WindowsApi::register_window_message_w("TaskbarCreated")?,
+            Ordering::SeqCst,
+        );
+
         Ok(Self { hwnd })
     }
 
@@ -131,6 +158,16 @@ impl Hidden {
                         _ => LRESULT(0),
                     }
                 }
+                // Sent when the user signs out or the system shuts down or restarts, after which the
+                // process is terminated as soon as this message has been handled
+                WM_ENDSESSION => {
+                    if wparam.0 != 0 {
+                        tracing::debug!("WM_ENDSESSION event received - session ending");
+                        shutdown::send_notification_and_wait("end of session");
+                    }
+
+                    LRESULT(0)
+                }
                 WM_WTSSESSION_CHANGE => {
                     match wparam.0 as u32 {
                         WTS_SESSION_LOCK => {
@@ -199,6 +236,31 @@ impl Hidden {
 
                     LRESULT(0)
                 }
+                message if message == SHELL_HOOK_MESSAGE.load(Ordering::SeqCst) => {
+                    #[allow(clippy::cast_possible_truncation)]
+                    if wparam.0 as u32 == HSHELL_FLASH {
+                        tracing::debug!("HSHELL_FLASH shell hook message received");
+                        // Blocking here would freeze the shell hook window until the event
+                        // loop catches up
+                        if winevent_listener::event_tx()
+                            .try_send(WindowManagerEvent::Flash(Window::from(lparam.0)))
+                            .is_err()
+                        {
+                            tracing::warn!("channel is full; dropping flash event");
+                        }
+                    }
+
+                    LRESULT(0)
+                }
+                message if message == TASKBAR_CREATED_MESSAGE.load(Ordering::SeqCst) => {
+                    tracing::debug!("TaskbarCreated message received - taskbar was recreated");
+
+                    monitor_reconciliator::send_notification(
+                        monitor_reconciliator::Notification::TaskbarCreated,
+                    );
+
+                    LRESULT(0)
+                }
                 _ => DefWindowProcW(window, message, wparam, lparam),
             }
         }
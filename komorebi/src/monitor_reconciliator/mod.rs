@@ -1,7 +1,9 @@
 #![deny(clippy::unwrap_used, clippy::expect_used)]
 
 use crate::border_manager;
+use crate::core::HookEvent;
 use crate::core::Rect;
+use crate::hook_manager;
 use crate::monitor;
 use crate::monitor::Monitor;
 use crate::monitor_reconciliator::hidden::Hidden;
@@ -23,6 +25,7 @@ pub mod hidden;
 pub enum Notification {
     ResolutionScalingChanged,
     WorkAreaChanged,
+    TaskbarCreated,
     DisplayConnectionChange,
     EnteringSuspendedState,
     ResumingFromSuspendedState,
@@ -149,8 +152,10 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
             Notification::ResumingFromSuspendedState | Notification::SessionUnlocked => {
                 // this is only handled above if the reconciliator is paused
             }
-            Notification::WorkAreaChanged => {
+            Notification::WorkAreaChanged | Notification::TaskbarCreated => {
                 tracing::debug!("handling work area changed notification");
+                wm.reconcile_hidden_taskbars();
+
                 let offset = wm.work_area_offset;
                 for monitor in wm.monitors_mut() {
                     let mut should_update = false;
@@ -183,6 +188,8 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
             }
             Notification::ResolutionScalingChanged => {
                 tracing::debug!("handling resolution/scaling changed notification");
+                wm.reconcile_hidden_taskbars();
+
                 let offset = wm.work_area_offset;
                 for monitor in wm.monitors_mut() {
                     let mut should_update = false;
@@ -230,6 +237,8 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
             }
             Notification::DisplayConnectionChange => {
                 tracing::debug!("handling display connection change notification");
+                wm.reconcile_hidden_taskbars();
+
                 let mut monitor_cache = MONITOR_CACHE
                     .get_or_init(|| Mutex::new(HashMap::new()))
                     .lock();
@@ -282,6 +291,11 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
                             .any(|attached| attached.device_id().eq(m.device_id()))
                         {
                             newly_removed_displays.push(m.device_id().clone());
+                            hook_manager::send_notification(
+                                HookEvent::MonitorDisconnected,
+                                serde_json::to_string(m)?,
+                            );
+
                             for workspace in m.workspaces() {
                                 for container in workspace.containers() {
                                     // Save the orphaned containers from the removed monitor
@@ -395,6 +409,11 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
                             if cache_hit {
                                 monitor_cache.remove(&device_id);
                             }
+
+                            hook_manager::send_notification(
+                                HookEvent::MonitorConnected,
+                                serde_json::to_string(m)?,
+                            );
                         }
                     }
                 }
@@ -0,0 +1,126 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use windows::Win32::Foundation::HINSTANCE;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::WindowsAndMessaging::CallNextHookEx;
+use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
+use windows::Win32::UI::WindowsAndMessaging::GetMessageW;
+use windows::Win32::UI::WindowsAndMessaging::PostThreadMessageW;
+use windows::Win32::UI::WindowsAndMessaging::SetWindowsHookExW;
+use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
+use windows::Win32::UI::WindowsAndMessaging::UnhookWindowsHookEx;
+use windows::Win32::UI::WindowsAndMessaging::HHOOK;
+use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::MSLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::WH_MOUSE_LL;
+use windows::Win32::UI::WindowsAndMessaging::WM_QUIT;
+
+use crate::drag_manager;
+use crate::gap_drag_manager;
+use crate::hot_edge_manager;
+use crate::scroll_manager;
+
+/// The thread which the low-level mouse hook has been installed on, if any
+static HOOK_THREAD_ID: Mutex<Option<u32>> = Mutex::new(None);
+
+/// Every mouse event on the system goes through a low-level mouse hook before it reaches any
+/// window, so the hook is only installed while at least one feature that needs it is enabled
+fn is_needed() -> bool {
+    drag_manager::DRAG_MODIFIER.lock().is_some()
+        || gap_drag_manager::GAP_DRAG_ENABLED.load(Ordering::SeqCst)
+        || scroll_manager::SCROLL_WORKSPACES_ENABLED.load(Ordering::SeqCst)
+        || !hot_edge_manager::hot_edges().is_empty()
+}
+
+/// Installs or removes the low-level mouse hook, depending on whether any of the features which
+/// use it are enabled; this has to be called whenever one of them is turned on or off
+pub fn update() {
+    let mut thread_id = HOOK_THREAD_ID.lock();
+
+    match (*thread_id, is_needed()) {
+        (None, true) => match start() {
+            Ok(id) => *thread_id = Some(id),
+            Err(error) => tracing::error!("could not install low-level mouse hook: {error}"),
+        },
+        (Some(id), false) => {
+            // The hook is removed by the thread that installed it once it leaves its message loop
+            if let Err(error) =
+                unsafe { PostThreadMessageW(id, WM_QUIT, WPARAM::default(), LPARAM::default()) }
+            {
+                tracing::warn!("could not remove low-level mouse hook: {error}");
+                return;
+            }
+
+            *thread_id = None;
+        }
+        _ => {}
+    }
+}
+
+fn start() -> color_eyre::Result<u32> {
+    let (tx, rx) = crossbeam_channel::bounded(1);
+
+    std::thread::spawn(move || {
+        let hook = match unsafe {
+            SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_hook), HINSTANCE::default(), 0)
+        } {
+            Ok(hook) => hook,
+            Err(error) => {
+                let _ = tx.send(Err(error));
+                return;
+            }
+        };
+
+        // Installing the hook gives this thread the message queue that it is stopped through
+        let _ = tx.send(Ok(unsafe { GetCurrentThreadId() }));
+
+        tracing::info!("listening");
+
+        // The hook is called on this thread, and only while its messages are being dispatched
+        unsafe {
+            let mut msg = MSG::default();
+
+            while GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {
+                // TODO: error handling
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            // Hooks have to be removed from the same thread that they were installed on
+            if UnhookWindowsHookEx(hook).is_err() {
+                tracing::warn!("could not remove low-level mouse hook");
+            }
+        }
+    });
+
+    let thread_id = rx.recv_timeout(Duration::from_secs(1))??;
+
+    Ok(thread_id)
+}
+
+/// Hands every mouse event to each of the features in turn, until one of them swallows it; mouse
+/// movements are never swallowed, so every feature sees all of them
+unsafe extern "system" fn mouse_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    if code >= 0 {
+        let event = unsafe { &*(lparam.0 as *const MSLLHOOKSTRUCT) };
+        let message = wparam.0 as u32;
+
+        if drag_manager::handle_mouse_input(message, event)
+            || gap_drag_manager::handle_mouse_input(message, event)
+            || scroll_manager::handle_mouse_input(message, event)
+            || hot_edge_manager::handle_mouse_input(message, event)
+        {
+            return LRESULT(1);
+        }
+    }
+
+    unsafe { CallNextHookEx(HHOOK::default(), code, wparam, lparam) }
+}
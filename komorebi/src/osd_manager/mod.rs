@@ -0,0 +1,143 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+mod osd;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use crossbeam_utils::atomic::AtomicConsume;
+use parking_lot::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::core::Rect;
+use crate::osd_manager::osd::Osd;
+use crate::State;
+use crate::StateDelta;
+
+pub static OSD_ENABLED: AtomicBool = AtomicBool::new(false);
+pub static OSD_DURATION: AtomicU64 = AtomicU64::new(1000);
+
+/// What is currently drawn by the overlay, which is read whenever its window is painted
+static OSD_CONTENT: Mutex<Option<Notification>> = Mutex::new(None);
+
+#[derive(Debug, Clone)]
+pub struct OsdWorkspace {
+    name: String,
+    windows: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    work_area: Rect,
+    workspaces: Vec<OsdWorkspace>,
+    focused_workspace_idx: usize,
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_state_notifications(state: &State, delta: &StateDelta) {
+    if !OSD_ENABLED.load_consume() {
+        return;
+    }
+
+    for monitor_delta in &delta.monitors {
+        if monitor_delta.focused_workspace_idx.is_none() {
+            continue;
+        }
+
+        let Some(monitor) = state.monitors.elements().get(monitor_delta.monitor_idx) else {
+            continue;
+        };
+
+        let workspaces = monitor
+            .workspaces()
+            .iter()
+            .enumerate()
+            .map(|(idx, workspace)| OsdWorkspace {
+                name: workspace
+                    .name()
+                    .clone()
+                    .unwrap_or_else(|| format!("{}", idx + 1)),
+                windows: workspace.containers().len()
+                    + workspace.floating_windows().len()
+                    + usize::from(workspace.monocle_container().is_some())
+                    + usize::from(workspace.maximized_window().is_some()),
+            })
+            .collect();
+
+        let notification = Notification {
+            work_area: *monitor.work_area_size(),
+            workspaces,
+            focused_workspace_idx: monitor.focused_workspace_idx(),
+        };
+
+        if event_tx().try_send(notification).is_err() {
+            tracing::warn!("channel is full; dropping notification")
+        }
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+    let osd = Osd::create()?;
+    let mut hide_at = None;
+
+    loop {
+        // The overlay stays up for the configured duration after the most recent switch, so
+        // cycling through several workspaces in a row doesn't make it flicker
+        let notification = match hide_at {
+            Some(deadline) => match receiver.recv_deadline(deadline) {
+                Ok(notification) => notification,
+                Err(RecvTimeoutError::Timeout) => {
+                    osd.hide();
+                    hide_at = None;
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match receiver.recv() {
+                Ok(notification) => notification,
+                Err(_) => break,
+            },
+        };
+
+        if let Err(error) = osd.show(notification) {
+            tracing::error!("could not show workspace overlay: {error}");
+        }
+
+        hide_at = Some(Instant::now() + Duration::from_millis(OSD_DURATION.load_consume()));
+    }
+
+    osd.destroy()
+}
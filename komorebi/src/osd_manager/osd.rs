@@ -0,0 +1,275 @@
+use crate::border_manager;
+use crate::core::Rect;
+use crate::osd_manager::Notification;
+use crate::osd_manager::OSD_CONTENT;
+use crate::stackbar_manager::STACKBAR_FOCUSED_TEXT_COLOUR;
+use crate::stackbar_manager::STACKBAR_FONT_FAMILY;
+use crate::stackbar_manager::STACKBAR_TAB_BACKGROUND_COLOUR;
+use crate::stackbar_manager::STACKBAR_UNFOCUSED_TEXT_COLOUR;
+use crate::windows_api;
+use crate::WindowsApi;
+use crossbeam_utils::atomic::AtomicConsume;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::time::Duration;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::COLORREF;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::Graphics::Gdi::BeginPaint;
+use windows::Win32::Graphics::Gdi::CreateFontIndirectW;
+use windows::Win32::Graphics::Gdi::DeleteObject;
+use windows::Win32::Graphics::Gdi::DrawTextW;
+use windows::Win32::Graphics::Gdi::Ellipse;
+use windows::Win32::Graphics::Gdi::EndPaint;
+use windows::Win32::Graphics::Gdi::GetDeviceCaps;
+use windows::Win32::Graphics::Gdi::GetStockObject;
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::Graphics::Gdi::RoundRect;
+use windows::Win32::Graphics::Gdi::SelectObject;
+use windows::Win32::Graphics::Gdi::SetBkColor;
+use windows::Win32::Graphics::Gdi::SetDCBrushColor;
+use windows::Win32::Graphics::Gdi::SetDCPenColor;
+use windows::Win32::Graphics::Gdi::SetTextColor;
+use windows::Win32::Graphics::Gdi::DC_BRUSH;
+use windows::Win32::Graphics::Gdi::DC_PEN;
+use windows::Win32::Graphics::Gdi::DT_CENTER;
+use windows::Win32::Graphics::Gdi::DT_END_ELLIPSIS;
+use windows::Win32::Graphics::Gdi::DT_SINGLELINE;
+use windows::Win32::Graphics::Gdi::DT_VCENTER;
+use windows::Win32::Graphics::Gdi::FONT_QUALITY;
+use windows::Win32::Graphics::Gdi::FW_BOLD;
+use windows::Win32::Graphics::Gdi::HDC;
+use windows::Win32::Graphics::Gdi::LOGFONTW;
+use windows::Win32::Graphics::Gdi::LOGPIXELSY;
+use windows::Win32::Graphics::Gdi::PAINTSTRUCT;
+use windows::Win32::Graphics::Gdi::PROOF_QUALITY;
+use windows::Win32::System::WindowsProgramming::MulDiv;
+use windows::Win32::UI::WindowsAndMessaging::DefWindowProcW;
+use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
+use windows::Win32::UI::WindowsAndMessaging::GetMessageW;
+use windows::Win32::UI::WindowsAndMessaging::PostQuitMessage;
+use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
+use windows::Win32::UI::WindowsAndMessaging::CS_HREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::CS_VREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::WM_DESTROY;
+use windows::Win32::UI::WindowsAndMessaging::WM_PAINT;
+use windows::Win32::UI::WindowsAndMessaging::WNDCLASSW;
+
+const TILE_WIDTH: i32 = 120;
+const TILE_HEIGHT: i32 = 72;
+const GAP: i32 = 12;
+const DOT_SIZE: i32 = 8;
+const MAX_DOTS: usize = 6;
+const FONT_SIZE: i32 = 12;
+
+#[derive(Debug)]
+pub struct Osd {
+    pub hwnd: isize,
+}
+
+impl Osd {
+    pub const fn hwnd(&self) -> HWND {
+        HWND(windows_api::as_ptr!(self.hwnd))
+    }
+
+    pub fn create() -> color_eyre::Result<Self> {
+        let name: Vec<u16> = "komoosd\0".encode_utf16().collect();
+        let class_name = PCWSTR(name.as_ptr());
+
+        let h_module = WindowsApi::module_handle_w()?;
+
+        let window_class = WNDCLASSW {
+            hInstance: h_module.into(),
+            lpszClassName: class_name,
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(Self::callback),
+            hbrBackground: WindowsApi::create_solid_brush(0),
+            ..Default::default()
+        };
+
+        let _ = WindowsApi::register_class_w(&window_class);
+
+        let (hwnd_sender, hwnd_receiver) = mpsc::channel();
+
+        let instance = h_module.0 as isize;
+        std::thread::spawn(move || -> color_eyre::Result<()> {
+            // Like borders, the overlay is a topmost layered window which never takes focus and
+            // which is transparent wherever nothing has been drawn
+            let hwnd = WindowsApi::create_border_window(PCWSTR(name.as_ptr()), instance)?;
+            hwnd_sender.send(hwnd)?;
+
+            let mut msg: MSG = MSG::default();
+
+            loop {
+                unsafe {
+                    if !GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {
+                        tracing::debug!("osd window event processing thread shutdown");
+                        break;
+                    };
+                    // TODO: error handling
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+
+                std::thread::sleep(Duration::from_millis(10))
+            }
+
+            Ok(())
+        });
+
+        Ok(Self {
+            hwnd: hwnd_receiver.recv()?,
+        })
+    }
+
+    pub fn destroy(&self) -> color_eyre::Result<()> {
+        WindowsApi::close_window(self.hwnd)
+    }
+
+    /// Shows the overlay centred on the work area of the monitor in the notification
+    pub fn show(&self, notification: Notification) -> color_eyre::Result<()> {
+        let count = i32::try_from(notification.workspaces.len())?;
+        let width = count * TILE_WIDTH + (count + 1) * GAP;
+        let height = TILE_HEIGHT + 2 * GAP;
+        let work_area = notification.work_area;
+
+        let rect = Rect {
+            left: work_area.left + (work_area.right - width) / 2,
+            top: work_area.top + (work_area.bottom - height) / 2,
+            right: width,
+            bottom: height,
+        };
+
+        *OSD_CONTENT.lock() = Some(notification);
+
+        WindowsApi::position_window(self.hwnd, &rect, true)?;
+        WindowsApi::restore_window(self.hwnd);
+
+        let _ = unsafe { InvalidateRect(self.hwnd(), None, true) };
+
+        Ok(())
+    }
+
+    pub fn hide(&self) {
+        WindowsApi::hide_window(self.hwnd);
+    }
+
+    pub extern "system" fn callback(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        unsafe {
+            match message {
+                WM_PAINT => {
+                    let mut ps = PAINTSTRUCT::default();
+                    let hdc = BeginPaint(window, &mut ps);
+
+                    if let Some(notification) = &*OSD_CONTENT.lock() {
+                        paint(hdc, notification);
+                    }
+
+                    // TODO: error handling
+                    let _ = EndPaint(window, &ps);
+                    LRESULT(0)
+                }
+                WM_DESTROY => {
+                    PostQuitMessage(0);
+                    LRESULT(0)
+                }
+                _ => DefWindowProcW(window, message, wparam, lparam),
+            }
+        }
+    }
+}
+
+/// Draws a tile for each workspace with its name and a dot for each of its windows, using the
+/// stackbar colours for the tiles and the focused border colour for the focused workspace
+unsafe fn paint(hdc: HDC, notification: &Notification) {
+    let background = STACKBAR_TAB_BACKGROUND_COLOUR.load_consume();
+    let focused_text_colour = STACKBAR_FOCUSED_TEXT_COLOUR.load_consume();
+    let unfocused_text_colour = STACKBAR_UNFOCUSED_TEXT_COLOUR.load_consume();
+    let highlight = border_manager::FOCUSED.load(Ordering::SeqCst);
+
+    let mut logfont = LOGFONTW {
+        lfWeight: FW_BOLD.0 as i32,
+        lfQuality: FONT_QUALITY(PROOF_QUALITY.0),
+        lfHeight: -MulDiv(FONT_SIZE, GetDeviceCaps(hdc, LOGPIXELSY), 72),
+        ..Default::default()
+    };
+
+    if let Some(font_name) = &*STACKBAR_FONT_FAMILY.lock() {
+        // The last element is left as the nul terminator
+        for (i, c) in font_name.encode_utf16().take(31).enumerate() {
+            logfont.lfFaceName[i] = c;
+        }
+    }
+
+    let hfont = CreateFontIndirectW(&logfont);
+
+    let previous_font = SelectObject(hdc, hfont);
+    SelectObject(hdc, GetStockObject(DC_PEN));
+    SelectObject(hdc, GetStockObject(DC_BRUSH));
+
+    for (idx, workspace) in notification.workspaces.iter().enumerate() {
+        let (fill, text_colour) = if idx == notification.focused_workspace_idx {
+            (highlight, focused_text_colour)
+        } else {
+            (background, unfocused_text_colour)
+        };
+
+        let left = GAP + idx as i32 * (TILE_WIDTH + GAP);
+        let right = left + TILE_WIDTH;
+        let bottom = GAP + TILE_HEIGHT;
+
+        SetDCPenColor(hdc, COLORREF(fill));
+        SetDCBrushColor(hdc, COLORREF(fill));
+        // TODO: error handling
+        let _ = RoundRect(hdc, left, GAP, right, bottom, 12, 12);
+
+        SetBkColor(hdc, COLORREF(fill));
+        SetTextColor(hdc, COLORREF(text_colour));
+
+        let mut label: Vec<u16> = workspace.name.encode_utf16().collect();
+        let mut rect = RECT {
+            left: left + 8,
+            top: GAP,
+            right: right - 8,
+            bottom: bottom - DOT_SIZE * 2,
+        };
+
+        DrawTextW(
+            hdc,
+            &mut label,
+            &mut rect,
+            DT_SINGLELINE | DT_CENTER | DT_VCENTER | DT_END_ELLIPSIS,
+        );
+
+        let dots = workspace.windows.min(MAX_DOTS) as i32;
+
+        if dots > 0 {
+            SetDCPenColor(hdc, COLORREF(text_colour));
+            SetDCBrushColor(hdc, COLORREF(text_colour));
+
+            let dots_width = dots * DOT_SIZE + (dots - 1) * DOT_SIZE;
+            let top = bottom - DOT_SIZE * 2;
+            let mut dot_left = left + (TILE_WIDTH - dots_width) / 2;
+
+            for _ in 0..dots {
+                // TODO: error handling
+                let _ = Ellipse(hdc, dot_left, top, dot_left + DOT_SIZE, top + DOT_SIZE);
+                dot_left += DOT_SIZE * 2;
+            }
+        }
+    }
+
+    SelectObject(hdc, previous_font);
+    // TODO: error handling
+    let _ = DeleteObject(hfont);
+}
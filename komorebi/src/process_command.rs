@@ -4,22 +4,32 @@ use std::fs::OpenOptions;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Read;
-use std::net::Shutdown;
+use std::io::Write;
+use std::net::IpAddr;
+use std::net::SocketAddr;
 use std::net::TcpListener;
 use std::net::TcpStream;
 use std::num::NonZeroUsize;
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::OnceLock;
 use std::time::Duration;
 
 use color_eyre::eyre::anyhow;
+use color_eyre::eyre::bail;
 use color_eyre::Result;
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
 use miow::pipe::connect;
 use net2::TcpStreamExt;
 use parking_lot::Mutex;
 use schemars::gen::SchemaSettings;
 use schemars::schema_for;
+use tracing_subscriber::filter::Directive;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::EnvFilter;
 use uds_windows::UnixStream;
 
 use crate::core::config_generation::ApplicationConfiguration;
@@ -29,16 +39,23 @@ use crate::core::config_generation::MatchingStrategy;
 use crate::core::ApplicationIdentifier;
 use crate::core::Axis;
 use crate::core::BorderImplementation;
+use crate::core::CommandFailure;
 use crate::core::FocusFollowsMouseImplementation;
+use crate::core::Health;
+use crate::core::KomorebiError;
 use crate::core::Layout;
+use crate::core::LogLevel;
 use crate::core::MoveBehaviour;
 use crate::core::OperationDirection;
 use crate::core::Rect;
 use crate::core::Sizing;
 use crate::core::SocketMessage;
 use crate::core::StateQuery;
+use crate::core::VersionInformation;
+use crate::core::VersionedSocketMessage;
 use crate::core::WindowContainerBehaviour;
 use crate::core::WindowKind;
+use crate::core::PROTOCOL_VERSION;
 
 use crate::border_manager;
 use crate::border_manager::IMPLEMENTATION;
@@ -46,12 +63,22 @@ use crate::border_manager::STYLE;
 use crate::colour::Rgb;
 use crate::config_generation::WorkspaceMatchingRule;
 use crate::current_virtual_desktop;
-use crate::notify_subscribers;
+use crate::flash_manager;
+use crate::hint_manager;
+use crate::metrics;
+use crate::process_event;
+use crate::session::Session;
+use crate::session_manager;
 use crate::stackbar_manager;
 use crate::stackbar_manager::STACKBAR_FONT_FAMILY;
 use crate::stackbar_manager::STACKBAR_FONT_SIZE;
 use crate::static_config::StaticConfig;
+use crate::switcher_manager;
+use crate::switcher_manager::SwitcherEntry;
+use crate::switcher_manager::SwitcherGroup;
+use crate::system_api;
 use crate::theme_manager;
+use crate::toast_manager;
 use crate::transparency_manager;
 use crate::window::RuleDebug;
 use crate::window::Window;
@@ -62,6 +89,7 @@ use crate::winevent_listener;
 use crate::GlobalState;
 use crate::Notification;
 use crate::NotificationEvent;
+use crate::PendingNotification;
 use crate::State;
 use crate::ANIMATION_DURATION;
 use crate::ANIMATION_ENABLED;
@@ -70,15 +98,18 @@ use crate::ANIMATION_STYLE;
 use crate::CUSTOM_FFM;
 use crate::DATA_DIR;
 use crate::DISPLAY_INDEX_PREFERENCES;
+use crate::FOCUS_FOLLOWS_MOUSE_DELAY;
 use crate::HIDING_BEHAVIOUR;
 use crate::IGNORE_IDENTIFIERS;
 use crate::INITIAL_CONFIGURATION_LOADED;
 use crate::LAYERED_WHITELIST;
+use crate::LOG_FILTER;
 use crate::MANAGE_IDENTIFIERS;
 use crate::MONITOR_INDEX_PREFERENCES;
 use crate::NO_TITLEBAR;
 use crate::OBJECT_NAME_CHANGE_ON_LAUNCH;
 use crate::REMOVE_TITLEBARS;
+use crate::RESTORE_SESSION;
 use crate::SUBSCRIPTION_PIPES;
 use crate::SUBSCRIPTION_SOCKETS;
 use crate::SUBSCRIPTION_SOCKET_OPTIONS;
@@ -94,6 +125,54 @@ use stackbar_manager::STACKBAR_TAB_HEIGHT;
 use stackbar_manager::STACKBAR_TAB_WIDTH;
 use stackbar_manager::STACKBAR_UNFOCUSED_TEXT_COLOUR;
 
+/// A command received on komorebi.sock or over TCP, which is handled on the same thread as window
+/// manager events so that commands and events never have to wait on each other for the lock
+pub struct CommandRequest {
+    message: SocketMessage,
+    reply: Sender<(Vec<u8>, Result<()>)>,
+}
+
+static COMMAND_CHANNEL: OnceLock<(Sender<CommandRequest>, Receiver<CommandRequest>)> =
+    OnceLock::new();
+
+// Commands used to be dropped when the window manager lock could not be acquired within a second,
+// and they are not left waiting for room on the queue for any longer than that either
+const COMMAND_QUEUE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// Once a command has been queued it has to wait for the events and commands ahead of it too
+const COMMAND_REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn command_channel() -> &'static (Sender<CommandRequest>, Receiver<CommandRequest>) {
+    COMMAND_CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+pub fn command_rx() -> Receiver<CommandRequest> {
+    command_channel().1.clone()
+}
+
+/// Queues a command to be handled by the window manager and waits for the reply that it produces
+pub fn submit_command(message: SocketMessage) -> Result<(Vec<u8>, Result<()>)> {
+    let (reply_tx, reply_rx) = crossbeam_channel::bounded(1);
+
+    command_channel()
+        .0
+        .send_timeout(
+            CommandRequest {
+                message,
+                reply: reply_tx,
+            },
+            COMMAND_QUEUE_TIMEOUT,
+        )
+        .map_err(|_| anyhow!("the command queue is full; dropping command"))?;
+
+    reply_rx
+        .recv_timeout(COMMAND_REPLY_TIMEOUT)
+        .map_err(|error| match error {
+            RecvTimeoutError::Timeout => anyhow!("timed out waiting for a reply to the command"),
+            RecvTimeoutError::Disconnected => anyhow!("the command was dropped without a reply"),
+        })
+}
+
 #[tracing::instrument]
 pub fn listen_for_commands(wm: Arc<Mutex<WindowManager>>) {
     std::thread::spawn(move || loop {
@@ -109,9 +188,12 @@ pub fn listen_for_commands(wm: Arc<Mutex<WindowManager>>) {
             tracing::info!("listening on komorebi.sock");
             for client in listener.incoming() {
                 match client {
-                    Ok(stream) => match read_commands_uds(&wm, stream) {
+                    Ok(stream) => match read_commands_uds(stream) {
                         Ok(()) => {}
-                        Err(error) => tracing::error!("{}", error),
+                        Err(error) => {
+                            tracing::error!("{}", error);
+                            toast_manager::send_error_notification(&error.to_string());
+                        }
                     },
                     Err(error) => {
                         tracing::error!("{}", error);
@@ -127,44 +209,60 @@ pub fn listen_for_commands(wm: Arc<Mutex<WindowManager>>) {
 }
 
 #[tracing::instrument]
-pub fn listen_for_commands_tcp(wm: Arc<Mutex<WindowManager>>, port: usize) {
-    let listener =
-        TcpListener::bind(format!("0.0.0.0:{port}")).expect("could not start tcp server");
-
-    std::thread::spawn(move || {
-        tracing::info!("listening on 0.0.0.0:43663");
-        for client in listener.incoming() {
-            match client {
-                Ok(mut stream) => {
-                    stream
-                        .set_keepalive(Some(Duration::from_secs(30)))
-                        .expect("TCP keepalive should be set");
-
-                    let addr = stream
-                        .peer_addr()
-                        .expect("incoming connection should have an address")
-                        .to_string();
+pub fn listen_for_commands_tcp(address: IpAddr, port: u16) {
+    let socket = SocketAddr::new(address, port);
+    let listener = TcpListener::bind(socket).expect("could not start tcp server");
+
+    if !address.is_loopback() {
+        tracing::warn!(
+            "the tcp server is accepting connections from other machines on {socket}, only do this on a trusted network"
+        );
+    }
 
-                    let mut connections = TCP_CONNECTIONS.lock();
+    std::thread::spawn(move || loop {
+        let listener = listener.try_clone().expect("could not clone tcp listener");
 
-                    connections.insert(
-                        addr.clone(),
-                        stream.try_clone().expect("stream should be cloneable"),
-                    );
+        let _ = std::thread::spawn(move || {
+            tracing::info!("listening on {socket}");
+            for client in listener.incoming() {
+                match client {
+                    Ok(mut stream) => {
+                        stream
+                            .set_keepalive(Some(Duration::from_secs(30)))
+                            .expect("TCP keepalive should be set");
 
-                    tracing::info!("listening for incoming tcp messages from {}", &addr);
+                        let addr = stream
+                            .peer_addr()
+                            .expect("incoming connection should have an address")
+                            .to_string();
 
-                    match read_commands_tcp(&wm, &mut stream, &addr) {
-                        Ok(()) => {}
-                        Err(error) => tracing::error!("{}", error),
+                        let mut connections = TCP_CONNECTIONS.lock();
+
+                        connections.insert(
+                            addr.clone(),
+                            stream.try_clone().expect("stream should be cloneable"),
+                        );
+
+                        tracing::info!("listening for incoming tcp messages from {}", &addr);
+
+                        match read_commands_tcp(&mut stream, &addr) {
+                            Ok(()) => {}
+                            Err(error) => {
+                                tracing::error!("{}", error);
+                                toast_manager::send_error_notification(&error.to_string());
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        tracing::error!("{}", error);
+                        break;
                     }
-                }
-                Err(error) => {
-                    tracing::error!("{}", error);
-                    break;
                 }
             }
-        }
+        })
+        .join();
+
+        tracing::error!("restarting failed thread");
     });
 }
 
@@ -172,7 +270,7 @@ impl WindowManager {
     // TODO(raggi): wrap reply in a newtype that can decorate a human friendly
     // name for the peer, such as getting the pid of the komorebic process for
     // the UDS or the IP:port for TCP.
-    #[tracing::instrument(skip(self, reply))]
+    #[tracing::instrument(skip(self, reply), fields(monitor = self.focused_monitor_idx(), workspace = self.focused_workspace_idx().ok()))]
     pub fn process_command(
         &mut self,
         message: SocketMessage,
@@ -193,6 +291,7 @@ impl WindowManager {
         #[allow(clippy::useless_asref)]
         // We don't have From implemented for &mut WindowManager
         let initial_state = State::from(self.as_ref());
+        let initial_focused_hwnd = self.focused_hwnd();
 
         match message {
             SocketMessage::CycleFocusWorkspace(_) | SocketMessage::FocusWorkspaceNumber(_) => {
@@ -230,6 +329,9 @@ impl WindowManager {
             SocketMessage::CycleMoveWindow(direction) => {
                 self.move_container_in_cycle_direction(direction)?;
             }
+            SocketMessage::RotateContainers(direction) => {
+                self.rotate_containers(direction)?;
+            }
             SocketMessage::StackWindow(direction) => self.add_window_to_container(direction)?,
             SocketMessage::UnstackWindow => self.remove_window_from_container()?,
             SocketMessage::StackAll => self.stack_all()?,
@@ -255,9 +357,98 @@ impl WindowManager {
                 WindowsApi::center_cursor_in_rect(&focused_window_rect)?;
                 WindowsApi::left_click();
             }
+            SocketMessage::Hint => {
+                let mut hwnds = vec![];
+
+                for monitor in self.monitors() {
+                    let Some(workspace) = monitor.focused_workspace() else {
+                        continue;
+                    };
+
+                    if let Some(window) = workspace.maximized_window() {
+                        hwnds.push(window.hwnd);
+                    } else if let Some(container) = workspace.monocle_container() {
+                        hwnds.extend(container.focused_window().map(|window| window.hwnd));
+                    } else {
+                        hwnds.extend(
+                            workspace
+                                .containers()
+                                .iter()
+                                .filter_map(|container| container.focused_window())
+                                .map(|window| window.hwnd),
+                        );
+                        hwnds.extend(
+                            workspace
+                                .floating_windows()
+                                .iter()
+                                .map(|window| window.hwnd),
+                        );
+                    }
+                }
+
+                hint_manager::send_notification(hwnds, self.mouse_follows_focus);
+            }
+            SocketMessage::FocusLast => {
+                self.focus_last()?;
+            }
+            SocketMessage::JumpToExe(ref exe, launch) => {
+                self.jump_to_exe(exe, launch)?;
+            }
+            SocketMessage::Switcher => {
+                let mut groups = vec![];
+
+                for monitor in self.monitors() {
+                    for (workspace_idx, workspace) in monitor.workspaces().iter().enumerate() {
+                        let workspace_name = workspace
+                            .name()
+                            .clone()
+                            .unwrap_or_else(|| format!("{}", workspace_idx + 1));
+
+                        let mut windows = vec![];
+
+                        if let Some(window) = workspace.maximized_window() {
+                            windows.push(*window);
+                        }
+
+                        if let Some(container) = workspace.monocle_container() {
+                            windows.extend(container.focused_window().copied());
+                        }
+
+                        windows.extend(
+                            workspace
+                                .containers()
+                                .iter()
+                                .filter_map(|container| container.focused_window())
+                                .copied(),
+                        );
+                        windows.extend(workspace.floating_windows().iter().copied());
+
+                        groups.push(SwitcherGroup {
+                            label: format!("{} / {}", monitor.name(), workspace_name),
+                            entries: windows
+                                .into_iter()
+                                .map(|window| SwitcherEntry {
+                                    hwnd: window.hwnd,
+                                    title: window.title().unwrap_or_default(),
+                                })
+                                .collect(),
+                        });
+                    }
+                }
+
+                let focused_hwnd = WindowsApi::foreground_window().ok();
+                switcher_manager::send_notification(
+                    self.focused_monitor_work_area()?,
+                    groups,
+                    focused_hwnd,
+                );
+            }
             SocketMessage::Close => {
                 Window::from(WindowsApi::foreground_window()?).close()?;
             }
+            SocketMessage::CloseWorkspaceWindows(force) => {
+                self.close_workspace_windows(force)?;
+            }
             SocketMessage::Minimize => {
                 Window::from(WindowsApi::foreground_window()?).minimize();
             }
@@ -461,7 +652,7 @@ impl WindowManager {
                     let monitor = self
                         .monitors_mut()
                         .get_mut(monitor_idx)
-                        .ok_or_else(|| anyhow!("there is no monitor"))?;
+                        .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
                     monitor
                         .focused_workspace_mut()
@@ -571,22 +762,28 @@ impl WindowManager {
                 self.move_container_to_monitor(monitor_idx, Option::from(workspace_idx), true)?;
             }
             SocketMessage::SendContainerToNamedWorkspace(ref workspace) => {
-                if let Some((monitor_idx, workspace_idx)) =
-                    self.monitor_workspace_index_by_name(workspace)
-                {
-                    self.move_container_to_monitor(
-                        monitor_idx,
-                        Option::from(workspace_idx),
-                        false,
-                    )?;
-                }
+                let (monitor_idx, workspace_idx) = self
+                    .monitor_workspace_index_by_name(workspace)
+                    .ok_or_else(|| {
+                        KomorebiError::NoSuchNamedWorkspace(
+                            workspace.clone(),
+                            self.workspace_names(),
+                        )
+                    })?;
+
+                self.move_container_to_monitor(monitor_idx, Option::from(workspace_idx), false)?;
             }
             SocketMessage::MoveContainerToNamedWorkspace(ref workspace) => {
-                if let Some((monitor_idx, workspace_idx)) =
-                    self.monitor_workspace_index_by_name(workspace)
-                {
-                    self.move_container_to_monitor(monitor_idx, Option::from(workspace_idx), true)?;
-                }
+                let (monitor_idx, workspace_idx) = self
+                    .monitor_workspace_index_by_name(workspace)
+                    .ok_or_else(|| {
+                        KomorebiError::NoSuchNamedWorkspace(
+                            workspace.clone(),
+                            self.workspace_names(),
+                        )
+                    })?;
+
+                self.move_container_to_monitor(monitor_idx, Option::from(workspace_idx), true)?;
             }
 
             SocketMessage::MoveWorkspaceToMonitorNumber(monitor_idx) => {
@@ -614,6 +811,15 @@ impl WindowManager {
             SocketMessage::ToggleTiling => {
                 self.toggle_tiling()?;
             }
+            SocketMessage::ToggleFloatLayer => {
+                self.toggle_float_layer()?;
+            }
+            SocketMessage::SetFloatRect(rect, monitor_relative) => {
+                self.set_float_rect(rect, monitor_relative)?;
+            }
+            SocketMessage::CenterFloat(percentage) => {
+                self.center_float(percentage)?;
+            }
             SocketMessage::CycleFocusMonitor(direction) => {
                 let monitor_idx = direction.next_idx(
                     self.focused_monitor_idx(),
@@ -631,12 +837,12 @@ impl WindowManager {
             SocketMessage::Retile => {
                 border_manager::BORDER_TEMPORARILY_DISABLED.store(false, Ordering::SeqCst);
                 border_manager::destroy_all_borders()?;
-                self.retile_all(false)?
+                self.force_retile_all(false)?
             }
             SocketMessage::RetileWithResizeDimensions => {
                 border_manager::BORDER_TEMPORARILY_DISABLED.store(false, Ordering::SeqCst);
                 border_manager::destroy_all_borders()?;
-                self.retile_all(true)?
+                self.force_retile_all(true)?
             }
             SocketMessage::FlipLayout(layout_flip) => self.flip_layout(layout_flip)?,
             SocketMessage::ChangeLayout(layout) => self.change_workspace_layout_default(layout)?,
@@ -827,32 +1033,19 @@ impl WindowManager {
                 }
             }
             SocketMessage::FocusNamedWorkspace(ref name) => {
-                if let Some((monitor_idx, workspace_idx)) =
-                    self.monitor_workspace_index_by_name(name)
-                {
-                    self.focus_monitor(monitor_idx)?;
-                    self.focus_workspace(workspace_idx)?;
-                }
+                let (monitor_idx, workspace_idx) =
+                    self.monitor_workspace_index_by_name(name).ok_or_else(|| {
+                        KomorebiError::NoSuchNamedWorkspace(name.clone(), self.workspace_names())
+                    })?;
+
+                self.focus_monitor(monitor_idx)?;
+                self.focus_workspace(workspace_idx)?;
             }
             SocketMessage::Stop => {
                 tracing::info!(
                     "received stop command, restoring all hidden windows and terminating process"
                 );
-                self.restore_all_windows()?;
-
-                if WindowsApi::focus_follows_mouse()? {
-                    WindowsApi::disable_focus_follows_mouse()?;
-                }
-
-                let sockets = SUBSCRIPTION_SOCKETS.lock();
-                for path in (*sockets).values() {
-                    if let Ok(stream) = UnixStream::connect(path) {
-                        stream.shutdown(Shutdown::Both)?;
-                    }
-                }
-
-                let socket = DATA_DIR.join("komorebi.sock");
-                let _ = std::fs::remove_file(socket);
+                self.shutdown()?;
 
                 std::process::exit(0)
             }
@@ -897,6 +1090,16 @@ impl WindowManager {
 
                 tracing::info!("replying to state done");
             }
+            SocketMessage::CompactState => {
+                // Pretty-printing the whole state is only worth it for a human reading the output
+                // of 'komorebic state'; bars and scripts which query it often get it compact
+                let state = match serde_json::to_string(&window_manager::State::from(&*self)) {
+                    Ok(state) => state,
+                    Err(error) => error.to_string(),
+                };
+
+                reply.write_all(state.as_bytes())?;
+            }
             SocketMessage::GlobalState => {
                 let state = match serde_json::to_string_pretty(&GlobalState::default()) {
                     Ok(state) => state,
@@ -926,6 +1129,28 @@ impl WindowManager {
 
                 reply.write_all(visible_windows_state.as_bytes())?;
             }
+            SocketMessage::KnownHwnds => {
+                let known_hwnds = serde_json::to_string_pretty(&self.known_hwnds())
+                    .unwrap_or_else(|error| error.to_string());
+
+                reply.write_all(known_hwnds.as_bytes())?;
+            }
+            SocketMessage::CheckHealth => {
+                let health = serde_json::to_string_pretty(&health())?;
+                reply.write_all(health.as_bytes())?;
+            }
+            SocketMessage::Stats => {
+                let stats = serde_json::to_string_pretty(&metrics::stats())
+                    .unwrap_or_else(|error| error.to_string());
+
+                reply.write_all(stats.as_bytes())?;
+            }
+            SocketMessage::BarStatus => {
+                let bar_status = serde_json::to_string_pretty(&State::from(&*self).bar_status())
+                    .unwrap_or_else(|error| error.to_string());
+
+                reply.write_all(bar_status.as_bytes())?;
+            }
             SocketMessage::MonitorInformation => {
                 let mut monitors = HashMap::new();
                 for monitor in self.monitors() {
@@ -939,25 +1164,43 @@ impl WindowManager {
             }
             SocketMessage::Query(query) => {
                 let response = match query {
-                    StateQuery::FocusedMonitorIndex => self.focused_monitor_idx(),
+                    StateQuery::FocusedMonitorIndex => self.focused_monitor_idx().to_string(),
                     StateQuery::FocusedWorkspaceIndex => self
                         .focused_monitor()
                         .ok_or_else(|| anyhow!("there is no monitor"))?
-                        .focused_workspace_idx(),
-                    StateQuery::FocusedContainerIndex => {
-                        self.focused_workspace()?.focused_container_idx()
-                    }
+                        .focused_workspace_idx()
+                        .to_string(),
+                    StateQuery::FocusedContainerIndex => self
+                        .focused_workspace()?
+                        .focused_container_idx()
+                        .to_string(),
                     StateQuery::FocusedWindowIndex => {
-                        self.focused_container()?.focused_window_idx()
+                        self.focused_container()?.focused_window_idx().to_string()
                     }
-                }
-                .to_string();
+                    StateQuery::FocusedWindow => {
+                        let hwnd = WindowsApi::foreground_window()?;
+                        serde_json::to_string_pretty(&self.window_query(hwnd))?
+                    }
+                    StateQuery::FocusedWorkspace => {
+                        serde_json::to_string_pretty(&self.focused_workspace_query()?)?
+                    }
+                    StateQuery::FocusedMonitor => {
+                        serde_json::to_string_pretty(&self.focused_monitor_query()?)?
+                    }
+                    StateQuery::WindowAtCursor => {
+                        let hwnd = WindowsApi::root_window(WindowsApi::window_at_cursor_pos()?)?;
+                        serde_json::to_string_pretty(&self.window_query(hwnd))?
+                    }
+                };
 
                 reply.write_all(response.as_bytes())?;
             }
             SocketMessage::ResizeWindowEdge(direction, sizing) => {
                 self.resize_window(direction, sizing, self.resize_delta, true)?;
             }
+            SocketMessage::CycleWidth(direction) => {
+                self.cycle_width(direction)?;
+            }
             SocketMessage::ResizeWindowAxis(axis, sizing) => {
                 // If the user has a custom layout, allow for the resizing of the primary column
                 // with this signal
@@ -1093,7 +1336,9 @@ impl WindowManager {
                                 "the windows implementation of focus follows mouse cannot be enabled while the komorebi implementation is enabled"
                             );
                         } else if enable {
-                            WindowsApi::enable_focus_follows_mouse()?;
+                            WindowsApi::enable_focus_follows_mouse(
+                                FOCUS_FOLLOWS_MOUSE_DELAY.load(Ordering::SeqCst),
+                            )?;
                             self.focus_follows_mouse =
                                 Option::from(FocusFollowsMouseImplementation::Windows);
                         } else {
@@ -1143,7 +1388,9 @@ impl WindowManager {
                         } else {
                             match self.focus_follows_mouse {
                                 None => {
-                                    WindowsApi::enable_focus_follows_mouse()?;
+                                    WindowsApi::enable_focus_follows_mouse(
+                                        FOCUS_FOLLOWS_MOUSE_DELAY.load(Ordering::SeqCst),
+                                    )?;
                                     self.focus_follows_mouse = Option::from(implementation);
                                 }
                                 Some(FocusFollowsMouseImplementation::Windows) => {
@@ -1276,6 +1523,12 @@ impl WindowManager {
                     self.retile_all(false)?;
                 }
             }
+            SocketMessage::HideTaskbar(monitor_idx) => {
+                self.set_taskbar_visibility(monitor_idx, false)?;
+            }
+            SocketMessage::ShowTaskbar(monitor_idx) => {
+                self.set_taskbar_visibility(monitor_idx, true)?;
+            }
             SocketMessage::QuickSave => {
                 let workspace = self.focused_workspace()?;
                 let resize = workspace.resize_dimensions();
@@ -1331,13 +1584,13 @@ impl WindowManager {
                 let socket_path = DATA_DIR.join(socket);
                 sockets.insert(socket.clone(), socket_path);
             }
-            SocketMessage::AddSubscriberSocketWithOptions(ref socket, options) => {
+            SocketMessage::AddSubscriberSocketWithOptions(ref socket, ref options) => {
                 let mut sockets = SUBSCRIPTION_SOCKETS.lock();
                 let socket_path = DATA_DIR.join(socket);
                 sockets.insert(socket.clone(), socket_path);
 
                 let mut socket_options = SUBSCRIPTION_SOCKET_OPTIONS.lock();
-                socket_options.insert(socket.clone(), options);
+                socket_options.insert(socket.clone(), options.clone());
             }
             SocketMessage::RemoveSubscriberSocket(ref socket) => {
                 let mut sockets = SUBSCRIPTION_SOCKETS.lock();
@@ -1481,6 +1734,9 @@ impl WindowManager {
             SocketMessage::BorderOffset(offset) => {
                 border_manager::BORDER_OFFSET.store(offset, Ordering::SeqCst);
             }
+            SocketMessage::BorderRadius(radius) => {
+                border_manager::BORDER_RADIUS.store(radius, Ordering::SeqCst);
+            }
             SocketMessage::Animation(enable) => {
                 ANIMATION_ENABLED.store(enable, Ordering::SeqCst);
             }
@@ -1570,10 +1826,23 @@ impl WindowManager {
 
                 reply.write_all(config.as_bytes())?;
             }
-            SocketMessage::RemoveTitleBar(_, ref id) => {
+            SocketMessage::RemoveTitleBar(identifier, ref id) => {
                 let mut identifiers = NO_TITLEBAR.lock();
-                if !identifiers.contains(id) {
-                    identifiers.push(id.clone());
+                let mut should_push = true;
+                for i in &*identifiers {
+                    if let MatchingRule::Simple(i) = i {
+                        if i.id.eq(id) {
+                            should_push = false;
+                        }
+                    }
+                }
+
+                if should_push {
+                    identifiers.push(MatchingRule::Simple(IdWithIdentifier {
+                        kind: identifier,
+                        id: id.clone(),
+                        matching_strategy: Option::from(MatchingStrategy::Legacy),
+                    }));
                 }
             }
             SocketMessage::ToggleTitleBars => {
@@ -1582,6 +1851,10 @@ impl WindowManager {
                 self.update_focused_workspace(false, false)?;
             }
             SocketMessage::DebugWindow(hwnd) => {
+                if !WindowsApi::is_window(hwnd) {
+                    return Err(KomorebiError::WindowGone(hwnd).into());
+                }
+
                 let window = Window::from(hwnd);
                 let mut rule_debug = RuleDebug::default();
                 let _ = window.should_manage(None, &mut rule_debug);
@@ -1589,73 +1862,242 @@ impl WindowManager {
 
                 reply.write_all(schema.as_bytes())?;
             }
+            SocketMessage::LogLevel(level) => {
+                let filter = match level {
+                    LogLevel::Error => LevelFilter::ERROR,
+                    LogLevel::Warn => LevelFilter::WARN,
+                    LogLevel::Info => LevelFilter::INFO,
+                    LogLevel::Debug => LevelFilter::DEBUG,
+                    LogLevel::Trace => LevelFilter::TRACE,
+                };
+
+                // Only the default level is changed, and any directives for specific targets which
+                // were given in RUST_LOG when komorebi was started are kept
+                let mut env_filter = EnvFilter::default().add_directive(filter.into());
+                for directive in std::env::var("RUST_LOG").unwrap_or_default().split(',') {
+                    let directive = directive.trim();
+                    if directive.parse::<LevelFilter>().is_ok() {
+                        continue;
+                    }
+
+                    if let Ok(directive) = directive.parse::<Directive>() {
+                        env_filter = env_filter.add_directive(directive);
+                    }
+                }
+
+                LOG_FILTER
+                    .get()
+                    .ok_or_else(|| anyhow!("the log filter has not been initialized"))?
+                    .reload(env_filter)?;
+
+                tracing::info!("log level set to {level}");
+            }
             SocketMessage::Theme(theme) => {
                 theme_manager::send_notification(theme);
             }
+            SocketMessage::Version => {
+                let version = serde_json::to_string_pretty(&VersionInformation::default())?;
+
+                reply.write_all(version.as_bytes())?;
+            }
+            SocketMessage::SaveSession => {
+                Session::from(&*self).save()?;
+            }
+            SocketMessage::DryRun(ref message) => {
+                let dry_run = self.dry_run((**message).clone())?;
+                reply.write_all(serde_json::to_string_pretty(&dry_run)?.as_bytes())?;
+            }
+            SocketMessage::Batch(ref messages) => {
+                if self.batch_in_progress
+                    || messages
+                        .iter()
+                        .any(|message| matches!(message, SocketMessage::Batch(_)))
+                {
+                    bail!("batches cannot be nested");
+                }
+
+                tracing::info!("processing batch of {} commands", messages.len());
+
+                let mut buffer = vec![];
+                self.batch_in_progress = true;
+
+                let mut result = Ok(());
+                for message in messages {
+                    result = self.process_command(message.clone(), &mut buffer);
+                    if result.is_err() {
+                        break;
+                    }
+                }
+
+                // Whatever was applied before a failure still needs to be laid out
+                self.batch_in_progress = false;
+                self.retile_all(true)?;
+                self.update_focused_workspace(self.mouse_follows_focus, true)?;
+
+                reply.write_all(&buffer)?;
+                result?;
+            }
             // Deprecated commands
             SocketMessage::AltFocusHack(_)
             | SocketMessage::IdentifyBorderOverflowApplication(_, _) => {}
         };
 
-        notify_subscribers(
-            Notification {
+        // Subscribers and managers are notified once with the final state of the batch
+        if self.batch_in_progress {
+            tracing::info!("processed");
+            return Ok(());
+        }
+
+        self.update_dirty_workspaces()?;
+
+        if RESTORE_SESSION.load(Ordering::SeqCst) {
+            session_manager::send_notification();
+        }
+
+        if let Some(hwnd) = self.focused_hwnd() {
+            if initial_focused_hwnd != Some(hwnd) && !system_api::is_dry_run() {
+                flash_manager::send_notification(hwnd);
+            }
+        }
+
+        let state = State::from(self.as_ref());
+
+        self.pending_notifications.push(PendingNotification {
+            notification: Notification {
                 event: NotificationEvent::Socket(message.clone()),
-                state: self.as_ref().into(),
+                delta: initial_state.delta(&state),
+                state,
             },
-            initial_state.has_been_modified(self.as_ref()),
-        )?;
-
-        border_manager::send_notification(None);
-        transparency_manager::send_notification();
-        stackbar_manager::send_notification();
+            state_has_been_modified: initial_state.has_been_modified(self.as_ref()),
+            hwnd: None,
+        });
 
         tracing::info!("processed");
         Ok(())
     }
 }
 
-pub fn read_commands_uds(wm: &Arc<Mutex<WindowManager>>, mut stream: UnixStream) -> Result<()> {
+impl CommandRequest {
+    /// Sends the reply to the client, which is only done once the changes to windows made by the
+    /// command have been applied so that `komorebic --wait` returns when they are in place
+    pub fn respond(self, reply: (Vec<u8>, Result<()>)) {
+        if self.reply.send(reply).is_err() {
+            tracing::warn!("client disconnected before receiving a reply");
+        }
+    }
+}
+
+impl WindowManager {
+    pub fn handle_command_request(&mut self, request: &CommandRequest) -> (Vec<u8>, Result<()>) {
+        let mut reply = vec![];
+
+        let result = if self.is_paused
+            && !matches!(
+                request.message,
+                SocketMessage::TogglePause
+                    | SocketMessage::State
+                    | SocketMessage::CompactState
+                    | SocketMessage::GlobalState
+                    | SocketMessage::KnownHwnds
+                    | SocketMessage::Stats
+                    | SocketMessage::CheckHealth
+                    | SocketMessage::BarStatus
+                    | SocketMessage::Version
+                    | SocketMessage::SaveSession
+                    | SocketMessage::LogLevel(_)
+                    | SocketMessage::Stop
+            ) {
+            tracing::trace!("ignoring while paused");
+            Ok(())
+        } else {
+            self.process_command(request.message.clone(), &mut reply)
+        };
+
+        // Anything written before the failure is replaced, so that clients only ever have to
+        // check the reply for a CommandFailure to know whether the command succeeded
+        if let Err(error) = &result {
+            reply = serde_json::to_vec(&CommandFailure::from(error)).unwrap_or_default();
+        }
+
+        (reply, result)
+    }
+}
+
+/// Unwraps a SocketMessage from its protocol version envelope, rejecting messages from clients
+/// which speak a different version of the IPC protocol. Version queries are always let through so
+/// that clients are able to report the mismatch to the user.
+pub fn unwrap_versioned_message(
+    raw: &str,
+    mut reply: impl std::io::Write,
+) -> Result<SocketMessage> {
+    let VersionedSocketMessage {
+        message,
+        protocol_version,
+    } = VersionedSocketMessage::from_str(raw)?;
+
+    match protocol_version {
+        Some(version)
+            if version != PROTOCOL_VERSION && !matches!(message, SocketMessage::Version) =>
+        {
+            let error = format!(
+                "client speaks IPC protocol version {version}, but komorebi speaks IPC protocol version {PROTOCOL_VERSION}; please make sure that all komorebi binaries are from the same release"
+            );
+
+            reply.write_all(error.as_bytes())?;
+            bail!(error)
+        }
+        _ => Ok(message),
+    }
+}
+
+fn health() -> Health {
+    Health {
+        event_hook_installed: winevent_listener::is_hook_installed(),
+        event_loop_responsive: process_event::is_event_loop_responsive(),
+        last_event_timestamp: winevent_listener::last_event_timestamp(),
+    }
+}
+
+pub fn read_commands_uds(mut stream: UnixStream) -> Result<()> {
     let reader = BufReader::new(stream.try_clone()?);
     // TODO(raggi): while this processes more than one command, if there are
     // replies there is no clearly defined protocol for framing yet - it's
     // perhaps whole-json objects for now, but termination is signalled by
     // socket shutdown.
     for line in reader.lines() {
-        let message = SocketMessage::from_str(&line?)?;
+        let message = unwrap_versioned_message(&line?, &mut stream)?;
+
+        // Version handshakes don't touch the window manager state, so they can always be answered
+        // immediately without waiting on the window manager lock
+        if matches!(message, SocketMessage::Version) {
+            let version = serde_json::to_string_pretty(&VersionInformation::default())?;
+            stream.write_all(version.as_bytes())?;
+            continue;
+        }
 
-        match wm.try_lock_for(Duration::from_secs(1)) {
-            None => {
-                tracing::warn!(
-                    "could not acquire window manager lock, not processing message: {message}"
-                );
-            }
-            Some(mut wm) => {
-                if wm.is_paused {
-                    return match message {
-                        SocketMessage::TogglePause
-                        | SocketMessage::State
-                        | SocketMessage::GlobalState
-                        | SocketMessage::Stop => Ok(wm.process_command(message, &mut stream)?),
-                        _ => {
-                            tracing::trace!("ignoring while paused");
-                            Ok(())
-                        }
-                    };
-                }
+        // Health checks have to be answered even when the event loop is wedged
+        if matches!(message, SocketMessage::CheckHealth) {
+            let health = serde_json::to_string_pretty(&health())?;
+            stream.write_all(health.as_bytes())?;
+            continue;
+        }
 
-                wm.process_command(message.clone(), &mut stream)?;
-            }
+        let (reply, result) = submit_command(message)?;
+
+        // Clients which don't wait for a reply will already have hung up, in which case the
+        // failure itself is more interesting than the failure to report it
+        if let Err(error) = stream.write_all(&reply) {
+            result?;
+            return Err(error.into());
         }
+
+        result?;
     }
 
     Ok(())
 }
 
-pub fn read_commands_tcp(
-    wm: &Arc<Mutex<WindowManager>>,
-    stream: &mut TcpStream,
-    addr: &str,
-) -> Result<()> {
+pub fn read_commands_tcp(stream: &mut TcpStream, addr: &str) -> Result<()> {
     let mut reader = BufReader::new(stream.try_clone()?);
 
     loop {
@@ -1668,7 +2110,8 @@ pub fn read_commands_tcp(
                 break;
             }
             Ok(size) => {
-                let Ok(message) = SocketMessage::from_str(&String::from_utf8_lossy(&buf[..size]))
+                let Ok(message) =
+                    unwrap_versioned_message(&String::from_utf8_lossy(&buf[..size]), &mut *stream)
                 else {
                     tracing::warn!("client sent an invalid message, disconnecting: {addr}");
                     let mut connections = TCP_CONNECTIONS.lock();
@@ -1676,22 +2119,15 @@ pub fn read_commands_tcp(
                     break;
                 };
 
-                let mut wm = wm.lock();
-
-                if wm.is_paused {
-                    return match message {
-                        SocketMessage::TogglePause
-                        | SocketMessage::State
-                        | SocketMessage::GlobalState
-                        | SocketMessage::Stop => Ok(wm.process_command(message, stream)?),
-                        _ => {
-                            tracing::trace!("ignoring while paused");
-                            Ok(())
-                        }
-                    };
+                if matches!(message, SocketMessage::CheckHealth) {
+                    let health = serde_json::to_string_pretty(&health())?;
+                    stream.write_all(health.as_bytes())?;
+                    continue;
                 }
 
-                wm.process_command(message.clone(), &mut *stream)?;
+                let (reply, result) = submit_command(message)?;
+                stream.write_all(&reply)?;
+                result?;
             }
         }
     }
@@ -1,4 +1,6 @@
-use std::fs::OpenOptions;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::Duration;
@@ -6,23 +8,37 @@ use std::time::Instant;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::Result;
+use crossbeam_channel::Receiver;
 use crossbeam_utils::atomic::AtomicConsume;
 use parking_lot::Mutex;
 
+use crate::core::DropBehaviour;
+use crate::core::KomorebiError;
 use crate::core::OperationDirection;
 use crate::core::Rect;
 use crate::core::Sizing;
 use crate::core::WindowContainerBehaviour;
 
+use crate::animation;
 use crate::border_manager;
 use crate::border_manager::BORDER_OFFSET;
 use crate::border_manager::BORDER_WIDTH;
 use crate::current_virtual_desktop;
-use crate::notify_subscribers;
+use crate::drop_zone_manager;
+use crate::known_hwnds_manager;
+use crate::metrics;
+use crate::process_command;
+use crate::session_manager;
+use crate::splash_screen_manager;
 use crate::stackbar_manager;
+use crate::styles::ExtendedWindowStyle;
+use crate::system_api;
+use crate::system_api::WindowOperation;
 use crate::transparency_manager;
+use crate::unix_timestamp_millis;
 use crate::window::should_act;
 use crate::window::RuleDebug;
+use crate::window::Window;
 use crate::window_manager::WindowManager;
 use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api::WindowsApi;
@@ -32,46 +48,311 @@ use crate::workspace_reconciliator::ALT_TAB_HWND;
 use crate::workspace_reconciliator::ALT_TAB_HWND_INSTANT;
 use crate::Notification;
 use crate::NotificationEvent;
+use crate::PendingNotification;
 use crate::State;
-use crate::DATA_DIR;
-use crate::FLOATING_APPLICATIONS;
+use crate::APPLIED_CORNER_PREFERENCES;
+use crate::DROP_BEHAVIOUR;
+use crate::ELEVATED_HWNDS;
+use crate::FLOAT_SNAPPING_ENABLED;
+use crate::FULLSCREEN_MONITORS;
 use crate::HIDDEN_HWNDS;
+use crate::KNOWN_HWNDS_FILE;
+use crate::LAYOUT_REJECTIONS;
+use crate::ORIGINAL_RECTS;
+use crate::PAUSE_ON_FULLSCREEN;
+use crate::PENDING_LAYOUT_CHECKS;
+use crate::PENDING_SPLASH_SCREENS;
+use crate::POSITIONED_RECTS;
 use crate::REGEX_IDENTIFIERS;
+use crate::RESTORE_SESSION;
+use crate::RULE_SETTLE_PERIOD;
+use crate::SETTLING_WINDOWS;
+use crate::SPLASH_SCREEN_GRACE_PERIOD;
 use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
+use crate::URGENT_HWNDS;
 
+/// Events which arrive within this long of each other are handled together as a single batch
+const COALESCE_WINDOW: Duration = Duration::from_millis(10);
+
+/// How many of the most recently focused windows are remembered for focus-last
+const FOCUS_HISTORY_LEN: usize = 16;
+
+// Commands have to be handled from the start (eg. complete-configuration), but events are only
+// handled once the initial configuration has been loaded
+static PROCESSING_EVENTS: AtomicBool = AtomicBool::new(false);
+
+pub fn start_processing_events() {
+    PROCESSING_EVENTS.store(true, Ordering::SeqCst);
+}
+
+/// The event loop wakes up at least every 100ms even when there is nothing to handle, so it is
+/// considered to be wedged if it has not come around for this long
+const EVENT_LOOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+static EVENT_LOOP_HEARTBEAT: AtomicU64 = AtomicU64::new(0);
+
+/// Events for windows on monitors where a fullscreen window is focused, along with the id of the
+/// monitor, which are handled in the order that they arrived once that monitor is resumed
+static SUSPENDED_EVENTS: Mutex<Vec<(isize, WindowManagerEvent)>> = Mutex::new(Vec::new());
+
+fn resume_suspended_events(monitor_id: isize) -> Vec<WindowManagerEvent> {
+    let mut suspended_events = SUSPENDED_EVENTS.lock();
+    let (resumed, remaining): (Vec<_>, Vec<_>) = std::mem::take(&mut *suspended_events)
+        .into_iter()
+        .partition(|(suspended_monitor_id, _)| *suspended_monitor_id == monitor_id);
+
+    *suspended_events = remaining;
+
+    tracing::info!(
+        "resuming event processing after fullscreen window exited or lost focus, handling {} suspended event(s)",
+        resumed.len()
+    );
+
+    resumed.into_iter().map(|(_, event)| event).collect()
+}
+
+pub fn is_event_loop_responsive() -> bool {
+    let heartbeat = EVENT_LOOP_HEARTBEAT.load(Ordering::Relaxed);
+    heartbeat != 0
+        && unix_timestamp_millis().saturating_sub(heartbeat) < EVENT_LOOP_TIMEOUT.as_millis() as u64
+}
+
+/// Window manager events and commands are consumed from a single queue on this thread, so that
+/// they are always handled one after another in the order that they arrived
 #[tracing::instrument]
 pub fn listen_for_events(wm: Arc<Mutex<WindowManager>>) {
     let receiver = wm.lock().incoming_events.clone();
+    let commands = process_command::command_rx();
+
+    // Supervise the event loop so that komorebi never keeps running without handling events and
+    // commands if the thread dies
+    std::thread::spawn(move || loop {
+        let event_loop = {
+            let wm = wm.clone();
+            let receiver = receiver.clone();
+            let commands = commands.clone();
+
+            move || {
+                tracing::info!("listening");
+                loop {
+                    EVENT_LOOP_HEARTBEAT.store(unix_timestamp_millis(), Ordering::Relaxed);
+
+                    let events = if PROCESSING_EVENTS.load(Ordering::SeqCst) {
+                        receiver.clone()
+                    } else {
+                        crossbeam_channel::never()
+                    };
 
-    std::thread::spawn(move || {
-        tracing::info!("listening");
-        loop {
-            if let Ok(event) = receiver.recv() {
-                let mut guard = wm.lock();
-                match guard.process_event(event) {
-                    Ok(()) => {}
-                    Err(error) => {
-                        if cfg!(debug_assertions) {
-                            tracing::error!("{:?}", error)
-                        } else {
-                            tracing::error!("{}", error)
+                    crossbeam_channel::select! {
+                        recv(events) -> event => {
+                            if let Ok(event) = event {
+                                let events = coalesce_events(event, &receiver);
+
+                                let waiting = Instant::now();
+                                let mut guard = wm.lock();
+                                metrics::record_lock_wait(waiting.elapsed());
+
+                                // Changes to windows are only worked out while the lock is held,
+                                // and are applied once it has been released
+                                system_api::start_deferring();
+
+                                for event in events {
+                                    let processing = Instant::now();
+                                    let result = catch_panic(|| {
+                                        // Windows which have been let go of after being dragged
+                                        // or resized with the mouse snap back into the layout
+                                        if matches!(event, WindowManagerEvent::MoveResizeEnd(..)) {
+                                            animation::as_mouse_drag_snap(|| {
+                                                guard.process_event(event)
+                                            })
+                                        } else {
+                                            guard.process_event(event)
+                                        }
+                                    })
+                                    .unwrap_or_else(|| {
+                                        Err(anyhow!("panicked while processing event: {event}"))
+                                    });
+                                    metrics::record_event(event.title(), processing.elapsed());
+
+                                    if let Err(error) = result {
+                                        if cfg!(debug_assertions) {
+                                            tracing::error!("{:?}", error)
+                                        } else {
+                                            tracing::error!("{}", error)
+                                        }
+                                    }
+                                }
+
+                                let operations = system_api::stop_deferring();
+                                let notifications =
+                                    std::mem::take(&mut guard.pending_notifications);
+                                drop(guard);
+
+                                apply_deferred(&operations, notifications);
+                            }
                         }
+                        recv(commands) -> request => {
+                            if let Ok(request) = request {
+                                let waiting = Instant::now();
+                                let mut guard = wm.lock();
+                                metrics::record_lock_wait(waiting.elapsed());
+
+                                system_api::start_deferring();
+
+                                let processing = Instant::now();
+                                let reply = catch_panic(|| guard.handle_command_request(&request));
+                                metrics::record_command(processing.elapsed());
+
+                                let operations = system_api::stop_deferring();
+                                let notifications =
+                                    std::mem::take(&mut guard.pending_notifications);
+                                drop(guard);
+
+                                apply_deferred(&operations, notifications);
+
+                                // A command which panics drops its reply sender, which the
+                                // client sees as komorebi having failed to reply
+                                match reply {
+                                    Some(reply) => request.respond(reply),
+                                    None => tracing::error!("panicked while processing command"),
+                                }
+                            }
+                        }
+                        // Check again whether events should be handled yet
+                        default(Duration::from_millis(100)) => {}
                     }
                 }
             }
+        };
+
+        let _ = std::thread::spawn(event_loop).join();
+
+        tracing::error!("event loop thread exited unexpectedly; restarting");
+
+        // Events which piled up while there was nothing handling them are out of date by now
+        let stale_events = receiver.try_iter().count();
+        if stale_events > 0 {
+            tracing::warn!("discarded {stale_events} stale events");
         }
     });
 }
 
+/// Applies the changes to windows which were deferred while handling events or a command, and then
+/// sends the notifications which were held back until they had been applied
+fn apply_deferred(operations: &[WindowOperation], notifications: Vec<PendingNotification>) {
+    Window::apply_operations(operations);
+
+    for notification in notifications {
+        if let Err(error) = notification.send() {
+            tracing::error!("could not notify subscribers: {error}");
+        }
+    }
+}
+
+// parking_lot locks are never poisoned, so a panic while handling a single event or command can be
+// logged (by the panic hook) and skipped without taking the whole event loop thread down with it
+fn catch_panic<T>(f: impl FnOnce() -> T) -> Option<T> {
+    std::panic::catch_unwind(AssertUnwindSafe(f)).ok()
+}
+
+// Applications often emit storms of identical events for the same window (eg. repeated Show or
+// FocusChange events), and only the first of these needs to be handled as long as nothing else
+// has happened to that window in between
+fn coalesce_events(
+    first: WindowManagerEvent,
+    receiver: &Receiver<WindowManagerEvent>,
+) -> Vec<WindowManagerEvent> {
+    let mut events = vec![first];
+    let deadline = Instant::now() + COALESCE_WINDOW;
+
+    while let Ok(event) = receiver.recv_deadline(deadline) {
+        let is_duplicate = events
+            .iter()
+            .rev()
+            .find(|pending| pending.hwnd() == event.hwnd())
+            .is_some_and(|pending| *pending == event);
+
+        if is_duplicate {
+            tracing::trace!("coalescing duplicate event: {event}");
+            metrics::record_coalesced_event();
+        } else {
+            events.push(event);
+        }
+    }
+
+    events
+}
+
 impl WindowManager {
+    /// Keeps picture-in-picture windows on top of everything else while they are left unmanaged
+    fn handle_picture_in_picture_window(&mut self, window: Window) -> Result<()> {
+        // Windows can take on the title of a picture-in-picture window after they have already
+        // been managed, in which case they are let go of
+        if self.focused_workspace()?.contains_window(window.hwnd) {
+            tracing::info!("unmanaging picture-in-picture window: {window}");
+
+            self.focused_workspace_mut()?.remove_window(window.hwnd)?;
+            self.update_focused_workspace(false, false)?;
+        }
+
+        let is_topmost = window
+            .ex_style()
+            .is_ok_and(|ex_style| ex_style.contains(ExtendedWindowStyle::TOPMOST));
+
+        if !is_topmost {
+            if let Err(error) = WindowsApi::set_topmost(window.hwnd) {
+                tracing::warn!("could not keep picture-in-picture window {window} on top: {error}");
+            }
+        }
+
+        Ok(())
+    }
+
     #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
-    #[tracing::instrument(skip(self, event), fields(event = event.title(), winevent = event.winevent(), hwnd = event.hwnd()))]
+    #[tracing::instrument(skip(self, event), fields(event = event.title(), winevent = event.winevent(), hwnd = event.hwnd(), monitor = self.focused_monitor_idx(), workspace = self.focused_workspace_idx().ok()))]
     pub fn process_event(&mut self, event: WindowManagerEvent) -> Result<()> {
         if self.is_paused {
             tracing::trace!("ignoring while paused");
             return Ok(());
         }
 
+        // Fullscreen windows rarely pass should_manage, so they are tracked before it
+        let (suspended, resumed_events) = self.track_fullscreen_windows(event);
+
+        for resumed in resumed_events {
+            if let Err(error) = self.process_event(resumed) {
+                tracing::warn!("could not handle event suspended for a fullscreen window: {error}");
+            }
+        }
+
+        if suspended {
+            tracing::trace!("deferring while a fullscreen window is focused on this monitor");
+            return Ok(());
+        }
+
+        // Destroyed windows never reach the event handlers below, so urgency is cleared up front
+        if let WindowManagerEvent::Destroy(_, window) | WindowManagerEvent::Unmanage(window) = event
+        {
+            URGENT_HWNDS.lock().remove(&window.hwnd);
+        }
+
+        // Destroyed windows never pass should_manage, so they have to be forgotten before it
+        if let WindowManagerEvent::Destroy(_, window) = event {
+            ELEVATED_HWNDS.lock().remove(&window.hwnd);
+            SETTLING_WINDOWS.lock().remove(&window.hwnd);
+            APPLIED_CORNER_PREFERENCES.lock().remove(&window.hwnd);
+            ORIGINAL_RECTS.lock().remove(&window.hwnd);
+            POSITIONED_RECTS.lock().remove(&window.hwnd);
+            LAYOUT_REJECTIONS.lock().remove(&window.hwnd);
+            PENDING_LAYOUT_CHECKS.lock().remove(&window.hwnd);
+
+            // Managed windows are removed right away instead of waiting for the reaper to notice
+            // that they no longer exist
+            if !window.is_window() && self.remove_destroyed_window(window.hwnd)? {
+                border_manager::send_notification(None);
+            }
+        }
+
         let mut rule_debug = RuleDebug::default();
 
         let should_manage = event.window().should_manage(Some(event), &mut rule_debug)?;
@@ -107,6 +388,27 @@ impl WindowManager {
                     border_manager::send_notification(Option::from(event.hwnd()));
                 }
 
+                if rule_debug.matches_picture_in_picture.is_some()
+                    && matches!(
+                        event,
+                        WindowManagerEvent::Show(..)
+                            | WindowManagerEvent::Uncloak(..)
+                            | WindowManagerEvent::FocusChange(..)
+                            | WindowManagerEvent::TitleUpdate(..)
+                    )
+                {
+                    self.handle_picture_in_picture_window(event.window())?;
+                }
+
+                return Ok(());
+            }
+        }
+
+        // Windows which cannot be activated only reach this point when they match a manage rule,
+        // and since they never actually take focus their focus events are not focus changes
+        if let WindowManagerEvent::FocusChange(_, window) = event {
+            if window.is_no_activate() {
+                tracing::trace!("ignoring focus change for window which cannot be activated");
                 return Ok(());
             }
         }
@@ -168,12 +470,22 @@ impl WindowManager {
             return Ok(());
         }
 
+        if let WindowManagerEvent::TitleUpdate(_, window)
+        | WindowManagerEvent::Show(WinEvent::ObjectNameChange, window) = event
+        {
+            self.reevaluate_settling_window(window)?;
+        }
+
         match event {
             WindowManagerEvent::Raise(window) => {
                 window.focus(false)?;
                 self.has_pending_raise_op = false;
             }
             WindowManagerEvent::Destroy(_, window) | WindowManagerEvent::Unmanage(window) => {
+                if matches!(event, WindowManagerEvent::Unmanage(_)) {
+                    window.restore_title_bar()?;
+                }
+
                 if self.focused_workspace()?.contains_window(window.hwnd) {
                     self.focused_workspace_mut()?.remove_window(window.hwnd)?;
                     self.update_focused_workspace(false, false)?;
@@ -245,6 +557,13 @@ impl WindowManager {
                 already_moved_window_handles.remove(&window.hwnd);
             }
             WindowManagerEvent::FocusChange(_, window) => {
+                URGENT_HWNDS.lock().remove(&window.hwnd);
+
+                if self.focus_history.front() != Some(&window.hwnd) {
+                    self.focus_history.retain(|hwnd| *hwnd != window.hwnd);
+                    self.focus_history.push_front(window.hwnd);
+                    self.focus_history.truncate(FOCUS_HISTORY_LEN);
+                }
                 self.update_focused_workspace(self.mouse_follows_focus, false)?;
 
                 let workspace = self.focused_workspace_mut()?;
@@ -279,6 +598,24 @@ impl WindowManager {
             WindowManagerEvent::Show(_, window)
             | WindowManagerEvent::Manage(window)
             | WindowManagerEvent::Uncloak(_, window) => {
+                // Some applications create their windows minimized or hidden and only restore
+                // them later; these are managed by the Show or Uncloak event which is sent once
+                // they have actually become visible, rather than being tiled while still hidden.
+                // Events for windows which are already managed are handled as usual
+                if !matches!(event, WindowManagerEvent::Manage(_))
+                    && (!window.is_visible() || window.is_miminized())
+                    && !self.known_hwnds().contains(&window.hwnd)
+                {
+                    tracing::debug!("deferring management of window which is not visible yet");
+                    return Ok(());
+                }
+
+                if let WindowManagerEvent::Show(winevent, _) = event {
+                    if self.defer_splash_screen(winevent, window) {
+                        return Ok(());
+                    }
+                }
+
                 let focused_monitor_idx = self.focused_monitor_idx();
                 let focused_workspace_idx =
                     self.focused_workspace_idx_for_monitor_idx(focused_monitor_idx)?;
@@ -345,33 +682,56 @@ impl WindowManager {
                     let monocle_container = workspace.monocle_container().clone();
 
                     if !workspace_contains_window && !needs_reconciliation {
-                        let floating_applications = FLOATING_APPLICATIONS.lock();
-                        let regex_identifiers = REGEX_IDENTIFIERS.lock();
-                        let mut should_float = false;
+                        let matches_floating_applications = window.matches_floating_applications();
+                        let mut should_float = matches_floating_applications;
 
-                        if !floating_applications.is_empty() {
-                            if let (Ok(title), Ok(exe_name), Ok(class), Ok(path)) =
-                                (window.title(), window.exe(), window.class(), window.path())
-                            {
-                                should_float = should_act(
-                                    &title,
-                                    &exe_name,
-                                    &class,
-                                    &path,
-                                    &floating_applications,
-                                    &regex_identifiers,
-                                )
-                                .is_some();
-                            }
-                        }
+                        // Elevated windows only reach this point when they should be floated
+                        should_float = should_float || ELEVATED_HWNDS.lock().contains(&window.hwnd);
+
+                        // Dialogs and other transient windows should never be tiled, they belong
+                        // on top of the window which owns them
+                        let owner = window.owner();
+                        should_float = should_float || owner.is_some();
 
                         behaviour.float_override = behaviour.float_override
                             || (should_float && !matches!(event, WindowManagerEvent::Manage(_)));
 
+                        // The title of a window often changes shortly after it has been shown, so
+                        // the floating application rules are evaluated again on the first change
+                        // unless the window was floated for a reason other than those rules
+                        if !matches!(event, WindowManagerEvent::Manage(_))
+                            && behaviour.float_override == matches_floating_applications
+                            && RULE_SETTLE_PERIOD.load(Ordering::SeqCst) > 0
+                        {
+                            let settle_period =
+                                Duration::from_millis(RULE_SETTLE_PERIOD.load(Ordering::SeqCst));
+
+                            // Windows which never change their title would otherwise never be
+                            // forgotten
+                            let mut settling_windows = SETTLING_WINDOWS.lock();
+                            settling_windows
+                                .retain(|_, (managed_at, _)| managed_at.elapsed() <= settle_period);
+                            settling_windows.insert(
+                                window.hwnd,
+                                (Instant::now(), matches_floating_applications),
+                            );
+                        }
+
                         if behaviour.float_override {
                             workspace.floating_windows_mut().push(window);
+
+                            if let Some(owner) = owner {
+                                window.center_over(&WindowsApi::window_rect(owner.hwnd)?)?;
+                            }
+
                             self.update_focused_workspace(false, false)?;
                         } else {
+                            // Windows which open maximized would otherwise keep covering their
+                            // neighbours, since the layout is applied on top of the maximized state
+                            if window.is_maximized() {
+                                window.unmaximize();
+                            }
+
                             match behaviour.current_behaviour {
                                 WindowContainerBehaviour::Create => {
                                     workspace.new_container_for_window(window);
@@ -406,6 +766,13 @@ impl WindowManager {
                 }
             }
             WindowManagerEvent::MoveResizeStart(_, window) => {
+                if FLOAT_SNAPPING_ENABLED.load(Ordering::SeqCst)
+                    && self.is_floating_window(window.hwnd)
+                {
+                    self.pending_float_move =
+                        Option::from((window.hwnd, WindowsApi::window_rect(window.hwnd)?));
+                }
+
                 if *self.focused_workspace()?.tile() {
                     let monitor_idx = self.focused_monitor_idx();
                     let workspace_idx = self
@@ -423,6 +790,10 @@ impl WindowManager {
 
                     self.pending_move_op =
                         Option::from((monitor_idx, workspace_idx, container_idx));
+
+                    drop_zone_manager::send_notification(drop_zone_manager::Notification::Start(
+                        window.hwnd,
+                    ));
                 }
             }
             WindowManagerEvent::MoveResizeEnd(_, window) => {
@@ -431,6 +802,18 @@ impl WindowManager {
                 let pending = self.pending_move_op;
                 // Always consume the pending move op whenever this event is handled
                 self.pending_move_op = None;
+                drop_zone_manager::send_notification(drop_zone_manager::Notification::End);
+
+                // Floating windows are only snapped when they have been moved; snapping a resized
+                // window would also move the edges which were not dragged
+                if let Some((hwnd, start)) = self.pending_float_move.take() {
+                    if hwnd == window.hwnd {
+                        let end = WindowsApi::window_rect(hwnd)?;
+                        if end != start && end.right == start.right && end.bottom == start.bottom {
+                            self.snap_floating_window(window, end)?;
+                        }
+                    }
+                }
 
                 let target_monitor_idx = self
                     .monitor_idx_from_current_pos()
@@ -496,12 +879,10 @@ impl WindowManager {
                     };
 
                     // If we have moved across the monitors, use that override, otherwise determine
-                    // if a move has taken place by ruling out a resize
-                    let right_bottom_constant = 0;
-
-                    let is_move = moved_across_monitors
-                        || resize.right.abs() == right_bottom_constant
-                            && resize.bottom.abs() == right_bottom_constant;
+                    // if a move has taken place by ruling out a resize. Both rects describe the
+                    // visible frame (DWMWA_EXTENDED_FRAME_BOUNDS), so there are no invisible
+                    // borders to compensate for here
+                    let is_move = moved_across_monitors || resize.right == 0 && resize.bottom == 0;
 
                     if is_move {
                         tracing::info!("moving with mouse");
@@ -513,16 +894,29 @@ impl WindowManager {
                                 origin_container_idx,
                             )) = pending
                             {
+                                // The container which was focused when the drag started is not
+                                // necessarily the one holding the dragged window, and moving the
+                                // wrong one would leave a hole where the dragged window used to be
+                                let origin_container_idx = self
+                                    .monitors()
+                                    .get(origin_monitor_idx)
+                                    .ok_or(KomorebiError::NoSuchMonitor(origin_monitor_idx))?
+                                    .workspaces()
+                                    .get(origin_workspace_idx)
+                                    .ok_or(KomorebiError::NoSuchWorkspace(origin_workspace_idx))?
+                                    .container_idx_for_window(window.hwnd)
+                                    .unwrap_or(origin_container_idx);
+
                                 let target_workspace_idx = self
                                     .monitors()
                                     .get(target_monitor_idx)
-                                    .ok_or_else(|| anyhow!("there is no monitor at this idx"))?
+                                    .ok_or(KomorebiError::NoSuchMonitor(target_monitor_idx))?
                                     .focused_workspace_idx();
 
                                 let target_container_idx = self
                                     .monitors()
                                     .get(target_monitor_idx)
-                                    .ok_or_else(|| anyhow!("there is no monitor at this idx"))?
+                                    .ok_or(KomorebiError::NoSuchMonitor(target_monitor_idx))?
                                     .focused_workspace()
                                     .ok_or_else(|| {
                                         anyhow!("there is no focused workspace for this monitor")
@@ -546,13 +940,14 @@ impl WindowManager {
 
                                 // We want to make sure both the origin and target monitors are updated,
                                 // so that we don't have ghost tiles until we force an interaction on
-                                // the origin monitor's focused workspace
+                                // the origin monitor's focused workspace. Both workspaces are already
+                                // the focused ones on their monitors, so they are only retiled here;
+                                // focusing them again would focus a window on the origin monitor and
+                                // take focus away from the window which has just been dropped
                                 self.focus_monitor(origin_monitor_idx)?;
-                                self.focus_workspace(origin_workspace_idx)?;
                                 self.update_focused_workspace(false, false)?;
 
                                 self.focus_monitor(target_monitor_idx)?;
-                                self.focus_workspace(target_workspace_idx)?;
                                 self.update_focused_workspace(false, false)?;
                             }
                             // Here we handle a simple move on the same monitor which is treated as
@@ -565,8 +960,33 @@ impl WindowManager {
                                 WindowContainerBehaviour::Create => {
                                     match workspace.container_idx_from_current_point() {
                                         Some(target_idx) => {
-                                            workspace
-                                                .swap_containers(focused_container_idx, target_idx);
+                                            // Holding shift while letting go of the window uses
+                                            // the other drop behaviour for this drop only
+                                            let drop_behaviour = match (
+                                                *DROP_BEHAVIOUR.lock(),
+                                                WindowsApi::shift_is_pressed(),
+                                            ) {
+                                                (DropBehaviour::Swap, false)
+                                                | (DropBehaviour::Insert, true) => {
+                                                    DropBehaviour::Swap
+                                                }
+                                                (DropBehaviour::Insert, false)
+                                                | (DropBehaviour::Swap, true) => {
+                                                    DropBehaviour::Insert
+                                                }
+                                            };
+
+                                            match drop_behaviour {
+                                                DropBehaviour::Swap => workspace.swap_containers(
+                                                    focused_container_idx,
+                                                    target_idx,
+                                                ),
+                                                DropBehaviour::Insert => workspace.move_container(
+                                                    focused_container_idx,
+                                                    target_idx,
+                                                )?,
+                                            }
+
                                             self.update_focused_workspace(false, false)?;
                                         }
                                         None => {
@@ -644,6 +1064,24 @@ impl WindowManager {
                     }
                 }
             }
+            WindowManagerEvent::Flash(window) => {
+                // Windows can flash while they are already in the foreground, which shouldn't
+                // mark their workspace as needing attention
+                if WindowsApi::foreground_window()? != window.hwnd {
+                    URGENT_HWNDS.lock().insert(window.hwnd);
+                }
+            }
+            WindowManagerEvent::RejectedLayout(window) => {
+                let workspace = self.focused_workspace_mut()?;
+
+                if workspace.container_for_window(window.hwnd).is_some() {
+                    tracing::info!("floating window which keeps rejecting its layout: {window}");
+
+                    workspace.remove_window(window.hwnd)?;
+                    workspace.floating_windows_mut().push(window);
+                    self.update_focused_workspace(false, false)?;
+                }
+            }
             WindowManagerEvent::MouseCapture(..)
             | WindowManagerEvent::Cloak(..)
             | WindowManagerEvent::TitleUpdate(..) => {}
@@ -654,38 +1092,38 @@ impl WindowManager {
             window.center(&self.focused_monitor_work_area()?)?;
         }
 
-        tracing::trace!("updating list of known hwnds");
-        let mut known_hwnds = vec![];
-        for monitor in self.monitors() {
-            for workspace in monitor.workspaces() {
-                for container in workspace.containers() {
-                    for window in container.windows() {
-                        known_hwnds.push(window.hwnd);
-                    }
-                }
-            }
+        self.update_dirty_workspaces()?;
+
+        if KNOWN_HWNDS_FILE.load(Ordering::SeqCst) {
+            known_hwnds_manager::send_notification(self.known_hwnds());
+        }
+
+        if RESTORE_SESSION.load(Ordering::SeqCst) {
+            session_manager::send_notification();
         }
 
-        let hwnd_json = DATA_DIR.join("komorebi.hwnd.json");
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(hwnd_json)?;
+        // Title changes of the focused window don't modify the state, but they should still reach
+        // subscribers which only listen for state changes, such as bars showing the window title
+        let is_focused_title_change = match event {
+            WindowManagerEvent::TitleUpdate(_, window)
+            | WindowManagerEvent::Show(WinEvent::ObjectNameChange, window) => {
+                WindowsApi::foreground_window().is_ok_and(|hwnd| hwnd == window.hwnd)
+            }
+            _ => false,
+        };
 
-        serde_json::to_writer_pretty(&file, &known_hwnds)?;
+        let state = State::from(self.as_ref());
 
-        notify_subscribers(
-            Notification {
+        self.pending_notifications.push(PendingNotification {
+            notification: Notification {
                 event: NotificationEvent::WindowManager(event),
-                state: self.as_ref().into(),
+                delta: initial_state.delta(&state),
+                state,
             },
-            initial_state.has_been_modified(self.as_ref()),
-        )?;
-
-        border_manager::send_notification(Some(event.hwnd()));
-        transparency_manager::send_notification();
-        stackbar_manager::send_notification();
+            state_has_been_modified: initial_state.has_been_modified(self.as_ref())
+                || is_focused_title_change,
+            hwnd: Some(event.hwnd()),
+        });
 
         // Too many spammy OBJECT_NAMECHANGE events from JetBrains IDEs
         if !matches!(
@@ -699,4 +1137,400 @@ impl WindowManager {
 
         Ok(())
     }
+
+    /// Windows which look like splash screens are only managed once they have stayed open for
+    /// the grace period, so that a window which disappears again never creates a container or
+    /// causes a retile; returns true if the event should not be processed yet
+    fn defer_splash_screen(&self, winevent: WinEvent, window: Window) -> bool {
+        let grace_period = SPLASH_SCREEN_GRACE_PERIOD.load(Ordering::SeqCst);
+        if grace_period == 0 || !window.is_splash_screen_candidate() {
+            return false;
+        }
+
+        let mut pending = PENDING_SPLASH_SCREENS.lock();
+        let grace_period = Duration::from_millis(grace_period);
+
+        match pending.get(&window.hwnd) {
+            Some(shown) if shown.elapsed() >= grace_period => {
+                pending.remove(&window.hwnd);
+                false
+            }
+            Some(_) => true,
+            None => {
+                if self.known_hwnds().contains(&window.hwnd) {
+                    return false;
+                }
+
+                let shown = Instant::now();
+                pending.insert(window.hwnd, shown);
+                drop(pending);
+
+                splash_screen_manager::send_notification(window, winevent, shown + grace_period);
+
+                true
+            }
+        }
+    }
+
+    /// Evaluates the floating application and workspace rules again for a recently managed window,
+    /// which is needed for applications that only set their final title once their window has
+    /// been created
+    fn reevaluate_settling_window(&mut self, window: Window) -> Result<()> {
+        let Some((managed_at, matched)) = SETTLING_WINDOWS.lock().remove(&window.hwnd) else {
+            return Ok(());
+        };
+
+        let settle_period = Duration::from_millis(RULE_SETTLE_PERIOD.load(Ordering::SeqCst));
+        if managed_at.elapsed() > settle_period {
+            return Ok(());
+        }
+
+        let matches = window.matches_floating_applications();
+
+        if matches != matched {
+            let mut retile_monitor_idx = None;
+
+            // The window may have been moved elsewhere by a workspace rule in the meantime
+            'monitors: for (i, monitor) in self.monitors_mut().iter_mut().enumerate() {
+                let focused_workspace_idx = monitor.focused_workspace_idx();
+
+                for (j, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
+                    if matches && workspace.container_for_window(window.hwnd).is_some() {
+                        tracing::info!(
+                            "floating window which matches a rule after retitling: {window}"
+                        );
+
+                        workspace.remove_window(window.hwnd)?;
+                        workspace.floating_windows_mut().push(window);
+                    } else if !matches && workspace.floating_windows().contains(&window) {
+                        tracing::info!("tiling window which no longer matches a rule: {window}");
+
+                        workspace.remove_window(window.hwnd)?;
+                        workspace.new_container_for_window(window);
+                    } else {
+                        continue;
+                    }
+
+                    if j == focused_workspace_idx {
+                        retile_monitor_idx = Option::from(i);
+                    } else {
+                        workspace.set_dirty(true);
+                    }
+
+                    break 'monitors;
+                }
+            }
+
+            if let Some(monitor_idx) = retile_monitor_idx {
+                self.update_focused_workspace_by_monitor_idx(monitor_idx)?;
+            }
+        }
+
+        // Workspace rules which only apply to the initial placement of a window have already been
+        // checked against its first title, so they are given another chance with the settled one
+        self.already_moved_window_handles
+            .lock()
+            .remove(&window.hwnd);
+
+        self.enforce_workspace_rules()
+    }
+
+    /// Events for windows on a monitor where a fullscreen window such as a game is focused are
+    /// suspended until that window is no longer fullscreen or loses focus, so that nothing is
+    /// retiled and no work is done while the game is running
+    ///
+    /// Returns whether the event has been suspended, along with any previously suspended events
+    /// which have to be handled now that their monitor has been resumed
+    fn track_fullscreen_windows(
+        &self,
+        event: WindowManagerEvent,
+    ) -> (bool, Vec<WindowManagerEvent>) {
+        let mut fullscreen_monitors = FULLSCREEN_MONITORS.lock();
+        let mut resumed = vec![];
+
+        if !PAUSE_ON_FULLSCREEN.load(Ordering::SeqCst) {
+            fullscreen_monitors.clear();
+            resumed.extend(SUSPENDED_EVENTS.lock().drain(..).map(|(_, event)| event));
+            return (false, resumed);
+        }
+
+        let is_focus_change = matches!(event, WindowManagerEvent::FocusChange(..));
+
+        // Nothing has to be looked up for the vast majority of events, which arrive while no
+        // fullscreen window is focused
+        if fullscreen_monitors.is_empty() && !is_focus_change {
+            return (false, resumed);
+        }
+
+        let window = event.window();
+
+        // A fullscreen window which loses focus to any other window, on any monitor, no longer
+        // suspends the events for its own monitor
+        if is_focus_change {
+            let unfocused = fullscreen_monitors
+                .iter()
+                .filter(|(_, hwnd)| **hwnd != window.hwnd)
+                .map(|(monitor_id, _)| *monitor_id)
+                .collect::<Vec<_>>();
+
+            for monitor_id in unfocused {
+                fullscreen_monitors.remove(&monitor_id);
+                resumed.extend(resume_suspended_events(monitor_id));
+            }
+        }
+
+        let monitor_id = WindowsApi::monitor_from_window(window.hwnd);
+
+        let Some(monitor_size) = self
+            .monitors()
+            .iter()
+            .find(|monitor| monitor.id() == monitor_id)
+            .map(|monitor| *monitor.size())
+        else {
+            return (false, resumed);
+        };
+
+        if is_focus_change && window.is_fullscreen_on(&monitor_size) {
+            if fullscreen_monitors.insert(monitor_id, window.hwnd) != Some(window.hwnd) {
+                tracing::info!("suspending event processing for fullscreen window: {window}");
+            }
+
+            return (true, resumed);
+        }
+
+        let Some(&hwnd) = fullscreen_monitors.get(&monitor_id) else {
+            return (false, resumed);
+        };
+
+        let fullscreen_window = Window::from(hwnd);
+
+        let still_fullscreen = fullscreen_window.is_window()
+            && fullscreen_window.is_visible()
+            && !fullscreen_window.is_miminized()
+            && fullscreen_window.is_fullscreen_on(&monitor_size)
+            && WindowsApi::foreground_window().is_ok_and(|foreground| foreground == hwnd);
+
+        if still_fullscreen {
+            let mut suspended_events = SUSPENDED_EVENTS.lock();
+
+            // Windows which keep retitling themselves would otherwise fill the queue with
+            // identical events
+            if !suspended_events.contains(&(monitor_id, event)) {
+                suspended_events.push((monitor_id, event));
+            }
+
+            return (true, resumed);
+        }
+
+        fullscreen_monitors.remove(&monitor_id);
+        resumed.extend(resume_suspended_events(monitor_id));
+
+        (false, resumed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rect::rect;
+    use crate::core::SocketMessage;
+    use crate::system_api::mock_system_api;
+    use crate::system_api::SystemApi;
+    use crate::INITIAL_CONFIGURATION_LOADED;
+
+    /// Drives a window manager with the same events and commands that the event loop would hand
+    /// it, for fake windows and monitors on the mock desktop
+    struct Simulation {
+        wm: WindowManager,
+    }
+
+    impl Simulation {
+        fn new(size: Rect) -> Self {
+            INITIAL_CONFIGURATION_LOADED.store(true, Ordering::SeqCst);
+
+            let mut simulation = Self {
+                wm: WindowManager::for_tests(size).unwrap(),
+            };
+
+            simulation.prepare_workspaces();
+            simulation
+        }
+
+        fn add_monitor(&mut self, id: isize, size: Rect) {
+            let name = format!("TEST{id}");
+            self.wm.monitors_mut().push_back(crate::monitor::new(
+                id,
+                size,
+                size,
+                name.clone(),
+                name.clone(),
+                name,
+            ));
+
+            self.prepare_workspaces();
+        }
+
+        // Two workspaces on every monitor, without any padding so that the layouts fill the
+        // monitors exactly
+        fn prepare_workspaces(&mut self) {
+            for monitor in self.wm.monitors_mut() {
+                monitor.ensure_workspace_count(2);
+
+                for workspace in monitor.workspaces_mut() {
+                    workspace.set_workspace_padding(Some(0));
+                    workspace.set_container_padding(Some(0));
+                }
+            }
+        }
+
+        /// Opens a fake window, which is picked up through its show event
+        fn open(&mut self, hwnd: isize) {
+            mock_system_api().add_window(hwnd, rect(0, 0, 800, 600));
+            self.event(WindowManagerEvent::Show(
+                WinEvent::ObjectShow,
+                Window::from(hwnd),
+            ));
+        }
+
+        /// Closes a fake window, which is noticed through its destroy event
+        fn close(&mut self, hwnd: isize) {
+            mock_system_api().remove_window(hwnd);
+            self.event(WindowManagerEvent::Destroy(
+                WinEvent::ObjectDestroy,
+                Window::from(hwnd),
+            ));
+        }
+
+        fn event(&mut self, event: WindowManagerEvent) {
+            self.wm.process_event(event).unwrap();
+        }
+
+        fn command(&mut self, message: SocketMessage) {
+            self.wm.process_command(message, std::io::sink()).unwrap();
+        }
+
+        /// The windows of every container on a workspace, in the order of the layout
+        fn containers(&self, monitor_idx: usize, workspace_idx: usize) -> Vec<Vec<isize>> {
+            self.wm.monitors()[monitor_idx].workspaces()[workspace_idx]
+                .containers()
+                .iter()
+                .map(|container| container.windows().iter().map(|w| w.hwnd).collect())
+                .collect()
+        }
+
+        /// Asserts that the focused window of every container on the focused workspace of the
+        /// monitor is where the layout of the workspace puts it
+        fn assert_tiled(&self, monitor_idx: usize) {
+            let workspace = self.wm.monitors()[monitor_idx].focused_workspace().unwrap();
+            let layouts = workspace.latest_layout();
+
+            assert_eq!(layouts.len(), workspace.containers().len());
+
+            for (container, layout) in workspace.containers().iter().zip(layouts) {
+                let hwnd = container.focused_window().unwrap().hwnd;
+                assert_eq!(mock_system_api().window(hwnd).unwrap().rect, *layout);
+            }
+        }
+    }
+
+    fn is_shown(hwnd: isize) -> bool {
+        let window = Window::from(hwnd);
+        window.is_visible() && !window.is_miminized()
+    }
+
+    #[test]
+    fn shown_windows_are_tiled_in_the_order_that_they_were_opened() {
+        let mut simulation = Simulation::new(rect(0, 0, 1920, 1080));
+
+        for hwnd in [5000, 5001, 5002] {
+            simulation.open(hwnd);
+        }
+
+        assert_eq!(
+            simulation.containers(0, 0),
+            vec![vec![5000], vec![5001], vec![5002]]
+        );
+
+        simulation.assert_tiled(0);
+    }
+
+    #[test]
+    fn destroyed_windows_are_removed_from_the_layout() {
+        let mut simulation = Simulation::new(rect(0, 0, 1920, 1080));
+
+        for hwnd in [5010, 5011, 5012] {
+            simulation.open(hwnd);
+        }
+
+        simulation.close(5011);
+
+        assert_eq!(simulation.containers(0, 0), vec![vec![5010], vec![5012]]);
+        simulation.assert_tiled(0);
+    }
+
+    #[test]
+    fn minimized_windows_are_removed_from_the_layout() {
+        let mut simulation = Simulation::new(rect(0, 0, 1920, 1080));
+
+        for hwnd in [5020, 5021] {
+            simulation.open(hwnd);
+        }
+
+        mock_system_api().minimize_window(5021);
+        simulation.event(WindowManagerEvent::Minimize(
+            WinEvent::SystemMinimizeStart,
+            Window::from(5021),
+        ));
+
+        assert_eq!(simulation.containers(0, 0), vec![vec![5020]]);
+        simulation.assert_tiled(0);
+    }
+
+    #[test]
+    fn windows_are_stacked_while_appending() {
+        let mut simulation = Simulation::new(rect(0, 0, 1920, 1080));
+
+        simulation.open(5030);
+        simulation.command(SocketMessage::ToggleWindowContainerBehaviour);
+        simulation.open(5031);
+
+        assert_eq!(simulation.containers(0, 0), vec![vec![5030, 5031]]);
+        assert!(!is_shown(5030));
+        assert!(is_shown(5031));
+        simulation.assert_tiled(0);
+    }
+
+    #[test]
+    fn containers_moved_to_another_workspace_are_followed() {
+        let mut simulation = Simulation::new(rect(0, 0, 1920, 1080));
+
+        for hwnd in [5040, 5041] {
+            simulation.open(hwnd);
+        }
+
+        simulation.command(SocketMessage::MoveContainerToWorkspaceNumber(1));
+
+        assert_eq!(simulation.containers(0, 0), vec![vec![5040]]);
+        assert_eq!(simulation.containers(0, 1), vec![vec![5041]]);
+        assert_eq!(simulation.wm.focused_workspace_idx().unwrap(), 1);
+        assert!(!is_shown(5040));
+        assert!(is_shown(5041));
+        simulation.assert_tiled(0);
+    }
+
+    #[test]
+    fn windows_are_opened_on_the_focused_monitor() {
+        let mut simulation = Simulation::new(rect(0, 0, 1920, 1080));
+        simulation.add_monitor(1, rect(1920, 0, 1920, 1080));
+
+        simulation.open(5050);
+        simulation.command(SocketMessage::FocusMonitorNumber(1));
+        simulation.open(5051);
+
+        assert_eq!(simulation.containers(0, 0), vec![vec![5050]]);
+        assert_eq!(simulation.containers(1, 0), vec![vec![5051]]);
+        assert!(mock_system_api().window(5051).unwrap().rect.left >= 1920);
+        simulation.assert_tiled(0);
+        simulation.assert_tiled(1);
+    }
 }
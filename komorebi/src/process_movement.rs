@@ -1,4 +1,7 @@
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 
 use parking_lot::Mutex;
 use winput::message_loop;
@@ -8,11 +11,15 @@ use winput::Action;
 use crate::core::FocusFollowsMouseImplementation;
 
 use crate::window_manager::WindowManager;
+use crate::FOCUS_FOLLOWS_MOUSE_DELAY;
 
 #[tracing::instrument]
 pub fn listen_for_movements(wm: Arc<Mutex<WindowManager>>) {
     std::thread::spawn(move || {
         let mut ignore_movement = false;
+        // When the cursor last moved, if it has yet to rest for long enough to raise the window
+        // under it
+        let mut moved_at: Option<Instant> = None;
 
         let receiver = message_loop::start().expect("could not start winput message loop");
 
@@ -22,23 +29,48 @@ pub fn listen_for_movements(wm: Arc<Mutex<WindowManager>>) {
                 focus_follows_mouse,
                 Some(FocusFollowsMouseImplementation::Komorebi)
             ) {
-                match receiver.next_event() {
+                let delay = Duration::from_millis(FOCUS_FOLLOWS_MOUSE_DELAY.load(Ordering::SeqCst));
+
+                let event = match moved_at {
+                    Some(moved_at) => {
+                        receiver.next_event_timeout(delay.saturating_sub(moved_at.elapsed()))
+                    }
+                    None => Some(receiver.next_event()),
+                };
+
+                match event {
                     // Don't want to send any raise events while we are dragging or resizing
-                    Event::MouseButton { action, .. } => match action {
-                        Action::Press => ignore_movement = true,
+                    Some(Event::MouseButton { action, .. }) => match action {
+                        Action::Press => {
+                            ignore_movement = true;
+                            moved_at = None;
+                        }
                         Action::Release => ignore_movement = false,
                     },
-                    Event::MouseMoveRelative { .. } => {
+                    Some(Event::MouseMoveRelative { .. }) => {
                         if !ignore_movement {
-                            match wm.lock().raise_window_at_cursor_pos() {
-                                Ok(()) => {}
-                                Err(error) => tracing::error!("{}", error),
+                            if delay.is_zero() {
+                                raise_window_at_cursor_pos(&wm);
+                            } else {
+                                moved_at = Some(Instant::now());
                             }
                         }
                     }
                     _ => {}
                 }
+
+                if moved_at.is_some_and(|moved_at| moved_at.elapsed() >= delay) {
+                    moved_at = None;
+                    raise_window_at_cursor_pos(&wm);
+                }
             }
         }
     });
 }
+
+fn raise_window_at_cursor_pos(wm: &Arc<Mutex<WindowManager>>) {
+    match wm.lock().raise_window_at_cursor_pos() {
+        Ok(()) => {}
+        Err(error) => tracing::error!("{}", error),
+    }
+}
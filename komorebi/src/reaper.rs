@@ -1,11 +1,17 @@
 #![deny(clippy::unwrap_used, clippy::expect_used)]
 
 use crate::border_manager;
+use crate::windows_api::WindowsApi;
 use crate::WindowManager;
+use crate::URGENT_HWNDS;
 use parking_lot::Mutex;
 use std::sync::Arc;
 use std::time::Duration;
 
+/// Destroyed windows are normally removed when their Destroy event is handled, so the reaper only
+/// has to catch the few which disappear without one and can run infrequently
+const REAP_INTERVAL: Duration = Duration::from_secs(10);
+
 pub fn watch_for_orphans(wm: Arc<Mutex<WindowManager>>) {
     std::thread::spawn(move || loop {
         match find_orphans(wm.clone()) {
@@ -29,29 +35,18 @@ pub fn find_orphans(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
     let arc = wm.clone();
 
     loop {
-        std::thread::sleep(Duration::from_secs(1));
+        std::thread::sleep(REAP_INTERVAL);
 
         let mut wm = arc.lock();
         let offset = wm.work_area_offset;
 
         for (i, monitor) in wm.monitors_mut().iter_mut().enumerate() {
-            let work_area = *monitor.work_area_size();
-            let window_based_work_area_offset = (
-                monitor.window_based_work_area_offset_limit(),
-                monitor.window_based_work_area_offset(),
-            );
-
-            let offset = if monitor.work_area_offset().is_some() {
-                monitor.work_area_offset()
-            } else {
-                offset
-            };
+            let mut reaped_workspaces = vec![];
 
             for (j, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
                 let reaped_orphans = workspace.reap_orphans()?;
                 if reaped_orphans.0 > 0 || reaped_orphans.1 > 0 {
-                    workspace.update(&work_area, offset, window_based_work_area_offset)?;
-                    border_manager::send_notification(None);
+                    reaped_workspaces.push(j);
                     tracing::info!(
                         "reaped {} orphan window(s) and {} orphaned container(s) on monitor: {}, workspace: {}",
                         reaped_orphans.0,
@@ -61,6 +56,18 @@ pub fn find_orphans(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
                     );
                 }
             }
+
+            // Hidden workspaces are only laid out again once they are shown
+            for j in reaped_workspaces {
+                monitor.update_workspace(j, offset)?;
+                border_manager::send_notification(None);
+            }
         }
+
+        // Windows which disappear without a Destroy event would otherwise be reported as urgent
+        // forever
+        URGENT_HWNDS
+            .lock()
+            .retain(|hwnd| WindowsApi::is_window(*hwnd));
     }
 }
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::collections::VecDeque;
 
 use schemars::JsonSchema;
@@ -44,9 +45,93 @@ impl<T> Ring<T> {
         self.elements.get_mut(self.focused)
     }
 
+    /// Swaps the elements at the given indices; the focused index does not change, so focus moves
+    /// to the other element if the focused element is one of the two being swapped
     pub fn swap(&mut self, i: usize, j: usize) {
         self.elements.swap(i, j);
     }
+
+    /// Inserts an element at the given index, keeping focus on the element which was focused
+    /// before the insertion
+    ///
+    /// Panics if the index is greater than the number of elements
+    pub fn insert(&mut self, idx: usize, element: T) {
+        let was_empty = self.elements.is_empty();
+        self.elements.insert(idx, element);
+
+        if !was_empty && idx <= self.focused {
+            self.focused += 1;
+        }
+    }
+
+    /// Removes the element at the given index, keeping focus on the element which was focused
+    /// before the removal, or on the element before it if the focused element was removed
+    pub fn remove(&mut self, idx: usize) -> Option<T> {
+        let element = self.elements.remove(idx)?;
+
+        if idx <= self.focused {
+            self.focused = self.focused.saturating_sub(1);
+        }
+
+        Some(element)
+    }
+
+    /// Rotates the elements `n` places to the left, with focus following the focused element
+    ///
+    /// Panics if `n` is greater than the number of elements
+    pub fn rotate_left(&mut self, n: usize) {
+        self.elements.rotate_left(n);
+
+        let len = self.elements.len();
+        if len > 0 {
+            self.focused = (self.focused + len - n) % len;
+        }
+    }
+
+    /// Rotates the elements `n` places to the right, with focus following the focused element
+    ///
+    /// Panics if `n` is greater than the number of elements
+    pub fn rotate_right(&mut self, n: usize) {
+        self.elements.rotate_right(n);
+
+        let len = self.elements.len();
+        if len > 0 {
+            self.focused = (self.focused + n) % len;
+        }
+    }
+
+    /// Retains only the elements for which `f` returns true, keeping focus on the element which
+    /// was focused if it is retained, or on the closest retained element before it if it is not
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let focused = self.focused;
+        let mut idx = 0;
+        let mut retained_before_focused = 0;
+        let mut focused_retained = false;
+
+        self.elements.retain(|element| {
+            let retain = f(element);
+
+            if retain {
+                match idx.cmp(&focused) {
+                    Ordering::Less => retained_before_focused += 1,
+                    Ordering::Equal => focused_retained = true,
+                    Ordering::Greater => {}
+                }
+            }
+
+            idx += 1;
+            retain
+        });
+
+        self.focused = if focused_retained {
+            retained_before_focused
+        } else {
+            retained_before_focused.saturating_sub(1)
+        };
+    }
 }
 
 macro_rules! impl_ring_elements {
@@ -77,3 +162,136 @@ macro_rules! impl_ring_elements {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(elements: &[i32], focused: usize) -> Ring<i32> {
+        let mut ring = Ring::default();
+        ring.elements_mut().extend(elements);
+        ring.focus(focused);
+        ring
+    }
+
+    fn contents(ring: &Ring<i32>) -> Vec<i32> {
+        ring.elements().iter().copied().collect()
+    }
+
+    #[test]
+    fn insert_keeps_focus_on_the_focused_element() {
+        let mut before = ring(&[1, 2, 3], 1);
+        before.insert(0, 0);
+        assert_eq!(contents(&before), vec![0, 1, 2, 3]);
+        assert_eq!(before.focused(), Some(&2));
+
+        let mut at = ring(&[1, 2, 3], 1);
+        at.insert(1, 0);
+        assert_eq!(contents(&at), vec![1, 0, 2, 3]);
+        assert_eq!(at.focused(), Some(&2));
+
+        let mut after = ring(&[1, 2, 3], 1);
+        after.insert(3, 0);
+        assert_eq!(contents(&after), vec![1, 2, 3, 0]);
+        assert_eq!(after.focused(), Some(&2));
+    }
+
+    #[test]
+    fn insert_into_an_empty_ring_focuses_the_element() {
+        let mut ring = ring(&[], 0);
+        ring.insert(0, 1);
+        assert_eq!(ring.focused(), Some(&1));
+    }
+
+    #[test]
+    fn remove_keeps_focus_on_the_focused_element() {
+        let mut before = ring(&[1, 2, 3], 1);
+        assert_eq!(before.remove(0), Some(1));
+        assert_eq!(before.focused(), Some(&2));
+
+        let mut after = ring(&[1, 2, 3], 1);
+        assert_eq!(after.remove(2), Some(3));
+        assert_eq!(after.focused(), Some(&2));
+    }
+
+    #[test]
+    fn removing_the_focused_element_focuses_the_one_before_it() {
+        let mut middle = ring(&[1, 2, 3], 1);
+        assert_eq!(middle.remove(1), Some(2));
+        assert_eq!(middle.focused(), Some(&1));
+
+        let mut first = ring(&[1, 2, 3], 0);
+        assert_eq!(first.remove(0), Some(1));
+        assert_eq!(first.focused(), Some(&2));
+    }
+
+    #[test]
+    fn remove_out_of_bounds_changes_nothing() {
+        let mut ring = ring(&[1, 2, 3], 2);
+        assert_eq!(ring.remove(3), None);
+        assert_eq!(contents(&ring), vec![1, 2, 3]);
+        assert_eq!(ring.focused(), Some(&3));
+    }
+
+    #[test]
+    fn rotate_left_follows_the_focused_element() {
+        let mut ring = ring(&[1, 2, 3, 4], 0);
+        ring.rotate_left(1);
+        assert_eq!(contents(&ring), vec![2, 3, 4, 1]);
+        assert_eq!(ring.focused(), Some(&1));
+
+        ring.rotate_left(3);
+        assert_eq!(contents(&ring), vec![1, 2, 3, 4]);
+        assert_eq!(ring.focused(), Some(&1));
+    }
+
+    #[test]
+    fn rotate_right_follows_the_focused_element() {
+        let mut ring = ring(&[1, 2, 3, 4], 3);
+        ring.rotate_right(1);
+        assert_eq!(contents(&ring), vec![4, 1, 2, 3]);
+        assert_eq!(ring.focused(), Some(&4));
+
+        ring.rotate_right(2);
+        assert_eq!(contents(&ring), vec![2, 3, 4, 1]);
+        assert_eq!(ring.focused(), Some(&4));
+    }
+
+    #[test]
+    fn rotating_an_empty_ring_does_nothing() {
+        let mut ring = ring(&[], 0);
+        ring.rotate_left(0);
+        ring.rotate_right(0);
+        assert_eq!(ring.focused_idx(), 0);
+    }
+
+    #[test]
+    fn retain_keeps_focus_on_a_retained_focused_element() {
+        let mut ring = ring(&[1, 2, 3, 4, 5], 3);
+        ring.retain(|element| element % 2 == 0);
+        assert_eq!(contents(&ring), vec![2, 4]);
+        assert_eq!(ring.focused(), Some(&4));
+    }
+
+    #[test]
+    fn retain_focuses_the_closest_retained_element_before_a_removed_focused_element() {
+        let mut middle = ring(&[1, 2, 3, 4, 5], 2);
+        middle.retain(|element| *element != 3);
+        assert_eq!(contents(&middle), vec![1, 2, 4, 5]);
+        assert_eq!(middle.focused(), Some(&2));
+
+        let mut first = ring(&[1, 2, 3], 0);
+        first.retain(|element| *element != 1);
+        assert_eq!(contents(&first), vec![2, 3]);
+        assert_eq!(first.focused(), Some(&2));
+    }
+
+    #[test]
+    fn swap_leaves_the_focused_index_in_place() {
+        let mut ring = ring(&[1, 2, 3], 0);
+        ring.swap(0, 2);
+        assert_eq!(contents(&ring), vec![3, 2, 1]);
+        assert_eq!(ring.focused_idx(), 0);
+        assert_eq!(ring.focused(), Some(&3));
+    }
+}
@@ -0,0 +1,138 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use crossbeam_utils::atomic::AtomicConsume;
+use std::sync::atomic::AtomicBool;
+use std::sync::OnceLock;
+use windows::Win32::Foundation::POINT;
+use windows::Win32::UI::WindowsAndMessaging::MSLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::WHEEL_DELTA;
+use windows::Win32::UI::WindowsAndMessaging::WM_MOUSEWHEEL;
+
+use crate::core::CycleDirection;
+use crate::core::SocketMessage;
+use crate::process_command;
+use crate::Window;
+use crate::WindowsApi;
+
+pub static SCROLL_WORKSPACES_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The classes of the windows which draw the desktop background, depending on whether or not the
+/// wallpaper has been changed since the shell started
+pub const DESKTOP_CLASSES: [&str; 2] = ["Progman", "WorkerW"];
+
+/// A turn of the mouse wheel over the desktop or komorebi-bar, with the distance that it was
+/// turned by
+pub struct Notification(i16);
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+fn send_notification(notification: Notification) {
+    if event_tx().try_send(notification).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    // Precision touchpads and free-spinning wheels report turns of less than a notch, which are
+    // added up so that every full notch switches exactly one workspace
+    let mut distance: i32 = 0;
+
+    for Notification(delta) in receiver {
+        if distance.signum() != i32::from(delta).signum() {
+            distance = 0;
+        }
+
+        distance += i32::from(delta);
+
+        let notches = distance / WHEEL_DELTA as i32;
+        distance %= WHEEL_DELTA as i32;
+
+        // Turning the wheel away from the user scrolls up, which goes back to the previous
+        // workspace
+        let direction = if notches > 0 {
+            CycleDirection::Previous
+        } else {
+            CycleDirection::Next
+        };
+
+        for _ in 0..notches.abs() {
+            // The workspace switch is made on the monitor under the cursor, which is the one that
+            // has been scrolled over
+            match process_command::submit_command(SocketMessage::CycleFocusWorkspace(direction)) {
+                Ok((_, Ok(()))) => {}
+                Ok((_, Err(error))) | Err(error) => {
+                    tracing::error!("could not cycle workspaces: {error}");
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the point is over the desktop background or komorebi-bar, rather than over a window
+/// which might want to scroll its own contents
+fn is_scroll_target(point: POINT) -> bool {
+    let Ok(hwnd) = WindowsApi::window_from_point(point).and_then(WindowsApi::root_window) else {
+        return false;
+    };
+
+    if WindowsApi::real_window_class_w(hwnd)
+        .is_ok_and(|class| DESKTOP_CLASSES.contains(&class.as_str()))
+    {
+        return true;
+    }
+
+    // The bar doesn't have a class of its own, so it can only be told apart by its executable
+    Window::from(hwnd)
+        .exe()
+        .is_ok_and(|exe| exe == "komorebi-bar.exe")
+}
+
+/// Called from the low-level mouse hook; swallows wheel turns over the desktop and komorebi-bar,
+/// which are used to cycle workspaces instead
+pub fn handle_mouse_input(message: u32, event: &MSLLHOOKSTRUCT) -> bool {
+    if message != WM_MOUSEWHEEL
+        || !SCROLL_WORKSPACES_ENABLED.load_consume()
+        || !is_scroll_target(event.pt)
+    {
+        return false;
+    }
+
+    // The distance that the wheel was turned by is in the high word
+    send_notification(Notification((event.mouseData >> 16) as u16 as i16));
+
+    true
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use color_eyre::Result;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::window::Window;
+use crate::window_manager::WindowManager;
+use crate::DATA_DIR;
+
+/// The workspace assignments of all managed windows, which are used to place windows back into
+/// the workspaces they were on when komorebi is restarted
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Session {
+    pub focused_monitor_idx: usize,
+    pub monitors: Vec<SessionMonitor>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionMonitor {
+    pub device_id: String,
+    pub focused_workspace_idx: usize,
+    pub workspaces: Vec<SessionWorkspace>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionWorkspace {
+    pub containers: Vec<Vec<SessionWindow>>,
+    pub floating_windows: Vec<SessionWindow>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionWindow {
+    pub hwnd: isize,
+    pub exe: String,
+    pub class: String,
+    pub title: String,
+}
+
+impl SessionWindow {
+    fn unresolved(window: Window) -> Self {
+        Self {
+            hwnd: window.hwnd,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Window> for SessionWindow {
+    fn from(window: Window) -> Self {
+        Self {
+            hwnd: window.hwnd,
+            exe: window.exe().unwrap_or_default(),
+            class: window.class().unwrap_or_default(),
+            title: window.title().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&WindowManager> for Session {
+    fn from(wm: &WindowManager) -> Self {
+        Self::capture(wm).with_window_details()
+    }
+}
+
+impl Session {
+    /// Captures the workspace assignments of all managed windows by hwnd only, which is cheap
+    /// enough to do while holding the window manager lock; the exe, class and title of each
+    /// window are looked up afterwards by [`Session::with_window_details`]
+    pub fn capture(wm: &WindowManager) -> Self {
+        let mut session = Self {
+            focused_monitor_idx: wm.focused_monitor_idx(),
+            monitors: vec![],
+        };
+
+        for monitor in wm.monitors() {
+            let mut session_monitor = SessionMonitor {
+                device_id: monitor.device_id().clone(),
+                focused_workspace_idx: monitor.focused_workspace_idx(),
+                workspaces: vec![],
+            };
+
+            for workspace in monitor.workspaces() {
+                let mut session_workspace = SessionWorkspace::default();
+
+                for container in workspace.containers() {
+                    session_workspace.containers.push(
+                        container
+                            .windows()
+                            .iter()
+                            .copied()
+                            .map(SessionWindow::unresolved)
+                            .collect(),
+                    );
+                }
+
+                // Monocle and maximized windows are restored as regular containers
+                if let Some(container) = workspace.monocle_container() {
+                    session_workspace.containers.push(
+                        container
+                            .windows()
+                            .iter()
+                            .copied()
+                            .map(SessionWindow::unresolved)
+                            .collect(),
+                    );
+                }
+
+                if let Some(window) = workspace.maximized_window() {
+                    session_workspace
+                        .containers
+                        .push(vec![SessionWindow::unresolved(*window)]);
+                }
+
+                for window in workspace.floating_windows() {
+                    session_workspace
+                        .floating_windows
+                        .push(SessionWindow::unresolved(*window));
+                }
+
+                session_monitor.workspaces.push(session_workspace);
+            }
+
+            session.monitors.push(session_monitor);
+        }
+
+        session
+    }
+
+    /// Looks up the exe, class and title of every window captured by [`Session::capture`]
+    pub fn with_window_details(mut self) -> Self {
+        for monitor in &mut self.monitors {
+            for workspace in &mut monitor.workspaces {
+                for saved in workspace
+                    .containers
+                    .iter_mut()
+                    .flatten()
+                    .chain(workspace.floating_windows.iter_mut())
+                {
+                    *saved = SessionWindow::from(Window::from(saved.hwnd));
+                }
+            }
+        }
+
+        self
+    }
+
+    pub fn path() -> PathBuf {
+        DATA_DIR.join("komorebi.session.json")
+    }
+
+    pub fn load() -> Result<Self> {
+        let content = std::fs::read_to_string(Self::path())?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        // Write to a temporary file first so that a crash never leaves a truncated session behind
+        let tmp = DATA_DIR.join("komorebi.session.json.tmp");
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(true)
+            .open(&tmp)?;
+
+        serde_json::to_writer_pretty(&file, self)?;
+        file.flush()?;
+        file.sync_all()?;
+        drop(file);
+
+        std::fs::rename(tmp, Self::path())?;
+
+        Ok(())
+    }
+
+    /// Resolves the windows in this session to the given windows which currently exist, returning
+    /// a map of session hwnds to current hwnds
+    ///
+    /// Window handles are not stable across reboots and can be reused by the system, so a window
+    /// is matched in order of preference on:
+    ///
+    /// 1. the same hwnd and exe
+    /// 2. the same exe, class and title
+    /// 3. the same exe and class
+    ///
+    /// Each pass runs over the whole session before the next one starts so that a weaker match
+    /// can never claim a window which would have been a stronger match for another entry
+    pub fn resolve(&self, windows: &[SessionWindow]) -> HashMap<isize, isize> {
+        let session_windows = self.windows();
+
+        let mut resolved = HashMap::new();
+        let mut claimed = HashSet::new();
+
+        let passes: [fn(&SessionWindow, &SessionWindow) -> bool; 3] = [
+            |saved, current| saved.hwnd == current.hwnd && saved.exe == current.exe,
+            |saved, current| {
+                saved.exe == current.exe
+                    && saved.class == current.class
+                    && saved.title == current.title
+            },
+            |saved, current| saved.exe == current.exe && saved.class == current.class,
+        ];
+
+        for matches in passes {
+            for saved in &session_windows {
+                if resolved.contains_key(&saved.hwnd) {
+                    continue;
+                }
+
+                if let Some(current) = windows
+                    .iter()
+                    .find(|current| !claimed.contains(&current.hwnd) && matches(saved, current))
+                {
+                    claimed.insert(current.hwnd);
+                    resolved.insert(saved.hwnd, current.hwnd);
+                }
+            }
+        }
+
+        resolved
+    }
+
+    fn windows(&self) -> Vec<&SessionWindow> {
+        let mut windows = vec![];
+
+        for monitor in &self.monitors {
+            for workspace in &monitor.workspaces {
+                windows.extend(workspace.containers.iter().flatten());
+                windows.extend(workspace.floating_windows.iter());
+            }
+        }
+
+        windows
+    }
+}
@@ -0,0 +1,88 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::session::Session;
+use crate::WindowManager;
+
+/// The minimum interval between two writes of komorebi.session.json
+const DEBOUNCE: Duration = Duration::from_millis(1000);
+
+pub struct Notification;
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification() {
+    if event_tx().try_send(Notification).is_err() {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications(wm: Arc<Mutex<WindowManager>>) {
+    std::thread::spawn(move || loop {
+        match handle_notifications(wm.clone()) {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+    let mut last_written = None;
+
+    while receiver.recv().is_ok() {
+        // Looking up the exe and title of every managed window is comparatively expensive, so
+        // bursts of events are collapsed into a single snapshot once the debounce interval has
+        // elapsed
+        let deadline = Instant::now() + DEBOUNCE;
+
+        loop {
+            match receiver.recv_deadline(deadline) {
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        // Only the hwnds are collected while holding the lock, the exe, class and title of each
+        // window are looked up once it has been released
+        let session = Session::capture(&wm.lock());
+        let session = session.with_window_details();
+
+        if last_written.as_ref() == Some(&session) {
+            continue;
+        }
+
+        tracing::trace!("updating session");
+        session.save()?;
+        last_written = Some(session);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// How long the system waits for a process to clean up at the end of a session or after its
+/// console window has been closed before terminating it
+const GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+pub struct Notification(pub &'static str);
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(1))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+/// Asks the main thread to restore all hidden windows and terminate the process
+pub fn send_notification(reason: &'static str) {
+    if event_tx().try_send(Notification(reason)).is_err() {
+        tracing::trace!("shutdown already requested; dropping notification")
+    }
+}
+
+/// Asks for a shutdown and then blocks until the main thread has terminated the process, for
+/// callers which the system would terminate as soon as they return
+pub fn send_notification_and_wait(reason: &'static str) {
+    send_notification(reason);
+    std::thread::sleep(GRACE_PERIOD);
+}
+
+pub fn wait_for_notification() -> color_eyre::Result<&'static str> {
+    Ok(event_rx().recv()?.0)
+}
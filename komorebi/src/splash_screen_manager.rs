@@ -0,0 +1,112 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::RecvTimeoutError;
+use crossbeam_channel::Sender;
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::window::Window;
+use crate::window_manager_event::WindowManagerEvent;
+use crate::winevent::WinEvent;
+use crate::winevent_listener;
+use crate::PENDING_SPLASH_SCREENS;
+
+pub struct Notification {
+    window: Window,
+    winevent: WinEvent,
+    deadline: Instant,
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+/// Sends the show event of a window which looks like a splash screen through the event loop again
+/// once its grace period has elapsed, if the window is still around by then
+pub fn send_notification(window: Window, winevent: WinEvent, deadline: Instant) {
+    if event_tx()
+        .try_send(Notification {
+            window,
+            winevent,
+            deadline,
+        })
+        .is_err()
+    {
+        tracing::warn!("channel is full; dropping notification");
+        PENDING_SPLASH_SCREENS.lock().remove(&window.hwnd);
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    // Ordered by deadline, as the grace period can change while windows are pending
+    let mut pending: Vec<Notification> = vec![];
+
+    loop {
+        let received = match pending.first() {
+            Some(next) => receiver.recv_deadline(next.deadline),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match received {
+            Ok(notification) => {
+                let idx = pending.partition_point(|other| other.deadline <= notification.deadline);
+                pending.insert(idx, notification);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        let now = Instant::now();
+        let due = pending.partition_point(|notification| notification.deadline <= now);
+
+        for Notification {
+            window, winevent, ..
+        } in pending.drain(..due)
+        {
+            if !window.is_window() || !window.is_visible() {
+                tracing::debug!(
+                    "window disappeared within the splash screen grace period: {}",
+                    window.hwnd
+                );
+                PENDING_SPLASH_SCREENS.lock().remove(&window.hwnd);
+                continue;
+            }
+
+            if winevent_listener::event_tx()
+                .try_send(WindowManagerEvent::Show(winevent, window))
+                .is_err()
+            {
+                tracing::warn!("channel is full; dropping deferred show event");
+                PENDING_SPLASH_SCREENS.lock().remove(&window.hwnd);
+            }
+        }
+    }
+}
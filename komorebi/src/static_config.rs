@@ -8,9 +8,19 @@ use crate::core::BorderImplementation;
 use crate::core::StackbarLabel;
 use crate::core::StackbarMode;
 use crate::current_virtual_desktop;
+use crate::drag_manager;
+use crate::drop_zone_manager;
+use crate::flash_manager;
+use crate::gap_drag_manager;
+use crate::hook_manager;
+use crate::hot_edge_manager;
+use crate::indicator_manager;
 use crate::monitor::Monitor;
 use crate::monitor_reconciliator;
+use crate::mouse_listener;
+use crate::osd_manager;
 use crate::ring::Ring;
+use crate::scroll_manager;
 use crate::stackbar_manager::STACKBAR_FOCUSED_TEXT_COLOUR;
 use crate::stackbar_manager::STACKBAR_FONT_FAMILY;
 use crate::stackbar_manager::STACKBAR_FONT_SIZE;
@@ -21,6 +31,7 @@ use crate::stackbar_manager::STACKBAR_TAB_HEIGHT;
 use crate::stackbar_manager::STACKBAR_TAB_WIDTH;
 use crate::stackbar_manager::STACKBAR_UNFOCUSED_TEXT_COLOUR;
 use crate::theme_manager;
+use crate::toast_manager;
 use crate::transparency_manager;
 use crate::window;
 use crate::window_manager::WindowManager;
@@ -28,26 +39,49 @@ use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api::WindowsApi;
 use crate::workspace::Workspace;
 use crate::CrossBoundaryBehaviour;
+use crate::ANIMATE_LAYOUT_CHANGES;
+use crate::ANIMATE_MOUSE_DRAG_SNAPS;
 use crate::ANIMATION_DURATION;
 use crate::ANIMATION_ENABLED;
 use crate::ANIMATION_FPS;
 use crate::ANIMATION_STYLE;
+use crate::CORNER_PREFERENCE;
+use crate::CORNER_PREFERENCE_RULES;
 use crate::DATA_DIR;
 use crate::DEFAULT_CONTAINER_PADDING;
 use crate::DEFAULT_WORKSPACE_PADDING;
 use crate::DISPLAY_INDEX_PREFERENCES;
+use crate::DROP_BEHAVIOUR;
 use crate::FLOATING_APPLICATIONS;
+use crate::FLOAT_ELEVATED_WINDOWS;
+use crate::FLOAT_LAYOUT_REJECTING_WINDOWS;
+use crate::FLOAT_SNAPPING_DISTANCE;
+use crate::FLOAT_SNAPPING_ENABLED;
+use crate::FLOAT_SNAPPING_GRID;
+use crate::FOCUS_FOLLOWS_MOUSE_DELAY;
 use crate::HIDING_BEHAVIOUR;
+use crate::HIDING_BEHAVIOUR_RULES;
 use crate::IGNORE_IDENTIFIERS;
+use crate::KNOWN_HWNDS_FILE;
 use crate::LAYERED_WHITELIST;
 use crate::MANAGE_IDENTIFIERS;
 use crate::MONITOR_INDEX_PREFERENCES;
+use crate::NO_TITLEBAR;
 use crate::OBJECT_NAME_CHANGE_ON_LAUNCH;
+use crate::PAUSE_ON_FULLSCREEN;
+use crate::PICTURE_IN_PICTURE_IDENTIFIERS;
 use crate::REGEX_IDENTIFIERS;
+use crate::REMOVE_TITLEBARS;
+use crate::RESTORE_SESSION;
+use crate::RULE_SETTLE_PERIOD;
 use crate::SLOW_APPLICATION_COMPENSATION_TIME;
 use crate::SLOW_APPLICATION_IDENTIFIERS;
+use crate::SNAP_BEHAVIOUR;
+use crate::SPLASH_SCREEN_GRACE_PERIOD;
+use crate::SPLASH_SCREEN_IDENTIFIERS;
 use crate::TRANSPARENCY_BLACKLIST;
 use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
+use crate::WIDTH_PRESETS;
 use crate::WINDOWS_11;
 use crate::WORKSPACE_MATCHING_RULES;
 
@@ -62,13 +96,19 @@ use crate::core::config_generation::MatchingStrategy;
 use crate::core::resolve_home_path;
 use crate::core::AnimationStyle;
 use crate::core::BorderStyle;
+use crate::core::CornerPreference;
 use crate::core::DefaultLayout;
+use crate::core::DragModifier;
+use crate::core::DropBehaviour;
 use crate::core::FocusFollowsMouseImplementation;
 use crate::core::HidingBehaviour;
+use crate::core::HookEvent;
 use crate::core::Layout;
 use crate::core::MoveBehaviour;
 use crate::core::OperationBehaviour;
 use crate::core::Rect;
+use crate::core::ScreenEdge;
+use crate::core::SnapBehaviour;
 use crate::core::SocketMessage;
 use crate::core::WindowContainerBehaviour;
 use crate::core::WindowManagementBehaviour;
@@ -83,6 +123,7 @@ use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::io::ErrorKind;
 use std::io::Write;
 use std::path::PathBuf;
@@ -266,6 +307,10 @@ pub struct StaticConfig {
     /// END OF LIFE FEATURE: Determine focus follows mouse implementation (default: None)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub focus_follows_mouse: Option<FocusFollowsMouseImplementation>,
+    /// How long in ms the cursor has to rest over a window before focus follows mouse focuses it
+    /// (default: 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_follows_mouse_delay: Option<u64>,
     /// Enable or disable mouse follows focus (default: true)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mouse_follows_focus: Option<bool>,
@@ -280,6 +325,9 @@ pub struct StaticConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(alias = "active_window_border_offset")]
     pub border_offset: Option<i32>,
+    /// Radius of the corners of the window border when they are rounded (default: 20)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub border_radius: Option<i32>,
     /// Display an active window border (default: false)
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(alias = "active_window_border")]
@@ -319,6 +367,28 @@ pub struct StaticConfig {
     /// Which Windows signal to use when hiding windows (default: Cloak)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub window_hiding_behaviour: Option<HidingBehaviour>,
+    /// Per-application overrides of the window hiding behaviour
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_hiding_behaviour_rules: Option<Vec<HidingBehaviourRule>>,
+    /// Corner rounding preference applied to tiled windows on Windows 11 (default: None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_corner_preference: Option<CornerPreference>,
+    /// Per-application overrides of the corner rounding preference, which also apply to floating
+    /// windows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window_corner_preference_rules: Option<Vec<CornerPreferenceRule>>,
+    /// What to do with managed windows which are snapped by the system, eg. with Win+Arrow or
+    /// a snap layout (default: Revert)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snap_behaviour: Option<SnapBehaviour>,
+    /// What to do with managed windows which are dragged onto another window container with the
+    /// mouse, which can be reversed for a single drop by holding shift (default: Swap)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_behaviour: Option<DropBehaviour>,
+    /// Hold this modifier to drag tiled windows with the mouse from anywhere inside of them,
+    /// rather than only by their title bars (default: None)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mouse_drag_modifier: Option<DragModifier>,
     /// Global work area (space used for tiling) offset (default: None)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub global_work_area_offset: Option<Rect>,
@@ -332,6 +402,10 @@ pub struct StaticConfig {
     /// Identify applications which should be managed as floating windows
     #[serde(skip_serializing_if = "Option::is_none")]
     pub floating_applications: Option<Vec<MatchingRule>>,
+    /// Identify picture-in-picture windows, which are left unmanaged and kept on top of all other
+    /// windows so that they stay visible when switching workspaces
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub picture_in_picture_applications: Option<Vec<MatchingRule>>,
     /// Identify border overflow applications
     #[serde(skip_serializing_if = "Option::is_none")]
     pub border_overflow_applications: Option<Vec<MatchingRule>>,
@@ -344,6 +418,11 @@ pub struct StaticConfig {
     /// Identify applications that send EVENT_OBJECT_NAMECHANGE on launch (very rare)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub object_name_change_applications: Option<Vec<MatchingRule>>,
+    /// Identify applications which should have their title bars removed while they are tiled; the
+    /// title bars are put back when they stop being tiled, and can be toggled with
+    /// toggle-title-bars
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remove_titlebar_applications: Option<Vec<MatchingRule>>,
     /// Set monitor index preferences
     #[serde(skip_serializing_if = "Option::is_none")]
     pub monitor_index_preferences: Option<HashMap<usize, Rect>>,
@@ -365,12 +444,165 @@ pub struct StaticConfig {
     /// How long to wait when compensating for slow applications, in milliseconds (default: 20)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub slow_application_compensation_time: Option<u64>,
+    /// Identify applications which show splash screens that should only be managed once they
+    /// have stayed open for the splash screen grace period
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splash_screen_applications: Option<Vec<MatchingRule>>,
+    /// How long to wait before managing small borderless windows and windows of splash screen
+    /// applications, in milliseconds, so that windows which disappear again within that time
+    /// never cause a retile (default: 1000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splash_screen_grace_period: Option<u64>,
+    /// How long after a window has been shown the first change of its title causes the floating
+    /// application and workspace rules to be evaluated again, in milliseconds, for applications
+    /// which only set their final title once their window has been created (default: 2000)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_settle_period: Option<u64>,
+    /// Suspend the handling of events on a monitor while a fullscreen window such as a game is
+    /// focused on it (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pause_on_fullscreen: Option<bool>,
+    /// Continuously write the list of known window handles to komorebi.hwnd.json so that windows
+    /// can be restored with 'komorebic restore-windows' even if komorebi crashes (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub known_hwnds_file: Option<bool>,
+    /// Continuously save the workspace assignments of managed windows to komorebi.session.json
+    /// and place windows which still exist back into those workspaces on startup (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restore_session: Option<bool>,
+    /// Float windows of elevated processes, which cannot be tiled while komorebi is running
+    /// unelevated, instead of leaving them unmanaged (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub float_elevated_windows: Option<bool>,
+    /// Float tiled windows which keep refusing the size assigned to them by the layout, such as
+    /// fixed-size dialogs (default: true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub float_layout_rejecting_windows: Option<bool>,
+    /// Commands to run when specific events occur, with the event JSON passed on stdin
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<Vec<Hook>>,
+    /// Commands to run when the cursor rests against an edge or in a corner of a monitor
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hot_edges: Option<Vec<HotEdge>>,
+    /// Toast notification configuration options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toasts: Option<ToastsConfig>,
+    /// Workspace switcher overlay configuration options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub osd: Option<OsdConfig>,
+    /// Focus flash configuration options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_flash: Option<FocusFlashConfig>,
+    /// Floating window snapping configuration options
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub float_snapping: Option<FloatSnappingConfig>,
+    /// Show a badge at the top of monitors whose focused workspace has a monocle container or a
+    /// maximized window, with the number of containers hidden behind it (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monocle_indicator: Option<bool>,
+    /// Show the layout slot that a window will be placed in while it is being dragged with the
+    /// mouse (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_zone_preview: Option<bool>,
+    /// Cycle the workspaces of a monitor by scrolling the mouse wheel over its desktop or over
+    /// komorebi-bar (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scroll_workspaces: Option<bool>,
+    /// Drag the gaps between tiled windows with the mouse to resize the windows on either side of
+    /// them (default: false)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gap_dragging: Option<bool>,
+    /// Percentages of the width of the workspace which the cycle-width command moves the focused
+    /// window through (default: [33, 50, 67])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width_presets: Option<Vec<u8>>,
     /// Komorebi status bar configuration files for multiple instances on different monitors
     #[serde(skip_serializing_if = "Option::is_none")]
     // this option is a little special because it is only consumed by komorebic
     pub bar_configurations: Option<Vec<PathBuf>>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Hook {
+    /// Event which should trigger the command
+    pub event: HookEvent,
+    /// Executable to run
+    pub command: String,
+    /// Arguments to pass to the executable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HotEdge {
+    /// Edge or corner of a monitor which triggers the command
+    pub edge: ScreenEdge,
+    /// Command to run when the cursor rests against the edge or in the corner
+    pub command: SocketMessage,
+    /// How long in ms the cursor has to rest there before the command is run (default: 500)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dwell: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HidingBehaviourRule {
+    /// Which Windows signal to use when hiding matching windows
+    pub behaviour: HidingBehaviour,
+    /// Rules matching the applications which should use this hiding behaviour
+    pub applications: Vec<MatchingRule>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CornerPreferenceRule {
+    /// Corner rounding preference for matching windows
+    pub preference: CornerPreference,
+    /// Rules matching the applications which should use this corner rounding preference
+    pub applications: Vec<MatchingRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ToastsConfig {
+    /// Enable or disable toast notifications (default: false)
+    enabled: bool,
+    /// Raise a toast when a workspace is focused (default: true)
+    workspace_focused: Option<bool>,
+    /// Raise a toast when the layout of the focused workspace changes (default: true)
+    layout_changed: Option<bool>,
+    /// Raise a toast when a command fails (default: true)
+    errors: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct OsdConfig {
+    /// Show an overlay listing the workspaces of a monitor when switching workspaces (default: false)
+    enabled: bool,
+    /// How long the overlay is shown for after the last workspace switch in ms (default: 1000)
+    duration: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FloatSnappingConfig {
+    /// Snap floating windows into place when they are moved with the mouse (default: false)
+    enabled: bool,
+    /// Size in pixels of the grid that floating windows are snapped to when there are no edges
+    /// close enough to snap to, measured from the top left corner of the work area; 0 turns the
+    /// grid off (default: 0)
+    grid: Option<i32>,
+    /// How close in pixels an edge of a floating window has to be to an edge of the work area or
+    /// of another floating window to be snapped to it (default: 10)
+    distance: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FocusFlashConfig {
+    /// Briefly highlight a window when it is focused by a komorebi command (default: false)
+    enabled: bool,
+    /// How long the highlight is shown for in ms (default: 200)
+    duration: Option<u64>,
+    /// Alpha value of the highlight [[0-255]] (default: 64)
+    alpha: Option<u8>,
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct AnimationsConfig {
     /// Enable or disable animations (default: false)
@@ -381,6 +613,10 @@ pub struct AnimationsConfig {
     style: Option<AnimationStyle>,
     /// Set the animation FPS (default: 60)
     fps: Option<u64>,
+    /// Animate windows moving into place when the layout changes (default: true)
+    layout_changes: Option<bool>,
+    /// Animate windows snapping into place after being dragged or resized with the mouse (default: true)
+    mouse_drag_snaps: Option<bool>,
 }
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "palette")]
@@ -578,10 +814,14 @@ impl From<&WindowManager> for StaticConfig {
             minimum_window_height: Some(window::MINIMUM_HEIGHT.load(Ordering::SeqCst)),
             minimum_window_width: Some(window::MINIMUM_WIDTH.load(Ordering::SeqCst)),
             focus_follows_mouse: value.focus_follows_mouse,
+            focus_follows_mouse_delay: Option::from(
+                FOCUS_FOLLOWS_MOUSE_DELAY.load(Ordering::SeqCst),
+            ),
             mouse_follows_focus: Option::from(value.mouse_follows_focus),
             app_specific_configuration_path: None,
             border_width: Option::from(border_manager::BORDER_WIDTH.load(Ordering::SeqCst)),
             border_offset: Option::from(border_manager::BORDER_OFFSET.load(Ordering::SeqCst)),
+            border_radius: Option::from(border_manager::BORDER_RADIUS.load(Ordering::SeqCst)),
             border: Option::from(border_manager::BORDER_ENABLED.load(Ordering::SeqCst)),
             border_colours,
             transparency: Option::from(
@@ -602,23 +842,87 @@ impl From<&WindowManager> for StaticConfig {
             ),
             monitors: Option::from(monitors),
             window_hiding_behaviour: Option::from(*HIDING_BEHAVIOUR.lock()),
+            window_hiding_behaviour_rules: Option::from(HIDING_BEHAVIOUR_RULES.lock().clone()),
+            window_corner_preference: *CORNER_PREFERENCE.lock(),
+            window_corner_preference_rules: Option::from(CORNER_PREFERENCE_RULES.lock().clone()),
+            snap_behaviour: Option::from(*SNAP_BEHAVIOUR.lock()),
+            drop_behaviour: Option::from(*DROP_BEHAVIOUR.lock()),
+            mouse_drag_modifier: *drag_manager::DRAG_MODIFIER.lock(),
             global_work_area_offset: value.work_area_offset,
             ignore_rules: None,
             floating_applications: None,
+            picture_in_picture_applications: None,
             manage_rules: None,
             border_overflow_applications: None,
             tray_and_multi_window_applications: None,
             layered_applications: None,
             object_name_change_applications: None,
+            remove_titlebar_applications: None,
             monitor_index_preferences: Option::from(MONITOR_INDEX_PREFERENCES.lock().clone()),
             display_index_preferences: Option::from(DISPLAY_INDEX_PREFERENCES.lock().clone()),
             stackbar: None,
-            animation: None,
+            animation: Option::from(AnimationsConfig {
+                enabled: ANIMATION_ENABLED.load(Ordering::SeqCst),
+                duration: Option::from(ANIMATION_DURATION.load(Ordering::SeqCst)),
+                style: Option::from(*ANIMATION_STYLE.lock()),
+                fps: Option::from(ANIMATION_FPS.load(Ordering::SeqCst)),
+                layout_changes: Option::from(ANIMATE_LAYOUT_CHANGES.load(Ordering::SeqCst)),
+                mouse_drag_snaps: Option::from(ANIMATE_MOUSE_DRAG_SNAPS.load(Ordering::SeqCst)),
+            }),
             theme: None,
             slow_application_compensation_time: Option::from(
                 SLOW_APPLICATION_COMPENSATION_TIME.load(Ordering::SeqCst),
             ),
             slow_application_identifiers: Option::from(SLOW_APPLICATION_IDENTIFIERS.lock().clone()),
+            splash_screen_applications: Option::from(SPLASH_SCREEN_IDENTIFIERS.lock().clone()),
+            splash_screen_grace_period: Option::from(
+                SPLASH_SCREEN_GRACE_PERIOD.load(Ordering::SeqCst),
+            ),
+            rule_settle_period: Option::from(RULE_SETTLE_PERIOD.load(Ordering::SeqCst)),
+            pause_on_fullscreen: Option::from(PAUSE_ON_FULLSCREEN.load(Ordering::SeqCst)),
+            known_hwnds_file: Option::from(KNOWN_HWNDS_FILE.load(Ordering::SeqCst)),
+            restore_session: Option::from(RESTORE_SESSION.load(Ordering::SeqCst)),
+            float_elevated_windows: Option::from(FLOAT_ELEVATED_WINDOWS.load(Ordering::SeqCst)),
+            float_layout_rejecting_windows: Option::from(
+                FLOAT_LAYOUT_REJECTING_WINDOWS.load(Ordering::SeqCst),
+            ),
+            hooks: Option::from(hook_manager::hooks()),
+            hot_edges: Option::from(hot_edge_manager::hot_edges()),
+            toasts: Option::from(ToastsConfig {
+                enabled: toast_manager::TOASTS_ENABLED.load(Ordering::SeqCst),
+                workspace_focused: Option::from(
+                    toast_manager::TOAST_ON_WORKSPACE_FOCUSED.load(Ordering::SeqCst),
+                ),
+                layout_changed: Option::from(
+                    toast_manager::TOAST_ON_LAYOUT_CHANGED.load(Ordering::SeqCst),
+                ),
+                errors: Option::from(toast_manager::TOAST_ON_ERROR.load(Ordering::SeqCst)),
+            }),
+            osd: Option::from(OsdConfig {
+                enabled: osd_manager::OSD_ENABLED.load(Ordering::SeqCst),
+                duration: Option::from(osd_manager::OSD_DURATION.load(Ordering::SeqCst)),
+            }),
+            focus_flash: Option::from(FocusFlashConfig {
+                enabled: flash_manager::FLASH_ENABLED.load(Ordering::SeqCst),
+                duration: Option::from(flash_manager::FLASH_DURATION.load(Ordering::SeqCst)),
+                alpha: Option::from(flash_manager::FLASH_ALPHA.load(Ordering::SeqCst)),
+            }),
+            float_snapping: Option::from(FloatSnappingConfig {
+                enabled: FLOAT_SNAPPING_ENABLED.load(Ordering::SeqCst),
+                grid: Option::from(FLOAT_SNAPPING_GRID.load(Ordering::SeqCst)),
+                distance: Option::from(FLOAT_SNAPPING_DISTANCE.load(Ordering::SeqCst)),
+            }),
+            monocle_indicator: Option::from(
+                indicator_manager::INDICATOR_ENABLED.load(Ordering::SeqCst),
+            ),
+            drop_zone_preview: Option::from(
+                drop_zone_manager::DROP_ZONE_ENABLED.load(Ordering::SeqCst),
+            ),
+            scroll_workspaces: Option::from(
+                scroll_manager::SCROLL_WORKSPACES_ENABLED.load(Ordering::SeqCst),
+            ),
+            gap_dragging: Option::from(gap_drag_manager::GAP_DRAG_ENABLED.load(Ordering::SeqCst)),
+            width_presets: Option::from(WIDTH_PRESETS.lock().clone()),
             bar_configurations: None,
         }
     }
@@ -642,6 +946,14 @@ impl StaticConfig {
             *window_hiding_behaviour = behaviour;
         }
 
+        *SNAP_BEHAVIOUR.lock() = self.snap_behaviour.unwrap_or(SnapBehaviour::Revert);
+        *DROP_BEHAVIOUR.lock() = self.drop_behaviour.unwrap_or(DropBehaviour::Swap);
+        FOCUS_FOLLOWS_MOUSE_DELAY.store(
+            self.focus_follows_mouse_delay.unwrap_or(0),
+            Ordering::SeqCst,
+        );
+        *drag_manager::DRAG_MODIFIER.lock() = self.mouse_drag_modifier;
+
         if let Some(height) = self.minimum_window_height {
             window::MINIMUM_HEIGHT.store(height, Ordering::SeqCst);
         }
@@ -654,10 +966,67 @@ impl StaticConfig {
             ANIMATION_ENABLED.store(animations.enabled, Ordering::SeqCst);
             ANIMATION_DURATION.store(animations.duration.unwrap_or(250), Ordering::SeqCst);
             ANIMATION_FPS.store(animations.fps.unwrap_or(60), Ordering::SeqCst);
+            ANIMATE_LAYOUT_CHANGES
+                .store(animations.layout_changes.unwrap_or(true), Ordering::SeqCst);
+            ANIMATE_MOUSE_DRAG_SNAPS.store(
+                animations.mouse_drag_snaps.unwrap_or(true),
+                Ordering::SeqCst,
+            );
             let mut animation_style = ANIMATION_STYLE.lock();
             *animation_style = animations.style.unwrap_or(AnimationStyle::Linear);
         }
 
+        let toasts = self.toasts.as_ref();
+        toast_manager::TOASTS_ENABLED.store(
+            toasts.is_some_and(|toasts| toasts.enabled),
+            Ordering::SeqCst,
+        );
+        toast_manager::TOAST_ON_WORKSPACE_FOCUSED.store(
+            toasts
+                .and_then(|toasts| toasts.workspace_focused)
+                .unwrap_or(true),
+            Ordering::SeqCst,
+        );
+        toast_manager::TOAST_ON_LAYOUT_CHANGED.store(
+            toasts
+                .and_then(|toasts| toasts.layout_changed)
+                .unwrap_or(true),
+            Ordering::SeqCst,
+        );
+        toast_manager::TOAST_ON_ERROR.store(
+            toasts.and_then(|toasts| toasts.errors).unwrap_or(true),
+            Ordering::SeqCst,
+        );
+
+        if let Some(osd) = &self.osd {
+            osd_manager::OSD_ENABLED.store(osd.enabled, Ordering::SeqCst);
+            osd_manager::OSD_DURATION.store(osd.duration.unwrap_or(1000), Ordering::SeqCst);
+        }
+
+        if let Some(focus_flash) = &self.focus_flash {
+            flash_manager::FLASH_ENABLED.store(focus_flash.enabled, Ordering::SeqCst);
+            flash_manager::FLASH_DURATION
+                .store(focus_flash.duration.unwrap_or(200), Ordering::SeqCst);
+            flash_manager::FLASH_ALPHA.store(focus_flash.alpha.unwrap_or(64), Ordering::SeqCst);
+        }
+
+        if let Some(float_snapping) = &self.float_snapping {
+            FLOAT_SNAPPING_ENABLED.store(float_snapping.enabled, Ordering::SeqCst);
+            FLOAT_SNAPPING_GRID.store(float_snapping.grid.unwrap_or(0), Ordering::SeqCst);
+            FLOAT_SNAPPING_DISTANCE.store(float_snapping.distance.unwrap_or(10), Ordering::SeqCst);
+        }
+
+        indicator_manager::INDICATOR_ENABLED
+            .store(self.monocle_indicator.unwrap_or(false), Ordering::SeqCst);
+        drop_zone_manager::DROP_ZONE_ENABLED
+            .store(self.drop_zone_preview.unwrap_or(false), Ordering::SeqCst);
+        scroll_manager::SCROLL_WORKSPACES_ENABLED
+            .store(self.scroll_workspaces.unwrap_or(false), Ordering::SeqCst);
+        gap_drag_manager::GAP_DRAG_ENABLED
+            .store(self.gap_dragging.unwrap_or(false), Ordering::SeqCst);
+
+        *WIDTH_PRESETS.lock() = self.width_presets.clone().unwrap_or(vec![33, 50, 67]);
+
         if let Some(container) = self.default_container_padding {
             DEFAULT_CONTAINER_PADDING.store(container, Ordering::SeqCst);
         }
@@ -668,6 +1037,7 @@ impl StaticConfig {
 
         border_manager::BORDER_WIDTH.store(self.border_width.unwrap_or(8), Ordering::SeqCst);
         border_manager::BORDER_OFFSET.store(self.border_offset.unwrap_or(-1), Ordering::SeqCst);
+        border_manager::BORDER_RADIUS.store(self.border_radius.unwrap_or(20), Ordering::SeqCst);
 
         if let Some(enabled) = &self.border {
             border_manager::BORDER_ENABLED.store(*enabled, Ordering::SeqCst);
@@ -725,6 +1095,26 @@ impl StaticConfig {
         transparency_manager::TRANSPARENCY_ALPHA
             .store(self.transparency_alpha.unwrap_or(200), Ordering::SeqCst);
 
+        KNOWN_HWNDS_FILE.store(self.known_hwnds_file.unwrap_or(false), Ordering::SeqCst);
+        RESTORE_SESSION.store(self.restore_session.unwrap_or(false), Ordering::SeqCst);
+        FLOAT_ELEVATED_WINDOWS.store(
+            self.float_elevated_windows.unwrap_or(false),
+            Ordering::SeqCst,
+        );
+        FLOAT_LAYOUT_REJECTING_WINDOWS.store(
+            self.float_layout_rejecting_windows.unwrap_or(true),
+            Ordering::SeqCst,
+        );
+        SPLASH_SCREEN_GRACE_PERIOD.store(
+            self.splash_screen_grace_period.unwrap_or(1000),
+            Ordering::SeqCst,
+        );
+        RULE_SETTLE_PERIOD.store(self.rule_settle_period.unwrap_or(2000), Ordering::SeqCst);
+        PAUSE_ON_FULLSCREEN.store(self.pause_on_fullscreen.unwrap_or(true), Ordering::SeqCst);
+        hook_manager::set_hooks(self.hooks.clone().unwrap_or_default());
+        hot_edge_manager::set_hot_edges(self.hot_edges.clone().unwrap_or_default());
+        mouse_listener::update();
+
         let mut ignore_identifiers = IGNORE_IDENTIFIERS.lock();
         let mut regex_identifiers = REGEX_IDENTIFIERS.lock();
         let mut manage_identifiers = MANAGE_IDENTIFIERS.lock();
@@ -733,7 +1123,10 @@ impl StaticConfig {
         let mut layered_identifiers = LAYERED_WHITELIST.lock();
         let mut transparency_blacklist = TRANSPARENCY_BLACKLIST.lock();
         let mut slow_application_identifiers = SLOW_APPLICATION_IDENTIFIERS.lock();
+        let mut splash_screen_identifiers = SPLASH_SCREEN_IDENTIFIERS.lock();
         let mut floating_applications = FLOATING_APPLICATIONS.lock();
+        let mut picture_in_picture_identifiers = PICTURE_IN_PICTURE_IDENTIFIERS.lock();
+        let mut no_titlebar = NO_TITLEBAR.lock();
 
         if let Some(rules) = &mut self.ignore_rules {
             populate_rules(rules, &mut ignore_identifiers, &mut regex_identifiers)?;
@@ -743,6 +1136,14 @@ impl StaticConfig {
             populate_rules(rules, &mut floating_applications, &mut regex_identifiers)?;
         }
 
+        if let Some(rules) = &mut self.picture_in_picture_applications {
+            populate_rules(
+                rules,
+                &mut picture_in_picture_identifiers,
+                &mut regex_identifiers,
+            )?;
+        }
+
         if let Some(rules) = &mut self.manage_rules {
             populate_rules(rules, &mut manage_identifiers, &mut regex_identifiers)?;
         }
@@ -771,6 +1172,11 @@ impl StaticConfig {
             populate_rules(rules, &mut transparency_blacklist, &mut regex_identifiers)?;
         }
 
+        if let Some(rules) = &mut self.remove_titlebar_applications {
+            populate_rules(rules, &mut no_titlebar, &mut regex_identifiers)?;
+            REMOVE_TITLEBARS.store(!no_titlebar.is_empty(), Ordering::SeqCst);
+        }
+
         if let Some(rules) = &mut self.slow_application_identifiers {
             populate_rules(
                 rules,
@@ -779,6 +1185,58 @@ impl StaticConfig {
             )?;
         }
 
+        if let Some(rules) = &mut self.splash_screen_applications {
+            populate_rules(
+                rules,
+                &mut splash_screen_identifiers,
+                &mut regex_identifiers,
+            )?;
+        }
+
+        if let Some(hiding_behaviour_rules) = &mut self.window_hiding_behaviour_rules {
+            let mut rules = vec![];
+
+            for hiding_behaviour_rule in hiding_behaviour_rules {
+                let mut applications = vec![];
+                populate_rules(
+                    &mut hiding_behaviour_rule.applications,
+                    &mut applications,
+                    &mut regex_identifiers,
+                )?;
+
+                rules.push(HidingBehaviourRule {
+                    behaviour: hiding_behaviour_rule.behaviour,
+                    applications,
+                });
+            }
+
+            *HIDING_BEHAVIOUR_RULES.lock() = rules;
+        }
+
+        *CORNER_PREFERENCE.lock() = self.window_corner_preference;
+
+        if let Some(corner_preference_rules) = &mut self.window_corner_preference_rules {
+            let mut rules = vec![];
+
+            for corner_preference_rule in corner_preference_rules {
+                let mut applications = vec![];
+                populate_rules(
+                    &mut corner_preference_rule.applications,
+                    &mut applications,
+                    &mut regex_identifiers,
+                )?;
+
+                rules.push(CornerPreferenceRule {
+                    preference: corner_preference_rule.preference,
+                    applications,
+                });
+            }
+
+            *CORNER_PREFERENCE_RULES.lock() = rules;
+        }
+
+        window::Window::reevaluate_corner_preference(None);
+
         if let Some(stackbar) = &self.stackbar {
             if let Some(height) = &stackbar.height {
                 STACKBAR_TAB_HEIGHT.store(*height, Ordering::SeqCst);
@@ -1050,13 +1508,19 @@ impl StaticConfig {
             hotwatch: Hotwatch::new()?,
             has_pending_raise_op: false,
             pending_move_op: None,
+            pending_float_move: None,
+            focus_history: VecDeque::new(),
             already_moved_window_handles: Arc::new(Mutex::new(HashSet::new())),
+            batch_in_progress: false,
+            pending_notifications: vec![],
         };
 
         match value.focus_follows_mouse {
             None => WindowsApi::disable_focus_follows_mouse()?,
             Some(FocusFollowsMouseImplementation::Windows) => {
-                WindowsApi::enable_focus_follows_mouse()?;
+                WindowsApi::enable_focus_follows_mouse(
+                    FOCUS_FOLLOWS_MOUSE_DELAY.load(Ordering::SeqCst),
+                )?;
             }
             Some(FocusFollowsMouseImplementation::Komorebi) => {}
         };
@@ -1241,7 +1705,9 @@ impl StaticConfig {
         match value.focus_follows_mouse {
             None => WindowsApi::disable_focus_follows_mouse()?,
             Some(FocusFollowsMouseImplementation::Windows) => {
-                WindowsApi::enable_focus_follows_mouse()?;
+                WindowsApi::enable_focus_follows_mouse(
+                    FOCUS_FOLLOWS_MOUSE_DELAY.load(Ordering::SeqCst),
+                )?;
             }
             Some(FocusFollowsMouseImplementation::Komorebi) => {}
         };
@@ -0,0 +1,285 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+mod switcher;
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use parking_lot::Mutex;
+use std::sync::atomic::AtomicIsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use windows::Win32::Foundation::HINSTANCE;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::Graphics::Gdi::InvalidateRect;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_DOWN;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_ESCAPE;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_RETURN;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_TAB;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_UP;
+use windows::Win32::UI::WindowsAndMessaging::CallNextHookEx;
+use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
+use windows::Win32::UI::WindowsAndMessaging::GetMessageW;
+use windows::Win32::UI::WindowsAndMessaging::KillTimer;
+use windows::Win32::UI::WindowsAndMessaging::PostQuitMessage;
+use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+use windows::Win32::UI::WindowsAndMessaging::SetWindowsHookExW;
+use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
+use windows::Win32::UI::WindowsAndMessaging::UnhookWindowsHookEx;
+use windows::Win32::UI::WindowsAndMessaging::HHOOK;
+use windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::WH_KEYBOARD_LL;
+use windows::Win32::UI::WindowsAndMessaging::WM_KEYDOWN;
+use windows::Win32::UI::WindowsAndMessaging::WM_SYSKEYDOWN;
+use windows::Win32::UI::WindowsAndMessaging::WM_TIMER;
+
+use crate::core::Rect;
+use crate::switcher_manager::switcher::Switcher;
+use crate::windows_api;
+use crate::WindowManager;
+
+/// The switcher is taken down after this long if nothing has been chosen
+const TIMEOUT_MS: u32 = 30000;
+
+/// What is currently drawn by the switcher, which the keyboard hook moves the selection of
+static SWITCHER_CONTENT: Mutex<Option<Content>> = Mutex::new(None);
+static SWITCHER_HWND: AtomicIsize = AtomicIsize::new(0);
+static SELECTED_HWND: Mutex<Option<isize>> = Mutex::new(None);
+
+#[derive(Debug, Clone)]
+pub struct SwitcherEntry {
+    pub hwnd: isize,
+    pub title: String,
+}
+
+/// The containers of a single workspace, listed under the name of the workspace and its monitor
+#[derive(Debug, Clone)]
+pub struct SwitcherGroup {
+    pub label: String,
+    pub entries: Vec<SwitcherEntry>,
+}
+
+pub struct Notification {
+    work_area: Rect,
+    groups: Vec<SwitcherGroup>,
+    focused_hwnd: Option<isize>,
+}
+
+#[derive(Debug, Clone)]
+enum Row {
+    Header(String),
+    Entry(SwitcherEntry),
+}
+
+#[derive(Debug, Clone)]
+struct Content {
+    rows: Vec<Row>,
+    /// The index of the selected row, which is always an entry
+    selected: usize,
+}
+
+impl Content {
+    fn selected_hwnd(&self) -> Option<isize> {
+        match self.rows.get(self.selected) {
+            Some(Row::Entry(entry)) => Some(entry.hwnd),
+            _ => None,
+        }
+    }
+
+    /// Moves the selection to the next entry in either direction, skipping over headers and
+    /// wrapping around at either end
+    fn cycle(&mut self, forward: bool) {
+        let len = self.rows.len();
+
+        for step in 1..len {
+            let idx = if forward {
+                (self.selected + step) % len
+            } else {
+                (self.selected + len - step) % len
+            };
+
+            if matches!(self.rows[idx], Row::Entry(_)) {
+                self.selected = idx;
+                return;
+            }
+        }
+    }
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(1))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification(work_area: Rect, groups: Vec<SwitcherGroup>, focused_hwnd: Option<isize>) {
+    if event_tx()
+        .try_send(Notification {
+            work_area,
+            groups,
+            focused_hwnd,
+        })
+        .is_err()
+    {
+        tracing::warn!("the switcher is already being shown; dropping notification")
+    }
+}
+
+pub fn listen_for_notifications(wm: Arc<Mutex<WindowManager>>) {
+    std::thread::spawn(move || loop {
+        match handle_notifications(wm.clone()) {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+
+    for notification in receiver {
+        let mut rows = vec![];
+
+        for group in notification.groups {
+            if group.entries.is_empty() {
+                continue;
+            }
+
+            rows.push(Row::Header(group.label));
+            rows.extend(group.entries.into_iter().map(Row::Entry));
+        }
+
+        // Start on the focused container, or the first one if it isn't listed
+        let Some(selected) = rows
+            .iter()
+            .position(|row| {
+                matches!(row, Row::Entry(entry) if Some(entry.hwnd) == notification.focused_hwnd)
+            })
+            .or_else(|| rows.iter().position(|row| matches!(row, Row::Entry(_))))
+        else {
+            continue;
+        };
+
+        let row_count = rows.len();
+        *SWITCHER_CONTENT.lock() = Some(Content { rows, selected });
+        *SELECTED_HWND.lock() = None;
+
+        let switcher = match Switcher::create(&notification.work_area, row_count) {
+            Ok(switcher) => switcher,
+            Err(error) => {
+                tracing::error!("could not show switcher: {error}");
+                SWITCHER_CONTENT.lock().take();
+                continue;
+            }
+        };
+
+        SWITCHER_HWND.store(switcher.hwnd, Ordering::SeqCst);
+
+        let hook = unsafe {
+            SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook), HINSTANCE::default(), 0)
+        };
+
+        match hook {
+            Ok(hook) => unsafe {
+                // The hook, the timer and the switcher window all belong to this thread, so their
+                // messages are dispatched here until a choice has been made or the timer has fired
+                let timer = SetTimer(HWND::default(), 0, TIMEOUT_MS, None);
+                let mut msg = MSG::default();
+
+                while GetMessageW(&mut msg, HWND::default(), 0, 0).as_bool() {
+                    if msg.message == WM_TIMER && msg.wParam.0 == timer {
+                        break;
+                    }
+
+                    // TODO: error handling
+                    let _ = TranslateMessage(&msg);
+                    DispatchMessageW(&msg);
+                }
+
+                // TODO: error handling
+                let _ = KillTimer(HWND::default(), timer);
+                // TODO: error handling
+                let _ = UnhookWindowsHookEx(hook);
+            },
+            Err(error) => tracing::error!("could not install keyboard hook for switcher: {error}"),
+        }
+
+        SWITCHER_HWND.store(0, Ordering::SeqCst);
+        switcher.destroy();
+        SWITCHER_CONTENT.lock().take();
+
+        // The window is looked up again in case it has been moved while the switcher was open
+        if let Some(hwnd) = SELECTED_HWND.lock().take() {
+            if let Err(error) = wm.lock().focus_managed_window(hwnd) {
+                tracing::warn!("could not focus the window chosen in the switcher: {error}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the keys used to pick a container while the switcher is on screen; any other key is
+/// passed through
+unsafe extern "system" fn keyboard_hook(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let is_key_down = wparam.0 == WM_KEYDOWN as usize || wparam.0 == WM_SYSKEYDOWN as usize;
+
+    if code >= 0 && is_key_down {
+        let event = unsafe { &*(lparam.0 as *const KBDLLHOOKSTRUCT) };
+        let key = event.vkCode;
+
+        let handled = if key == u32::from(VK_DOWN.0) || key == u32::from(VK_TAB.0) {
+            if let Some(content) = &mut *SWITCHER_CONTENT.lock() {
+                content.cycle(true);
+            }
+
+            true
+        } else if key == u32::from(VK_UP.0) {
+            if let Some(content) = &mut *SWITCHER_CONTENT.lock() {
+                content.cycle(false);
+            }
+
+            true
+        } else if key == u32::from(VK_RETURN.0) {
+            *SELECTED_HWND.lock() = SWITCHER_CONTENT
+                .lock()
+                .as_ref()
+                .and_then(Content::selected_hwnd);
+
+            unsafe { PostQuitMessage(0) };
+            true
+        } else if key == u32::from(VK_ESCAPE.0) {
+            unsafe { PostQuitMessage(0) };
+            true
+        } else {
+            false
+        };
+
+        if handled {
+            let hwnd = SWITCHER_HWND.load(Ordering::SeqCst);
+            let _ = unsafe { InvalidateRect(HWND(windows_api::as_ptr!(hwnd)), None, true) };
+
+            return LRESULT(1);
+        }
+    }
+
+    unsafe { CallNextHookEx(HHOOK::default(), code, wparam, lparam) }
+}
@@ -0,0 +1,257 @@
+use crate::border_manager;
+use crate::core::Rect;
+use crate::stackbar_manager::STACKBAR_FOCUSED_TEXT_COLOUR;
+use crate::stackbar_manager::STACKBAR_FONT_FAMILY;
+use crate::stackbar_manager::STACKBAR_TAB_BACKGROUND_COLOUR;
+use crate::stackbar_manager::STACKBAR_UNFOCUSED_TEXT_COLOUR;
+use crate::switcher_manager::Content;
+use crate::switcher_manager::Row;
+use crate::switcher_manager::SWITCHER_CONTENT;
+use crate::windows_api;
+use crate::WindowsApi;
+use crossbeam_utils::atomic::AtomicConsume;
+use std::sync::atomic::Ordering;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::COLORREF;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::LRESULT;
+use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::Graphics::Gdi::BeginPaint;
+use windows::Win32::Graphics::Gdi::CreateFontIndirectW;
+use windows::Win32::Graphics::Gdi::DeleteObject;
+use windows::Win32::Graphics::Gdi::DrawTextW;
+use windows::Win32::Graphics::Gdi::EndPaint;
+use windows::Win32::Graphics::Gdi::GetDeviceCaps;
+use windows::Win32::Graphics::Gdi::GetStockObject;
+use windows::Win32::Graphics::Gdi::RoundRect;
+use windows::Win32::Graphics::Gdi::SelectObject;
+use windows::Win32::Graphics::Gdi::SetBkColor;
+use windows::Win32::Graphics::Gdi::SetDCBrushColor;
+use windows::Win32::Graphics::Gdi::SetDCPenColor;
+use windows::Win32::Graphics::Gdi::SetTextColor;
+use windows::Win32::Graphics::Gdi::DC_BRUSH;
+use windows::Win32::Graphics::Gdi::DC_PEN;
+use windows::Win32::Graphics::Gdi::DT_END_ELLIPSIS;
+use windows::Win32::Graphics::Gdi::DT_LEFT;
+use windows::Win32::Graphics::Gdi::DT_SINGLELINE;
+use windows::Win32::Graphics::Gdi::DT_VCENTER;
+use windows::Win32::Graphics::Gdi::FONT_QUALITY;
+use windows::Win32::Graphics::Gdi::FW_BOLD;
+use windows::Win32::Graphics::Gdi::FW_NORMAL;
+use windows::Win32::Graphics::Gdi::HDC;
+use windows::Win32::Graphics::Gdi::HFONT;
+use windows::Win32::Graphics::Gdi::LOGFONTW;
+use windows::Win32::Graphics::Gdi::LOGPIXELSY;
+use windows::Win32::Graphics::Gdi::PAINTSTRUCT;
+use windows::Win32::Graphics::Gdi::PROOF_QUALITY;
+use windows::Win32::System::WindowsProgramming::MulDiv;
+use windows::Win32::UI::WindowsAndMessaging::DefWindowProcW;
+use windows::Win32::UI::WindowsAndMessaging::DestroyWindow;
+use windows::Win32::UI::WindowsAndMessaging::CS_HREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::CS_VREDRAW;
+use windows::Win32::UI::WindowsAndMessaging::WM_PAINT;
+use windows::Win32::UI::WindowsAndMessaging::WNDCLASSW;
+
+const WIDTH: i32 = 560;
+const ROW_HEIGHT: i32 = 28;
+const PADDING: i32 = 8;
+const INDENT: i32 = 24;
+const MAX_ROWS: usize = 16;
+const FONT_SIZE: i32 = 11;
+
+/// A list of containers grouped by workspace, which is created on the thread that dispatches its
+/// messages
+#[derive(Debug)]
+pub struct Switcher {
+    pub hwnd: isize,
+}
+
+impl Switcher {
+    pub const fn hwnd(&self) -> HWND {
+        HWND(windows_api::as_ptr!(self.hwnd))
+    }
+
+    /// Creates the switcher centred on the work area, tall enough to show the given number of rows
+    pub fn create(work_area: &Rect, rows: usize) -> color_eyre::Result<Self> {
+        let height = i32::try_from(rows.min(MAX_ROWS))? * ROW_HEIGHT + 2 * PADDING;
+
+        let rect = Rect {
+            left: work_area.left + (work_area.right - WIDTH) / 2,
+            top: work_area.top + (work_area.bottom - height) / 2,
+            right: WIDTH,
+            bottom: height,
+        };
+
+        let name: Vec<u16> = "komoswitcher\0".encode_utf16().collect();
+        let class_name = PCWSTR(name.as_ptr());
+
+        let h_module = WindowsApi::module_handle_w()?;
+
+        let window_class = WNDCLASSW {
+            hInstance: h_module.into(),
+            lpszClassName: class_name,
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(Self::callback),
+            hbrBackground: WindowsApi::create_solid_brush(0),
+            ..Default::default()
+        };
+
+        let _ = WindowsApi::register_class_w(&window_class);
+
+        let hwnd = WindowsApi::create_border_window(class_name, h_module.0 as isize)?;
+        let switcher = Self { hwnd };
+
+        if let Err(error) = WindowsApi::position_window(hwnd, &rect, true) {
+            switcher.destroy();
+            return Err(error);
+        }
+
+        WindowsApi::restore_window(hwnd);
+
+        Ok(switcher)
+    }
+
+    pub fn destroy(self) {
+        // TODO: error handling
+        let _ = unsafe { DestroyWindow(self.hwnd()) };
+    }
+
+    pub extern "system" fn callback(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        unsafe {
+            match message {
+                WM_PAINT => {
+                    let mut ps = PAINTSTRUCT::default();
+                    let hdc = BeginPaint(window, &mut ps);
+
+                    if let Some(content) = &*SWITCHER_CONTENT.lock() {
+                        paint(hdc, content);
+                    }
+
+                    // TODO: error handling
+                    let _ = EndPaint(window, &ps);
+                    LRESULT(0)
+                }
+                _ => DefWindowProcW(window, message, wparam, lparam),
+            }
+        }
+    }
+}
+
+unsafe fn create_font(hdc: HDC, weight: i32) -> HFONT {
+    let mut logfont = LOGFONTW {
+        lfWeight: weight,
+        lfQuality: FONT_QUALITY(PROOF_QUALITY.0),
+        lfHeight: -MulDiv(FONT_SIZE, GetDeviceCaps(hdc, LOGPIXELSY), 72),
+        ..Default::default()
+    };
+
+    if let Some(font_name) = &*STACKBAR_FONT_FAMILY.lock() {
+        // The last element is left as the nul terminator
+        for (i, c) in font_name.encode_utf16().take(31).enumerate() {
+            logfont.lfFaceName[i] = c;
+        }
+    }
+
+    CreateFontIndirectW(&logfont)
+}
+
+/// Draws the workspace headers and their containers on the stackbar background, highlighting the
+/// selected container with the focused border colour; when there are more rows than fit, the list
+/// is scrolled so that the selection stays in view
+unsafe fn paint(hdc: HDC, content: &Content) {
+    let background = STACKBAR_TAB_BACKGROUND_COLOUR.load_consume();
+    let focused_text_colour = STACKBAR_FOCUSED_TEXT_COLOUR.load_consume();
+    let unfocused_text_colour = STACKBAR_UNFOCUSED_TEXT_COLOUR.load_consume();
+    let highlight = border_manager::FOCUSED.load(Ordering::SeqCst);
+
+    let visible_rows = content.rows.len().min(MAX_ROWS);
+    let first_row = (content.selected + 1).saturating_sub(visible_rows);
+    let height = visible_rows as i32 * ROW_HEIGHT + 2 * PADDING;
+
+    let header_font = create_font(hdc, FW_BOLD.0 as i32);
+    let entry_font = create_font(hdc, FW_NORMAL.0 as i32);
+    let previous_font = SelectObject(hdc, header_font);
+
+    SelectObject(hdc, GetStockObject(DC_PEN));
+    SelectObject(hdc, GetStockObject(DC_BRUSH));
+    SetDCPenColor(hdc, COLORREF(background));
+    SetDCBrushColor(hdc, COLORREF(background));
+    // TODO: error handling
+    let _ = RoundRect(hdc, 0, 0, WIDTH, height, 12, 12);
+
+    for (position, (idx, row)) in content
+        .rows
+        .iter()
+        .enumerate()
+        .skip(first_row)
+        .take(visible_rows)
+        .enumerate()
+    {
+        let top = PADDING + position as i32 * ROW_HEIGHT;
+        let bottom = top + ROW_HEIGHT;
+
+        let (text, font, fill, text_colour, left) = match row {
+            Row::Header(label) => (
+                label,
+                header_font,
+                background,
+                unfocused_text_colour,
+                PADDING * 2,
+            ),
+            Row::Entry(entry) => {
+                let fill = if idx == content.selected {
+                    highlight
+                } else {
+                    background
+                };
+
+                (
+                    &entry.title,
+                    entry_font,
+                    fill,
+                    focused_text_colour,
+                    PADDING * 2 + INDENT,
+                )
+            }
+        };
+
+        if fill != background {
+            SetDCPenColor(hdc, COLORREF(fill));
+            SetDCBrushColor(hdc, COLORREF(fill));
+            // TODO: error handling
+            let _ = RoundRect(hdc, PADDING, top, WIDTH - PADDING, bottom, 8, 8);
+        }
+
+        SelectObject(hdc, font);
+        SetBkColor(hdc, COLORREF(fill));
+        SetTextColor(hdc, COLORREF(text_colour));
+
+        let mut text: Vec<u16> = text.encode_utf16().collect();
+        let mut rect = RECT {
+            left,
+            top,
+            right: WIDTH - PADDING * 2,
+            bottom,
+        };
+
+        DrawTextW(
+            hdc,
+            &mut text,
+            &mut rect,
+            DT_SINGLELINE | DT_LEFT | DT_VCENTER | DT_END_ELLIPSIS,
+        );
+    }
+
+    SelectObject(hdc, previous_font);
+    // TODO: error handling
+    let _ = DeleteObject(header_font);
+    // TODO: error handling
+    let _ = DeleteObject(entry_font);
+}
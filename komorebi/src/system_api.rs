@@ -0,0 +1,917 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+#[cfg(test)]
+use std::sync::Arc;
+use std::sync::OnceLock;
+
+use color_eyre::eyre::anyhow;
+use color_eyre::Result;
+#[cfg(test)]
+use parking_lot::Mutex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use windows::Win32::Foundation::HWND;
+
+use crate::com::SetCloak;
+use crate::core::CornerPreference;
+use crate::core::Rect;
+#[cfg(test)]
+use crate::styles::ExtendedWindowStyle;
+#[cfg(test)]
+use crate::styles::WindowStyle;
+use crate::windows_api;
+use crate::windows_api::WindowsApi;
+
+/// The parts of the Win32 API which window and workspace logic relies on to query and arrange
+/// windows, so that this logic can be run against a mock in tests without a Windows desktop
+/// session
+pub trait SystemApi: Send + Sync {
+    fn is_window(&self, hwnd: isize) -> bool;
+    fn is_window_visible(&self, hwnd: isize) -> bool;
+    fn is_iconic(&self, hwnd: isize) -> bool;
+    fn is_zoomed(&self, hwnd: isize) -> bool;
+    fn is_hung(&self, hwnd: isize) -> bool;
+    fn window_rect(&self, hwnd: isize) -> Result<Rect>;
+    fn minimum_window_size(&self, hwnd: isize) -> Result<(i32, i32)>;
+    fn position_window(&self, hwnd: isize, layout: &Rect, top: bool) -> Result<()>;
+    fn position_windows(&self, positions: &[(isize, Rect)]) -> Result<()>;
+    fn hide_window(&self, hwnd: isize);
+    fn restore_window(&self, hwnd: isize);
+    fn maximize_window(&self, hwnd: isize);
+    fn unmaximize_window(&self, hwnd: isize);
+    fn minimize_window(&self, hwnd: isize);
+    fn cloak_window(&self, hwnd: isize, cloaked: bool);
+    fn focus_window(&self, hwnd: isize) -> Result<()>;
+    fn raise_window(&self, hwnd: isize) -> Result<()>;
+    fn foreground_window(&self) -> Result<isize>;
+    fn center_cursor(&self, rect: &Rect) -> Result<()>;
+    fn set_style(&self, hwnd: isize, style: u32) -> Result<()>;
+    fn set_ex_style(&self, hwnd: isize, ex_style: u32) -> Result<()>;
+    fn set_corner_preference(&self, hwnd: isize, preference: CornerPreference) -> Result<()>;
+    fn window_process_id(&self, hwnd: isize) -> u32;
+    fn window_title(&self, hwnd: isize) -> Result<String>;
+    fn window_class(&self, hwnd: isize) -> Result<String>;
+    fn window_style(&self, hwnd: isize) -> Result<u32>;
+    fn window_ex_style(&self, hwnd: isize) -> Result<u32>;
+    fn is_cloaked(&self, hwnd: isize) -> Result<bool>;
+    fn process_exe(&self, process_id: u32) -> Result<String>;
+    fn process_path(&self, process_id: u32) -> Result<String>;
+}
+
+static SYSTEM_API: OnceLock<Box<dyn SystemApi>> = OnceLock::new();
+
+/// The implementation of [`SystemApi`] in use, which is [`Win32Api`] unless another one has been
+/// installed with [`set_system_api`], or [`RecordingApi`] while recording on this thread
+pub fn system_api() -> &'static dyn SystemApi {
+    if is_recording() {
+        return &RecordingApi;
+    }
+
+    installed_system_api()
+}
+
+fn installed_system_api() -> &'static dyn SystemApi {
+    SYSTEM_API.get_or_init(|| Box::new(Win32Api)).as_ref()
+}
+
+/// Installs an implementation of [`SystemApi`]; this has to happen before anything calls
+/// [`system_api`], which otherwise settles on [`Win32Api`]
+pub fn set_system_api(api: Box<dyn SystemApi>) -> Result<()> {
+    SYSTEM_API
+        .set(api)
+        .map_err(|_| anyhow!("the system api has already been initialized"))
+}
+
+pub struct Win32Api;
+
+impl SystemApi for Win32Api {
+    fn is_window(&self, hwnd: isize) -> bool {
+        WindowsApi::is_window(hwnd)
+    }
+
+    fn is_window_visible(&self, hwnd: isize) -> bool {
+        WindowsApi::is_window_visible(hwnd)
+    }
+
+    fn is_iconic(&self, hwnd: isize) -> bool {
+        WindowsApi::is_iconic(hwnd)
+    }
+
+    fn is_zoomed(&self, hwnd: isize) -> bool {
+        WindowsApi::is_zoomed(hwnd)
+    }
+
+    fn is_hung(&self, hwnd: isize) -> bool {
+        WindowsApi::is_hung_app_window(hwnd)
+    }
+
+    fn window_rect(&self, hwnd: isize) -> Result<Rect> {
+        WindowsApi::window_rect(hwnd)
+    }
+
+    fn minimum_window_size(&self, hwnd: isize) -> Result<(i32, i32)> {
+        WindowsApi::minimum_window_size(hwnd)
+    }
+
+    fn position_window(&self, hwnd: isize, layout: &Rect, top: bool) -> Result<()> {
+        WindowsApi::position_window(hwnd, layout, top)
+    }
+
+    fn position_windows(&self, positions: &[(isize, Rect)]) -> Result<()> {
+        WindowsApi::position_windows(positions)
+    }
+
+    fn hide_window(&self, hwnd: isize) {
+        WindowsApi::hide_window(hwnd);
+    }
+
+    fn restore_window(&self, hwnd: isize) {
+        WindowsApi::restore_window(hwnd);
+    }
+
+    fn maximize_window(&self, hwnd: isize) {
+        WindowsApi::maximize_window(hwnd);
+    }
+
+    fn unmaximize_window(&self, hwnd: isize) {
+        WindowsApi::unmaximize_window(hwnd);
+    }
+
+    fn minimize_window(&self, hwnd: isize) {
+        WindowsApi::minimize_window(hwnd);
+    }
+
+    fn cloak_window(&self, hwnd: isize, cloaked: bool) {
+        SetCloak(
+            HWND(windows_api::as_ptr!(hwnd)),
+            1,
+            if cloaked { 2 } else { 0 },
+        );
+    }
+
+    fn focus_window(&self, hwnd: isize) -> Result<()> {
+        WindowsApi::raise_and_focus_window(hwnd)
+    }
+
+    fn raise_window(&self, hwnd: isize) -> Result<()> {
+        WindowsApi::restack_window(hwnd)
+    }
+
+    fn foreground_window(&self) -> Result<isize> {
+        WindowsApi::foreground_window()
+    }
+
+    fn center_cursor(&self, rect: &Rect) -> Result<()> {
+        WindowsApi::center_cursor_in_rect(rect)
+    }
+
+    fn set_style(&self, hwnd: isize, style: u32) -> Result<()> {
+        WindowsApi::update_style(hwnd, style.try_into()?)
+    }
+
+    fn set_ex_style(&self, hwnd: isize, ex_style: u32) -> Result<()> {
+        WindowsApi::update_ex_style(hwnd, ex_style.try_into()?)
+    }
+
+    fn set_corner_preference(&self, hwnd: isize, preference: CornerPreference) -> Result<()> {
+        WindowsApi::set_corner_preference(hwnd, preference)
+    }
+
+    fn window_process_id(&self, hwnd: isize) -> u32 {
+        let (process_id, _) = WindowsApi::window_thread_process_id(hwnd);
+        process_id
+    }
+
+    fn window_title(&self, hwnd: isize) -> Result<String> {
+        WindowsApi::window_text_w(hwnd)
+    }
+
+    fn window_class(&self, hwnd: isize) -> Result<String> {
+        WindowsApi::real_window_class_w(hwnd)
+    }
+
+    fn window_style(&self, hwnd: isize) -> Result<u32> {
+        Ok(u32::try_from(WindowsApi::gwl_style(hwnd)?)?)
+    }
+
+    fn window_ex_style(&self, hwnd: isize) -> Result<u32> {
+        Ok(u32::try_from(WindowsApi::gwl_ex_style(hwnd)?)?)
+    }
+
+    fn is_cloaked(&self, hwnd: isize) -> Result<bool> {
+        WindowsApi::is_window_cloaked(hwnd)
+    }
+
+    fn process_exe(&self, process_id: u32) -> Result<String> {
+        let handle = WindowsApi::process_handle(process_id)?;
+        let exe = WindowsApi::exe(handle);
+        WindowsApi::close_process(handle)?;
+        exe
+    }
+
+    fn process_path(&self, process_id: u32) -> Result<String> {
+        let handle = WindowsApi::process_handle(process_id)?;
+        let path = WindowsApi::exe_path(handle);
+        WindowsApi::close_process(handle)?;
+        path
+    }
+}
+
+/// A change to a window which was recorded by [`RecordingApi`] instead of being applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "operation")]
+pub enum WindowOperation {
+    Position {
+        hwnd: isize,
+        rect: Rect,
+        top: bool,
+    },
+    Hide {
+        hwnd: isize,
+    },
+    Restore {
+        hwnd: isize,
+    },
+    Maximize {
+        hwnd: isize,
+    },
+    Unmaximize {
+        hwnd: isize,
+    },
+    Minimize {
+        hwnd: isize,
+    },
+    Cloak {
+        hwnd: isize,
+    },
+    Uncloak {
+        hwnd: isize,
+    },
+    Focus {
+        hwnd: isize,
+    },
+    Raise {
+        hwnd: isize,
+    },
+    CenterCursor {
+        rect: Rect,
+    },
+    Style {
+        hwnd: isize,
+        style: u32,
+    },
+    ExStyle {
+        hwnd: isize,
+        ex_style: u32,
+    },
+    Corners {
+        hwnd: isize,
+        preference: CornerPreference,
+    },
+}
+
+#[derive(Default)]
+struct Recording {
+    // Deferred changes are applied once the window manager lock has been released, whereas the
+    // changes recorded for a dry run are thrown away
+    deferred: bool,
+    operations: Vec<WindowOperation>,
+    rects: HashMap<isize, Rect>,
+    visibility: HashMap<isize, bool>,
+    foreground: Option<isize>,
+}
+
+thread_local! {
+    // A dry run can be started while the changes of the command that requested it are deferred,
+    // in which case the recordings are stacked and queries look through all of them
+    static RECORDING: RefCell<Vec<Recording>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Starts recording changes to windows made on this thread instead of applying them
+pub fn start_recording() {
+    RECORDING.with_borrow_mut(|recording| recording.push(Recording::default()));
+}
+
+/// Stops the most recently started recording on this thread, returning the changes which were
+/// recorded
+pub fn stop_recording() -> Vec<WindowOperation> {
+    RECORDING
+        .with_borrow_mut(Vec::pop)
+        .map(|recording| recording.operations)
+        .unwrap_or_default()
+}
+
+/// Starts deferring changes to windows made on this thread, so that they can be applied after
+/// the window manager lock has been released
+pub fn start_deferring() {
+    RECORDING.with_borrow_mut(|recording| {
+        recording.push(Recording {
+            deferred: true,
+            ..Default::default()
+        });
+    });
+}
+
+/// Stops all recording on this thread, returning the changes which were deferred; the changes of
+/// a dry run which never finished because of a panic are discarded
+pub fn stop_deferring() -> Vec<WindowOperation> {
+    RECORDING
+        .with_borrow_mut(std::mem::take)
+        .into_iter()
+        .filter(|recording| recording.deferred)
+        .flat_map(|recording| recording.operations)
+        .collect()
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.with_borrow(|recording| !recording.is_empty())
+}
+
+/// Whether changes to windows made on this thread are being recorded for a dry run, and will
+/// never be applied
+pub fn is_dry_run() -> bool {
+    RECORDING.with_borrow(|recording| recording.iter().any(|recording| !recording.deferred))
+}
+
+/// Runs the given function with changes to windows being applied right away, even if they are
+/// otherwise being deferred on this thread
+pub fn without_recording<T>(f: impl FnOnce() -> T) -> T {
+    let recording = RECORDING.with_borrow_mut(std::mem::take);
+    let result = f();
+    RECORDING.with_borrow_mut(|current| *current = recording);
+
+    result
+}
+
+fn record(operation: WindowOperation, f: impl FnOnce(&mut Recording)) {
+    RECORDING.with_borrow_mut(|recording| {
+        if let Some(recording) = recording.last_mut() {
+            recording.operations.push(operation);
+            f(recording);
+        }
+    });
+}
+
+fn recorded<T>(f: impl Fn(&Recording) -> Option<T>) -> Option<T> {
+    RECORDING.with_borrow(|recording| recording.iter().rev().find_map(f))
+}
+
+/// Records changes to windows instead of applying them, while answering queries as if they had
+/// been applied so that the rest of a layout is calculated in the same way as it would have been
+pub struct RecordingApi;
+
+impl SystemApi for RecordingApi {
+    fn is_window(&self, hwnd: isize) -> bool {
+        installed_system_api().is_window(hwnd)
+    }
+
+    fn is_window_visible(&self, hwnd: isize) -> bool {
+        recorded(|recording| recording.visibility.get(&hwnd).copied())
+            .unwrap_or_else(|| installed_system_api().is_window_visible(hwnd))
+    }
+
+    fn is_iconic(&self, hwnd: isize) -> bool {
+        installed_system_api().is_iconic(hwnd)
+    }
+
+    fn is_zoomed(&self, hwnd: isize) -> bool {
+        installed_system_api().is_zoomed(hwnd)
+    }
+
+    fn is_hung(&self, hwnd: isize) -> bool {
+        installed_system_api().is_hung(hwnd)
+    }
+
+    fn window_rect(&self, hwnd: isize) -> Result<Rect> {
+        recorded(|recording| recording.rects.get(&hwnd).copied())
+            .map_or_else(|| installed_system_api().window_rect(hwnd), Ok)
+    }
+
+    fn minimum_window_size(&self, hwnd: isize) -> Result<(i32, i32)> {
+        installed_system_api().minimum_window_size(hwnd)
+    }
+
+    fn position_window(&self, hwnd: isize, layout: &Rect, top: bool) -> Result<()> {
+        record(
+            WindowOperation::Position {
+                hwnd,
+                rect: *layout,
+                top,
+            },
+            |recording| {
+                recording.rects.insert(hwnd, *layout);
+            },
+        );
+
+        Ok(())
+    }
+
+    fn position_windows(&self, positions: &[(isize, Rect)]) -> Result<()> {
+        for (hwnd, layout) in positions {
+            self.position_window(*hwnd, layout, false)?;
+        }
+
+        Ok(())
+    }
+
+    fn hide_window(&self, hwnd: isize) {
+        record(WindowOperation::Hide { hwnd }, |recording| {
+            recording.visibility.insert(hwnd, false);
+        });
+    }
+
+    fn restore_window(&self, hwnd: isize) {
+        record(WindowOperation::Restore { hwnd }, |recording| {
+            recording.visibility.insert(hwnd, true);
+        });
+    }
+
+    fn maximize_window(&self, hwnd: isize) {
+        record(WindowOperation::Maximize { hwnd }, |recording| {
+            recording.visibility.insert(hwnd, true);
+        });
+    }
+
+    fn unmaximize_window(&self, hwnd: isize) {
+        record(WindowOperation::Unmaximize { hwnd }, |_| {});
+    }
+
+    fn minimize_window(&self, hwnd: isize) {
+        record(WindowOperation::Minimize { hwnd }, |_| {});
+    }
+
+    fn cloak_window(&self, hwnd: isize, cloaked: bool) {
+        let operation = if cloaked {
+            WindowOperation::Cloak { hwnd }
+        } else {
+            WindowOperation::Uncloak { hwnd }
+        };
+
+        record(operation, |_| {});
+    }
+
+    fn focus_window(&self, hwnd: isize) -> Result<()> {
+        record(WindowOperation::Focus { hwnd }, |recording| {
+            recording.foreground = Some(hwnd);
+        });
+
+        Ok(())
+    }
+
+    fn raise_window(&self, hwnd: isize) -> Result<()> {
+        record(WindowOperation::Raise { hwnd }, |_| {});
+
+        Ok(())
+    }
+
+    fn foreground_window(&self) -> Result<isize> {
+        recorded(|recording| recording.foreground)
+            .map_or_else(|| installed_system_api().foreground_window(), Ok)
+    }
+
+    fn center_cursor(&self, rect: &Rect) -> Result<()> {
+        record(WindowOperation::CenterCursor { rect: *rect }, |_| {});
+
+        Ok(())
+    }
+
+    fn set_style(&self, hwnd: isize, style: u32) -> Result<()> {
+        record(WindowOperation::Style { hwnd, style }, |_| {});
+
+        Ok(())
+    }
+
+    fn set_ex_style(&self, hwnd: isize, ex_style: u32) -> Result<()> {
+        record(WindowOperation::ExStyle { hwnd, ex_style }, |_| {});
+
+        Ok(())
+    }
+
+    fn set_corner_preference(&self, hwnd: isize, preference: CornerPreference) -> Result<()> {
+        record(WindowOperation::Corners { hwnd, preference }, |_| {});
+
+        Ok(())
+    }
+
+    fn window_process_id(&self, hwnd: isize) -> u32 {
+        installed_system_api().window_process_id(hwnd)
+    }
+
+    fn window_title(&self, hwnd: isize) -> Result<String> {
+        installed_system_api().window_title(hwnd)
+    }
+
+    fn window_class(&self, hwnd: isize) -> Result<String> {
+        installed_system_api().window_class(hwnd)
+    }
+
+    fn window_style(&self, hwnd: isize) -> Result<u32> {
+        installed_system_api().window_style(hwnd)
+    }
+
+    fn window_ex_style(&self, hwnd: isize) -> Result<u32> {
+        installed_system_api().window_ex_style(hwnd)
+    }
+
+    fn is_cloaked(&self, hwnd: isize) -> Result<bool> {
+        installed_system_api().is_cloaked(hwnd)
+    }
+
+    fn process_exe(&self, process_id: u32) -> Result<String> {
+        installed_system_api().process_exe(process_id)
+    }
+
+    fn process_path(&self, process_id: u32) -> Result<String> {
+        installed_system_api().process_path(process_id)
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MockWindow {
+    pub rect: Rect,
+    pub visible: bool,
+    pub minimized: bool,
+    pub maximized: bool,
+    pub minimum_size: Option<(i32, i32)>,
+}
+
+/// An in-memory desktop of fake windows, which are moved, shown and hidden as requested without
+/// ever touching the real desktop; clones share the same desktop, so that it can still be inspected
+/// after a clone has been installed with [`set_system_api`]
+#[cfg(test)]
+#[derive(Debug, Default, Clone)]
+pub struct MockSystemApi {
+    windows: Arc<Mutex<HashMap<isize, MockWindow>>>,
+    foreground: Arc<Mutex<isize>>,
+    cursor: Arc<Mutex<(i32, i32)>>,
+}
+
+#[cfg(test)]
+impl MockSystemApi {
+    pub fn add_window(&self, hwnd: isize, rect: Rect) {
+        self.windows.lock().insert(
+            hwnd,
+            MockWindow {
+                rect,
+                visible: true,
+                ..Default::default()
+            },
+        );
+    }
+
+    pub fn remove_window(&self, hwnd: isize) {
+        self.windows.lock().remove(&hwnd);
+    }
+
+    pub fn window(&self, hwnd: isize) -> Option<MockWindow> {
+        self.windows.lock().get(&hwnd).copied()
+    }
+
+    pub fn set_foreground_window(&self, hwnd: isize) {
+        *self.foreground.lock() = hwnd;
+    }
+
+    pub fn cursor_position(&self) -> (i32, i32) {
+        *self.cursor.lock()
+    }
+
+    pub fn set_minimum_size(&self, hwnd: isize, minimum_size: (i32, i32)) {
+        self.update(hwnd, |window| window.minimum_size = Some(minimum_size));
+    }
+
+    fn update(&self, hwnd: isize, f: impl FnOnce(&mut MockWindow)) {
+        if let Some(window) = self.windows.lock().get_mut(&hwnd) {
+            f(window);
+        }
+    }
+}
+
+#[cfg(test)]
+impl SystemApi for MockSystemApi {
+    fn is_window(&self, hwnd: isize) -> bool {
+        self.windows.lock().contains_key(&hwnd)
+    }
+
+    fn is_window_visible(&self, hwnd: isize) -> bool {
+        self.window(hwnd).is_some_and(|window| window.visible)
+    }
+
+    fn is_iconic(&self, hwnd: isize) -> bool {
+        self.window(hwnd).is_some_and(|window| window.minimized)
+    }
+
+    fn is_zoomed(&self, hwnd: isize) -> bool {
+        self.window(hwnd).is_some_and(|window| window.maximized)
+    }
+
+    fn is_hung(&self, _hwnd: isize) -> bool {
+        false
+    }
+
+    fn window_rect(&self, hwnd: isize) -> Result<Rect> {
+        self.window(hwnd)
+            .map(|window| window.rect)
+            .ok_or_else(|| anyhow!("there is no window with hwnd {hwnd}"))
+    }
+
+    fn minimum_window_size(&self, hwnd: isize) -> Result<(i32, i32)> {
+        self.window(hwnd)
+            .and_then(|window| window.minimum_size)
+            .ok_or_else(|| anyhow!("there is no minimum size for hwnd {hwnd}"))
+    }
+
+    fn position_window(&self, hwnd: isize, layout: &Rect, _top: bool) -> Result<()> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        self.update(hwnd, |window| window.rect = *layout);
+
+        Ok(())
+    }
+
+    fn position_windows(&self, positions: &[(isize, Rect)]) -> Result<()> {
+        // Like DeferWindowPos, either every window is positioned or none of them are
+        if let Some((hwnd, _)) = positions.iter().find(|(hwnd, _)| !self.is_window(*hwnd)) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        for (hwnd, layout) in positions {
+            self.update(*hwnd, |window| window.rect = *layout);
+        }
+
+        Ok(())
+    }
+
+    fn hide_window(&self, hwnd: isize) {
+        self.update(hwnd, |window| window.visible = false);
+    }
+
+    fn restore_window(&self, hwnd: isize) {
+        self.update(hwnd, |window| {
+            window.visible = true;
+            window.minimized = false;
+            window.maximized = false;
+        });
+    }
+
+    fn maximize_window(&self, hwnd: isize) {
+        self.update(hwnd, |window| {
+            window.visible = true;
+            window.minimized = false;
+            window.maximized = true;
+        });
+    }
+
+    fn unmaximize_window(&self, hwnd: isize) {
+        self.update(hwnd, |window| window.maximized = false);
+    }
+
+    fn minimize_window(&self, hwnd: isize) {
+        self.update(hwnd, |window| window.minimized = true);
+    }
+
+    fn cloak_window(&self, hwnd: isize, cloaked: bool) {
+        self.update(hwnd, |window| window.visible = !cloaked);
+    }
+
+    fn focus_window(&self, hwnd: isize) -> Result<()> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        self.set_foreground_window(hwnd);
+
+        Ok(())
+    }
+
+    fn raise_window(&self, hwnd: isize) -> Result<()> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(())
+    }
+
+    fn foreground_window(&self) -> Result<isize> {
+        Ok(*self.foreground.lock())
+    }
+
+    fn center_cursor(&self, rect: &Rect) -> Result<()> {
+        *self.cursor.lock() = (rect.left + (rect.right / 2), rect.top + (rect.bottom / 2));
+
+        Ok(())
+    }
+
+    fn set_style(&self, hwnd: isize, _style: u32) -> Result<()> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(())
+    }
+
+    fn set_ex_style(&self, hwnd: isize, _ex_style: u32) -> Result<()> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(())
+    }
+
+    fn set_corner_preference(&self, hwnd: isize, _preference: CornerPreference) -> Result<()> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(())
+    }
+
+    // Every fake window looks like an ordinary application window which komorebi should manage,
+    // and belongs to a process of its own; real process ids are multiples of four, so the odd ids
+    // given to these are never mistaken for a real process when the session or elevation of the
+    // process is looked up
+    fn window_process_id(&self, hwnd: isize) -> u32 {
+        if self.is_window(hwnd) {
+            u32::try_from(hwnd * 4 + 1).unwrap_or_default()
+        } else {
+            0
+        }
+    }
+
+    fn window_title(&self, hwnd: isize) -> Result<String> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(format!("Window {hwnd}"))
+    }
+
+    fn window_class(&self, hwnd: isize) -> Result<String> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(String::from("MockWindow"))
+    }
+
+    fn window_style(&self, hwnd: isize) -> Result<u32> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok((WindowStyle::CAPTION | WindowStyle::THICKFRAME | WindowStyle::VISIBLE).bits())
+    }
+
+    fn window_ex_style(&self, hwnd: isize) -> Result<u32> {
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(ExtendedWindowStyle::WINDOWEDGE.bits())
+    }
+
+    fn is_cloaked(&self, hwnd: isize) -> Result<bool> {
+        // Cloaked windows are hidden windows as far as the mock is concerned
+        if !self.is_window(hwnd) {
+            return Err(anyhow!("there is no window with hwnd {hwnd}"));
+        }
+
+        Ok(false)
+    }
+
+    fn process_exe(&self, process_id: u32) -> Result<String> {
+        Ok(format!("mock{process_id}.exe"))
+    }
+
+    fn process_path(&self, process_id: u32) -> Result<String> {
+        Ok(format!("C:\\mock{process_id}.exe"))
+    }
+}
+
+/// The mock desktop which is installed as the system api for tests; the system api can only be
+/// installed once per process, so every test shares the same mock desktop and has to use window
+/// handles of its own
+#[cfg(test)]
+pub fn mock_system_api() -> &'static MockSystemApi {
+    static MOCK: OnceLock<MockSystemApi> = OnceLock::new();
+
+    MOCK.get_or_init(|| {
+        let mock = MockSystemApi::default();
+        set_system_api(Box::new(mock.clone()))
+            .expect("the system api was initialized before the mock could be installed");
+
+        mock
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rect::rect;
+
+    #[test]
+    fn changes_are_applied_to_the_installed_api() {
+        let mock = mock_system_api();
+        mock.add_window(1, rect(0, 0, 100, 100));
+
+        system_api()
+            .position_window(1, &rect(10, 10, 200, 200), false)
+            .unwrap();
+        system_api().minimize_window(1);
+
+        assert_eq!(
+            mock.window(1),
+            Some(MockWindow {
+                rect: rect(10, 10, 200, 200),
+                visible: true,
+                minimized: true,
+                maximized: false,
+                minimum_size: None,
+            })
+        );
+    }
+
+    #[test]
+    fn batches_are_only_positioned_when_every_window_exists() {
+        let mock = mock_system_api();
+        mock.add_window(10, rect(0, 0, 100, 100));
+
+        let positions = [(10, rect(0, 0, 500, 500)), (11, rect(500, 0, 500, 500))];
+        assert!(system_api().position_windows(&positions).is_err());
+        assert_eq!(mock.window(10).unwrap().rect, rect(0, 0, 100, 100));
+
+        mock.add_window(11, rect(0, 0, 100, 100));
+        system_api().position_windows(&positions).unwrap();
+        assert_eq!(mock.window(10).unwrap().rect, rect(0, 0, 500, 500));
+        assert_eq!(mock.window(11).unwrap().rect, rect(500, 0, 500, 500));
+    }
+
+    #[test]
+    fn recorded_changes_are_answered_without_being_applied() {
+        let mock = mock_system_api();
+        mock.add_window(20, rect(0, 0, 100, 100));
+
+        start_recording();
+        system_api()
+            .position_window(20, &rect(50, 50, 300, 300), false)
+            .unwrap();
+        system_api().hide_window(20);
+
+        assert!(is_dry_run());
+        assert_eq!(
+            system_api().window_rect(20).unwrap(),
+            rect(50, 50, 300, 300)
+        );
+        assert!(!system_api().is_window_visible(20));
+
+        assert_eq!(
+            stop_recording(),
+            vec![
+                WindowOperation::Position {
+                    hwnd: 20,
+                    rect: rect(50, 50, 300, 300),
+                    top: false,
+                },
+                WindowOperation::Hide { hwnd: 20 },
+            ]
+        );
+
+        assert!(!is_recording());
+        assert_eq!(system_api().window_rect(20).unwrap(), rect(0, 0, 100, 100));
+        assert!(system_api().is_window_visible(20));
+    }
+
+    #[test]
+    fn unfinished_dry_runs_are_discarded_with_deferred_changes() {
+        let mock = mock_system_api();
+        mock.add_window(30, rect(0, 0, 100, 100));
+        mock.add_window(31, rect(0, 0, 100, 100));
+
+        start_deferring();
+        system_api().hide_window(30);
+
+        start_recording();
+        system_api().hide_window(31);
+
+        // A dry run which never finished, eg. because of a panic, is thrown away
+        assert_eq!(stop_deferring(), vec![WindowOperation::Hide { hwnd: 30 }]);
+        assert!(!is_recording());
+        assert!(mock.window(30).unwrap().visible);
+        assert!(mock.window(31).unwrap().visible);
+    }
+
+    #[test]
+    fn changes_can_bypass_the_recording() {
+        let mock = mock_system_api();
+        mock.add_window(40, rect(0, 0, 100, 100));
+
+        start_deferring();
+        without_recording(|| system_api().hide_window(40));
+
+        assert!(stop_deferring().is_empty());
+        assert!(!mock.window(40).unwrap().visible);
+    }
+}
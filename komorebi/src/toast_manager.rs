@@ -0,0 +1,157 @@
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crossbeam_channel::Receiver;
+use crossbeam_channel::Sender;
+use crossbeam_utils::atomic::AtomicConsume;
+use std::sync::atomic::AtomicBool;
+use std::sync::OnceLock;
+use windows::core::HSTRING;
+use windows::Data::Xml::Dom::XmlDocument;
+use windows::UI::Notifications::ToastNotification;
+use windows::UI::Notifications::ToastNotificationManager;
+use windows::UI::Notifications::ToastTemplateType;
+
+use crate::State;
+use crate::StateDelta;
+
+pub static TOASTS_ENABLED: AtomicBool = AtomicBool::new(false);
+pub static TOAST_ON_WORKSPACE_FOCUSED: AtomicBool = AtomicBool::new(true);
+pub static TOAST_ON_LAYOUT_CHANGED: AtomicBool = AtomicBool::new(true);
+pub static TOAST_ON_ERROR: AtomicBool = AtomicBool::new(true);
+
+// komorebi is not a packaged application with its own AppUserModelID, so toasts are raised using
+// the AppUserModelID of PowerShell, which is registered on every Windows installation
+const APP_USER_MODEL_ID: &str =
+    "{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\\WindowsPowerShell\\v1.0\\powershell.exe";
+
+pub struct Notification {
+    title: String,
+    body: String,
+}
+
+static CHANNEL: OnceLock<(Sender<Notification>, Receiver<Notification>)> = OnceLock::new();
+
+pub fn channel() -> &'static (Sender<Notification>, Receiver<Notification>) {
+    CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
+}
+
+fn event_tx() -> Sender<Notification> {
+    channel().0.clone()
+}
+
+fn event_rx() -> Receiver<Notification> {
+    channel().1.clone()
+}
+
+pub fn send_notification(title: &str, body: &str) {
+    if !TOASTS_ENABLED.load_consume() {
+        return;
+    }
+
+    if event_tx()
+        .try_send(Notification {
+            title: title.to_string(),
+            body: body.to_string(),
+        })
+        .is_err()
+    {
+        tracing::warn!("channel is full; dropping notification")
+    }
+}
+
+pub fn send_error_notification(error: &str) {
+    if TOAST_ON_ERROR.load_consume() {
+        send_notification("komorebi error", error);
+    }
+}
+
+pub fn send_state_notifications(state: &State, delta: &StateDelta) {
+    if !TOASTS_ENABLED.load_consume() {
+        return;
+    }
+
+    for monitor_delta in &delta.monitors {
+        let Some(monitor) = state.monitors.elements().get(monitor_delta.monitor_idx) else {
+            continue;
+        };
+
+        let monitor_name = format!("Monitor {}", monitor_delta.monitor_idx + 1);
+
+        for workspace_delta in &monitor_delta.workspaces {
+            let is_focused_workspace =
+                workspace_delta.workspace_idx == monitor.focused_workspace_idx();
+
+            if !workspace_delta.layout
+                || !is_focused_workspace
+                || !TOAST_ON_LAYOUT_CHANGED.load_consume()
+            {
+                continue;
+            }
+
+            if let Some(workspace) = monitor.workspaces().get(workspace_delta.workspace_idx) {
+                send_notification(
+                    &format!("Layout: {}", workspace.layout_name()),
+                    &monitor_name,
+                );
+            }
+        }
+
+        if monitor_delta.focused_workspace_idx.is_some()
+            && TOAST_ON_WORKSPACE_FOCUSED.load_consume()
+        {
+            let workspace_idx = monitor.focused_workspace_idx();
+            let workspace_name = monitor
+                .focused_workspace()
+                .and_then(|workspace| workspace.name().clone())
+                .unwrap_or_else(|| format!("{}", workspace_idx + 1));
+
+            send_notification(&format!("Workspace {workspace_name}"), &monitor_name);
+        }
+    }
+}
+
+pub fn listen_for_notifications() {
+    std::thread::spawn(move || loop {
+        match handle_notifications() {
+            Ok(()) => {
+                tracing::warn!("restarting finished thread");
+            }
+            Err(error) => {
+                tracing::warn!("restarting failed thread: {}", error);
+            }
+        }
+    });
+}
+
+pub fn handle_notifications() -> color_eyre::Result<()> {
+    tracing::info!("listening");
+
+    let receiver = event_rx();
+    let notifier =
+        ToastNotificationManager::CreateToastNotifierWithId(&HSTRING::from(APP_USER_MODEL_ID))?;
+
+    for notification in receiver {
+        let toast = ToastNotification::CreateToastNotification(&toast_xml(
+            &notification.title,
+            &notification.body,
+        )?)?;
+
+        if let Err(error) = notifier.Show(&toast) {
+            tracing::warn!("could not show toast notification: {error}");
+        }
+    }
+
+    Ok(())
+}
+
+fn toast_xml(title: &str, body: &str) -> windows::core::Result<XmlDocument> {
+    let xml = ToastNotificationManager::GetTemplateContent(ToastTemplateType::ToastText02)?;
+    let text = xml.GetElementsByTagName(&HSTRING::from("text"))?;
+
+    text.Item(0)?
+        .AppendChild(&xml.CreateTextNode(&HSTRING::from(title))?)?;
+    text.Item(1)?
+        .AppendChild(&xml.CreateTextNode(&HSTRING::from(body))?)?;
+
+    Ok(xml)
+}
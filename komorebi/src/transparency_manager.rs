@@ -174,7 +174,7 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
                                 if should_make_transparent {
                                     match window.transparent() {
                                         Err(error) => {
-                                            let hwnd = foreground_hwnd;
+                                            let hwnd = window.hwnd;
                                             tracing::error!("failed to make unfocused window {hwnd} transparent: {error}" )
                                         }
                                         Ok(..) => {
@@ -195,10 +195,8 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
                             if window_idx != focused_window_idx {
                                 known_hwnds.lock().push(window.hwnd);
                             } else {
-                                if let Err(error) =
-                                    c.focused_window().copied().unwrap_or_default().opaque()
-                                {
-                                    let hwnd = foreground_hwnd;
+                                if let Err(error) = window.opaque() {
+                                    let hwnd = window.hwnd;
                                     tracing::error!(
                                         "failed to make focused window {hwnd} opaque: {error}"
                                     )
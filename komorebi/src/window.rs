@@ -1,18 +1,19 @@
 use crate::border_manager;
-use crate::com::SetCloak;
 use crate::focus_manager;
 use crate::stackbar_manager;
 use crate::windows_api;
+use crate::winevent_listener;
 use crate::ANIMATIONS_IN_PROGRESS;
 use crate::ANIMATION_DURATION;
-use crate::ANIMATION_ENABLED;
 use crate::SLOW_APPLICATION_COMPENSATION_TIME;
 use crate::SLOW_APPLICATION_IDENTIFIERS;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Write as _;
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicI32;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
@@ -30,31 +31,79 @@ use serde::Deserialize;
 use serde::Serialize;
 use serde::Serializer;
 use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Gdi::HMONITOR;
 
 use crate::core::ApplicationIdentifier;
+use crate::core::CornerPreference;
 use crate::core::HidingBehaviour;
 use crate::core::Rect;
 
+use crate::animation;
 use crate::animation::Animation;
 use crate::styles::ExtendedWindowStyle;
 use crate::styles::WindowStyle;
+use crate::system_api;
+use crate::system_api::system_api;
+use crate::system_api::WindowOperation;
 use crate::transparency_manager;
 use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_api::WindowsApi;
+use crate::APPLIED_CORNER_PREFERENCES;
+use crate::CORNER_PREFERENCE;
+use crate::CORNER_PREFERENCE_RULES;
+use crate::ELEVATED_HWNDS;
 use crate::FLOATING_APPLICATIONS;
+use crate::FLOAT_ELEVATED_WINDOWS;
+use crate::FLOAT_LAYOUT_REJECTING_WINDOWS;
 use crate::HIDDEN_HWNDS;
 use crate::HIDING_BEHAVIOUR;
+use crate::HIDING_BEHAVIOUR_OVERRIDES;
+use crate::HIDING_BEHAVIOUR_RULES;
 use crate::IGNORE_IDENTIFIERS;
 use crate::LAYERED_WHITELIST;
+use crate::LAYOUT_REJECTIONS;
 use crate::MANAGE_IDENTIFIERS;
 use crate::NO_TITLEBAR;
+use crate::ORIGINAL_RECTS;
+use crate::PENDING_LAYOUT_CHECKS;
 use crate::PERMAIGNORE_CLASSES;
+use crate::PICTURE_IN_PICTURE_IDENTIFIERS;
+use crate::POSITIONED_RECTS;
+use crate::PROCESS_ELEVATION;
 use crate::REGEX_IDENTIFIERS;
+use crate::RUNNING_ELEVATED;
+use crate::SESSION_ID;
+use crate::SPLASH_SCREEN_IDENTIFIERS;
+use crate::WINDOWS_11;
+use crate::WINDOW_METADATA;
+use crate::WINDOW_OPERATIONS_APPLIED;
 use crate::WSL2_UI_PROCESSES;
 
 pub static MINIMUM_WIDTH: AtomicI32 = AtomicI32::new(0);
 pub static MINIMUM_HEIGHT: AtomicI32 = AtomicI32::new(0);
 
+static ELEVATION_WARNING_LOGGED: AtomicBool = AtomicBool::new(false);
+
+/// Metadata of a window which does not change over its lifetime, apart from the title, and which
+/// is expensive to look up on every event
+#[derive(Debug, Default, Clone)]
+pub struct WindowMetadata {
+    process_id: u32,
+    exe: Option<String>,
+    path: Option<String>,
+    class: Option<String>,
+    title: Option<String>,
+    minimum_size: Option<(i32, i32)>,
+}
+
+/// The number of consecutive layout updates in which a window has to refuse its size before it is
+/// floated
+const LAYOUT_REJECTION_THRESHOLD: usize = 3;
+
+/// Windows which round their size to a grid, such as terminals sizing themselves to a multiple of
+/// their character cell, are allowed to deviate from their layout by up to this many pixels
+const LAYOUT_TOLERANCE: i32 = 50;
+
 #[derive(Debug, Default, Clone, Copy, Deserialize, JsonSchema, PartialEq)]
 pub struct Window {
     pub hwnd: isize,
@@ -149,7 +198,7 @@ impl Serialize for Window {
         )?;
         state.serialize_field(
             "rect",
-            &WindowsApi::window_rect(self.hwnd).unwrap_or_default(),
+            &system_api().window_rect(self.hwnd).unwrap_or_default(),
         )?;
         state.end()
     }
@@ -161,7 +210,7 @@ impl Window {
     }
 
     pub fn move_to_area(&mut self, current_area: &Rect, target_area: &Rect) -> Result<()> {
-        let current_rect = WindowsApi::window_rect(self.hwnd)?;
+        let current_rect = system_api().window_rect(self.hwnd)?;
         let x_diff = target_area.left - current_area.left;
         let y_diff = target_area.top - current_area.top;
         let x_ratio = f32::abs((target_area.right as f32) / (current_area.right as f32));
@@ -200,6 +249,21 @@ impl Window {
         )
     }
 
+    /// Centers the window over the given rect without changing its size
+    pub fn center_over(self, rect: &Rect) -> Result<()> {
+        let window_rect = system_api().window_rect(self.hwnd)?;
+
+        self.set_position(
+            &Rect {
+                left: rect.left + ((rect.right - window_rect.right) / 2),
+                top: rect.top + ((rect.bottom - window_rect.bottom) / 2),
+                right: window_rect.right,
+                bottom: window_rect.bottom,
+            },
+            true,
+        )
+    }
+
     pub fn animate_position(&self, start_rect: &Rect, target_rect: &Rect, top: bool) -> Result<()> {
         let start_rect = *start_rect;
         let target_rect = *target_rect;
@@ -219,8 +283,8 @@ impl Window {
                 let new_rect = Animation::lerp_rect(&start_rect, &target_rect, progress);
 
                 if progress == 1.0 {
-                    WindowsApi::position_window(hwnd, &new_rect, top)?;
-                    if WindowsApi::foreground_window().unwrap_or_default() == hwnd {
+                    system_api().position_window(hwnd, &new_rect, top)?;
+                    if system_api().foreground_window().unwrap_or_default() == hwnd {
                         focus_manager::send_notification(hwnd)
                     }
 
@@ -248,42 +312,329 @@ impl Window {
     }
 
     pub fn set_position(&self, layout: &Rect, top: bool) -> Result<()> {
-        let window_rect = WindowsApi::window_rect(self.hwnd)?;
+        let window_rect = system_api().window_rect(self.hwnd)?;
+
+        ORIGINAL_RECTS
+            .lock()
+            .entry(self.hwnd)
+            .or_insert(window_rect);
 
         if window_rect.eq(layout) {
             return Ok(());
         }
 
-        if ANIMATION_ENABLED.load(Ordering::SeqCst) {
+        // Animations run on their own thread, which would apply positions that are being recorded
+        if animation::should_animate() {
             self.animate_position(&window_rect, layout, top)
         } else {
-            WindowsApi::position_window(self.hwnd, layout, top)
+            system_api().position_window(self.hwnd, layout, top)?;
+            self.track_layout_rejection(layout);
+            self.remember_position();
+            Ok(())
+        }
+    }
+
+    /// Positions all of the given windows in a single batch, so that a layout is applied in one
+    /// repaint instead of windows visibly moving into place one after another
+    pub fn set_positions(positions: &[(Self, Rect)]) -> Result<()> {
+        // Animations move each window independently over time, so there is nothing to batch
+        if animation::should_animate() || positions.len() < 2 {
+            for (window, layout) in positions {
+                window.set_position(layout, false)?;
+            }
+
+            return Ok(());
+        }
+
+        let mut batch = vec![];
+
+        for (window, layout) in positions {
+            let window_rect = system_api().window_rect(window.hwnd)?;
+
+            ORIGINAL_RECTS
+                .lock()
+                .entry(window.hwnd)
+                .or_insert(window_rect);
+
+            if !window_rect.eq(layout) {
+                batch.push((window.hwnd, *layout));
+            }
+        }
+
+        Self::position_batch(&batch)
+    }
+
+    fn position_batch(batch: &[(isize, Rect)]) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        // Windows which have stopped responding are positioned asynchronously on their own, as
+        // they would otherwise hold up the whole batch
+        let (hung, batch): (Vec<_>, Vec<_>) = batch
+            .iter()
+            .copied()
+            .partition(|(hwnd, _)| system_api().is_hung(*hwnd));
+
+        for (hwnd, layout) in &hung {
+            system_api().position_window(*hwnd, layout, false)?;
+        }
+
+        // A single window which cannot be positioned, such as an elevated window when komorebi
+        // is not running elevated, fails the whole batch
+        if !batch.is_empty() {
+            if let Err(error) = system_api().position_windows(&batch) {
+                tracing::debug!("falling back to positioning windows individually: {error}");
+
+                for (hwnd, layout) in &batch {
+                    system_api().position_window(*hwnd, layout, false)?;
+                }
+            }
+        }
+
+        for (hwnd, layout) in hung.iter().chain(&batch) {
+            let window = Self::from(*hwnd);
+            window.track_layout_rejection(layout);
+            window.remember_position();
+        }
+
+        Ok(())
+    }
+
+    /// Applies changes to windows which were deferred while the window manager lock was held, in
+    /// the order that they were made, positioning consecutive windows in a single batch
+    pub fn apply_operations(operations: &[WindowOperation]) {
+        let mut batch: Vec<(isize, Rect)> = vec![];
+
+        for operation in operations {
+            if let WindowOperation::Position {
+                hwnd,
+                rect,
+                top: false,
+            } = *operation
+            {
+                // Only the last position of a window within a batch is of any consequence
+                match batch.iter_mut().find(|(batched, _)| *batched == hwnd) {
+                    Some((_, batched)) => *batched = rect,
+                    None => batch.push((hwnd, rect)),
+                }
+
+                continue;
+            }
+
+            if let Err(error) = Self::position_batch(&std::mem::take(&mut batch)) {
+                tracing::warn!("could not position windows: {error}");
+            }
+
+            let api = system_api();
+            let result = match *operation {
+                // Windows which are brought to the top of the Z order can't be batched
+                WindowOperation::Position { hwnd, rect, top } => {
+                    api.position_window(hwnd, &rect, top).map(|()| {
+                        let window = Self::from(hwnd);
+                        window.track_layout_rejection(&rect);
+                        window.remember_position();
+                    })
+                }
+                WindowOperation::Hide { hwnd } => {
+                    api.hide_window(hwnd);
+                    Ok(())
+                }
+                WindowOperation::Restore { hwnd } => {
+                    api.restore_window(hwnd);
+                    Ok(())
+                }
+                WindowOperation::Maximize { hwnd } => {
+                    api.maximize_window(hwnd);
+                    Ok(())
+                }
+                WindowOperation::Unmaximize { hwnd } => {
+                    api.unmaximize_window(hwnd);
+                    Ok(())
+                }
+                WindowOperation::Minimize { hwnd } => {
+                    api.minimize_window(hwnd);
+                    Ok(())
+                }
+                WindowOperation::Cloak { hwnd } => {
+                    api.cloak_window(hwnd, true);
+                    Ok(())
+                }
+                WindowOperation::Uncloak { hwnd } => {
+                    api.cloak_window(hwnd, false);
+                    Ok(())
+                }
+                WindowOperation::Focus { hwnd } => api.focus_window(hwnd),
+                WindowOperation::Raise { hwnd } => api.raise_window(hwnd),
+                WindowOperation::CenterCursor { rect } => api.center_cursor(&rect),
+                WindowOperation::Style { hwnd, style } => api.set_style(hwnd, style),
+                WindowOperation::ExStyle { hwnd, ex_style } => api.set_ex_style(hwnd, ex_style),
+                WindowOperation::Corners { hwnd, preference } => {
+                    api.set_corner_preference(hwnd, preference)
+                }
+            };
+
+            if let Err(error) = result {
+                tracing::warn!("could not apply {operation:?}: {error}");
+            }
+        }
+
+        if let Err(error) = Self::position_batch(&batch) {
+            tracing::warn!("could not position windows: {error}");
+        }
+
+        if !operations.is_empty() {
+            WINDOW_OPERATIONS_APPLIED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Whether the window is further away from the given layout than can be explained by the
+    /// window rounding its own size
+    pub fn deviates_from_layout(self, layout: &Rect) -> bool {
+        let Ok(rect) = system_api().window_rect(self.hwnd) else {
+            return false;
+        };
+
+        (rect.left - layout.left).abs() > LAYOUT_TOLERANCE
+            || (rect.top - layout.top).abs() > LAYOUT_TOLERANCE
+            || (rect.right - layout.right).abs() > LAYOUT_TOLERANCE
+            || (rect.bottom - layout.bottom).abs() > LAYOUT_TOLERANCE
+    }
+
+    // Some windows, such as fixed-size dialogs and games, snap back to their own size whenever
+    // they are positioned, and would otherwise be fought over on every single layout update
+    //
+    // Many windows only apply their own minimum size or snapping a moment after they have been
+    // positioned, so the layout is only compared against the window once its location changes
+    // have settled, see [`Window::check_layout_rejections`]
+    /// Remembers where a window ended up after being positioned, once the position has actually
+    /// been applied, so that it does not have to be positioned again for as long as it stays there
+    fn remember_position(self) {
+        if system_api::is_recording() {
+            return;
+        }
+
+        let mut positioned_rects = POSITIONED_RECTS.lock();
+        match system_api().window_rect(self.hwnd) {
+            Ok(rect) => positioned_rects.insert(self.hwnd, rect),
+            Err(_) => positioned_rects.remove(&self.hwnd),
+        };
+    }
+
+    /// Whether a window is still where it ended up the last time that it was positioned
+    pub fn is_where_positioned(self) -> bool {
+        let Some(positioned) = POSITIONED_RECTS.lock().get(&self.hwnd).copied() else {
+            return false;
+        };
+
+        system_api()
+            .window_rect(self.hwnd)
+            .is_ok_and(|rect| rect == positioned)
+    }
+
+    /// Forgets where a window ended up the last time that it was positioned, until it has been
+    /// positioned again
+    pub fn forget_position(self) {
+        // Nothing is positioned during a dry run
+        if system_api::is_dry_run() {
+            return;
+        }
+
+        POSITIONED_RECTS.lock().remove(&self.hwnd);
+    }
+
+    fn track_layout_rejection(self, layout: &Rect) {
+        // A position which has only been recorded so far cannot have been rejected yet
+        if !FLOAT_LAYOUT_REJECTING_WINDOWS.load(Ordering::SeqCst) || system_api::is_recording() {
+            return;
+        }
+
+        PENDING_LAYOUT_CHECKS.lock().insert(self.hwnd, *layout);
+    }
+
+    /// Compares the given windows against the layouts which were last applied to them, floating
+    /// windows which have refused their layout too many times in a row
+    pub fn check_layout_rejections(hwnds: &HashSet<isize>) {
+        let pending = {
+            let mut pending_layout_checks = PENDING_LAYOUT_CHECKS.lock();
+            hwnds
+                .iter()
+                .filter_map(|hwnd| {
+                    pending_layout_checks
+                        .remove(hwnd)
+                        .map(|layout| (*hwnd, layout))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for (hwnd, layout) in pending {
+            Self::from(hwnd).check_layout_rejection(&layout);
         }
     }
 
+    fn check_layout_rejection(self, layout: &Rect) {
+        let rejected = self.deviates_from_layout(layout);
+        let mut layout_rejections = LAYOUT_REJECTIONS.lock();
+
+        if !rejected {
+            layout_rejections.remove(&self.hwnd);
+            return;
+        }
+
+        let rejections = layout_rejections.entry(self.hwnd).or_default();
+        *rejections += 1;
+
+        // The event is sent again after the next rejected layout if the queue is full
+        if *rejections >= LAYOUT_REJECTION_THRESHOLD {
+            if winevent_listener::event_tx()
+                .try_send(WindowManagerEvent::RejectedLayout(self))
+                .is_ok()
+            {
+                layout_rejections.remove(&self.hwnd);
+            } else {
+                tracing::warn!("channel is full; dropping rejected layout event");
+            }
+        }
+    }
+
+    // The minimum size is queried with a blocking WM_GETMINMAXINFO message, which should not be
+    // sent to every tiled window on every layout update
+    pub fn minimum_size(self) -> Option<(i32, i32)> {
+        self.cached_metadata(
+            |metadata| &mut metadata.minimum_size,
+            || system_api().minimum_window_size(self.hwnd),
+        )
+        .ok()
+    }
+
     pub fn is_maximized(self) -> bool {
-        WindowsApi::is_zoomed(self.hwnd)
+        system_api().is_zoomed(self.hwnd)
     }
 
     pub fn is_miminized(self) -> bool {
-        WindowsApi::is_iconic(self.hwnd)
+        system_api().is_iconic(self.hwnd)
+    }
+
+    pub fn is_snapped(self) -> bool {
+        WindowsApi::is_window_arranged(self.hwnd)
     }
 
     pub fn is_visible(self) -> bool {
-        WindowsApi::is_window_visible(self.hwnd)
+        system_api().is_window_visible(self.hwnd)
     }
 
     pub fn hide(self) {
+        let hiding_behaviour = self.hiding_behaviour();
+
         let mut programmatically_hidden_hwnds = HIDDEN_HWNDS.lock();
         if !programmatically_hidden_hwnds.contains(&self.hwnd) {
             programmatically_hidden_hwnds.push(self.hwnd);
         }
 
-        let hiding_behaviour = HIDING_BEHAVIOUR.lock();
-        match *hiding_behaviour {
-            HidingBehaviour::Hide => WindowsApi::hide_window(self.hwnd),
-            HidingBehaviour::Minimize => WindowsApi::minimize_window(self.hwnd),
-            HidingBehaviour::Cloak => SetCloak(self.hwnd(), 1, 2),
+        match hiding_behaviour {
+            HidingBehaviour::Hide => system_api().hide_window(self.hwnd),
+            HidingBehaviour::Minimize => system_api().minimize_window(self.hwnd),
+            HidingBehaviour::Cloak => system_api().cloak_window(self.hwnd, true),
         }
     }
 
@@ -296,19 +647,150 @@ impl Window {
             programmatically_hidden_hwnds.remove(idx);
         }
 
-        let hiding_behaviour = HIDING_BEHAVIOUR.lock();
-        match *hiding_behaviour {
+        let hiding_behaviour = HIDING_BEHAVIOUR_OVERRIDES
+            .lock()
+            .remove(&self.hwnd)
+            .unwrap_or_else(|| *HIDING_BEHAVIOUR.lock());
+
+        match hiding_behaviour {
             HidingBehaviour::Hide | HidingBehaviour::Minimize => {
-                WindowsApi::restore_window(self.hwnd);
+                system_api().restore_window(self.hwnd);
+            }
+            HidingBehaviour::Cloak => system_api().cloak_window(self.hwnd, false),
+        }
+    }
+
+    /// The behaviour to use when hiding this window, which is the global window hiding behaviour
+    /// unless the window matches one of the configured per-application hiding behaviour rules
+    fn hiding_behaviour(self) -> HidingBehaviour {
+        let rules = HIDING_BEHAVIOUR_RULES.lock();
+
+        if !rules.is_empty() {
+            if let (Ok(title), Ok(exe), Ok(class), Ok(path)) =
+                (self.title(), self.exe(), self.class(), self.path())
+            {
+                let regex_identifiers = REGEX_IDENTIFIERS.lock();
+
+                for rule in &*rules {
+                    if should_act(
+                        &title,
+                        &exe,
+                        &class,
+                        &path,
+                        &rule.applications,
+                        &regex_identifiers,
+                    )
+                    .is_some()
+                    {
+                        HIDING_BEHAVIOUR_OVERRIDES
+                            .lock()
+                            .insert(self.hwnd, rule.behaviour);
+
+                        return rule.behaviour;
+                    }
+                }
+            }
+        }
+
+        HIDING_BEHAVIOUR_OVERRIDES.lock().remove(&self.hwnd);
+        *HIDING_BEHAVIOUR.lock()
+    }
+
+    /// Applies the corner rounding preference of a matching per-application rule or, for tiled
+    /// windows, the global corner rounding preference; windows which were previously given a
+    /// preference that no longer applies are handed back to the system default
+    ///
+    /// This runs for every window on every layout update, so the preference is only resolved
+    /// again once the window has been floated or tiled since it was last resolved, or after it
+    /// has been marked for re-evaluation by [`Window::reevaluate_corner_preference`]
+    pub fn apply_corner_preference(self, floating: bool) {
+        // Which preferences have been applied is remembered, which a dry run must not change
+        if !*WINDOWS_11 || system_api::is_dry_run() {
+            return;
+        }
+
+        let current = match APPLIED_CORNER_PREFERENCES.lock().get(&self.hwnd) {
+            Some((resolved_floating, _)) if *resolved_floating == Some(floating) => return,
+            Some((_, current)) => *current,
+            None => CornerPreference::Default,
+        };
+
+        let preference = self
+            .corner_preference_rule()
+            .or_else(|| {
+                if floating {
+                    None
+                } else {
+                    *CORNER_PREFERENCE.lock()
+                }
+            })
+            .unwrap_or(CornerPreference::Default);
+
+        if preference != current {
+            if let Err(error) = system_api().set_corner_preference(self.hwnd, preference) {
+                tracing::debug!("could not set corner preference of {}: {error}", self.hwnd);
+                return;
+            }
+        }
+
+        APPLIED_CORNER_PREFERENCES
+            .lock()
+            .insert(self.hwnd, (Option::from(floating), preference));
+    }
+
+    /// Marks the corner rounding preference of a window, or of every window if no hwnd is given,
+    /// to be resolved again on the next layout update
+    pub fn reevaluate_corner_preference(hwnd: Option<isize>) {
+        let mut applied = APPLIED_CORNER_PREFERENCES.lock();
+
+        match hwnd {
+            Some(hwnd) => {
+                if let Some((floating, _)) = applied.get_mut(&hwnd) {
+                    *floating = None;
+                }
+            }
+            None => {
+                for (floating, _) in applied.values_mut() {
+                    *floating = None;
+                }
             }
-            HidingBehaviour::Cloak => SetCloak(self.hwnd(), 1, 0),
         }
     }
 
+    fn corner_preference_rule(self) -> Option<CornerPreference> {
+        let rules = CORNER_PREFERENCE_RULES.lock();
+        if rules.is_empty() {
+            return None;
+        }
+
+        let (Ok(title), Ok(exe), Ok(class), Ok(path)) =
+            (self.title(), self.exe(), self.class(), self.path())
+        else {
+            return None;
+        };
+
+        let regex_identifiers = REGEX_IDENTIFIERS.lock();
+
+        rules
+            .iter()
+            .find(|rule| {
+                should_act(
+                    &title,
+                    &exe,
+                    &class,
+                    &path,
+                    &rule.applications,
+                    &regex_identifiers,
+                )
+                .is_some()
+            })
+            .map(|rule| rule.preference)
+    }
+
     pub fn minimize(self) {
         let exe = self.exe().unwrap_or_default();
         if !exe.contains("komorebi-bar") {
-            WindowsApi::minimize_window(self.hwnd);
+            system_api().minimize_window(self.hwnd);
         }
     }
 
@@ -325,7 +807,7 @@ impl Window {
             programmatically_hidden_hwnds.remove(idx);
         }
 
-        WindowsApi::maximize_window(self.hwnd);
+        system_api().maximize_window(self.hwnd);
     }
 
     pub fn unmaximize(self) {
@@ -337,27 +819,27 @@ impl Window {
             programmatically_hidden_hwnds.remove(idx);
         }
 
-        WindowsApi::unmaximize_window(self.hwnd);
+        system_api().unmaximize_window(self.hwnd);
     }
 
     pub fn focus(self, mouse_follows_focus: bool) -> Result<()> {
         // If the target window is already focused, do nothing.
-        if let Ok(ihwnd) = WindowsApi::foreground_window() {
+        if let Ok(ihwnd) = system_api().foreground_window() {
             if ihwnd == self.hwnd {
                 // Center cursor in Window
                 if mouse_follows_focus {
-                    WindowsApi::center_cursor_in_rect(&WindowsApi::window_rect(self.hwnd)?)?;
+                    system_api().center_cursor(&system_api().window_rect(self.hwnd)?)?;
                 }
 
                 return Ok(());
             }
         }
 
-        WindowsApi::raise_and_focus_window(self.hwnd)?;
+        system_api().focus_window(self.hwnd)?;
 
         // Center cursor in Window
         if mouse_follows_focus {
-            WindowsApi::center_cursor_in_rect(&WindowsApi::window_rect(self.hwnd)?)?;
+            system_api().center_cursor(&system_api().window_rect(self.hwnd)?)?;
         }
 
         Ok(())
@@ -387,71 +869,317 @@ impl Window {
         WindowsApi::set_window_accent(self.hwnd, None)
     }
 
-    #[cfg(target_pointer_width = "64")]
     pub fn update_style(self, style: &WindowStyle) -> Result<()> {
-        WindowsApi::update_style(self.hwnd, isize::try_from(style.bits())?)
+        system_api().set_style(self.hwnd, style.bits())
     }
 
-    #[cfg(target_pointer_width = "32")]
-    pub fn update_style(self, style: &WindowStyle) -> Result<()> {
-        WindowsApi::update_style(self.hwnd, i32::try_from(style.bits())?)
-    }
-
-    #[cfg(target_pointer_width = "64")]
-    pub fn update_ex_style(self, style: &ExtendedWindowStyle) -> Result<()> {
-        WindowsApi::update_ex_style(self.hwnd, isize::try_from(style.bits())?)
-    }
-
-    #[cfg(target_pointer_width = "32")]
     pub fn update_ex_style(self, style: &ExtendedWindowStyle) -> Result<()> {
-        WindowsApi::update_ex_style(self.hwnd, i32::try_from(style.bits())?)
+        system_api().set_ex_style(self.hwnd, style.bits())
     }
 
     pub fn style(self) -> Result<WindowStyle> {
-        let bits = u32::try_from(WindowsApi::gwl_style(self.hwnd)?)?;
+        let bits = system_api().window_style(self.hwnd)?;
         Ok(WindowStyle::from_bits_truncate(bits))
     }
 
     pub fn ex_style(self) -> Result<ExtendedWindowStyle> {
-        let bits = u32::try_from(WindowsApi::gwl_ex_style(self.hwnd)?)?;
+        let bits = system_api().window_ex_style(self.hwnd)?;
         Ok(ExtendedWindowStyle::from_bits_truncate(bits))
     }
 
     pub fn title(self) -> Result<String> {
-        WindowsApi::window_text_w(self.hwnd)
+        self.cached_metadata(
+            |metadata| &mut metadata.title,
+            || system_api().window_title(self.hwnd),
+        )
     }
 
     pub fn path(self) -> Result<String> {
-        let (process_id, _) = WindowsApi::window_thread_process_id(self.hwnd);
-        let handle = WindowsApi::process_handle(process_id)?;
-        let path = WindowsApi::exe_path(handle);
-        WindowsApi::close_process(handle)?;
-        path
+        let lookup = || system_api().process_path(self.application_process_id());
+
+        if self.is_uwp_frame_without_content() {
+            return lookup();
+        }
+
+        self.cached_metadata(|metadata| &mut metadata.path, lookup)
     }
 
     pub fn exe(self) -> Result<String> {
-        let (process_id, _) = WindowsApi::window_thread_process_id(self.hwnd);
-        let handle = WindowsApi::process_handle(process_id)?;
-        let exe = WindowsApi::exe(handle);
-        WindowsApi::close_process(handle)?;
-        exe
+        let lookup = || system_api().process_exe(self.application_process_id());
+
+        if self.is_uwp_frame_without_content() {
+            return lookup();
+        }
+
+        self.cached_metadata(|metadata| &mut metadata.exe, lookup)
+    }
+
+    /// Returns a metadata field from the cache, looking it up and caching it if it is not known yet
+    ///
+    /// Window handles can be reused by the system once a window has been destroyed, so cached
+    /// metadata is only trusted while the window still belongs to the same process
+    fn cached_metadata<T: Clone>(
+        self,
+        field: fn(&mut WindowMetadata) -> &mut Option<T>,
+        lookup: impl FnOnce() -> Result<T>,
+    ) -> Result<T> {
+        let process_id = self.process_id();
+
+        {
+            let mut cache = WINDOW_METADATA.lock();
+            if let Some(metadata) = cache.get_mut(&self.hwnd) {
+                if metadata.process_id == process_id {
+                    if let Some(value) = field(metadata) {
+                        return Ok(value.clone());
+                    }
+                } else {
+                    cache.remove(&self.hwnd);
+                }
+            }
+        }
+
+        // The lock is not held during the lookup, as GetWindowText can block on hung windows
+        let value = lookup()?;
+
+        let mut cache = WINDOW_METADATA.lock();
+        let metadata = cache.entry(self.hwnd).or_insert_with(|| WindowMetadata {
+            process_id,
+            ..Default::default()
+        });
+
+        if metadata.process_id == process_id {
+            *field(metadata) = Some(value.clone());
+        }
+
+        Ok(value)
+    }
+
+    /// Forgets the cached title of a window, which has to be looked up again once it has changed
+    pub fn invalidate_cached_title(hwnd: isize) {
+        if let Some(metadata) = WINDOW_METADATA.lock().get_mut(&hwnd) {
+            metadata.title = None;
+        }
+
+        // Corner preference rules can match on the title
+        Self::reevaluate_corner_preference(Option::from(hwnd));
+    }
+
+    /// Forgets all cached metadata of a window which has been destroyed, along with the elevation
+    /// of its process if it was the last known window of that process
+    pub fn invalidate_cached_metadata(hwnd: isize) {
+        let mut cache = WINDOW_METADATA.lock();
+
+        if let Some(metadata) = cache.remove(&hwnd) {
+            if !cache
+                .values()
+                .any(|other| other.process_id == metadata.process_id)
+            {
+                PROCESS_ELEVATION.lock().remove(&metadata.process_id);
+            }
+        }
+    }
+
+    /// The visible window which owns this one, if any; owned windows such as dialogs are
+    /// transient and always stay on top of their owner
+    pub fn owner(self) -> Option<Window> {
+        let owner = WindowsApi::owner_window(self.hwnd).ok()?;
+
+        // Some frameworks make every top-level window owned by a hidden application window
+        if system_api().is_window_visible(owner) {
+            Option::from(Window::from(owner))
+        } else {
+            None
+        }
     }
 
     pub fn process_id(self) -> u32 {
-        let (process_id, _) = WindowsApi::window_thread_process_id(self.hwnd);
-        process_id
+        system_api().window_process_id(self.hwnd)
+    }
+
+    /// UWP applications are hosted in an ApplicationFrameWindow owned by ApplicationFrameHost.exe,
+    /// while the content of the application is drawn in a CoreWindow child which is owned by the
+    /// process of the real application
+    pub fn uwp_core_window(self) -> Option<isize> {
+        if !self.is_uwp_frame() {
+            return None;
+        }
+
+        let frame_process_id = self.process_id();
+        let mut child_after = 0;
+
+        // Only a CoreWindow owned by a different process belongs to the hosted application
+        while let Ok(child) =
+            WindowsApi::find_window_ex_w(self.hwnd, child_after, "Windows.UI.Core.CoreWindow")
+        {
+            if system_api().window_process_id(child) != frame_process_id {
+                return Some(child);
+            }
+
+            child_after = child;
+        }
+
+        None
+    }
+
+    pub fn is_uwp_frame(self) -> bool {
+        self.class()
+            .is_ok_and(|class| class == "ApplicationFrameWindow")
+    }
+
+    // The exe and path of a UWP frame are those of ApplicationFrameHost.exe until the content of
+    // the hosted application has been attached, so they must not be cached before then
+    fn is_uwp_frame_without_content(self) -> bool {
+        self.is_uwp_frame() && self.uwp_core_window().is_none()
+    }
+
+    // Rules should match against the real application behind a UWP frame rather than against
+    // ApplicationFrameHost.exe, which hosts every UWP application
+    pub fn application_process_id(self) -> u32 {
+        let hwnd = self.uwp_core_window().unwrap_or(self.hwnd);
+        system_api().window_process_id(hwnd)
     }
 
     pub fn class(self) -> Result<String> {
-        WindowsApi::real_window_class_w(self.hwnd)
+        self.cached_metadata(
+            |metadata| &mut metadata.class,
+            || system_api().window_class(self.hwnd),
+        )
     }
 
     pub fn is_cloaked(self) -> Result<bool> {
-        WindowsApi::is_window_cloaked(self.hwnd)
+        system_api().is_cloaked(self.hwnd)
+    }
+
+    /// Windows of elevated processes cannot be moved or resized by an unelevated process
+    ///
+    /// Processes whose elevation cannot be determined are not treated as elevated
+    pub fn is_elevated_above_komorebi(self) -> bool {
+        if *RUNNING_ELEVATED {
+            return false;
+        }
+
+        let process_id = self.process_id();
+
+        if let Some(elevated) = PROCESS_ELEVATION.lock().get(&process_id) {
+            return elevated.unwrap_or_default();
+        }
+
+        // Lookups which fail for another reason, such as the process having exited, are retried
+        let Ok(elevated) = WindowsApi::is_process_elevated(process_id) else {
+            return false;
+        };
+
+        PROCESS_ELEVATION.lock().insert(process_id, elevated);
+
+        elevated.unwrap_or_default()
+    }
+
+    pub fn matches_floating_applications(self) -> bool {
+        let floating_applications = FLOATING_APPLICATIONS.lock();
+        if floating_applications.is_empty() {
+            return false;
+        }
+
+        let (Ok(title), Ok(exe_name), Ok(class), Ok(path)) =
+            (self.title(), self.exe(), self.class(), self.path())
+        else {
+            return false;
+        };
+
+        let regex_identifiers = REGEX_IDENTIFIERS.lock();
+
+        should_act(
+            &title,
+            &exe_name,
+            &class,
+            &path,
+            &floating_applications,
+            &regex_identifiers,
+        )
+        .is_some()
+    }
+
+    /// Exclusive and borderless fullscreen windows, usually games, cover the whole monitor
+    /// without a title bar or sit on top of everything else while doing so
+    pub fn is_fullscreen_on(self, monitor: &Rect) -> bool {
+        if system_api().window_rect(self.hwnd).ok().as_ref() != Some(monitor) {
+            return false;
+        }
+
+        // The desktop covers the whole monitor whenever it is focused
+        if self
+            .class()
+            .is_ok_and(|class| class == "Progman" || class == "WorkerW")
+        {
+            return false;
+        }
+
+        let borderless = self
+            .style()
+            .is_ok_and(|style| !style.contains(WindowStyle::CAPTION));
+        let topmost = self
+            .ex_style()
+            .is_ok_and(|ex_style| ex_style.contains(ExtendedWindowStyle::TOPMOST));
+
+        borderless || topmost
+    }
+
+    /// Splash screens and updaters are usually small, borderless and non-resizable, and often
+    /// disappear again within a second or two of being shown
+    pub fn is_splash_screen_candidate(self) -> bool {
+        let borderless = self.style().is_ok_and(|style| {
+            !style.intersects(WindowStyle::THICKFRAME | WindowStyle::CAPTION | WindowStyle::BORDER)
+        });
+
+        if borderless && self.is_small_for_monitor() {
+            return true;
+        }
+
+        let (Ok(title), Ok(exe_name), Ok(class), Ok(path)) =
+            (self.title(), self.exe(), self.class(), self.path())
+        else {
+            return false;
+        };
+
+        let splash_screen_identifiers = SPLASH_SCREEN_IDENTIFIERS.lock();
+        let regex_identifiers = REGEX_IDENTIFIERS.lock();
+
+        should_act(
+            &title,
+            &exe_name,
+            &class,
+            &path,
+            &splash_screen_identifiers,
+            &regex_identifiers,
+        )
+        .is_some()
+    }
+
+    // Borderless applications which fill most of their monitor, such as games and media players,
+    // are not splash screens
+    fn is_small_for_monitor(self) -> bool {
+        let hmonitor = HMONITOR(windows_api::as_ptr!(WindowsApi::monitor_from_window(
+            self.hwnd
+        )));
+
+        let (Ok(rect), Ok(info)) = (
+            system_api().window_rect(self.hwnd),
+            WindowsApi::monitor_info_w(hmonitor),
+        ) else {
+            return false;
+        };
+
+        let work_area = Rect::from(info.monitorInfo.rcWork);
+        rect.right * 2 <= work_area.right && rect.bottom * 2 <= work_area.bottom
+    }
+
+    pub fn is_no_activate(self) -> bool {
+        self.ex_style()
+            .is_ok_and(|ex_style| ex_style.contains(ExtendedWindowStyle::NOACTIVATE))
     }
 
     pub fn is_window(self) -> bool {
-        WindowsApi::is_window(self.hwnd)
+        system_api().is_window(self.hwnd)
     }
 
     pub fn remove_title_bar(self) -> Result<()> {
@@ -468,6 +1196,49 @@ impl Window {
         self.update_style(&style)
     }
 
+    /// Whether this window matches one of the rules for applications which have their title bars
+    /// removed while they are tiled
+    pub fn matches_no_titlebar(self) -> bool {
+        let no_titlebar = NO_TITLEBAR.lock();
+        if no_titlebar.is_empty() {
+            return false;
+        }
+
+        let (Ok(title), Ok(exe), Ok(class), Ok(path)) =
+            (self.title(), self.exe(), self.class(), self.path())
+        else {
+            return false;
+        };
+
+        let regex_identifiers = REGEX_IDENTIFIERS.lock();
+
+        should_act(
+            &title,
+            &exe,
+            &class,
+            &path,
+            &no_titlebar,
+            &regex_identifiers,
+        )
+        .is_some()
+    }
+
+    /// Puts back the title bar of a window which may have had it removed, for when the window is
+    /// no longer tiled by komorebi
+    pub fn restore_title_bar(self) -> Result<()> {
+        if self.matches_no_titlebar() && !self.style()?.contains(WindowStyle::CAPTION) {
+            self.add_title_bar()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_in_current_session(self) -> bool {
+        WindowsApi::session_id_for_process(self.process_id()).map_or(true, |session_id| {
+            session_id == SESSION_ID.load(Ordering::SeqCst)
+        })
+    }
+
     #[tracing::instrument(fields(exe, title), skip(debug))]
     pub fn should_manage(
         self,
@@ -480,7 +1251,13 @@ impl Window {
 
         debug.is_window = true;
 
-        let rect = WindowsApi::window_rect(self.hwnd).unwrap_or_default();
+        // Windows belonging to other interactive sessions (RDP, fast user switching) are managed
+        // by the instance of komorebi running in that session, if any
+        if !self.is_in_current_session() {
+            return Ok(false);
+        }
+
+        let rect = system_api().window_rect(self.hwnd).unwrap_or_default();
 
         if rect.right < MINIMUM_WIDTH.load(Ordering::SeqCst) {
             return Ok(false);
@@ -500,6 +1277,18 @@ impl Window {
 
         debug.has_title = true;
 
+        // UWP frames are shown before the application content has been attached to them, and
+        // should only be managed once the frame can be resolved to the real application
+        if self.is_uwp_frame_without_content()
+            && matches!(
+                event,
+                None | Some(WindowManagerEvent::Show(_, _) | WindowManagerEvent::FocusChange(_, _))
+            )
+        {
+            debug.is_uwp_frame_without_content = true;
+            return Ok(false);
+        }
+
         let is_cloaked = self.is_cloaked().unwrap_or_default();
 
         debug.is_cloaked = is_cloaked;
@@ -509,7 +1298,10 @@ impl Window {
         if let Some(event) = event {
             if matches!(
                 event,
-                WindowManagerEvent::Hide(_, _) | WindowManagerEvent::Cloak(_, _)
+                WindowManagerEvent::Hide(_, _)
+                    | WindowManagerEvent::Cloak(_, _)
+                    // Windows on hidden workspaces can still request attention
+                    | WindowManagerEvent::Flash(_)
             ) {
                 allow_cloaked = true;
             }
@@ -533,7 +1325,12 @@ impl Window {
                     if let (Ok(style), Ok(ex_style)) = (&self.style(), &self.ex_style()) {
                         debug.window_style = Some(*style);
                         debug.extended_window_style = Some(*ex_style);
-                        let eligible = window_is_eligible(self.hwnd, &title, &exe_name, &class, &path, style, ex_style, event, debug);
+                        let mut eligible = window_is_eligible(self.hwnd, &title, &exe_name, &class, &path, style, ex_style, event, debug);
+                        if eligible && self.is_elevated_above_komorebi() {
+                            debug.is_elevated = true;
+                            eligible = elevated_window_is_eligible(self.hwnd, &exe_name);
+                        }
+
                         debug.should_manage = eligible;
                         return Ok(eligible);
                     }
@@ -553,6 +1350,7 @@ pub struct RuleDebug {
     pub has_minimum_width: bool,
     pub has_minimum_height: bool,
     pub has_title: bool,
+    pub is_uwp_frame_without_content: bool,
     pub is_cloaked: bool,
     pub allow_cloaked: bool,
     pub allow_layered_transparency: bool,
@@ -567,8 +1365,26 @@ pub struct RuleDebug {
     pub matches_managed_override: Option<MatchingRule>,
     pub matches_layered_whitelist: Option<MatchingRule>,
     pub matches_floating_applications: Option<MatchingRule>,
+    pub matches_picture_in_picture: Option<MatchingRule>,
     pub matches_wsl2_gui: Option<String>,
-    pub matches_no_titlebar: Option<String>,
+    pub matches_no_titlebar: Option<MatchingRule>,
+    pub is_elevated: bool,
+    pub is_no_activate: bool,
+}
+
+// Elevated windows are only managed when they should be floated, since they would otherwise leave
+// a hole in the layout which komorebi is unable to fill
+fn elevated_window_is_eligible(hwnd: isize, exe_name: &str) -> bool {
+    if ELEVATED_HWNDS.lock().insert(hwnd) && !ELEVATION_WARNING_LOGGED.swap(true, Ordering::SeqCst)
+    {
+        tracing::warn!(
+            "{exe_name} is running elevated and cannot be managed while komorebi is running \
+            unelevated; run komorebi as an administrator or enable float_elevated_windows, and \
+            see elevated_windows in the state for all affected windows"
+        );
+    }
+
+    FLOAT_ELEVATED_WINDOWS.load(Ordering::SeqCst)
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -608,6 +1424,23 @@ fn window_is_eligible(
         false
     };
 
+    // Picture-in-picture windows are left unmanaged, so that they are never tiled or hidden along
+    // with a workspace and keep playing on top of whichever workspace is focused
+    let picture_in_picture_identifiers = PICTURE_IN_PICTURE_IDENTIFIERS.lock();
+    let is_picture_in_picture = if let Some(rule) = should_act(
+        title,
+        exe_name,
+        class,
+        path,
+        &picture_in_picture_identifiers,
+        &regex_identifiers,
+    ) {
+        debug.matches_picture_in_picture = Some(rule);
+        true
+    } else {
+        false
+    };
+
     let manage_identifiers = MANAGE_IDENTIFIERS.lock();
     let managed_override = if let Some(rule) = should_act(
         title,
@@ -635,7 +1468,15 @@ fn window_is_eligible(
         debug.matches_floating_applications = Some(rule);
     }
 
-    if should_ignore && !managed_override {
+    if (should_ignore || is_picture_in_picture) && !managed_override {
+        return false;
+    }
+
+    // Windows which cannot be activated, such as tool palettes and on-screen keyboards, never
+    // take focus and break the focus-follows model, so they are only managed when a manage rule
+    // explicitly asks for them
+    if ex_style.contains(ExtendedWindowStyle::NOACTIVATE) && !managed_override {
+        debug.is_no_activate = true;
         return false;
     }
 
@@ -674,8 +1515,20 @@ fn window_is_eligible(
     };
 
     let allow_titlebar_removed = {
-        let titlebars_removed = NO_TITLEBAR.lock();
-        titlebars_removed.contains(exe_name)
+        let no_titlebar = NO_TITLEBAR.lock();
+        if let Some(rule) = should_act(
+            title,
+            exe_name,
+            class,
+            path,
+            &no_titlebar,
+            &regex_identifiers,
+        ) {
+            debug.matches_no_titlebar = Some(rule);
+            true
+        } else {
+            false
+        }
     };
 
     {
@@ -1,10 +1,14 @@
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::io::ErrorKind;
+use std::net::Shutdown;
 use std::num::NonZeroUsize;
+use std::os::windows::process::CommandExt;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Command;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
@@ -20,9 +24,12 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use uds_windows::UnixListener;
+use uds_windows::UnixStream;
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
 
 use crate::core::config_generation::MatchingRule;
 use crate::core::custom_layout::CustomLayout;
+use crate::core::AnimationStyle;
 use crate::core::Arrangement;
 use crate::core::Axis;
 use crate::core::BorderImplementation;
@@ -31,12 +38,14 @@ use crate::core::CycleDirection;
 use crate::core::DefaultLayout;
 use crate::core::FocusFollowsMouseImplementation;
 use crate::core::HidingBehaviour;
+use crate::core::KomorebiError;
 use crate::core::Layout;
 use crate::core::MoveBehaviour;
 use crate::core::OperationBehaviour;
 use crate::core::OperationDirection;
 use crate::core::Rect;
 use crate::core::Sizing;
+use crate::core::SocketMessage;
 use crate::core::StackbarLabel;
 use crate::core::WindowContainerBehaviour;
 use crate::core::WindowManagementBehaviour;
@@ -47,9 +56,12 @@ use crate::config_generation::WorkspaceMatchingRule;
 use crate::container::Container;
 use crate::core::StackbarMode;
 use crate::current_virtual_desktop;
+use crate::flash_manager;
 use crate::load_configuration;
 use crate::monitor::Monitor;
 use crate::ring::Ring;
+use crate::session::Session;
+use crate::session::SessionWindow;
 use crate::should_act_individual;
 use crate::stackbar_manager::STACKBAR_FOCUSED_TEXT_COLOUR;
 use crate::stackbar_manager::STACKBAR_LABEL;
@@ -59,6 +71,9 @@ use crate::stackbar_manager::STACKBAR_TAB_HEIGHT;
 use crate::stackbar_manager::STACKBAR_TAB_WIDTH;
 use crate::stackbar_manager::STACKBAR_UNFOCUSED_TEXT_COLOUR;
 use crate::static_config::StaticConfig;
+use crate::system_api;
+use crate::system_api::system_api;
+use crate::system_api::WindowOperation;
 use crate::transparency_manager;
 use crate::window::Window;
 use crate::window_manager_event::WindowManagerEvent;
@@ -68,21 +83,35 @@ use crate::workspace::Workspace;
 use crate::BorderColours;
 use crate::Colour;
 use crate::CrossBoundaryBehaviour;
+use crate::PendingNotification;
 use crate::Rgb;
+use crate::ANIMATION_DURATION;
+use crate::ANIMATION_ENABLED;
+use crate::ANIMATION_FPS;
+use crate::ANIMATION_STYLE;
 use crate::CUSTOM_FFM;
 use crate::DATA_DIR;
 use crate::DISPLAY_INDEX_PREFERENCES;
+use crate::ELEVATED_HWNDS;
+use crate::FLOAT_SNAPPING_DISTANCE;
+use crate::FLOAT_SNAPPING_GRID;
+use crate::HIDDEN_HWNDS;
+use crate::HIDDEN_TASKBARS;
 use crate::HIDING_BEHAVIOUR;
 use crate::HOME_DIR;
 use crate::IGNORE_IDENTIFIERS;
 use crate::LAYERED_WHITELIST;
 use crate::MANAGE_IDENTIFIERS;
 use crate::MONITOR_INDEX_PREFERENCES;
-use crate::NO_TITLEBAR;
 use crate::OBJECT_NAME_CHANGE_ON_LAUNCH;
+use crate::ORIGINAL_RECTS;
 use crate::REGEX_IDENTIFIERS;
 use crate::REMOVE_TITLEBARS;
+use crate::RESTORE_SESSION;
+use crate::SUBSCRIPTION_SOCKETS;
 use crate::TRAY_AND_MULTI_WINDOW_IDENTIFIERS;
+use crate::URGENT_HWNDS;
+use crate::WIDTH_PRESETS;
 use crate::WORKSPACE_MATCHING_RULES;
 
 #[derive(Debug)]
@@ -103,7 +132,13 @@ pub struct WindowManager {
     pub virtual_desktop_id: Option<Vec<u8>>,
     pub has_pending_raise_op: bool,
     pub pending_move_op: Option<(usize, usize, usize)>,
+    /// A floating window which is being moved with the mouse, and where it was when it started
+    pub pending_float_move: Option<(isize, Rect)>,
+    /// The most recently focused windows, starting with the one that is focused now
+    pub focus_history: VecDeque<isize>,
     pub already_moved_window_handles: Arc<Mutex<HashSet<isize>>>,
+    pub batch_in_progress: bool,
+    pub pending_notifications: Vec<PendingNotification>,
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -120,6 +155,9 @@ pub struct State {
     pub focus_follows_mouse: Option<FocusFollowsMouseImplementation>,
     pub mouse_follows_focus: bool,
     pub has_pending_raise_op: bool,
+    /// Windows of elevated processes which cannot be managed while komorebi is unelevated
+    #[serde(default)]
+    pub elevated_windows: Vec<Window>,
 }
 
 impl State {
@@ -134,6 +172,10 @@ impl State {
             return true;
         }
 
+        if self.elevated_windows != new.elevated_windows {
+            return true;
+        }
+
         if self.new_window_behaviour != new.new_window_behaviour {
             return true;
         }
@@ -168,6 +210,213 @@ impl State {
 
         false
     }
+
+    /// Summarizes every monitor and its workspaces for status bars
+    pub fn bar_status(&self) -> Vec<MonitorStatus> {
+        let urgent_hwnds = URGENT_HWNDS.lock();
+        let focused_monitor_idx = self.monitors.focused_idx();
+
+        self.monitors
+            .elements()
+            .iter()
+            .enumerate()
+            .map(|(monitor_idx, monitor)| MonitorStatus {
+                monitor_idx,
+                name: monitor.name().clone(),
+                focused: monitor_idx == focused_monitor_idx,
+                focused_window_title: monitor
+                    .focused_workspace()
+                    .and_then(Workspace::focused_window)
+                    .and_then(|window| window.title().ok()),
+                focused_container_windows: monitor
+                    .focused_workspace()
+                    .map(|workspace| {
+                        if let Some(container) = workspace.monocle_container() {
+                            container.windows().len()
+                        } else if workspace.maximized_window().is_some() {
+                            1
+                        } else {
+                            workspace
+                                .focused_container()
+                                .map_or(0, |container| container.windows().len())
+                        }
+                    })
+                    .unwrap_or_default(),
+                workspaces: monitor
+                    .workspaces()
+                    .iter()
+                    .enumerate()
+                    .map(|(workspace_idx, workspace)| WorkspaceStatus {
+                        name: workspace
+                            .name()
+                            .clone()
+                            .unwrap_or_else(|| format!("{}", workspace_idx + 1)),
+                        focused: workspace_idx == monitor.focused_workspace_idx(),
+                        occupied: !workspace.is_empty(),
+                        urgent: urgent_hwnds
+                            .iter()
+                            .any(|hwnd| workspace.contains_window(*hwnd)),
+                        layout: workspace.layout_name(),
+                        stacks: workspace
+                            .containers()
+                            .iter()
+                            .chain(workspace.monocle_container())
+                            .filter(|container| container.windows().len() > 1)
+                            .count(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Computes a minimal description of what changed between this state and a newer state
+    pub fn delta(&self, new: &Self) -> StateDelta {
+        let mut delta = StateDelta::default();
+
+        let pre_focused_monitor_idx = self.monitors.focused_idx();
+        let post_focused_monitor_idx = new.monitors.focused_idx();
+        if pre_focused_monitor_idx != post_focused_monitor_idx {
+            delta.focused_monitor_idx = Option::from(Change {
+                pre: pre_focused_monitor_idx,
+                post: post_focused_monitor_idx,
+            });
+        }
+
+        let pre_monitors = self.monitors.elements();
+        let post_monitors = new.monitors.elements();
+        if pre_monitors.len() != post_monitors.len() {
+            delta.monitor_count = Option::from(Change {
+                pre: pre_monitors.len(),
+                post: post_monitors.len(),
+            });
+        }
+
+        let pre_hwnds = known_hwnds(pre_monitors);
+        let post_hwnds = known_hwnds(post_monitors);
+        delta.managed_windows = post_hwnds
+            .iter()
+            .filter(|hwnd| !pre_hwnds.contains(hwnd))
+            .copied()
+            .collect();
+        delta.unmanaged_windows = pre_hwnds
+            .iter()
+            .filter(|hwnd| !post_hwnds.contains(hwnd))
+            .copied()
+            .collect();
+
+        for (monitor_idx, (pre, post)) in pre_monitors.iter().zip(post_monitors).enumerate() {
+            if pre == post {
+                continue;
+            }
+
+            let mut monitor_delta = MonitorDelta {
+                monitor_idx,
+                focused_workspace_idx: None,
+                workspaces: vec![],
+            };
+
+            if pre.focused_workspace_idx() != post.focused_workspace_idx() {
+                monitor_delta.focused_workspace_idx = Option::from(Change {
+                    pre: pre.focused_workspace_idx(),
+                    post: post.focused_workspace_idx(),
+                });
+            }
+
+            for (workspace_idx, (pre, post)) in
+                pre.workspaces().iter().zip(post.workspaces()).enumerate()
+            {
+                if pre == post {
+                    continue;
+                }
+
+                let pre_containers = pre.containers();
+                let post_containers = post.containers();
+                let containers = (0..pre_containers.len().max(post_containers.len()))
+                    .filter(|idx| pre_containers.get(*idx) != post_containers.get(*idx))
+                    .collect();
+
+                let focused_container_idx =
+                    if pre.focused_container_idx() == post.focused_container_idx() {
+                        None
+                    } else {
+                        Option::from(Change {
+                            pre: pre.focused_container_idx(),
+                            post: post.focused_container_idx(),
+                        })
+                    };
+
+                monitor_delta.workspaces.push(WorkspaceDelta {
+                    workspace_idx,
+                    focused_container_idx,
+                    containers,
+                    floating_windows: pre.floating_windows() != post.floating_windows(),
+                    monocle_container: pre.monocle_container() != post.monocle_container(),
+                    maximized_window: pre.maximized_window() != post.maximized_window(),
+                    layout: pre.layout() != post.layout() || pre.tile() != post.tile(),
+                });
+            }
+
+            delta.monitors.push(monitor_delta);
+        }
+
+        delta
+    }
+}
+
+/// The values of a property before and after an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Change<T> {
+    pub pre: T,
+    pub post: T,
+}
+
+/// A minimal description of the parts of the window manager state that were changed by an event
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct StateDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused_monitor_idx: Option<Change<usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monitor_count: Option<Change<usize>>,
+    /// Windows which started being managed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub managed_windows: Vec<isize>,
+    /// Windows which stopped being managed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unmanaged_windows: Vec<isize>,
+    /// Monitors which were modified, identified by their index
+    pub monitors: Vec<MonitorDelta>,
+}
+
+/// The changes that a command would make, as worked out by a dry run
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DryRun {
+    /// The changes which would be made to the window manager state
+    pub delta: StateDelta,
+    /// The changes which would be made to windows, in the order that they would be made
+    pub operations: Vec<WindowOperation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MonitorDelta {
+    pub monitor_idx: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused_workspace_idx: Option<Change<usize>>,
+    /// Workspaces which were modified, identified by their index
+    pub workspaces: Vec<WorkspaceDelta>,
+}
+
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceDelta {
+    pub workspace_idx: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focused_container_idx: Option<Change<usize>>,
+    /// Indices of containers which were added, removed or modified
+    pub containers: Vec<usize>,
+    pub floating_windows: bool,
+    pub monocle_container: bool,
+    pub maximized_window: bool,
+    pub layout: bool,
 }
 
 #[allow(clippy::struct_excessive_bools)]
@@ -177,7 +426,14 @@ pub struct GlobalState {
     pub border_colours: BorderColours,
     pub border_style: BorderStyle,
     pub border_offset: i32,
+    pub border_radius: i32,
     pub border_width: i32,
+    pub transparency_enabled: bool,
+    pub transparency_alpha: u8,
+    pub animation_enabled: bool,
+    pub animation_duration: u64,
+    pub animation_style: AnimationStyle,
+    pub animation_fps: u64,
     pub stackbar_mode: StackbarMode,
     pub stackbar_label: StackbarLabel,
     pub stackbar_focused_text_colour: Colour,
@@ -224,7 +480,14 @@ impl Default for GlobalState {
             },
             border_style: STYLE.load(),
             border_offset: border_manager::BORDER_OFFSET.load(Ordering::SeqCst),
+            border_radius: border_manager::BORDER_RADIUS.load(Ordering::SeqCst),
             border_width: border_manager::BORDER_WIDTH.load(Ordering::SeqCst),
+            transparency_enabled: transparency_manager::TRANSPARENCY_ENABLED.load(Ordering::SeqCst),
+            transparency_alpha: transparency_manager::TRANSPARENCY_ALPHA.load(Ordering::SeqCst),
+            animation_enabled: ANIMATION_ENABLED.load(Ordering::SeqCst),
+            animation_duration: ANIMATION_DURATION.load(Ordering::SeqCst),
+            animation_style: *ANIMATION_STYLE.lock(),
+            animation_fps: ANIMATION_FPS.load(Ordering::SeqCst),
             stackbar_mode: STACKBAR_MODE.load(),
             stackbar_label: STACKBAR_LABEL.load(),
             stackbar_focused_text_colour: Colour::Rgb(Rgb::from(
@@ -255,6 +518,118 @@ impl Default for GlobalState {
     }
 }
 
+/// A small JSON fragment describing a window and where komorebi is managing it, if at all
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WindowQuery {
+    pub window: Window,
+    pub monitor_idx: Option<usize>,
+    pub workspace_idx: Option<usize>,
+    pub container_idx: Option<usize>,
+    pub floating: bool,
+}
+
+/// A small JSON fragment describing a workspace without its containers
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceQuery {
+    pub monitor_idx: usize,
+    pub workspace_idx: usize,
+    pub name: Option<String>,
+    pub layout: Layout,
+    pub tile: bool,
+    pub containers: usize,
+    pub floating_windows: usize,
+    pub focused_container_idx: usize,
+    pub monocle: bool,
+    pub maximized: bool,
+}
+
+/// A small JSON fragment describing a monitor without its workspaces
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct MonitorQuery {
+    pub monitor_idx: usize,
+    pub name: String,
+    pub device_id: String,
+    pub size: Rect,
+    pub work_area_size: Rect,
+    pub workspaces: usize,
+    pub focused_workspace_idx: usize,
+    pub focused_workspace_name: Option<String>,
+}
+
+/// A compact summary of a monitor and its workspaces with everything needed to render a
+/// workspace widget in a status bar
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MonitorStatus {
+    pub monitor_idx: usize,
+    pub name: String,
+    pub focused: bool,
+    /// The title of the focused window on the focused workspace of this monitor
+    pub focused_window_title: Option<String>,
+    /// The number of windows in the focused container on the focused workspace of this monitor,
+    /// which is more than one when the container is a stack
+    pub focused_container_windows: usize,
+    pub workspaces: Vec<WorkspaceStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct WorkspaceStatus {
+    pub name: String,
+    pub focused: bool,
+    /// The workspace contains at least one window
+    pub occupied: bool,
+    /// A window on the workspace has flashed to request attention and has not been focused since
+    pub urgent: bool,
+    pub layout: String,
+    /// The number of containers on the workspace which hold more than one window
+    pub stacks: usize,
+}
+
+/// Moves a span starting at `start` so that either of its ends lines up with the closest of the
+/// edges within `distance` of it, or so that its start lines up with a grid of `grid` cells
+/// starting at `origin` if none of them are close enough; a `grid` of 0 turns the grid off
+fn snap_span(start: i32, size: i32, edges: &[i32], distance: i32, grid: i32, origin: i32) -> i32 {
+    let closest = edges
+        .iter()
+        .flat_map(|edge| [edge - start, edge - (start + size)])
+        .filter(|offset| offset.abs() <= distance)
+        .min_by_key(|offset| offset.abs());
+
+    match closest {
+        Some(offset) => start + offset,
+        None if grid > 0 => origin + (start - origin + grid / 2).div_euclid(grid) * grid,
+        None => start,
+    }
+}
+
+fn known_hwnds(monitors: &VecDeque<Monitor>) -> Vec<isize> {
+    let mut known_hwnds = vec![];
+    for monitor in monitors {
+        for workspace in monitor.workspaces() {
+            for container in workspace.containers() {
+                for window in container.windows() {
+                    known_hwnds.push(window.hwnd);
+                }
+            }
+
+            if let Some(container) = workspace.monocle_container() {
+                for window in container.windows() {
+                    known_hwnds.push(window.hwnd);
+                }
+            }
+
+            if let Some(window) = workspace.maximized_window() {
+                known_hwnds.push(window.hwnd);
+            }
+
+            for window in workspace.floating_windows() {
+                known_hwnds.push(window.hwnd);
+            }
+        }
+    }
+
+    known_hwnds
+}
+
 impl AsRef<Self> for WindowManager {
     fn as_ref(&self) -> &Self {
         self
@@ -275,6 +650,12 @@ impl From<&WindowManager> for State {
             mouse_follows_focus: wm.mouse_follows_focus,
             has_pending_raise_op: wm.has_pending_raise_op,
             unmanaged_window_operation_behaviour: wm.unmanaged_window_operation_behaviour,
+            elevated_windows: ELEVATED_HWNDS
+                .lock()
+                .iter()
+                .copied()
+                .map(Window::from)
+                .collect(),
         }
     }
 }
@@ -308,9 +689,11 @@ impl EnforceWorkspaceRuleOp {
 impl WindowManager {
     #[tracing::instrument]
     pub fn new(incoming: Receiver<WindowManagerEvent>) -> Result<Self> {
-        let socket = DATA_DIR.join("komorebi.sock");
+        Self::listening_on(incoming, &DATA_DIR.join("komorebi.sock"))
+    }
 
-        match std::fs::remove_file(&socket) {
+    fn listening_on(incoming: Receiver<WindowManagerEvent>, socket: &Path) -> Result<Self> {
+        match std::fs::remove_file(socket) {
             Ok(()) => {}
             Err(error) => match error.kind() {
                 // Doing this because ::exists() doesn't work reliably on Windows via IntelliJ
@@ -321,7 +704,7 @@ impl WindowManager {
             },
         };
 
-        let listener = UnixListener::bind(&socket)?;
+        let listener = UnixListener::bind(socket)?;
 
         Ok(Self {
             monitors: Ring::default(),
@@ -340,7 +723,11 @@ impl WindowManager {
             hotwatch: Hotwatch::new()?,
             has_pending_raise_op: false,
             pending_move_op: None,
+            pending_float_move: None,
+            focus_history: VecDeque::new(),
             already_moved_window_handles: Arc::new(Mutex::new(HashSet::new())),
+            batch_in_progress: false,
+            pending_notifications: vec![],
         })
     }
 
@@ -535,7 +922,7 @@ impl WindowManager {
         let focused_workspace_idx = self
             .monitors()
             .get(focused_monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor with that index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(focused_monitor_idx))?
             .focused_workspace_idx();
 
         let workspace_matching_rules = WORKSPACE_MATCHING_RULES.lock();
@@ -626,20 +1013,20 @@ impl WindowManager {
             let target_area = *self
                 .monitors_mut()
                 .get_mut(op.target_monitor_idx)
-                .ok_or_else(|| anyhow!("there is no monitor with that index"))?
+                .ok_or(KomorebiError::NoSuchMonitor(op.target_monitor_idx))?
                 .work_area_size();
 
             let origin_monitor = self
                 .monitors_mut()
                 .get_mut(op.origin_monitor_idx)
-                .ok_or_else(|| anyhow!("there is no monitor with that index"))?;
+                .ok_or(KomorebiError::NoSuchMonitor(op.origin_monitor_idx))?;
 
             let origin_area = *origin_monitor.work_area_size();
 
             let origin_workspace = origin_monitor
                 .workspaces_mut()
                 .get_mut(op.origin_workspace_idx)
-                .ok_or_else(|| anyhow!("there is no workspace with that index"))?;
+                .ok_or(KomorebiError::NoSuchWorkspace(op.origin_workspace_idx))?;
 
             let mut window = Window::from(op.hwnd);
 
@@ -655,6 +1042,7 @@ impl WindowManager {
             }
 
             origin_workspace.remove_window(op.hwnd)?;
+            origin_workspace.set_dirty(true);
         }
 
         // Parse the operation again and associate those removed windows with the workspace that
@@ -663,7 +1051,7 @@ impl WindowManager {
             let target_monitor = self
                 .monitors_mut()
                 .get_mut(op.target_monitor_idx)
-                .ok_or_else(|| anyhow!("there is no monitor with that index"))?;
+                .ok_or(KomorebiError::NoSuchMonitor(op.target_monitor_idx))?;
 
             // The very first time this fn is called, the workspace might not even exist yet
             if target_monitor
@@ -678,7 +1066,7 @@ impl WindowManager {
             let target_workspace = target_monitor
                 .workspaces_mut()
                 .get_mut(op.target_workspace_idx)
-                .ok_or_else(|| anyhow!("there is no workspace with that index"))?;
+                .ok_or(KomorebiError::NoSuchWorkspace(op.target_workspace_idx))?;
 
             if op.floating {
                 target_workspace
@@ -695,6 +1083,8 @@ impl WindowManager {
                 //on the `new_container_for_window` function instead.
                 target_workspace.new_container_for_window(Window::from(op.hwnd));
             }
+
+            target_workspace.set_dirty(true);
         }
 
         // Only re-tile the focused workspace if we need to
@@ -705,8 +1095,93 @@ impl WindowManager {
         Ok(())
     }
 
+    /// Works out the state changes and window operations that a command would produce, without
+    /// touching any windows or keeping any of the changes
+    pub fn dry_run(&mut self, message: SocketMessage) -> Result<DryRun> {
+        if !message.supports_dry_run() {
+            return Err(KomorebiError::DryRunUnsupported.into());
+        }
+
+        let initial_state = State::from(self.as_ref());
+        let monitors = self.monitors.clone();
+        let mouse_follows_focus = self.mouse_follows_focus;
+        let has_pending_raise_op = self.has_pending_raise_op;
+        let hidden_hwnds = HIDDEN_HWNDS.lock().clone();
+
+        let pending_notifications = self.pending_notifications.len();
+
+        // The cursor is left where it is, and layouts are only applied once at the end, as they
+        // would be for a batch
+        self.mouse_follows_focus = false;
+        self.batch_in_progress = true;
+        system_api::start_recording();
+
+        let messages = match message {
+            SocketMessage::Batch(messages) => messages,
+            message => vec![message],
+        };
+
+        let mut reply = vec![];
+        let mut result = Ok(());
+        for message in messages {
+            result = self.process_command(message, &mut reply);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.batch_in_progress = false;
+        if result.is_ok() {
+            result = self
+                .retile_all(true)
+                .and_then(|()| self.update_focused_workspace(false, false));
+        }
+
+        let operations = system_api::stop_recording();
+        let delta = initial_state.delta(&State::from(self.as_ref()));
+
+        self.monitors = monitors;
+        self.mouse_follows_focus = mouse_follows_focus;
+        self.has_pending_raise_op = has_pending_raise_op;
+        *HIDDEN_HWNDS.lock() = hidden_hwnds;
+
+        // Subscribers are not told about changes which were never made
+        self.pending_notifications.truncate(pending_notifications);
+
+        result?;
+
+        Ok(DryRun { delta, operations })
+    }
+
+    /// Lays out workspaces which were changed while they were hidden and have been shown again
+    /// since without being updated
+    pub fn update_dirty_workspaces(&mut self) -> Result<()> {
+        // The layout will be applied once when the batch has been fully processed
+        if self.batch_in_progress {
+            return Ok(());
+        }
+
+        let offset = self.work_area_offset;
+
+        for monitor in self.monitors_mut() {
+            if monitor
+                .focused_workspace()
+                .is_some_and(|workspace| workspace.dirty())
+            {
+                monitor.update_focused_workspace(offset)?;
+            }
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn retile_all(&mut self, preserve_resize_dimensions: bool) -> Result<()> {
+        // The layout will be applied once when the batch has been fully processed
+        if self.batch_in_progress {
+            return Ok(());
+        }
+
         let offset = self.work_area_offset;
 
         for monitor in self.monitors_mut() {
@@ -739,18 +1214,33 @@ impl WindowManager {
         Ok(())
     }
 
+    /// Retiles every monitor without skipping the windows which look like they are already in
+    /// place, to recover from windows having been moved or resized behind komorebi's back
+    #[tracing::instrument(skip(self))]
+    pub fn force_retile_all(&mut self, preserve_resize_dimensions: bool) -> Result<()> {
+        tracing::info!("forcing retile");
+
+        for monitor in self.monitors_mut() {
+            if let Some(workspace) = monitor.focused_workspace_mut() {
+                workspace.forget_applied_layouts();
+            }
+        }
+
+        self.retile_all(preserve_resize_dimensions)
+    }
+
+    // Commands are handled on the same thread that consumes the event queue, so these events are
+    // processed directly instead of being sent to a queue which nothing would be draining
     #[tracing::instrument(skip(self))]
     pub fn manage_focused_window(&mut self) -> Result<()> {
         let hwnd = WindowsApi::foreground_window()?;
-        let event = WindowManagerEvent::Manage(Window::from(hwnd));
-        Ok(winevent_listener::event_tx().send(event)?)
+        self.process_event(WindowManagerEvent::Manage(Window::from(hwnd)))
     }
 
     #[tracing::instrument(skip(self))]
     pub fn unmanage_focused_window(&mut self) -> Result<()> {
         let hwnd = WindowsApi::foreground_window()?;
-        let event = WindowManagerEvent::Unmanage(Window::from(hwnd));
-        Ok(winevent_listener::event_tx().send(event)?)
+        self.process_event(WindowManagerEvent::Unmanage(Window::from(hwnd)))
     }
 
     #[tracing::instrument(skip(self))]
@@ -806,9 +1296,14 @@ impl WindowManager {
                 return Ok(());
             }
 
+            // This is called with the window manager lock held, which the event loop needs before
+            // it can make room in its queue, so the raise is skipped rather than waited on
             let event = WindowManagerEvent::Raise(Window::from(hwnd));
-            self.has_pending_raise_op = true;
-            winevent_listener::event_tx().send(event)?;
+            if winevent_listener::event_tx().try_send(event).is_ok() {
+                self.has_pending_raise_op = true;
+            } else {
+                tracing::warn!("channel is full; dropping raise event");
+            }
         } else {
             tracing::debug!(
                 "not raising unknown window: {}",
@@ -819,6 +1314,74 @@ impl WindowManager {
         Ok(())
     }
 
+    pub fn window_query(&self, hwnd: isize) -> WindowQuery {
+        let mut query = WindowQuery {
+            window: Window::from(hwnd),
+            monitor_idx: None,
+            workspace_idx: None,
+            container_idx: None,
+            floating: false,
+        };
+
+        for (monitor_idx, monitor) in self.monitors().iter().enumerate() {
+            for (workspace_idx, workspace) in monitor.workspaces().iter().enumerate() {
+                if workspace.contains_window(hwnd) {
+                    query.monitor_idx = Option::from(monitor_idx);
+                    query.workspace_idx = Option::from(workspace_idx);
+                    query.container_idx = workspace.container_idx_for_window(hwnd);
+                    query.floating = workspace.floating_windows().iter().any(|w| w.hwnd == hwnd);
+
+                    return query;
+                }
+            }
+        }
+
+        query
+    }
+
+    pub fn focused_workspace_query(&self) -> Result<WorkspaceQuery> {
+        let monitor_idx = self.focused_monitor_idx();
+        let workspace_idx = self.focused_workspace_idx()?;
+        let workspace = self.focused_workspace()?;
+
+        Ok(WorkspaceQuery {
+            monitor_idx,
+            workspace_idx,
+            name: workspace.name().clone(),
+            layout: workspace.layout().clone(),
+            tile: *workspace.tile(),
+            containers: workspace.containers().len(),
+            floating_windows: workspace.floating_windows().len(),
+            focused_container_idx: workspace.focused_container_idx(),
+            monocle: workspace.monocle_container().is_some(),
+            maximized: workspace.maximized_window().is_some(),
+        })
+    }
+
+    pub fn focused_monitor_query(&self) -> Result<MonitorQuery> {
+        let monitor_idx = self.focused_monitor_idx();
+        let monitor = self
+            .focused_monitor()
+            .ok_or_else(|| anyhow!("there is no monitor"))?;
+
+        Ok(MonitorQuery {
+            monitor_idx,
+            name: monitor.name().clone(),
+            device_id: monitor.device_id().clone(),
+            size: *monitor.size(),
+            work_area_size: *monitor.work_area_size(),
+            workspaces: monitor.workspaces().len(),
+            focused_workspace_idx: monitor.focused_workspace_idx(),
+            focused_workspace_name: monitor
+                .focused_workspace()
+                .and_then(|workspace| workspace.name().clone()),
+        })
+    }
+
+    pub fn known_hwnds(&self) -> Vec<isize> {
+        known_hwnds(self.monitors())
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn transfer_container(
         &mut self,
@@ -831,25 +1394,32 @@ impl WindowManager {
         let origin_container = self
             .monitors_mut()
             .get_mut(origin_monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(origin_monitor_idx))?
             .workspaces_mut()
             .get_mut(origin_workspace_idx)
-            .ok_or_else(|| anyhow!("there is no workspace at this index"))?
+            .ok_or(KomorebiError::NoSuchWorkspace(origin_workspace_idx))?
             .remove_container(origin_container_idx)
             .ok_or_else(|| anyhow!("there is no container at this index"))?;
 
         let target_workspace = self
             .monitors_mut()
             .get_mut(target_monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(target_monitor_idx))?
             .workspaces_mut()
             .get_mut(target_workspace_idx)
-            .ok_or_else(|| anyhow!("there is no workspace at this index"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(target_workspace_idx))?;
 
         target_workspace
             .containers_mut()
             .insert(target_container_idx, origin_container);
 
+        // Keep any resizes of the containers after the insertion point with their containers
+        if target_container_idx < target_workspace.resize_dimensions().len() {
+            target_workspace
+                .resize_dimensions_mut()
+                .insert(target_container_idx, None);
+        }
+
         target_workspace.focus_container(target_container_idx);
 
         Ok(())
@@ -867,38 +1437,38 @@ impl WindowManager {
         let origin_container = self
             .monitors_mut()
             .get_mut(origin_monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(origin_monitor_idx))?
             .workspaces_mut()
             .get_mut(origin_workspace_idx)
-            .ok_or_else(|| anyhow!("there is no workspace at this index"))?
+            .ok_or(KomorebiError::NoSuchWorkspace(origin_workspace_idx))?
             .remove_container(origin_container_idx)
             .ok_or_else(|| anyhow!("there is no container at this index"))?;
 
         let target_container = self
             .monitors_mut()
             .get_mut(target_monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(target_monitor_idx))?
             .workspaces_mut()
             .get_mut(target_workspace_idx)
-            .ok_or_else(|| anyhow!("there is no workspace at this index"))?
+            .ok_or(KomorebiError::NoSuchWorkspace(target_workspace_idx))?
             .remove_container(target_container_idx);
 
         self.monitors_mut()
             .get_mut(target_monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(target_monitor_idx))?
             .workspaces_mut()
             .get_mut(target_workspace_idx)
-            .ok_or_else(|| anyhow!("there is no workspace at this index"))?
+            .ok_or(KomorebiError::NoSuchWorkspace(target_workspace_idx))?
             .containers_mut()
             .insert(target_container_idx, origin_container);
 
         if let Some(target_container) = target_container {
             self.monitors_mut()
                 .get_mut(origin_monitor_idx)
-                .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+                .ok_or(KomorebiError::NoSuchMonitor(origin_monitor_idx))?
                 .workspaces_mut()
                 .get_mut(origin_workspace_idx)
-                .ok_or_else(|| anyhow!("there is no workspace at this index"))?
+                .ok_or(KomorebiError::NoSuchWorkspace(origin_workspace_idx))?
                 .containers_mut()
                 .insert(origin_container_idx, target_container);
         }
@@ -914,6 +1484,11 @@ impl WindowManager {
     ) -> Result<()> {
         tracing::info!("updating");
 
+        // The layout will be applied once when the batch has been fully processed
+        if self.batch_in_progress {
+            return Ok(());
+        }
+
         let offset = self.work_area_offset;
 
         self.focused_monitor_mut()
@@ -938,10 +1513,14 @@ impl WindowManager {
             } else {
                 let desktop_window = Window::from(WindowsApi::desktop_window()?);
 
-                let rect = self.focused_monitor_size()?;
-                WindowsApi::center_cursor_in_rect(&rect)?;
+                // There is no window to warp the cursor to, so it is taken to the middle of the
+                // monitor instead, but only if that is what the user has asked for
+                if self.mouse_follows_focus {
+                    let rect = self.focused_monitor_size()?;
+                    system_api().center_cursor(&rect)?;
+                }
 
-                match WindowsApi::raise_and_focus_window(desktop_window.hwnd) {
+                match system_api().focus_window(desktop_window.hwnd) {
                     Ok(()) => {}
                     Err(error) => {
                         tracing::warn!("{} {}:{}", error, file!(), line!());
@@ -952,7 +1531,7 @@ impl WindowManager {
             if self.focused_workspace()?.is_empty() {
                 let desktop_window = Window::from(WindowsApi::desktop_window()?);
 
-                match WindowsApi::raise_and_focus_window(desktop_window.hwnd) {
+                match system_api().focus_window(desktop_window.hwnd) {
                     Ok(()) => {}
                     Err(error) => {
                         tracing::warn!("{} {}:{}", error, file!(), line!());
@@ -1083,44 +1662,365 @@ impl WindowManager {
         Ok(())
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn cycle_width(&mut self, direction: CycleDirection) -> Result<()> {
+        self.handle_unmanaged_window_behaviour()?;
+
+        let mut presets = WIDTH_PRESETS.lock().clone();
+        presets.sort_unstable();
+        presets.dedup();
+
+        let workspace = self.focused_workspace()?;
+        if workspace.is_focused_window_monocle_or_maximized()? {
+            return Err(KomorebiError::MonocleOrMaximized.into());
+        }
+
+        if !matches!(workspace.layout(), Layout::Default(_)) {
+            bail!("container widths cannot be cycled when using custom layouts");
+        }
+
+        if workspace.containers().len() < 2 {
+            bail!("there must be at least two containers to cycle the width of one of them");
+        }
+
+        let layout = workspace.latest_layout();
+        let rect = *layout
+            .get(workspace.focused_container_idx())
+            .ok_or_else(|| anyhow!("there is no last layout"))?;
+
+        // Widths are measured against the space taken up by the whole layout, so that the
+        // workspace padding doesn't count towards the share of any container
+        let left = layout
+            .iter()
+            .map(|rect| rect.left)
+            .min()
+            .unwrap_or_default();
+        let right = layout
+            .iter()
+            .map(|rect| rect.left + rect.right)
+            .max()
+            .unwrap_or_default();
+        let width = right - left;
+
+        if width <= 0 {
+            bail!("there is no width to share between containers");
+        }
+
+        // Widths within a percentage point of a preset are treated as being at that preset, so
+        // that rounding in the layout doesn't stop the cycle from moving on
+        #[allow(clippy::cast_precision_loss)]
+        let current = rect.right as f32 * 100.0 / width as f32;
+        let target = match direction {
+            CycleDirection::Next => presets
+                .iter()
+                .find(|preset| f32::from(**preset) > current + 1.0)
+                .or_else(|| presets.first()),
+            CycleDirection::Previous => presets
+                .iter()
+                .rev()
+                .find(|preset| f32::from(**preset) < current - 1.0)
+                .or_else(|| presets.last()),
+        }
+        .copied()
+        .ok_or_else(|| anyhow!("there are no width presets to cycle through"))?;
+
+        tracing::info!("cycling container width to {target}%");
+
+        let difference = width * i32::from(target) / 100 - rect.right;
+        if difference == 0 {
+            return Ok(());
+        }
+
+        // Containers against the right side of the layout can only grow or shrink to the left
+        let edge = if rect.left + rect.right >= right - 1 {
+            OperationDirection::Left
+        } else {
+            OperationDirection::Right
+        };
+
+        let sizing = if difference > 0 {
+            Sizing::Increase
+        } else {
+            Sizing::Decrease
+        };
+
+        // Only half of the delta of a resize is applied to the edge which is being moved
+        self.resize_window(edge, sizing, difference.abs() * 2, true)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn restore_all_windows(&mut self) -> Result<()> {
         tracing::info!("restoring all hidden windows");
 
-        let no_titlebar = NO_TITLEBAR.lock();
         let known_transparent_hwnds = transparency_manager::known_hwnds();
         let border_implementation = border_manager::IMPLEMENTATION.load();
 
+        // Monocle, maximized and floating windows on unfocused workspaces are hidden too
+        for hwnd in self.known_hwnds() {
+            let window = Window::from(hwnd);
+
+            window.restore_title_bar()?;
+
+            if known_transparent_hwnds.contains(&window.hwnd) {
+                window.opaque()?;
+            }
+
+            if matches!(border_implementation, BorderImplementation::Windows) {
+                window.remove_accent()?;
+            }
+
+            window.restore();
+
+            // Leave windows where komorebi found them, which also puts them back on their
+            // original monitors
+            if let Some(rect) = ORIGINAL_RECTS.lock().remove(&window.hwnd) {
+                if let Err(error) = system_api().position_window(window.hwnd, &rect, false) {
+                    tracing::warn!("could not restore original position of {window}: {error}");
+                }
+            }
+        }
+
+        for monitor in self.monitors() {
+            if HIDDEN_TASKBARS.lock().remove(monitor.device_id()) {
+                if let Some(taskbar) = WindowsApi::taskbar_for_monitor(monitor.id()) {
+                    system_api().restore_window(taskbar);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes a destroyed window from whichever workspace it was on, updating the layout of that
+    /// workspace if it is currently shown on its monitor, and returning whether it was found
+    pub fn remove_destroyed_window(&mut self, hwnd: isize) -> Result<bool> {
+        let offset = self.work_area_offset;
+
         for monitor in self.monitors_mut() {
-            for workspace in monitor.workspaces_mut() {
-                if let Some(monocle) = workspace.monocle_container() {
-                    for window in monocle.windows() {
-                        if matches!(border_implementation, BorderImplementation::Windows) {
-                            window.remove_accent()?;
-                        }
-                    }
+            let work_area = *monitor.work_area_size();
+            let focused_workspace_idx = monitor.focused_workspace_idx();
+            let window_based_work_area_offset = (
+                monitor.window_based_work_area_offset_limit(),
+                monitor.window_based_work_area_offset(),
+            );
+
+            let offset = if monitor.work_area_offset().is_some() {
+                monitor.work_area_offset()
+            } else {
+                offset
+            };
+
+            for (workspace_idx, workspace) in monitor.workspaces_mut().iter_mut().enumerate() {
+                if !workspace.contains_window(hwnd) {
+                    continue;
                 }
 
-                for containers in workspace.containers_mut() {
-                    for window in containers.windows_mut() {
-                        if no_titlebar.contains(&window.exe()?) {
-                            window.add_title_bar()?;
-                        }
+                workspace.remove_window(hwnd)?;
 
-                        if known_transparent_hwnds.contains(&window.hwnd) {
-                            window.opaque()?;
-                        }
+                if workspace_idx == focused_workspace_idx {
+                    workspace.update(&work_area, offset, window_based_work_area_offset)?;
+                } else {
+                    workspace.set_dirty(true);
+                }
+
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Hides or shows the taskbar on a monitor, giving the space it occupied to tiled windows
+    /// while it is hidden
+    pub fn set_taskbar_visibility(&mut self, monitor_idx: usize, visible: bool) -> Result<()> {
+        let monitor = self
+            .monitors_mut()
+            .get_mut(monitor_idx)
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
+
+        let taskbar = WindowsApi::taskbar_for_monitor(monitor.id())
+            .ok_or_else(|| anyhow!("there is no taskbar on this monitor"))?;
+
+        if visible {
+            HIDDEN_TASKBARS.lock().remove(monitor.device_id());
+            system_api().restore_window(taskbar);
+        } else {
+            HIDDEN_TASKBARS.lock().insert(monitor.device_id().clone());
+            system_api().hide_window(taskbar);
+        }
+
+        let reference = WindowsApi::monitor(monitor.id())?;
+        monitor.set_work_area_size(*reference.work_area_size());
+
+        self.retile_all(false)
+    }
+
+    /// Forgets about hidden taskbars which have since been shown again by something other than
+    /// komorebi, such as explorer restarting, so that the space they occupy is given back to them
+    /// when the work areas of the monitors are next reconciled
+    pub fn reconcile_hidden_taskbars(&self) {
+        let mut hidden_taskbars = HIDDEN_TASKBARS.lock();
+
+        for monitor in self.monitors() {
+            if !hidden_taskbars.contains(monitor.device_id()) {
+                continue;
+            }
+
+            let shown = WindowsApi::taskbar_for_monitor(monitor.id())
+                .is_some_and(|taskbar| system_api().is_window_visible(taskbar));
+
+            if shown {
+                tracing::info!("taskbar was shown again on {}", monitor.device_id());
+                hidden_taskbars.remove(monitor.device_id());
+            }
+        }
+    }
+
+    /// Places managed windows back into the workspaces that they were assigned to in a session
+    #[tracing::instrument(skip(self, session))]
+    pub fn restore_session(&mut self, session: &Session) -> Result<()> {
+        tracing::info!("restoring session");
+
+        let windows = self
+            .known_hwnds()
+            .into_iter()
+            .map(|hwnd| SessionWindow::from(Window::from(hwnd)))
+            .collect::<Vec<_>>();
+
+        let resolved = session.resolve(&windows);
+
+        for (session_monitor_idx, session_monitor) in session.monitors.iter().enumerate() {
+            // Monitor indices can change between sessions, so prefer matching on the device id
+            let Some(monitor_idx) = self
+                .monitors()
+                .iter()
+                .position(|monitor| *monitor.device_id() == session_monitor.device_id)
+                .or_else(|| {
+                    (session_monitor_idx < self.monitors().len()).then_some(session_monitor_idx)
+                })
+            else {
+                continue;
+            };
 
-                        if matches!(border_implementation, BorderImplementation::Windows) {
-                            window.remove_accent()?;
+            for (workspace_idx, session_workspace) in session_monitor.workspaces.iter().enumerate()
+            {
+                let mut containers = vec![];
+                for session_container in &session_workspace.containers {
+                    let mut container = Container::default();
+                    for session_window in session_container {
+                        if let Some(window) = self.take_window(&resolved, session_window)? {
+                            container.add_window(window);
                         }
+                    }
 
-                        window.restore();
+                    if !container.windows().is_empty() {
+                        containers.push(container);
                     }
                 }
+
+                let mut floating_windows = vec![];
+                for session_window in &session_workspace.floating_windows {
+                    if let Some(window) = self.take_window(&resolved, session_window)? {
+                        floating_windows.push(window);
+                    }
+                }
+
+                let monitor = self
+                    .monitors_mut()
+                    .get_mut(monitor_idx)
+                    .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
+
+                monitor.ensure_workspace_count(workspace_idx + 1);
+
+                let workspace = monitor
+                    .workspaces_mut()
+                    .get_mut(workspace_idx)
+                    .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
+
+                for container in containers {
+                    workspace.add_container_to_back(container);
+                }
+
+                workspace.floating_windows_mut().extend(floating_windows);
+                workspace.set_dirty(true);
+            }
+
+            if let Some(monitor) = self.monitors_mut().get_mut(monitor_idx) {
+                if session_monitor.focused_workspace_idx < monitor.workspaces().len() {
+                    monitor.focus_workspace(session_monitor.focused_workspace_idx)?;
+                }
+            }
+        }
+
+        if session.focused_monitor_idx < self.monitors().len() {
+            self.monitors.focus(session.focused_monitor_idx);
+        }
+
+        let mouse_follows_focus = self.mouse_follows_focus;
+        for monitor in self.monitors_mut() {
+            monitor.load_focused_workspace(mouse_follows_focus)?;
+        }
+
+        self.update_dirty_workspaces()
+    }
+
+    // Removes the window that a session window was resolved to from wherever it is currently
+    // being managed so that it can be placed back into its session workspace
+    fn take_window(
+        &mut self,
+        resolved: &HashMap<isize, isize>,
+        session_window: &SessionWindow,
+    ) -> Result<Option<Window>> {
+        let Some(hwnd) = resolved.get(&session_window.hwnd).copied() else {
+            return Ok(None);
+        };
+
+        for monitor in self.monitors_mut() {
+            for workspace in monitor.workspaces_mut() {
+                if workspace.contains_window(hwnd) {
+                    workspace.remove_window(hwnd)?;
+                    return Ok(Option::from(Window::from(hwnd)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Restores all hidden windows and releases everything that has been acquired from the system
+    /// so that the process can exit without leaving anything behind
+    #[tracing::instrument(skip(self))]
+    pub fn shutdown(&mut self) -> Result<()> {
+        tracing::info!("shutting down");
+
+        // Stop receiving events first so that restoring windows doesn't trigger any more work
+        winevent_listener::stop();
+
+        // Any pending debounced write may not have happened yet, so save the final session here
+        if RESTORE_SESSION.load(Ordering::SeqCst) {
+            if let Err(error) = Session::from(&*self).save() {
+                tracing::error!("could not save session: {error}");
+            }
+        }
+
+        // The process exits before any deferred changes to windows would have been applied
+        system_api::without_recording(|| self.restore_all_windows())?;
+
+        if WindowsApi::focus_follows_mouse()? {
+            WindowsApi::disable_focus_follows_mouse()?;
+        }
+
+        let sockets = SUBSCRIPTION_SOCKETS.lock();
+        for path in (*sockets).values() {
+            if let Ok(stream) = UnixStream::connect(path) {
+                stream.shutdown(Shutdown::Both)?;
             }
         }
 
+        // Every window has been restored, so there is nothing left to recover from this list
+        let _ = std::fs::remove_file(DATA_DIR.join("komorebi.hwnd.json"));
+        let _ = std::fs::remove_file(DATA_DIR.join("komorebi.sock"));
+
         Ok(())
     }
 
@@ -1156,7 +2056,7 @@ impl WindowManager {
             let workspace = self.focused_workspace()?;
             let focused_hwnd = WindowsApi::foreground_window()?;
             if !workspace.contains_managed_window(focused_hwnd) {
-                bail!("ignoring commands while active window is not managed by komorebi");
+                return Err(KomorebiError::NotManaged(focused_hwnd).into());
             }
         }
 
@@ -1168,7 +2068,7 @@ impl WindowManager {
 
         self.monitors_mut()
             .get_mut(idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?
+            .ok_or(KomorebiError::NoSuchMonitor(idx))?
             .update_focused_workspace(offset)
     }
 
@@ -1183,7 +2083,7 @@ impl WindowManager {
             let first_monitor = self
                 .monitors()
                 .get(first_idx)
-                .ok_or_else(|| anyhow!("There is no monitor"))?;
+                .ok_or(KomorebiError::NoSuchMonitor(first_idx))?;
             first_monitor.focused_workspace_idx()
         };
 
@@ -1191,7 +2091,7 @@ impl WindowManager {
             let second_monitor = self
                 .monitors()
                 .get(second_idx)
-                .ok_or_else(|| anyhow!("There is no monitor"))?;
+                .ok_or(KomorebiError::NoSuchMonitor(second_idx))?;
             second_monitor.focused_workspace_idx()
         };
 
@@ -1200,24 +2100,24 @@ impl WindowManager {
         let first_workspaces = self
             .monitors_mut()
             .get_mut(first_idx)
-            .ok_or_else(|| anyhow!("There is no monitor"))?
+            .ok_or(KomorebiError::NoSuchMonitor(first_idx))?
             .remove_workspaces();
 
         let second_workspaces = self
             .monitors_mut()
             .get_mut(second_idx)
-            .ok_or_else(|| anyhow!("There is no monitor"))?
+            .ok_or(KomorebiError::NoSuchMonitor(second_idx))?
             .remove_workspaces();
 
         self.monitors_mut()
             .get_mut(first_idx)
-            .ok_or_else(|| anyhow!("There is no monitor"))?
+            .ok_or(KomorebiError::NoSuchMonitor(first_idx))?
             .workspaces_mut()
             .extend(second_workspaces);
 
         self.monitors_mut()
             .get_mut(second_idx)
-            .ok_or_else(|| anyhow!("There is no monitor"))?
+            .ok_or(KomorebiError::NoSuchMonitor(second_idx))?
             .workspaces_mut()
             .extend(first_workspaces);
 
@@ -1306,7 +2206,7 @@ impl WindowManager {
         let target_monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         if let Some(workspace_idx) = workspace_idx {
             target_monitor.focus_workspace(workspace_idx)?;
@@ -1399,7 +2299,7 @@ impl WindowManager {
             let target_monitor: &mut Monitor = self
                 .monitors_mut()
                 .get_mut(idx)
-                .ok_or_else(|| anyhow!("there is no monitor"))?;
+                .ok_or(KomorebiError::NoSuchMonitor(idx))?;
 
             target_monitor.workspaces_mut().push_back(workspace);
             target_monitor.focus_workspace(target_monitor.workspaces().len().saturating_sub(1))?;
@@ -1496,7 +2396,7 @@ impl WindowManager {
             None => {
                 let monitor_idx = self
                     .monitor_idx_in_direction(direction)
-                    .ok_or_else(|| anyhow!("there is no container or monitor in this direction"))?;
+                    .ok_or(KomorebiError::NothingInDirection(direction))?;
 
                 self.focus_monitor(monitor_idx)?;
                 let mouse_follows_focus = self.mouse_follows_focus;
@@ -1505,9 +2405,6 @@ impl WindowManager {
                     if let Some(monocle) = focused_workspace.monocle_container() {
                         if let Some(window) = monocle.focused_window() {
                             window.focus(mouse_follows_focus)?;
-                            WindowsApi::center_cursor_in_rect(&WindowsApi::window_rect(
-                                window.hwnd,
-                            )?)?;
 
                             cross_monitor_monocle = true;
                         }
@@ -1565,7 +2462,7 @@ impl WindowManager {
         // removing this messes up the monitor / container / window index somewhere
         // and results in the wrong window getting moved across the monitor boundary
         if workspace.is_focused_window_monocle_or_maximized()? {
-            bail!("ignoring command while active window is in monocle mode or maximized");
+            return Err(KomorebiError::MonocleOrMaximized.into());
         }
 
         tracing::info!("moving container");
@@ -1622,7 +2519,7 @@ impl WindowManager {
 
                 let target_monitor_idx = self
                     .monitor_idx_in_direction(direction)
-                    .ok_or_else(|| anyhow!("there is no container or monitor in this direction"))?;
+                    .ok_or(KomorebiError::NothingInDirection(direction))?;
 
                 {
                     // remove the container from the origin monitor workspace
@@ -1777,7 +2674,7 @@ impl WindowManager {
 
                 self.monitors_mut()
                     .get_mut(origin_monitor_idx)
-                    .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+                    .ok_or(KomorebiError::NoSuchMonitor(origin_monitor_idx))?
                     .update_focused_workspace(offset)?;
 
                 let a = self
@@ -1787,7 +2684,7 @@ impl WindowManager {
                 let b = self
                     .monitors_mut()
                     .get_mut(origin_monitor_idx)
-                    .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+                    .ok_or(KomorebiError::NoSuchMonitor(origin_monitor_idx))?
                     .id();
 
                 if !WindowsApi::monitors_have_same_dpi(a, b)? {
@@ -1849,7 +2746,7 @@ impl WindowManager {
 
         let workspace = self.focused_workspace_mut()?;
         if workspace.is_focused_window_monocle_or_maximized()? {
-            bail!("ignoring command while active window is in monocle mode or maximized");
+            return Err(KomorebiError::MonocleOrMaximized.into());
         }
 
         tracing::info!("moving container");
@@ -1864,6 +2761,25 @@ impl WindowManager {
         self.update_focused_workspace(self.mouse_follows_focus, true)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn rotate_containers(&mut self, direction: CycleDirection) -> Result<()> {
+        self.handle_unmanaged_window_behaviour()?;
+
+        let workspace = self.focused_workspace_mut()?;
+        if workspace.is_focused_window_monocle_or_maximized()? {
+            return Err(KomorebiError::MonocleOrMaximized.into());
+        }
+
+        if workspace.containers().len() < 2 {
+            bail!("there must be at least two containers to rotate");
+        }
+
+        tracing::info!("rotating containers");
+
+        workspace.rotate_containers(direction);
+        self.update_focused_workspace(self.mouse_follows_focus, true)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn cycle_container_window_in_direction(&mut self, direction: CycleDirection) -> Result<()> {
         self.handle_unmanaged_window_behaviour()?;
@@ -1995,9 +2911,9 @@ impl WindowManager {
             .is_some();
 
         if is_valid {
-            let new_idx = workspace.new_idx_for_direction(direction).ok_or_else(|| {
-                anyhow!("this is not a valid direction from the current position")
-            })?;
+            let new_idx = workspace
+                .new_idx_for_direction(direction)
+                .ok_or(KomorebiError::NothingInDirection(direction))?;
 
             let adjusted_new_index = if new_idx > current_container_idx
                 && !matches!(
@@ -2055,8 +2971,21 @@ impl WindowManager {
 
         tracing::info!("promoting focus");
 
+        // Resizing can leave a container other than the first one as the largest, which is the
+        // one that is treated as primary; ties go to the container which comes first
         let target_idx = match workspace.layout() {
-            Layout::Default(_) => 0,
+            Layout::Default(_) => workspace
+                .latest_layout()
+                .iter()
+                .take(workspace.containers().len())
+                .enumerate()
+                .max_by_key(|(idx, rect)| {
+                    (
+                        i64::from(rect.right) * i64::from(rect.bottom),
+                        Reverse(*idx),
+                    )
+                })
+                .map_or(0, |(idx, _)| idx),
             Layout::Custom(custom) => custom
                 .first_container_idx(custom.primary_idx().map_or(0, |primary_idx| primary_idx)),
         };
@@ -2110,6 +3039,169 @@ impl WindowManager {
         self.update_focused_workspace(is_floating_window, true)
     }
 
+    #[tracing::instrument(skip(self))]
+    pub fn toggle_float_layer(&mut self) -> Result<()> {
+        let workspace = self.focused_workspace_mut()?;
+        workspace.toggle_floating_windows_hidden();
+
+        let hidden = workspace.floating_windows_hidden();
+        if hidden {
+            tracing::info!("hiding floating windows");
+        } else {
+            tracing::info!("showing floating windows");
+        }
+
+        // A floating window may have had focus, which is handed back to the tiled windows
+        self.update_focused_workspace(false, hidden)
+    }
+
+    /// The focused window, as long as it is floating on the focused workspace
+    pub fn focused_floating_window(&self) -> Result<Window> {
+        let hwnd = WindowsApi::foreground_window()?;
+
+        self.focused_workspace()?
+            .floating_windows()
+            .iter()
+            .find(|window| window.hwnd == hwnd)
+            .copied()
+            .ok_or_else(|| anyhow!("the focused window is not floating"))
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn set_float_rect(&mut self, rect: Rect, monitor_relative: bool) -> Result<()> {
+        let window = self.focused_floating_window()?;
+
+        tracing::info!("setting floating window position");
+
+        let mut rect = rect;
+
+        // The floating window is on the focused workspace, so the focused monitor is its monitor
+        if monitor_relative {
+            let work_area = self.focused_monitor_work_area()?;
+            rect.left += work_area.left;
+            rect.top += work_area.top;
+        }
+
+        window.set_position(&rect, true)
+    }
+
+    #[tracing::instrument(skip(self))]
+    pub fn center_float(&mut self, percentage: Option<u8>) -> Result<()> {
+        let window = self.focused_floating_window()?;
+        let work_area = self.focused_monitor_work_area()?;
+
+        tracing::info!("centering floating window");
+
+        match percentage {
+            None => window.center_over(&work_area),
+            Some(percentage) => {
+                if percentage == 0 || percentage > 100 {
+                    bail!("the percentage of the work area must be between 1 and 100");
+                }
+
+                let width = work_area.right * i32::from(percentage) / 100;
+                let height = work_area.bottom * i32::from(percentage) / 100;
+
+                window.set_position(
+                    &Rect {
+                        left: work_area.left + ((work_area.right - width) / 2),
+                        top: work_area.top + ((work_area.bottom - height) / 2),
+                        right: width,
+                        bottom: height,
+                    },
+                    true,
+                )
+            }
+        }
+    }
+
+    /// Whether the window is floating on the focused workspace of any monitor
+    pub fn is_floating_window(&self, hwnd: isize) -> bool {
+        self.monitors()
+            .iter()
+            .filter_map(Monitor::focused_workspace)
+            .any(|workspace| {
+                workspace
+                    .floating_windows()
+                    .iter()
+                    .any(|window| window.hwnd == hwnd)
+            })
+    }
+
+    /// Snaps a floating window which has been moved with the mouse to the nearest edges of the
+    /// work area and of the other floating windows, or to the grid if no edges are close enough
+    #[tracing::instrument(skip(self))]
+    pub fn snap_floating_window(&mut self, window: Window, rect: Rect) -> Result<()> {
+        let monitor_idx = self
+            .monitor_idx_from_window(window)
+            .ok_or_else(|| anyhow!("there is no monitor associated with this window"))?;
+
+        let work_area = *self
+            .monitors()
+            .get(monitor_idx)
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?
+            .work_area_size();
+
+        let mut targets = vec![work_area];
+        for workspace in self
+            .monitors()
+            .iter()
+            .filter_map(Monitor::focused_workspace)
+        {
+            if workspace.floating_windows_hidden() {
+                continue;
+            }
+
+            for other in workspace.floating_windows() {
+                if other.hwnd != window.hwnd {
+                    if let Ok(other_rect) = WindowsApi::window_rect(other.hwnd) {
+                        targets.push(other_rect);
+                    }
+                }
+            }
+        }
+
+        let distance = FLOAT_SNAPPING_DISTANCE.load(Ordering::SeqCst);
+        let grid = FLOAT_SNAPPING_GRID.load(Ordering::SeqCst);
+
+        let vertical_edges = targets
+            .iter()
+            .flat_map(|target| [target.left, target.left + target.right])
+            .collect::<Vec<_>>();
+        let horizontal_edges = targets
+            .iter()
+            .flat_map(|target| [target.top, target.top + target.bottom])
+            .collect::<Vec<_>>();
+
+        let snapped = Rect {
+            left: snap_span(
+                rect.left,
+                rect.right,
+                &vertical_edges,
+                distance,
+                grid,
+                work_area.left,
+            ),
+            top: snap_span(
+                rect.top,
+                rect.bottom,
+                &horizontal_edges,
+                distance,
+                grid,
+                work_area.top,
+            ),
+            ..rect
+        };
+
+        if snapped == rect {
+            return Ok(());
+        }
+
+        tracing::info!("snapping floating window");
+
+        window.set_position(&snapped, true)
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn float_window(&mut self) -> Result<()> {
         tracing::info!("floating window");
@@ -2124,6 +3216,7 @@ impl WindowManager {
             .last_mut()
             .ok_or_else(|| anyhow!("there is no floating window"))?;
 
+        window.restore_title_bar()?;
         window.center(&work_area)?;
         window.focus(self.mouse_follows_focus)?;
 
@@ -2371,12 +3464,12 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         workspace.set_tile(tile);
 
@@ -2399,7 +3492,7 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let work_area = *monitor.work_area_size();
         let window_based_work_area_offset = (
@@ -2417,7 +3510,7 @@ impl WindowManager {
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         let rules: &mut Vec<(usize, Layout)> = workspace.layout_rules_mut();
         rules.retain(|pair| pair.0 != at_container_count);
@@ -2452,7 +3545,7 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let work_area = *monitor.work_area_size();
         let window_based_work_area_offset = (
@@ -2470,7 +3563,7 @@ impl WindowManager {
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         let layout = CustomLayout::from_path(path)?;
 
@@ -2502,7 +3595,7 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let work_area = *monitor.work_area_size();
         let window_based_work_area_offset = (
@@ -2520,7 +3613,7 @@ impl WindowManager {
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         let rules: &mut Vec<(usize, Layout)> = workspace.layout_rules_mut();
         rules.clear();
@@ -2549,7 +3642,7 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let work_area = *monitor.work_area_size();
         let window_based_work_area_offset = (
@@ -2567,7 +3660,7 @@ impl WindowManager {
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         workspace.set_layout(Layout::Default(layout));
 
@@ -2598,7 +3691,7 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let work_area = *monitor.work_area_size();
         let window_based_work_area_offset = (
@@ -2616,7 +3709,7 @@ impl WindowManager {
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         workspace.set_layout(Layout::Custom(layout));
         workspace.set_layout_flip(None);
@@ -2641,7 +3734,7 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         monitor.ensure_workspace_count(workspace_count);
 
@@ -2659,7 +3752,7 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         monitor.ensure_workspace_count(names.len());
 
@@ -2684,12 +3777,12 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         workspace.set_workspace_padding(Option::from(size));
 
@@ -2708,12 +3801,12 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         workspace.set_name(Option::from(name.clone()));
         monitor.workspace_names_mut().insert(workspace_idx, name);
@@ -2733,12 +3826,12 @@ impl WindowManager {
         let monitor = self
             .monitors_mut()
             .get_mut(monitor_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchMonitor(monitor_idx))?;
 
         let workspace = monitor
             .workspaces_mut()
             .get_mut(workspace_idx)
-            .ok_or_else(|| anyhow!("there is no monitor"))?;
+            .ok_or(KomorebiError::NoSuchWorkspace(workspace_idx))?;
 
         workspace.set_container_padding(Option::from(size));
 
@@ -2766,7 +3859,7 @@ impl WindowManager {
         if self.monitors().get(idx).is_some() {
             self.monitors.focus(idx);
         } else {
-            bail!("this is not a valid monitor index");
+            return Err(KomorebiError::NoSuchMonitor(idx).into());
         }
 
         Ok(())
@@ -2843,14 +3936,14 @@ impl WindowManager {
         Ok(self
             .monitors()
             .get(idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(idx))?
             .focused_workspace_idx())
     }
 
     pub fn focused_workspace_for_monitor_idx(&self, idx: usize) -> Result<&Workspace> {
         self.monitors()
             .get(idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(idx))?
             .focused_workspace()
             .ok_or_else(|| anyhow!("there is no workspace"))
     }
@@ -2858,7 +3951,7 @@ impl WindowManager {
     pub fn focused_workspace_for_monitor_idx_mut(&mut self, idx: usize) -> Result<&mut Workspace> {
         self.monitors_mut()
             .get_mut(idx)
-            .ok_or_else(|| anyhow!("there is no monitor at this index"))?
+            .ok_or(KomorebiError::NoSuchMonitor(idx))?
             .focused_workspace_mut()
             .ok_or_else(|| anyhow!("there is no workspace"))
     }
@@ -2895,6 +3988,15 @@ impl WindowManager {
         None
     }
 
+    /// The names of the workspaces on every monitor, in order
+    pub fn workspace_names(&self) -> Vec<String> {
+        self.monitors()
+            .iter()
+            .flat_map(|monitor| monitor.workspaces())
+            .filter_map(|workspace| workspace.name().clone())
+            .collect()
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn new_workspace(&mut self) -> Result<()> {
         tracing::info!("adding new workspace");
@@ -2928,9 +4030,272 @@ impl WindowManager {
             .ok_or_else(|| anyhow!("there is no window"))
     }
 
+    /// Brings the workspace which contains the window into view, wherever it is, and focuses the
+    /// container holding it
+    #[tracing::instrument(skip(self))]
+    pub fn focus_managed_window(&mut self, hwnd: isize) -> Result<()> {
+        let query = self.window_query(hwnd);
+        let (Some(monitor_idx), Some(workspace_idx)) = (query.monitor_idx, query.workspace_idx)
+        else {
+            bail!("the window {hwnd} is no longer managed");
+        };
+
+        tracing::info!("focusing managed window");
+
+        let focused_pair = (
+            self.focused_monitor_idx(),
+            self.focused_workspace_idx().unwrap_or_default(),
+        );
+
+        if focused_pair != (monitor_idx, workspace_idx) {
+            self.focus_monitor(monitor_idx)?;
+            self.focus_workspace(workspace_idx)?;
+        }
+
+        // Floating, maximized and monocle windows are not in a container of the layout, and are
+        // focused directly below
+        if let Ok(workspace) = self.focused_workspace_mut() {
+            let _ = workspace.focus_container_by_window(hwnd);
+        }
+
+        if WindowsApi::is_window(hwnd) {
+            Window::from(hwnd).focus(self.mouse_follows_focus)?;
+            flash_manager::send_notification(hwnd);
+        }
+
+        border_manager::send_notification(None);
+
+        Ok(())
+    }
+
+    /// Asks every window on the focused workspace to close, or ends the processes which own them
+    /// if `force` is set and they don't have managed windows on any other workspace
+    #[tracing::instrument(skip(self))]
+    pub fn close_workspace_windows(&mut self, force: bool) -> Result<()> {
+        let workspace = self.focused_workspace()?;
+
+        let mut windows = vec![];
+
+        if let Some(window) = workspace.maximized_window() {
+            windows.push(*window);
+        }
+
+        if let Some(container) = workspace.monocle_container() {
+            windows.extend(container.windows().iter().copied());
+        }
+
+        for container in workspace.containers() {
+            windows.extend(container.windows().iter().copied());
+        }
+
+        windows.extend(workspace.floating_windows().iter().copied());
+
+        tracing::info!("closing {} windows on the focused workspace", windows.len());
+
+        // Ending a process which also has managed windows on other workspaces would take those
+        // windows down with it, so the windows of these processes are always closed normally
+        let mut shared_process_ids = HashSet::new();
+
+        if force {
+            let hwnds = windows
+                .iter()
+                .map(|window| window.hwnd)
+                .collect::<HashSet<_>>();
+
+            for hwnd in self.known_hwnds() {
+                if !hwnds.contains(&hwnd) {
+                    shared_process_ids.insert(Window::from(hwnd).application_process_id());
+                }
+            }
+        }
+
+        // An application can have more than one window on the workspace, but its process can
+        // only be ended once
+        let mut terminated = HashSet::new();
+
+        for window in windows {
+            let process_id = window.application_process_id();
+
+            // Ending explorer.exe would take the taskbar and the desktop down with it, so its
+            // windows are always closed normally
+            let terminate = force
+                && !shared_process_ids.contains(&process_id)
+                && !window
+                    .exe()
+                    .is_ok_and(|exe| exe.eq_ignore_ascii_case("explorer.exe"));
+
+            let result = if terminate {
+                if !terminated.insert(process_id) {
+                    continue;
+                }
+
+                WindowsApi::terminate_process(process_id)
+            } else {
+                window.close()
+            };
+
+            // The windows which do close are removed when they are destroyed, so one which
+            // can't be closed doesn't need to stop the rest
+            if let Err(error) = result {
+                tracing::warn!("could not close window {window}: {error}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Focuses a window of the executable on the first workspace which has one, or launches the
+    /// executable if there are none and `launch` is set
+    #[tracing::instrument(skip(self))]
+    pub fn jump_to_exe(&mut self, exe: &str, launch: bool) -> Result<()> {
+        // Executable names given on the command line are typed by hand, so unlike the exe
+        // matching used elsewhere this ignores case
+        let hwnd = self.known_hwnds().into_iter().find(|hwnd| {
+            Window::from(*hwnd)
+                .exe()
+                .is_ok_and(|window_exe| window_exe.eq_ignore_ascii_case(exe))
+        });
+
+        match hwnd {
+            Some(hwnd) => {
+                tracing::info!("jumping to {exe}");
+                self.focus_managed_window(hwnd)
+            }
+            None if launch => {
+                tracing::info!("launching {exe}");
+
+                // start looks the executable up in the same places as the run dialog, which finds
+                // applications that are not on the path
+                Command::new("cmd")
+                    .args(["/C", "start", "", exe])
+                    .creation_flags(CREATE_NO_WINDOW.0)
+                    .spawn()?;
+
+                Ok(())
+            }
+            None => bail!("there is no managed window for {exe}"),
+        }
+    }
+
+    /// Focuses the most recently focused window other than the focused one, even if it is on
+    /// another workspace or monitor; windows which are no longer managed are skipped over
+    #[tracing::instrument(skip(self))]
+    pub fn focus_last(&mut self) -> Result<()> {
+        let focused_hwnd = self.focus_history.front().copied();
+
+        let hwnd = self
+            .focus_history
+            .iter()
+            .copied()
+            .filter(|hwnd| Some(*hwnd) != focused_hwnd)
+            .find(|hwnd| self.window_query(*hwnd).monitor_idx.is_some())
+            .ok_or_else(|| anyhow!("there is no previously focused window"))?;
+
+        tracing::info!("focusing last focused window");
+
+        self.focus_managed_window(hwnd)
+    }
+
+    /// The window on the focused workspace which komorebi considers to be focused, taking
+    /// maximized and monocle windows into account
+    pub fn focused_hwnd(&self) -> Option<isize> {
+        let workspace = self.focused_workspace().ok()?;
+
+        if let Some(window) = workspace.maximized_window() {
+            return Option::from(window.hwnd);
+        }
+
+        if let Some(container) = workspace.monocle_container() {
+            return container.focused_window().map(|window| window.hwnd);
+        }
+
+        self.focused_window().ok().map(|window| window.hwnd)
+    }
+
     fn focused_window_mut(&mut self) -> Result<&mut Window> {
         self.focused_container_mut()?
             .focused_window_mut()
             .ok_or_else(|| anyhow!("there is no window"))
     }
 }
+
+#[cfg(test)]
+impl WindowManager {
+    /// A window manager with a single monitor of the given size, which listens for commands on a
+    /// socket of its own rather than the one used by komorebi, for tests
+    pub fn for_tests(size: Rect) -> Result<Self> {
+        static INSTANCES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        let socket = std::env::temp_dir().join(format!(
+            "komorebi-test-{}-{}.sock",
+            std::process::id(),
+            INSTANCES.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        let (_, incoming) = crossbeam_channel::unbounded();
+        let mut wm = Self::listening_on(incoming, &socket)?;
+
+        let name = String::from("TEST");
+        wm.monitors_mut().push_back(crate::monitor::new(
+            0,
+            size,
+            size,
+            name.clone(),
+            name.clone(),
+            name,
+        ));
+
+        Ok(wm)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rect::rect;
+    use crate::system_api::mock_system_api;
+    use crate::system_api::SystemApi;
+    use crate::INITIAL_CONFIGURATION_LOADED;
+
+    #[test]
+    fn dry_runs_do_not_touch_any_windows() {
+        INITIAL_CONFIGURATION_LOADED.store(true, Ordering::SeqCst);
+
+        let mock = mock_system_api();
+        let hwnds = [3000, 3001, 3002];
+
+        let mut wm = WindowManager::for_tests(rect(0, 0, 1920, 1080)).unwrap();
+        for hwnd in hwnds {
+            mock.add_window(hwnd, rect(0, 0, 100, 100));
+            wm.focused_workspace_mut()
+                .unwrap()
+                .new_container_for_window(Window::from(hwnd));
+        }
+
+        wm.retile_all(false).unwrap();
+        mock.set_foreground_window(3002);
+
+        let windows = hwnds.map(|hwnd| mock.window(hwnd));
+        let cursor = mock.cursor_position();
+
+        let dry_run = wm
+            .dry_run(SocketMessage::ChangeLayout(DefaultLayout::Rows))
+            .unwrap();
+
+        assert!(dry_run
+            .operations
+            .iter()
+            .any(|operation| matches!(operation, WindowOperation::Position { .. })));
+
+        assert_eq!(hwnds.map(|hwnd| mock.window(hwnd)), windows);
+        assert_eq!(mock.foreground_window().unwrap(), 3002);
+        assert_eq!(mock.cursor_position(), cursor);
+
+        assert!(wm.mouse_follows_focus);
+        assert!(wm.pending_notifications.is_empty());
+        assert_eq!(
+            *wm.focused_workspace().unwrap().layout(),
+            Layout::Default(DefaultLayout::BSP)
+        );
+    }
+}
@@ -11,7 +11,7 @@ use crate::winevent::WinEvent;
 use crate::OBJECT_NAME_CHANGE_ON_LAUNCH;
 use crate::REGEX_IDENTIFIERS;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", content = "content")]
 pub enum WindowManagerEvent {
     Destroy(WinEvent, Window),
@@ -28,6 +28,8 @@ pub enum WindowManagerEvent {
     Unmanage(Window),
     Raise(Window),
     TitleUpdate(WinEvent, Window),
+    Flash(Window),
+    RejectedLayout(Window),
 }
 
 impl Display for WindowManagerEvent {
@@ -78,6 +80,12 @@ impl Display for WindowManagerEvent {
             Self::TitleUpdate(winevent, window) => {
                 write!(f, "TitleUpdate (WinEvent: {winevent}, Window: {window})")
             }
+            Self::Flash(window) => {
+                write!(f, "Flash (Window: {window})")
+            }
+            Self::RejectedLayout(window) => {
+                write!(f, "RejectedLayout (Window: {window})")
+            }
         }
     }
 }
@@ -98,7 +106,9 @@ impl WindowManagerEvent {
             | Self::Raise(window)
             | Self::Manage(window)
             | Self::Unmanage(window)
-            | Self::TitleUpdate(_, window) => window,
+            | Self::TitleUpdate(_, window)
+            | Self::Flash(window)
+            | Self::RejectedLayout(window) => window,
         }
     }
 
@@ -122,6 +132,8 @@ impl WindowManagerEvent {
             WindowManagerEvent::Unmanage(_) => "Unmanage",
             WindowManagerEvent::Raise(_) => "Raise",
             WindowManagerEvent::TitleUpdate(_, _) => "TitleUpdate",
+            WindowManagerEvent::Flash(_) => "Flash",
+            WindowManagerEvent::RejectedLayout(_) => "RejectedLayout",
         }
     }
 
@@ -140,7 +152,9 @@ impl WindowManagerEvent {
             | WindowManagerEvent::TitleUpdate(event, _) => Some(event.to_string()),
             WindowManagerEvent::Manage(_)
             | WindowManagerEvent::Unmanage(_)
-            | WindowManagerEvent::Raise(_) => None,
+            | WindowManagerEvent::Raise(_)
+            | WindowManagerEvent::Flash(_)
+            | WindowManagerEvent::RejectedLayout(_) => None,
         }
     }
 
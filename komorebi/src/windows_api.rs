@@ -2,17 +2,22 @@ use std::collections::VecDeque;
 use std::convert::TryFrom;
 use std::ffi::c_void;
 use std::mem::size_of;
+use std::time::Duration;
 
 use color_eyre::eyre::anyhow;
 use color_eyre::eyre::bail;
 use color_eyre::eyre::Error;
 use color_eyre::Result;
 use windows::core::Result as WindowsCrateResult;
+use windows::core::HSTRING;
 use windows::core::PCWSTR;
 use windows::core::PWSTR;
 use windows::Win32::Foundation::CloseHandle;
+use windows::Win32::Foundation::GetLastError;
 use windows::Win32::Foundation::BOOL;
 use windows::Win32::Foundation::COLORREF;
+use windows::Win32::Foundation::ERROR_ALREADY_EXISTS;
+use windows::Win32::Foundation::E_ACCESSDENIED;
 use windows::Win32::Foundation::HANDLE;
 use windows::Win32::Foundation::HINSTANCE;
 use windows::Win32::Foundation::HMODULE;
@@ -20,6 +25,8 @@ use windows::Win32::Foundation::HWND;
 use windows::Win32::Foundation::LPARAM;
 use windows::Win32::Foundation::POINT;
 use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::WAIT_ABANDONED;
+use windows::Win32::Foundation::WAIT_OBJECT_0;
 use windows::Win32::Foundation::WPARAM;
 use windows::Win32::Graphics::Dwm::DwmGetWindowAttribute;
 use windows::Win32::Graphics::Dwm::DwmSetWindowAttribute;
@@ -28,7 +35,10 @@ use windows::Win32::Graphics::Dwm::DWMWA_CLOAKED;
 use windows::Win32::Graphics::Dwm::DWMWA_COLOR_NONE;
 use windows::Win32::Graphics::Dwm::DWMWA_EXTENDED_FRAME_BOUNDS;
 use windows::Win32::Graphics::Dwm::DWMWA_WINDOW_CORNER_PREFERENCE;
+use windows::Win32::Graphics::Dwm::DWMWCP_DEFAULT;
+use windows::Win32::Graphics::Dwm::DWMWCP_DONOTROUND;
 use windows::Win32::Graphics::Dwm::DWMWCP_ROUND;
+use windows::Win32::Graphics::Dwm::DWMWCP_ROUNDSMALL;
 use windows::Win32::Graphics::Dwm::DWMWINDOWATTRIBUTE;
 use windows::Win32::Graphics::Dwm::DWM_CLOAKED_APP;
 use windows::Win32::Graphics::Dwm::DWM_CLOAKED_INHERITED;
@@ -47,33 +57,62 @@ use windows::Win32::Graphics::Gdi::HMONITOR;
 use windows::Win32::Graphics::Gdi::MONITORENUMPROC;
 use windows::Win32::Graphics::Gdi::MONITORINFOEXW;
 use windows::Win32::Graphics::Gdi::MONITOR_DEFAULTTONEAREST;
+use windows::Win32::Security::GetTokenInformation;
+use windows::Win32::Security::TokenElevation;
+use windows::Win32::Security::TOKEN_ELEVATION;
+use windows::Win32::Security::TOKEN_QUERY;
+use windows::Win32::System::Console::FreeConsole;
+use windows::Win32::System::Console::SetConsoleCtrlHandler;
+use windows::Win32::System::Console::PHANDLER_ROUTINE;
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
 use windows::Win32::System::RemoteDesktop::WTSRegisterSessionNotification;
+use windows::Win32::System::Threading::CreateEventW;
+use windows::Win32::System::Threading::CreateMutexW;
 use windows::Win32::System::Threading::GetCurrentProcessId;
+use windows::Win32::System::Threading::OpenEventW;
 use windows::Win32::System::Threading::OpenProcess;
+use windows::Win32::System::Threading::OpenProcessToken;
 use windows::Win32::System::Threading::QueryFullProcessImageNameW;
+use windows::Win32::System::Threading::SetEvent;
+use windows::Win32::System::Threading::TerminateProcess;
+use windows::Win32::System::Threading::WaitForSingleObject;
+use windows::Win32::System::Threading::EVENT_MODIFY_STATE;
+use windows::Win32::System::Threading::INFINITE;
 use windows::Win32::System::Threading::PROCESS_ACCESS_RIGHTS;
 use windows::Win32::System::Threading::PROCESS_NAME_WIN32;
-use windows::Win32::System::Threading::PROCESS_QUERY_INFORMATION;
+use windows::Win32::System::Threading::PROCESS_QUERY_LIMITED_INFORMATION;
+use windows::Win32::System::Threading::PROCESS_TERMINATE;
 use windows::Win32::UI::HiDpi::GetDpiForMonitor;
 use windows::Win32::UI::HiDpi::SetProcessDpiAwarenessContext;
 use windows::Win32::UI::HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2;
 use windows::Win32::UI::HiDpi::MDT_EFFECTIVE_DPI;
+use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
 use windows::Win32::UI::Input::KeyboardAndMouse::GetKeyState;
 use windows::Win32::UI::Input::KeyboardAndMouse::SendInput;
 use windows::Win32::UI::Input::KeyboardAndMouse::INPUT;
 use windows::Win32::UI::Input::KeyboardAndMouse::INPUT_0;
+use windows::Win32::UI::Input::KeyboardAndMouse::INPUT_KEYBOARD;
 use windows::Win32::UI::Input::KeyboardAndMouse::INPUT_MOUSE;
+use windows::Win32::UI::Input::KeyboardAndMouse::KEYBDINPUT;
+use windows::Win32::UI::Input::KeyboardAndMouse::KEYBD_EVENT_FLAGS;
+use windows::Win32::UI::Input::KeyboardAndMouse::KEYEVENTF_KEYUP;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEEVENTF_LEFTDOWN;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEEVENTF_LEFTUP;
 use windows::Win32::UI::Input::KeyboardAndMouse::MOUSEINPUT;
+use windows::Win32::UI::Input::KeyboardAndMouse::VIRTUAL_KEY;
 use windows::Win32::UI::Input::KeyboardAndMouse::VK_LBUTTON;
 use windows::Win32::UI::Input::KeyboardAndMouse::VK_MENU;
+use windows::Win32::UI::Input::KeyboardAndMouse::VK_SHIFT;
 use windows::Win32::UI::WindowsAndMessaging::AllowSetForegroundWindow;
+use windows::Win32::UI::WindowsAndMessaging::BeginDeferWindowPos;
 use windows::Win32::UI::WindowsAndMessaging::BringWindowToTop;
 use windows::Win32::UI::WindowsAndMessaging::CreateWindowExW;
+use windows::Win32::UI::WindowsAndMessaging::DeferWindowPos;
+use windows::Win32::UI::WindowsAndMessaging::EndDeferWindowPos;
 use windows::Win32::UI::WindowsAndMessaging::EnumWindows;
+use windows::Win32::UI::WindowsAndMessaging::FindWindowExW;
+use windows::Win32::UI::WindowsAndMessaging::GetAncestor;
 use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
 use windows::Win32::UI::WindowsAndMessaging::GetDesktopWindow;
 use windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
@@ -83,14 +122,19 @@ use windows::Win32::UI::WindowsAndMessaging::GetWindowLongPtrW;
 use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
 use windows::Win32::UI::WindowsAndMessaging::GetWindowTextW;
 use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+use windows::Win32::UI::WindowsAndMessaging::IsHungAppWindow;
 use windows::Win32::UI::WindowsAndMessaging::IsIconic;
 use windows::Win32::UI::WindowsAndMessaging::IsWindow;
+use windows::Win32::UI::WindowsAndMessaging::IsWindowArranged;
 use windows::Win32::UI::WindowsAndMessaging::IsWindowVisible;
 use windows::Win32::UI::WindowsAndMessaging::IsZoomed;
 use windows::Win32::UI::WindowsAndMessaging::MoveWindow;
 use windows::Win32::UI::WindowsAndMessaging::PostMessageW;
 use windows::Win32::UI::WindowsAndMessaging::RealGetWindowClassW;
 use windows::Win32::UI::WindowsAndMessaging::RegisterClassW;
+use windows::Win32::UI::WindowsAndMessaging::RegisterShellHookWindow;
+use windows::Win32::UI::WindowsAndMessaging::RegisterWindowMessageW;
+use windows::Win32::UI::WindowsAndMessaging::SendMessageTimeoutW;
 use windows::Win32::UI::WindowsAndMessaging::SetCursorPos;
 use windows::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
 use windows::Win32::UI::WindowsAndMessaging::SetLayeredWindowAttributes;
@@ -100,18 +144,24 @@ use windows::Win32::UI::WindowsAndMessaging::ShowWindow;
 use windows::Win32::UI::WindowsAndMessaging::SystemParametersInfoW;
 use windows::Win32::UI::WindowsAndMessaging::WindowFromPoint;
 use windows::Win32::UI::WindowsAndMessaging::CW_USEDEFAULT;
+use windows::Win32::UI::WindowsAndMessaging::GA_ROOT;
 use windows::Win32::UI::WindowsAndMessaging::GWL_EXSTYLE;
 use windows::Win32::UI::WindowsAndMessaging::GWL_STYLE;
 use windows::Win32::UI::WindowsAndMessaging::GW_HWNDNEXT;
+use windows::Win32::UI::WindowsAndMessaging::GW_OWNER;
 use windows::Win32::UI::WindowsAndMessaging::HWND_TOP;
+use windows::Win32::UI::WindowsAndMessaging::HWND_TOPMOST;
 use windows::Win32::UI::WindowsAndMessaging::LWA_ALPHA;
 use windows::Win32::UI::WindowsAndMessaging::LWA_COLORKEY;
+use windows::Win32::UI::WindowsAndMessaging::MINMAXINFO;
 use windows::Win32::UI::WindowsAndMessaging::SET_WINDOW_POS_FLAGS;
 use windows::Win32::UI::WindowsAndMessaging::SHOW_WINDOW_CMD;
+use windows::Win32::UI::WindowsAndMessaging::SMTO_ABORTIFHUNG;
 use windows::Win32::UI::WindowsAndMessaging::SPIF_SENDCHANGE;
 use windows::Win32::UI::WindowsAndMessaging::SPI_GETACTIVEWINDOWTRACKING;
 use windows::Win32::UI::WindowsAndMessaging::SPI_GETFOREGROUNDLOCKTIMEOUT;
 use windows::Win32::UI::WindowsAndMessaging::SPI_SETACTIVEWINDOWTRACKING;
+use windows::Win32::UI::WindowsAndMessaging::SPI_SETACTIVEWNDTRKTIMEOUT;
 use windows::Win32::UI::WindowsAndMessaging::SPI_SETFOREGROUNDLOCKTIMEOUT;
 use windows::Win32::UI::WindowsAndMessaging::SWP_NOMOVE;
 use windows::Win32::UI::WindowsAndMessaging::SWP_NOSIZE;
@@ -125,6 +175,7 @@ use windows::Win32::UI::WindowsAndMessaging::SYSTEM_PARAMETERS_INFO_ACTION;
 use windows::Win32::UI::WindowsAndMessaging::SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS;
 use windows::Win32::UI::WindowsAndMessaging::WINDOW_LONG_PTR_INDEX;
 use windows::Win32::UI::WindowsAndMessaging::WM_CLOSE;
+use windows::Win32::UI::WindowsAndMessaging::WM_GETMINMAXINFO;
 use windows::Win32::UI::WindowsAndMessaging::WNDCLASSW;
 use windows::Win32::UI::WindowsAndMessaging::WNDENUMPROC;
 use windows::Win32::UI::WindowsAndMessaging::WS_DISABLED;
@@ -135,6 +186,7 @@ use windows::Win32::UI::WindowsAndMessaging::WS_EX_TOPMOST;
 use windows::Win32::UI::WindowsAndMessaging::WS_POPUP;
 use windows::Win32::UI::WindowsAndMessaging::WS_SYSMENU;
 
+use crate::core::CornerPreference;
 use crate::core::Rect;
 
 use crate::container::Container;
@@ -410,13 +462,13 @@ impl WindowsApi {
             flags |= SetWindowPosition::NO_Z_ORDER;
         }
 
-        let shadow_rect = Self::shadow_rect(hwnd).unwrap_or_default();
-        let rect = Rect {
-            left: layout.left + shadow_rect.left,
-            top: layout.top + shadow_rect.top,
-            right: layout.right + shadow_rect.right,
-            bottom: layout.bottom + shadow_rect.bottom,
-        };
+        // A window which has stopped responding would otherwise block the caller until it starts
+        // responding again, so the request is posted to the window's thread instead
+        if Self::is_hung_app_window(hwnd.0 as isize) {
+            flags |= SetWindowPosition::ASYNC_WINDOW_POS;
+        }
+
+        let rect = Self::adjust_for_shadow(hwnd, layout);
 
         // Note: earlier code had set HWND_TOPMOST here, but we should not do
         // that. HWND_TOPMOST is a sticky z-order change, rather than a regular
@@ -451,6 +503,36 @@ impl WindowsApi {
         )
     }
 
+    // Bring the window to the top of the Z order without moving, resizing, activating or focusing
+    // it, which is used to stack the windows of a workspace the way they were before it was hidden
+    pub fn restack_window(hwnd: isize) -> Result<()> {
+        let flags = SetWindowPosition::NO_MOVE
+            | SetWindowPosition::NO_SIZE
+            | SetWindowPosition::NO_ACTIVATE;
+
+        Self::set_window_pos(
+            HWND(as_ptr!(hwnd)),
+            &Rect::default(),
+            HWND_TOP,
+            flags.bits(),
+        )
+    }
+
+    // Place the window above all non-topmost windows, where it stays even when other windows are
+    // raised, without activating or focusing it
+    pub fn set_topmost(hwnd: isize) -> Result<()> {
+        let flags = SetWindowPosition::NO_MOVE
+            | SetWindowPosition::NO_SIZE
+            | SetWindowPosition::NO_ACTIVATE;
+
+        Self::set_window_pos(
+            HWND(as_ptr!(hwnd)),
+            &Rect::default(),
+            HWND_TOPMOST,
+            flags.bits(),
+        )
+    }
+
     pub fn set_border_pos(hwnd: isize, layout: &Rect, position: isize) -> Result<()> {
         let flags = { SetWindowPosition::SHOW_WINDOW | SetWindowPosition::NO_ACTIVATE };
         Self::set_window_pos(
@@ -461,6 +543,52 @@ impl WindowsApi {
         )
     }
 
+    /// Positions several windows in a single batch, so that they are all repainted together
+    /// instead of visibly moving one after another; if any one of the windows cannot be
+    /// positioned, none of them are
+    pub fn position_windows(positions: &[(isize, Rect)]) -> Result<()> {
+        let flags = SetWindowPosition::NO_ACTIVATE
+            | SetWindowPosition::NO_SEND_CHANGING
+            | SetWindowPosition::NO_COPY_BITS
+            | SetWindowPosition::FRAME_CHANGED
+            | SetWindowPosition::NO_Z_ORDER;
+
+        let mut hdwp = unsafe { BeginDeferWindowPos(i32::try_from(positions.len())?) }?;
+
+        for (hwnd, layout) in positions {
+            let hwnd = HWND(as_ptr!(*hwnd));
+            let rect = Self::adjust_for_shadow(hwnd, layout);
+
+            hdwp = unsafe {
+                DeferWindowPos(
+                    hdwp,
+                    hwnd,
+                    HWND_TOP,
+                    rect.left,
+                    rect.top,
+                    rect.right,
+                    rect.bottom,
+                    SET_WINDOW_POS_FLAGS(flags.bits()),
+                )
+            }?;
+        }
+
+        unsafe { EndDeferWindowPos(hdwp) }.process()
+    }
+
+    // The painted region of the window will match the layout once the window has been positioned
+    // at the returned rect
+    fn adjust_for_shadow(hwnd: HWND, layout: &Rect) -> Rect {
+        let shadow_rect = Self::shadow_rect(hwnd).unwrap_or_default();
+
+        Rect {
+            left: layout.left + shadow_rect.left,
+            top: layout.top + shadow_rect.top,
+            right: layout.right + shadow_rect.right,
+            bottom: layout.bottom + shadow_rect.bottom,
+        }
+    }
+
     /// set_window_pos calls SetWindowPos without any accounting for Window decorations.
     fn set_window_pos(hwnd: HWND, layout: &Rect, position: HWND, flags: u32) -> Result<()> {
         unsafe {
@@ -575,6 +703,52 @@ impl WindowsApi {
         unsafe { GetWindow(HWND(as_ptr!(hwnd)), GW_HWNDNEXT)? }.process()
     }
 
+    pub fn find_window_ex_w(parent: isize, child_after: isize, class: &str) -> Result<isize> {
+        let class: Vec<u16> = format!("{class}\0").encode_utf16().collect();
+
+        unsafe {
+            FindWindowExW(
+                HWND(as_ptr!(parent)),
+                HWND(as_ptr!(child_after)),
+                PCWSTR(class.as_ptr()),
+                PCWSTR::null(),
+            )?
+        }
+        .process()
+    }
+
+    /// The primary (Shell_TrayWnd) or secondary (Shell_SecondaryTrayWnd) taskbar on a monitor
+    pub fn taskbar_for_monitor(hmonitor: isize) -> Option<isize> {
+        for class in ["Shell_TrayWnd", "Shell_SecondaryTrayWnd"] {
+            let mut child_after = 0;
+
+            while let Ok(hwnd) = Self::find_window_ex_w(0, child_after, class) {
+                if Self::monitor_from_window(hwnd) == hmonitor {
+                    return Some(hwnd);
+                }
+
+                child_after = hwnd;
+            }
+        }
+
+        None
+    }
+
+    pub fn owner_window(hwnd: isize) -> Result<isize> {
+        unsafe { GetWindow(HWND(as_ptr!(hwnd)), GW_OWNER)? }.process()
+    }
+
+    /// All top-level windows, ordered from the top of the Z order to the bottom
+    pub fn z_order() -> Result<Vec<isize>> {
+        let mut hwnds = vec![];
+        Self::enum_windows(
+            Some(windows_callbacks::z_order),
+            &mut hwnds as *mut Vec<isize> as isize,
+        )?;
+
+        Ok(hwnds)
+    }
+
     pub fn alt_tab_windows() -> Result<Vec<Window>> {
         let mut hwnds = vec![];
         Self::enum_windows(
@@ -620,6 +794,10 @@ impl WindowsApi {
     /// the window painted region. The four values in the returned Rect can be
     /// added to a position rect to compute a size for set_window_pos that will
     /// fill the target area, ignoring shadows.
+    ///
+    /// These values are queried from DWM for every window rather than assumed,
+    /// as they differ between Windows versions, DPI scales and applications
+    /// which draw their own non-standard frames.
     fn shadow_rect(hwnd: HWND) -> Result<Rect> {
         let window_rect = Self::window_rect(hwnd.0 as isize)?;
 
@@ -635,6 +813,35 @@ impl WindowsApi {
         })
     }
 
+    /// The minimum width and height which a window will accept as reported in its response to
+    /// WM_GETMINMAXINFO, excluding the invisible borders of the window
+    pub fn minimum_window_size(hwnd: isize) -> Result<(i32, i32)> {
+        let mut info = MINMAXINFO::default();
+
+        let result = unsafe {
+            SendMessageTimeoutW(
+                HWND(as_ptr!(hwnd)),
+                WM_GETMINMAXINFO,
+                WPARAM(0),
+                LPARAM(&mut info as *mut MINMAXINFO as isize),
+                SMTO_ABORTIFHUNG,
+                50,
+                None,
+            )
+        };
+
+        if result.0 == 0 {
+            bail!("could not query the minimum size of window {hwnd}");
+        }
+
+        let shadow_rect = Self::shadow_rect(HWND(as_ptr!(hwnd))).unwrap_or_default();
+
+        Ok((
+            (info.ptMinTrackSize.x - shadow_rect.right).max(0),
+            (info.ptMinTrackSize.y - shadow_rect.bottom).max(0),
+        ))
+    }
+
     pub fn round_rect(hdc: HDC, rect: &Rect, border_radius: i32) {
         unsafe {
             // TODO: error handling
@@ -674,6 +881,10 @@ impl WindowsApi {
         Self::window_from_point(Self::cursor_pos()?)
     }
 
+    pub fn root_window(hwnd: isize) -> Result<isize> {
+        unsafe { GetAncestor(HWND(as_ptr!(hwnd)), GA_ROOT) }.process()
+    }
+
     pub fn center_cursor_in_rect(rect: &Rect) -> Result<()> {
         Self::set_cursor_pos(rect.left + (rect.right / 2), rect.top + (rect.bottom / 2))
     }
@@ -698,14 +909,19 @@ impl WindowsApi {
     }
 
     pub fn process_id_to_session_id() -> Result<u32> {
-        let process_id = Self::current_process_id();
+        Self::session_id_for_process(Self::current_process_id())
+    }
+
+    pub fn session_id_for_process(process_id: u32) -> Result<u32> {
         let mut session_id = 0;
 
         unsafe {
             if ProcessIdToSessionId(process_id, &mut session_id).is_ok() {
                 Ok(session_id)
             } else {
-                Err(anyhow!("could not determine current session id"))
+                Err(anyhow!(
+                    "could not determine session id of process {process_id}"
+                ))
             }
         }
     }
@@ -815,8 +1031,89 @@ impl WindowsApi {
         unsafe { CloseHandle(handle) }.process()
     }
 
+    pub fn terminate_process(process_id: u32) -> Result<()> {
+        let handle = Self::open_process(PROCESS_TERMINATE, false, process_id)?;
+        let result = unsafe { TerminateProcess(handle, 1) }.process();
+        Self::close_process(handle)?;
+
+        result
+    }
+
     pub fn process_handle(process_id: u32) -> Result<HANDLE> {
-        Self::open_process(PROCESS_QUERY_INFORMATION, false, process_id)
+        // Limited information is enough to query the image name, and unlike full information it
+        // can also be obtained for processes which are running elevated
+        Self::open_process(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id)
+    }
+
+    /// Creates a named mutex owned by the calling thread, also returning whether a mutex with this
+    /// name already existed, in which case it is owned by whichever process created it first
+    pub fn create_mutex(name: &str) -> Result<(isize, bool)> {
+        let handle = unsafe { CreateMutexW(None, true, &HSTRING::from(name)) }.process()?;
+        let already_exists = unsafe { GetLastError() } == ERROR_ALREADY_EXISTS;
+
+        Ok((handle.0 as isize, already_exists))
+    }
+
+    /// Creates a named auto-reset event which other processes can signal with [`Self::set_event`]
+    pub fn create_event(name: &str) -> Result<isize> {
+        let handle = unsafe { CreateEventW(None, false, false, &HSTRING::from(name)) }.process()?;
+        Ok(handle.0 as isize)
+    }
+
+    pub fn set_event(name: &str) -> Result<()> {
+        let handle =
+            unsafe { OpenEventW(EVENT_MODIFY_STATE, false, &HSTRING::from(name)) }.process()?;
+
+        let result = unsafe { SetEvent(handle) };
+        Self::close_process(handle)?;
+
+        result.process()
+    }
+
+    /// Waits for a mutex or an event to be signalled, returning false if the timeout elapsed
+    /// first; a mutex abandoned by a process which exited while owning it counts as signalled
+    pub fn wait_for_object(handle: isize, timeout: Option<Duration>) -> bool {
+        let milliseconds = timeout.map_or(INFINITE, |timeout| {
+            u32::try_from(timeout.as_millis()).unwrap_or(INFINITE)
+        });
+
+        let result = unsafe { WaitForSingleObject(HANDLE(as_ptr!(handle)), milliseconds) };
+
+        result == WAIT_OBJECT_0 || result == WAIT_ABANDONED
+    }
+
+    /// Whether a process is running elevated, or `None` if this cannot be determined because the
+    /// token of the process cannot be opened, as is the case for protected processes
+    pub fn is_process_elevated(process_id: u32) -> Result<Option<bool>> {
+        let handle = Self::process_handle(process_id)?;
+        let mut token = HANDLE::default();
+
+        let opened = unsafe { OpenProcessToken(handle, TOKEN_QUERY, &mut token) };
+        Self::close_process(handle)?;
+
+        match opened {
+            Ok(()) => {}
+            Err(error) if error.code() == E_ACCESSDENIED => return Ok(None),
+            Err(error) => return Err(error.into()),
+        }
+
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut len = 0;
+
+        let result = unsafe {
+            GetTokenInformation(
+                token,
+                TokenElevation,
+                Some((&mut elevation as *mut TOKEN_ELEVATION).cast()),
+                u32::try_from(std::mem::size_of::<TOKEN_ELEVATION>())?,
+                &mut len,
+            )
+        };
+
+        Self::close_process(token)?;
+        result?;
+
+        Ok(Some(elevation.TokenIsElevated != 0))
     }
 
     pub fn exe_path(handle: HANDLE) -> Result<String> {
@@ -872,10 +1169,9 @@ impl WindowsApi {
         let mut cloaked: u32 = 0;
         Self::dwm_get_window_attribute(hwnd, DWMWA_CLOAKED, &mut cloaked)?;
 
-        Ok(matches!(
-            cloaked,
-            DWM_CLOAKED_APP | DWM_CLOAKED_SHELL | DWM_CLOAKED_INHERITED
-        ))
+        // The cloaked attribute is a bitmask, and windows such as suspended UWP applications or
+        // windows on other virtual desktops are often cloaked for more than one reason at once
+        Ok(cloaked & (DWM_CLOAKED_APP | DWM_CLOAKED_SHELL | DWM_CLOAKED_INHERITED) != 0)
     }
 
     pub fn is_window(hwnd: isize) -> bool {
@@ -894,6 +1190,16 @@ impl WindowsApi {
         unsafe { IsZoomed(HWND(as_ptr!(hwnd))) }.into()
     }
 
+    /// Whether the application which owns the window has stopped responding to messages
+    pub fn is_hung_app_window(hwnd: isize) -> bool {
+        unsafe { IsHungAppWindow(HWND(as_ptr!(hwnd))) }.into()
+    }
+
+    /// Whether the window has been snapped by the system, eg. with Win+Arrow or a snap layout
+    pub fn is_window_arranged(hwnd: isize) -> bool {
+        unsafe { IsWindowArranged(HWND(as_ptr!(hwnd))) }.into()
+    }
+
     pub fn monitor_info_w(hmonitor: HMONITOR) -> Result<MONITORINFOEXW> {
         let mut ex_info = MONITORINFOEXW::default();
         ex_info.monitorInfo.cbSize = u32::try_from(std::mem::size_of::<MONITORINFOEXW>())?;
@@ -1006,7 +1312,15 @@ impl WindowsApi {
     }
 
     #[allow(dead_code)]
-    pub fn enable_focus_follows_mouse() -> Result<()> {
+    pub fn enable_focus_follows_mouse(delay: u64) -> Result<()> {
+        // The timeout is passed as the value of the pointer rather than through it
+        Self::system_parameters_info_w(
+            SPI_SETACTIVEWNDTRKTIMEOUT,
+            0,
+            usize::try_from(delay)? as *mut c_void,
+            SPIF_SENDCHANGE,
+        )?;
+
         Self::system_parameters_info_w(
             SPI_SETACTIVEWINDOWTRACKING,
             0,
@@ -1076,6 +1390,25 @@ impl WindowsApi {
         .process()
     }
 
+    pub fn set_corner_preference(hwnd: isize, preference: CornerPreference) -> Result<()> {
+        let preference = match preference {
+            CornerPreference::Default => DWMWCP_DEFAULT,
+            CornerPreference::DoNotRound => DWMWCP_DONOTROUND,
+            CornerPreference::Round => DWMWCP_ROUND,
+            CornerPreference::RoundSmall => DWMWCP_ROUNDSMALL,
+        };
+
+        unsafe {
+            DwmSetWindowAttribute(
+                HWND(as_ptr!(hwnd)),
+                DWMWA_WINDOW_CORNER_PREFERENCE,
+                std::ptr::addr_of!(preference).cast(),
+                4,
+            )
+        }
+        .process()
+    }
+
     pub fn set_window_accent(hwnd: isize, color: Option<u32>) -> Result<()> {
         let col_ref = COLORREF(color.unwrap_or(DWMWA_COLOR_NONE));
         unsafe {
@@ -1159,6 +1492,13 @@ impl WindowsApi {
         actual != 0
     }
 
+    pub fn shift_is_pressed() -> bool {
+        let state = unsafe { GetKeyState(i32::from(VK_SHIFT.0)) };
+        #[allow(clippy::cast_sign_loss)]
+        let actual = (state as u16) & 0x8000;
+        actual != 0
+    }
+
     pub fn lbutton_is_pressed() -> bool {
         let state = unsafe { GetKeyState(i32::from(VK_LBUTTON.0)) };
         #[allow(clippy::cast_sign_loss)]
@@ -1166,6 +1506,53 @@ impl WindowsApi {
         actual != 0
     }
 
+    /// Unlike the other key state checks, this reflects the physical state of the key regardless of
+    /// which thread is asking, which is what hooks running on their own threads need
+    pub fn key_is_held(key: VIRTUAL_KEY) -> bool {
+        let state = unsafe { GetAsyncKeyState(i32::from(key.0)) };
+        #[allow(clippy::cast_sign_loss)]
+        let actual = (state as u16) & 0x8000;
+        actual != 0
+    }
+
+    /// Presses and releases a virtual key which is not assigned to anything, so that a modifier
+    /// which is released afterwards is not treated as having been tapped on its own
+    pub fn press_unassigned_key() -> u32 {
+        let key = VIRTUAL_KEY(0xE8);
+
+        let inputs = [
+            INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: key,
+                        wScan: 0,
+                        dwFlags: KEYBD_EVENT_FLAGS(0),
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            },
+            INPUT {
+                r#type: INPUT_KEYBOARD,
+                Anonymous: INPUT_0 {
+                    ki: KEYBDINPUT {
+                        wVk: key,
+                        wScan: 0,
+                        dwFlags: KEYEVENTF_KEYUP,
+                        time: 0,
+                        dwExtraInfo: 0,
+                    },
+                },
+            },
+        ];
+
+        #[allow(clippy::cast_possible_wrap, clippy::cast_possible_truncation)]
+        unsafe {
+            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32)
+        }
+    }
+
     pub fn left_click() -> u32 {
         let inputs = [
             INPUT {
@@ -1202,7 +1589,30 @@ impl WindowsApi {
         }
     }
 
+    pub fn free_console() -> Result<()> {
+        unsafe { FreeConsole() }.process()
+    }
+
+    pub fn set_console_ctrl_handler(handler: PHANDLER_ROUTINE) -> Result<()> {
+        unsafe { SetConsoleCtrlHandler(handler, true) }.process()
+    }
+
     pub fn wts_register_session_notification(hwnd: isize) -> Result<()> {
         unsafe { WTSRegisterSessionNotification(HWND(as_ptr!(hwnd)), 1) }.process()
     }
+
+    pub fn register_shell_hook_window(hwnd: isize) -> Result<()> {
+        unsafe { RegisterShellHookWindow(HWND(as_ptr!(hwnd))) }
+            .ok()
+            .process()
+    }
+
+    pub fn register_window_message_w(message: &str) -> Result<u32> {
+        let message: Vec<u16> = format!("{message}\0").encode_utf16().collect();
+
+        match unsafe { RegisterWindowMessageW(PCWSTR(message.as_ptr())) } {
+            0 => Err(std::io::Error::last_os_error().into()),
+            id => Ok(id),
+        }
+    }
 }
@@ -3,9 +3,14 @@ use std::collections::VecDeque;
 use windows::Win32::Foundation::BOOL;
 use windows::Win32::Foundation::HWND;
 use windows::Win32::Foundation::LPARAM;
+use windows::Win32::System::Console::CTRL_CLOSE_EVENT;
+use windows::Win32::System::Console::CTRL_LOGOFF_EVENT;
+use windows::Win32::System::Console::CTRL_SHUTDOWN_EVENT;
 use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
 
 use crate::container::Container;
+use crate::location_change_manager;
+use crate::shutdown;
 use crate::window::RuleDebug;
 use crate::window::Window;
 use crate::window_manager_event::WindowManagerEvent;
@@ -19,9 +24,10 @@ pub extern "system" fn enum_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
     let is_visible = WindowsApi::is_window_visible(hwnd.0 as isize);
     let is_window = WindowsApi::is_window(hwnd.0 as isize);
     let is_minimized = WindowsApi::is_iconic(hwnd.0 as isize);
+    let is_cloaked = WindowsApi::is_window_cloaked(hwnd.0 as isize).unwrap_or_default();
     let is_maximized = WindowsApi::is_zoomed(hwnd.0 as isize);
 
-    if is_visible && is_window && !is_minimized {
+    if is_visible && is_window && !is_minimized && !is_cloaked {
         let window = Window::from(hwnd);
 
         if let Ok(should_manage) = window.should_manage(None, &mut RuleDebug::default()) {
@@ -60,6 +66,13 @@ pub extern "system" fn alt_tab_windows(hwnd: HWND, lparam: LPARAM) -> BOOL {
     true.into()
 }
 
+pub extern "system" fn z_order(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    let hwnds = unsafe { &mut *(lparam.0 as *mut Vec<isize>) };
+    hwnds.push(hwnd.0 as isize);
+
+    true.into()
+}
+
 pub extern "system" fn win_event_hook(
     _h_win_event_hook: HWINEVENTHOOK,
     event: u32,
@@ -74,6 +87,8 @@ pub extern "system" fn win_event_hook(
         return;
     }
 
+    winevent_listener::record_event();
+
     let window = Window::from(hwnd);
 
     let winevent = match WinEvent::try_from(event) {
@@ -81,6 +96,21 @@ pub extern "system" fn win_event_hook(
         Err(_) => return,
     };
 
+    // Cached metadata is invalidated here rather than in the event loop so that it never outlives
+    // the window or its title, even while events are being ignored or coalesced
+    match winevent {
+        WinEvent::ObjectNameChange => Window::invalidate_cached_title(window.hwnd),
+        WinEvent::ObjectDestroy => Window::invalidate_cached_metadata(window.hwnd),
+        _ => {}
+    }
+
+    // Location changes are far too frequent to go through the main event loop, and are only of
+    // interest for managed windows which have resized themselves out of their layout
+    if matches!(winevent, WinEvent::ObjectLocationChange) {
+        location_change_manager::send_notification(window.hwnd);
+        return;
+    }
+
     let event_type = match WindowManagerEvent::from_win_event(winevent, window) {
         None => {
             tracing::trace!(
@@ -100,3 +130,19 @@ pub extern "system" fn win_event_hook(
         .send(event_type)
         .expect("could not send message on winevent_listener::event_tx");
 }
+
+pub extern "system" fn console_ctrl_handler(ctrl_type: u32) -> BOOL {
+    // The system terminates the process as soon as this handler returns for any of these, so it
+    // has to hold on until the main thread has restored all hidden windows
+    let reason = match ctrl_type {
+        CTRL_CLOSE_EVENT => "console close event",
+        CTRL_LOGOFF_EVENT => "logoff event",
+        CTRL_SHUTDOWN_EVENT => "shutdown event",
+        // Ctrl-C and Ctrl-Break are passed on to the next handler
+        _ => return false.into(),
+    };
+
+    shutdown::send_notification_and_wait(reason);
+
+    true.into()
+}
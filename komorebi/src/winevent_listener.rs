@@ -1,17 +1,28 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::OnceLock;
 use std::time::Duration;
 
 use crossbeam_channel::Receiver;
 use crossbeam_channel::Sender;
 use windows::Win32::Foundation::HWND;
+use windows::Win32::Foundation::LPARAM;
+use windows::Win32::Foundation::WPARAM;
+use windows::Win32::System::Threading::GetCurrentThreadId;
 use windows::Win32::UI::Accessibility::SetWinEventHook;
+use windows::Win32::UI::Accessibility::UnhookWinEvent;
 use windows::Win32::UI::WindowsAndMessaging::DispatchMessageW;
 use windows::Win32::UI::WindowsAndMessaging::GetMessageW;
+use windows::Win32::UI::WindowsAndMessaging::PostThreadMessageW;
 use windows::Win32::UI::WindowsAndMessaging::TranslateMessage;
 use windows::Win32::UI::WindowsAndMessaging::EVENT_MAX;
 use windows::Win32::UI::WindowsAndMessaging::EVENT_MIN;
 use windows::Win32::UI::WindowsAndMessaging::MSG;
+use windows::Win32::UI::WindowsAndMessaging::WM_QUIT;
 
+use crate::unix_timestamp_millis;
 use crate::window_manager_event::WindowManagerEvent;
 use crate::windows_callbacks;
 
@@ -19,11 +30,16 @@ static CHANNEL: OnceLock<(Sender<WindowManagerEvent>, Receiver<WindowManagerEven
     OnceLock::new();
 
 static EVENT_PUMP: OnceLock<std::thread::JoinHandle<()>> = OnceLock::new();
+static EVENT_PUMP_THREAD_ID: AtomicU32 = AtomicU32::new(0);
+static HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+static LAST_EVENT_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
 
 pub fn start() {
     EVENT_PUMP.get_or_init(|| {
         std::thread::spawn(move || {
-            unsafe {
+            EVENT_PUMP_THREAD_ID.store(unsafe { GetCurrentThreadId() }, Ordering::SeqCst);
+
+            let hook = unsafe {
                 SetWinEventHook(
                     EVENT_MIN,
                     EVENT_MAX,
@@ -35,6 +51,8 @@ pub fn start() {
                 )
             };
 
+            HOOK_INSTALLED.store(!hook.0.is_null(), Ordering::SeqCst);
+
             let mut msg: MSG = MSG::default();
 
             loop {
@@ -50,10 +68,60 @@ pub fn start() {
 
                 std::thread::sleep(Duration::from_millis(10))
             }
+
+            // Hooks have to be removed from the same thread that they were installed on
+            if !unsafe { UnhookWinEvent(hook) }.as_bool() {
+                tracing::warn!("could not remove windows event hook");
+            }
+
+            HOOK_INSTALLED.store(false, Ordering::SeqCst);
         })
     });
 }
 
+/// Stops the windows event processing thread and removes the windows event hook
+pub fn stop() {
+    let thread_id = EVENT_PUMP_THREAD_ID.load(Ordering::SeqCst);
+    if thread_id != 0 {
+        if let Err(error) =
+            unsafe { PostThreadMessageW(thread_id, WM_QUIT, WPARAM::default(), LPARAM::default()) }
+        {
+            tracing::warn!("could not stop windows event processing thread: {error}");
+            return;
+        }
+
+        // Give the thread a moment to leave its message loop and remove the hook
+        for _ in 0..50 {
+            if EVENT_PUMP
+                .get()
+                .map_or(true, std::thread::JoinHandle::is_finished)
+            {
+                break;
+            }
+
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
+
+/// Whether the windows event hook is installed and the thread which receives its events is running
+pub fn is_hook_installed() -> bool {
+    HOOK_INSTALLED.load(Ordering::SeqCst)
+        && EVENT_PUMP.get().is_some_and(|thread| !thread.is_finished())
+}
+
+pub fn record_event() {
+    LAST_EVENT_TIMESTAMP.store(unix_timestamp_millis(), Ordering::Relaxed);
+}
+
+/// Unix timestamp in milliseconds of the last event received from the windows event hook
+pub fn last_event_timestamp() -> Option<u64> {
+    match LAST_EVENT_TIMESTAMP.load(Ordering::Relaxed) {
+        0 => None,
+        timestamp => Some(timestamp),
+    }
+}
+
 fn channel() -> &'static (Sender<WindowManagerEvent>, Receiver<WindowManagerEvent>) {
     CHANNEL.get_or_init(|| crossbeam_channel::bounded(20))
 }
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::num::NonZeroUsize;
 use std::sync::atomic::Ordering;
@@ -12,6 +14,7 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::core::arrangement;
 use crate::core::Axis;
 use crate::core::CustomLayout;
 use crate::core::CycleDirection;
@@ -20,13 +23,16 @@ use crate::core::Layout;
 use crate::core::OperationDirection;
 use crate::core::Rect;
 
+use crate::animation;
 use crate::border_manager::BORDER_OFFSET;
 use crate::border_manager::BORDER_WIDTH;
 use crate::container::Container;
+use crate::metrics;
 use crate::ring::Ring;
 use crate::stackbar_manager;
 use crate::stackbar_manager::STACKBAR_TAB_HEIGHT;
 use crate::static_config::WorkspaceConfig;
+use crate::system_api::system_api;
 use crate::window::Window;
 use crate::window::WindowDetails;
 use crate::windows_api::WindowsApi;
@@ -34,7 +40,6 @@ use crate::WindowContainerBehaviour;
 use crate::DEFAULT_CONTAINER_PADDING;
 use crate::DEFAULT_WORKSPACE_PADDING;
 use crate::INITIAL_CONFIGURATION_LOADED;
-use crate::NO_TITLEBAR;
 use crate::REMOVE_TITLEBARS;
 
 #[allow(clippy::struct_field_names)]
@@ -88,6 +93,22 @@ pub struct Workspace {
     window_container_behaviour: Option<WindowContainerBehaviour>,
     #[getset(get = "pub", get_mut = "pub", set = "pub")]
     float_override: Option<bool>,
+    // Set while the floating windows have been hidden with toggle-float-layer, which keeps them
+    // hidden when the workspace is restored
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    floating_windows_hidden: bool,
+    #[serde(skip)]
+    z_order: Vec<isize>,
+    // The layout last applied to each tiled window, so that windows which have not moved since
+    // they were given the same layout do not have to be positioned again
+    #[serde(skip)]
+    applied_layouts: HashMap<isize, Rect>,
+    // Set when the workspace is changed while it is hidden, so that its layout is only worked out
+    // and applied once it is shown again
+    #[serde(skip)]
+    #[getset(get_copy = "pub", set = "pub")]
+    dirty: bool,
 }
 
 impl_ring_elements!(Workspace, Container);
@@ -113,6 +134,10 @@ impl Default for Workspace {
             apply_window_based_work_area_offset: true,
             window_container_behaviour: None,
             float_override: None,
+            floating_windows_hidden: false,
+            z_order: vec![],
+            applied_layouts: HashMap::new(),
+            dirty: false,
         }
     }
 }
@@ -181,6 +206,30 @@ impl Workspace {
     }
 
     pub fn hide(&mut self, omit: Option<isize>) {
+        // Hidden and minimized windows lose their place in the Z order, so it is recorded here to
+        // be reapplied when the workspace is restored; tiled windows never overlap each other, so
+        // this only matters for workspaces with floating or maximized windows on top of them
+        self.z_order.clear();
+
+        let has_overlapping_windows = self.maximized_window().is_some()
+            || (!self.floating_windows_hidden && !self.floating_windows().is_empty());
+
+        if has_overlapping_windows {
+            if let Ok(z_order) = WindowsApi::z_order() {
+                let hwnds = self
+                    .visible_windows()
+                    .into_iter()
+                    .flatten()
+                    .map(|window| window.hwnd)
+                    .collect::<HashSet<_>>();
+
+                self.z_order = z_order
+                    .into_iter()
+                    .filter(|hwnd| hwnds.contains(hwnd))
+                    .collect();
+            }
+        }
+
         for window in self.floating_windows_mut().iter_mut().rev() {
             let mut should_hide = omit.is_none();
 
@@ -236,15 +285,32 @@ impl Workspace {
             container.focus_window(container.focused_window_idx());
         }
 
-        for window in self.floating_windows() {
-            window.restore();
+        if !self.floating_windows_hidden {
+            for window in self.floating_windows() {
+                window.restore();
+            }
+        }
+
+        // Windows are raised from the bottom of the recorded Z order up, so that they are stacked
+        // the same way they were when the workspace was hidden
+        let z_order = std::mem::take(&mut self.z_order);
+        for hwnd in z_order.iter().rev() {
+            if let Err(error) = system_api().raise_window(*hwnd) {
+                tracing::debug!("could not restore z-order of window {hwnd}: {error}");
+            }
         }
 
         // Do this here to make sure that an error doesn't stop the restoration of other windows
         // Maximised windows and floating windows should always be drawn at the top of the Z order
-        // when switching to a workspace
+        // when switching to a workspace, unless the focused container was on top of them when
+        // the workspace was hidden
         if let Some(window) = to_focus {
-            if self.maximized_window().is_none() && self.floating_windows().is_empty() {
+            let was_on_top = z_order.first() == Some(&window.hwnd);
+
+            if was_on_top
+                || (self.maximized_window().is_none()
+                    && (self.floating_windows().is_empty() || self.floating_windows_hidden))
+            {
                 window.focus(mouse_follows_focus)?;
             }
         }
@@ -252,6 +318,26 @@ impl Workspace {
         Ok(())
     }
 
+    /// Forgets where the tiled windows were last placed, so that every one of them is positioned
+    /// again the next time the workspace is updated, even if it looks like it is already in place
+    pub fn forget_applied_layouts(&mut self) {
+        self.applied_layouts.clear();
+    }
+
+    /// Hides or shows all of the floating windows at once without moving them, so that they come
+    /// back exactly where they were
+    pub fn toggle_floating_windows_hidden(&mut self) {
+        self.floating_windows_hidden = !self.floating_windows_hidden;
+
+        for window in self.floating_windows() {
+            if self.floating_windows_hidden {
+                window.hide();
+            } else {
+                window.restore();
+            }
+        }
+    }
+
     pub fn update(
         &mut self,
         work_area: &Rect,
@@ -262,6 +348,9 @@ impl Workspace {
             return Ok(());
         }
 
+        metrics::record_retile();
+        self.dirty = false;
+
         let (window_based_work_area_offset_limit, window_based_work_area_offset) =
             window_based_work_area_offset;
 
@@ -332,6 +421,7 @@ impl Workspace {
                         adjusted_work_area.add_padding(width);
                     }
                     window.set_position(&adjusted_work_area, true)?;
+                    window.apply_corner_preference(false);
                 };
             } else if let Some(window) = self.maximized_window_mut() {
                 window.maximize();
@@ -348,11 +438,23 @@ impl Workspace {
                     self.resize_dimensions(),
                 );
 
+                let minimum_sizes = self
+                    .containers()
+                    .iter()
+                    .map(|container| container.focused_window().and_then(|w| w.minimum_size()))
+                    .collect::<Vec<_>>();
+
+                arrangement::respect_minimum_sizes(
+                    &mut layouts,
+                    &minimum_sizes,
+                    self.container_padding().unwrap_or_default(),
+                );
+
                 let should_remove_titlebars = REMOVE_TITLEBARS.load(Ordering::SeqCst);
-                let no_titlebar = NO_TITLEBAR.lock().clone();
 
                 let container_padding = self.container_padding().unwrap_or(0);
                 let containers = self.containers_mut();
+                let mut positions = vec![];
 
                 for (i, container) in containers.iter_mut().enumerate() {
                     let window_count = container.windows().len();
@@ -360,16 +462,18 @@ impl Workspace {
                     if let (Some(window), Some(layout)) =
                         (container.focused_window_mut(), layouts.get_mut(i))
                     {
-                        if should_remove_titlebars && no_titlebar.contains(&window.exe()?) {
-                            window.remove_title_bar()?;
-                        } else if no_titlebar.contains(&window.exe()?) {
-                            window.add_title_bar()?;
+                        if window.matches_no_titlebar() {
+                            if should_remove_titlebars {
+                                window.remove_title_bar()?;
+                            } else {
+                                window.add_title_bar()?;
+                            }
                         }
 
                         // If a window has been unmaximized via toggle-maximize, this block
                         // will make sure that it is unmaximized via restore_window
                         if window.is_maximized() && !managed_maximized_window {
-                            WindowsApi::restore_window(window.hwnd);
+                            system_api().restore_window(window.hwnd);
                         }
 
                         {
@@ -388,14 +492,48 @@ impl Workspace {
                             layout.bottom -= total_height;
                         }
 
-                        window.set_position(layout, false)?;
+                        positions.push((*window, *layout));
+                        window.apply_corner_preference(false);
                     }
                 }
 
+                let tiled_hwnds = positions
+                    .iter()
+                    .map(|(window, _)| window.hwnd)
+                    .collect::<HashSet<_>>();
+
+                self.applied_layouts
+                    .retain(|hwnd, _| tiled_hwnds.contains(hwnd));
+
+                // Animated windows are still on their way when this returns, so what they end up
+                // with cannot be known here
+                if animation::should_animate() {
+                    self.applied_layouts.clear();
+                    Window::set_positions(&positions)?;
+                } else {
+                    positions.retain(|(window, layout)| {
+                        self.applied_layouts.get(&window.hwnd) != Some(layout)
+                            || !window.is_where_positioned()
+                    });
+
+                    // Where the windows end up is only known once the positions have actually
+                    // been applied, which can be after the window manager lock is released
+                    for (window, layout) in &positions {
+                        window.forget_position();
+                        self.applied_layouts.insert(window.hwnd, *layout);
+                    }
+
+                    Window::set_positions(&positions)?;
+                }
+
                 self.set_latest_layout(layouts);
             }
         }
 
+        for window in self.floating_windows() {
+            window.apply_corner_preference(true);
+        }
+
         // Always make sure that the length of the resize dimensions vec is the same as the
         // number of layouts / containers. This should never actually truncate as the remove_window
         // function takes care of cleaning up resize dimensions when destroying empty containers
@@ -461,8 +599,7 @@ impl Workspace {
             }
         }
 
-        self.containers_mut()
-            .retain(|c| !container_ids.contains(c.id()));
+        self.containers.retain(|c| !container_ids.contains(c.id()));
 
         if remove_monocle {
             self.set_monocle_container(None);
@@ -581,7 +718,7 @@ impl Workspace {
     }
 
     pub fn is_focused_window_monocle_or_maximized(&self) -> Result<bool> {
-        let hwnd = WindowsApi::foreground_window()?;
+        let hwnd = system_api().foreground_window()?;
         if let Some(window) = self.maximized_window() {
             if hwnd == window.hwnd {
                 return Ok(true);
@@ -597,6 +734,31 @@ impl Workspace {
         Ok(false)
     }
 
+    /// A human readable name for the active layout of this workspace
+    pub fn layout_name(&self) -> String {
+        if !*self.tile() {
+            return String::from("Floating");
+        }
+
+        match self.layout() {
+            Layout::Default(layout) => layout.to_string(),
+            Layout::Custom(_) => String::from("Custom"),
+        }
+    }
+
+    /// The window which would be focused if this workspace were the focused workspace
+    pub fn focused_window(&self) -> Option<&Window> {
+        if let Some(container) = self.monocle_container() {
+            return container.focused_window();
+        }
+
+        if let Some(window) = self.maximized_window() {
+            return Option::from(window);
+        }
+
+        self.focused_container().and_then(Container::focused_window)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.containers().is_empty()
             && self.maximized_window().is_none()
@@ -655,6 +817,19 @@ impl Workspace {
         Ok(())
     }
 
+    /// Moves a container to another index, shifting the containers in between towards the index
+    /// that it was moved from
+    pub fn move_container(&mut self, from: usize, to: usize) -> Result<()> {
+        let container = self
+            .containers_mut()
+            .remove(from)
+            .ok_or_else(|| anyhow!("there is no container at this index"))?;
+
+        self.insert_container_at_idx(to, container);
+
+        Ok(())
+    }
+
     pub fn add_container_to_back(&mut self, container: Container) {
         self.containers_mut().push_back(container);
         self.focus_last_container();
@@ -682,7 +857,7 @@ impl Workspace {
         None
     }
 
-    fn container_idx_for_window(&self, hwnd: isize) -> Option<usize> {
+    pub fn container_idx_for_window(&self, hwnd: isize) -> Option<usize> {
         let mut idx = None;
         for (i, x) in self.containers().iter().enumerate() {
             if x.contains_window(hwnd) {
@@ -1263,7 +1438,7 @@ impl Workspace {
 
     pub fn new_maximized_window(&mut self) -> Result<()> {
         let focused_idx = self.focused_container_idx();
-        let foreground_hwnd = WindowsApi::foreground_window()?;
+        let foreground_hwnd = system_api().foreground_window()?;
         let mut floating_window = None;
 
         if !self.floating_windows().is_empty() {
@@ -1385,8 +1560,21 @@ impl Workspace {
         self.focus_container(j);
     }
 
+    /// Moves every container one place along the layout, with the last container wrapping around
+    /// to the first place when rotating forwards; the resize dimensions stay with their places
+    pub fn rotate_containers(&mut self, direction: CycleDirection) {
+        if self.containers().is_empty() {
+            return;
+        }
+
+        match direction {
+            CycleDirection::Next => self.containers.rotate_right(1),
+            CycleDirection::Previous => self.containers.rotate_left(1),
+        }
+    }
+
     pub fn remove_focused_floating_window(&mut self) -> Option<Window> {
-        let hwnd = WindowsApi::foreground_window().ok()?;
+        let hwnd = system_api().foreground_window().ok()?;
 
         let mut idx = None;
         for (i, window) in self.floating_windows.iter().enumerate() {
@@ -1474,3 +1662,83 @@ impl Workspace {
         self.focus_container(0);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::rect::rect;
+    use crate::system_api::mock_system_api;
+    use crate::system_api::SystemApi;
+
+    fn workspace(hwnds: &[isize]) -> Workspace {
+        INITIAL_CONFIGURATION_LOADED.store(true, Ordering::SeqCst);
+
+        let mut workspace = Workspace {
+            layout: Layout::Default(DefaultLayout::Columns),
+            workspace_padding: Some(0),
+            container_padding: Some(0),
+            ..Default::default()
+        };
+
+        for hwnd in hwnds {
+            mock_system_api().add_window(*hwnd, rect(0, 0, 100, 100));
+            workspace.new_container_for_window(Window::from(*hwnd));
+        }
+
+        workspace
+    }
+
+    #[test]
+    fn tiled_windows_are_positioned_in_their_layouts() {
+        let mock = mock_system_api();
+        let mut workspace = workspace(&[1000, 1001]);
+
+        workspace
+            .update(&rect(0, 0, 1000, 500), None, (0, None))
+            .unwrap();
+
+        let layouts = workspace.latest_layout().clone();
+        assert_eq!(layouts.len(), 2);
+        assert!(layouts[0].left < layouts[1].left);
+        assert_eq!(mock.window(1000).unwrap().rect, layouts[0]);
+        assert_eq!(mock.window(1001).unwrap().rect, layouts[1]);
+    }
+
+    #[test]
+    fn windows_which_were_moved_are_positioned_again() {
+        let mock = mock_system_api();
+        let mut workspace = workspace(&[1010, 1011]);
+
+        workspace
+            .update(&rect(0, 0, 1000, 500), None, (0, None))
+            .unwrap();
+
+        let layouts = workspace.latest_layout().clone();
+
+        // The layout has not changed, but the window is no longer where it was left
+        mock.position_window(1010, &rect(200, 200, 100, 100), false)
+            .unwrap();
+
+        workspace
+            .update(&rect(0, 0, 1000, 500), None, (0, None))
+            .unwrap();
+
+        assert_eq!(mock.window(1010).unwrap().rect, layouts[0]);
+        assert_eq!(mock.window(1011).unwrap().rect, layouts[1]);
+    }
+
+    #[test]
+    fn minimum_sizes_of_windows_are_respected() {
+        let mock = mock_system_api();
+        let mut workspace = workspace(&[1020, 1021]);
+
+        mock.set_minimum_size(1021, (800, 0));
+
+        workspace
+            .update(&rect(0, 0, 1000, 500), None, (0, None))
+            .unwrap();
+
+        // The space which the second window needs is taken from its neighbour
+        assert!(mock.window(1021).unwrap().rect.right > mock.window(1020).unwrap().rect.right);
+    }
+}
@@ -92,6 +92,8 @@ pub fn handle_notifications(wm: Arc<Mutex<WindowManager>>) -> color_eyre::Result
                 monitor.load_focused_workspace(mouse_follows_focus)?;
             }
 
+            wm.update_dirty_workspaces()?;
+
             // Drop our lock on the window manager state here to not slow down updates
             drop(wm);
 
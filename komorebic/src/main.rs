@@ -7,6 +7,7 @@ use std::fs::OpenOptions;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Write;
+use std::net::IpAddr;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Command;
@@ -14,17 +15,17 @@ use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 
+use clap::ArgAction;
 use clap::CommandFactory;
 use clap::Parser;
 use clap::ValueEnum;
+use clap_complete::Shell;
 use color_eyre::eyre::anyhow;
 use color_eyre::eyre::bail;
 use color_eyre::Result;
 use dirs::data_local_dir;
 use fs_tail::TailedFile;
 use komorebi_client::resolve_home_path;
-use komorebi_client::send_message;
-use komorebi_client::send_query;
 use komorebi_client::ApplicationSpecificConfiguration;
 use komorebi_client::Notification;
 use lazy_static::lazy_static;
@@ -35,9 +36,15 @@ use miette::SourceSpan;
 use paste::paste;
 use schemars::gen::SchemaSettings;
 use schemars::schema_for;
-use sysinfo::ProcessesToUpdate;
 use which::which;
+use windows::core::HSTRING;
+use windows::Win32::Foundation::CloseHandle;
 use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Gdi::HMONITOR;
+use windows::Win32::System::Threading::OpenMutexW;
+use windows::Win32::System::Threading::SYNCHRONIZATION_SYNCHRONIZE;
+use windows::Win32::UI::HiDpi::GetDpiForMonitor;
+use windows::Win32::UI::HiDpi::MDT_EFFECTIVE_DPI;
 use windows::Win32::UI::WindowsAndMessaging::ShowWindow;
 use windows::Win32::UI::WindowsAndMessaging::SHOW_WINDOW_CMD;
 use windows::Win32::UI::WindowsAndMessaging::SW_RESTORE;
@@ -45,9 +52,11 @@ use windows::Win32::UI::WindowsAndMessaging::SW_RESTORE;
 use komorebi_client::ApplicationConfigurationGenerator;
 use komorebi_client::ApplicationIdentifier;
 use komorebi_client::Axis;
+use komorebi_client::CommandFailure;
 use komorebi_client::CycleDirection;
 use komorebi_client::DefaultLayout;
 use komorebi_client::FocusFollowsMouseImplementation;
+use komorebi_client::Health;
 use komorebi_client::HidingBehaviour;
 use komorebi_client::MoveBehaviour;
 use komorebi_client::OperationBehaviour;
@@ -57,10 +66,15 @@ use komorebi_client::Sizing;
 use komorebi_client::SocketMessage;
 use komorebi_client::StateQuery;
 use komorebi_client::StaticConfig;
+use komorebi_client::SubscribeOptions;
 use komorebi_client::WindowKind;
+use komorebi_client::INSTANCE_MUTEX;
+use komorebi_client::PROTOCOL_VERSION;
 
 lazy_static! {
     static ref HAS_CUSTOM_CONFIG_HOME: AtomicBool = AtomicBool::new(false);
+    static ref WAIT_FOR_COMMANDS: AtomicBool = AtomicBool::new(false);
+    static ref DRY_RUN: AtomicBool = AtomicBool::new(false);
     static ref HOME_DIR: PathBuf = {
         std::env::var("KOMOREBI_CONFIG_HOME").map_or_else(
             |_| dirs::home_dir().expect("there is no home directory"),
@@ -105,6 +119,9 @@ lazy_static! {
 
 shadow_rs::shadow!(build);
 
+// The name of the scheduled task registered by enable-scheduled-task
+const SCHEDULED_TASK_NAME: &str = "komorebi";
+
 #[derive(thiserror::Error, Debug, miette::Diagnostic)]
 #[error("{message}")]
 #[diagnostic(code(komorebi::configuration), help("try fixing this syntax error"))]
@@ -151,6 +168,7 @@ gen_enum_subcommand_args! {
     Move: OperationDirection,
     CycleFocus: CycleDirection,
     CycleMove: CycleDirection,
+    RotateContainers: CycleDirection,
     CycleMoveToWorkspace: CycleDirection,
     CycleSendToWorkspace: CycleDirection,
     CycleSendToMonitor: CycleDirection,
@@ -160,6 +178,7 @@ gen_enum_subcommand_args! {
     CycleMoveWorkspaceToMonitor: CycleDirection,
     Stack: OperationDirection,
     CycleStack: CycleDirection,
+    CycleWidth: CycleDirection,
     FlipLayout: Axis,
     ChangeLayout: DefaultLayout,
     CycleLayout: CycleDirection,
@@ -189,7 +208,6 @@ gen_target_subcommand_args! {
     MoveToMonitor,
     MoveToWorkspace,
     SendToMonitor,
-    SendToWorkspace,
     FocusMonitor,
     FocusWorkspace,
     FocusWorkspaces,
@@ -211,6 +229,12 @@ macro_rules! gen_named_target_subcommand_args {
     };
 }
 
+#[derive(Parser)]
+pub struct SendToWorkspace {
+    /// Target index (zero-indexed), or the name of a workspace on any monitor
+    target: String,
+}
+
 gen_named_target_subcommand_args! {
     MoveToNamedWorkspace,
     SendToNamedWorkspace,
@@ -398,6 +422,47 @@ struct InvisibleBorders {
     bottom: i32,
 }
 
+#[derive(Parser)]
+struct SetFloatRect {
+    /// Left edge of the window
+    #[clap(allow_negative_numbers = true)]
+    x: i32,
+    /// Top edge of the window
+    #[clap(allow_negative_numbers = true)]
+    y: i32,
+    /// Width of the window
+    width: i32,
+    /// Height of the window
+    height: i32,
+    /// Treat the position as relative to the top left corner of the work area of the focused monitor
+    #[clap(long)]
+    monitor_relative: bool,
+}
+
+#[derive(Parser)]
+struct CloseWorkspaceWindows {
+    /// End the processes of the windows instead of asking them to close; windows of processes which also have managed windows on other workspaces are still asked to close
+    #[clap(long)]
+    force: bool,
+}
+
+#[derive(Parser)]
+struct Jump {
+    /// Executable name of the application to jump to (eg. spotify.exe)
+    #[clap(long)]
+    exe: String,
+    /// Launch the application if it has no managed windows
+    #[clap(long)]
+    launch: bool,
+}
+
+#[derive(Parser)]
+struct CenterFloat {
+    /// Resize the window to this percentage of the width and height of the work area
+    #[clap(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+    percentage: Option<u8>,
+}
+
 #[derive(Parser)]
 struct GlobalWorkAreaOffset {
     /// Size of the left work area offset (set right to left * 2 to maintain right padding)
@@ -424,6 +489,18 @@ struct MonitorWorkAreaOffset {
     bottom: i32,
 }
 
+#[derive(Parser)]
+struct HideTaskbar {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+}
+
+#[derive(Parser)]
+struct ShowTaskbar {
+    /// Monitor index (zero-indexed)
+    monitor: usize,
+}
+
 #[derive(Parser)]
 struct MonitorIndexPreference {
     /// Preferred monitor index (zero-indexed)
@@ -705,6 +782,12 @@ struct BorderOffset {
     /// Desired offset of the window border
     offset: i32,
 }
+
+#[derive(Parser)]
+struct BorderRadius {
+    /// Desired radius of the corners of the window border when they are rounded
+    radius: i32,
+}
 #[derive(Parser)]
 struct BorderStyle {
     /// Desired border style
@@ -758,8 +841,11 @@ struct Start {
     #[clap(short, long)]
     await_configuration: bool,
     /// Start a TCP server on the given port to allow the direct sending of SocketMessages
-    #[clap(short, long)]
-    tcp_port: Option<usize>,
+    #[clap(short, long, visible_alias = "tcp")]
+    tcp_port: Option<u16>,
+    /// Address for the TCP server to bind to (use 0.0.0.0 to accept connections from other machines)
+    #[clap(long, requires = "tcp_port")]
+    tcp_address: Option<IpAddr>,
     /// Start whkd in a background process
     #[clap(long)]
     whkd: bool,
@@ -769,6 +855,53 @@ struct Start {
     /// Start komorebi-bar in a background process
     #[clap(long)]
     bar: bool,
+    /// Place windows back into the workspaces they were on when the session was last saved
+    #[clap(hide = true)]
+    #[clap(long)]
+    restore_session: bool,
+}
+
+#[derive(Parser)]
+struct Restart {
+    /// Allow the use of komorebi's custom focus-follows-mouse implementation
+    #[clap(hide = true)]
+    #[clap(short, long = "ffm")]
+    ffm: bool,
+    /// Path to a static configuration JSON file
+    #[clap(short, long)]
+    config: Option<PathBuf>,
+    /// Wait for 'komorebic complete-configuration' to be sent before processing events
+    #[clap(short, long)]
+    await_configuration: bool,
+    /// Start a TCP server on the given port to allow the direct sending of SocketMessages
+    #[clap(short, long, visible_alias = "tcp")]
+    tcp_port: Option<u16>,
+    /// Address for the TCP server to bind to (use 0.0.0.0 to accept connections from other machines)
+    #[clap(long, requires = "tcp_port")]
+    tcp_address: Option<IpAddr>,
+}
+
+#[derive(Parser)]
+struct LogLevel {
+    #[clap(value_enum)]
+    log_level: komorebi_client::LogLevel,
+}
+
+#[derive(Parser)]
+struct Feedback {
+    /// Number of lines to include from the end of today's log file
+    #[clap(short, long, default_value = "500")]
+    lines: usize,
+    /// File to write the diagnostics bundle to (default: komorebi-feedback-<timestamp>.json)
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+struct Completions {
+    /// The shell to generate completions for
+    #[clap(value_enum)]
+    shell: Shell,
 }
 
 #[derive(Parser)]
@@ -806,6 +939,15 @@ struct LoadCustomLayout {
 struct SubscribeSocket {
     /// Name of the socket to send event notifications to
     socket: String,
+    /// Only send notifications when the window manager state or focused window title changes
+    #[clap(long)]
+    filter_state_changes: bool,
+    /// Only send notifications for these event types (eg. FocusChange, FocusWorkspaceNumber)
+    #[clap(long, value_delimiter = ',')]
+    filter_event_types: Option<Vec<String>>,
+    /// Send a compact per-monitor workspace status for status bars instead of full notifications
+    #[clap(long)]
+    bar_status: bool,
 }
 
 #[derive(Parser)]
@@ -880,15 +1022,39 @@ struct EnableAutostart {
     bar: bool,
 }
 
+#[derive(Parser)]
+struct EnableScheduledTask {
+    /// Path to a static configuration JSON file
+    #[clap(action, short, long)]
+    config: Option<PathBuf>,
+    /// Enable komorebi's custom focus-follows-mouse implementation
+    #[clap(hide = true)]
+    #[clap(short, long = "ffm")]
+    ffm: bool,
+}
+
 #[derive(Parser)]
 struct ReplaceConfiguration {
     /// Static configuration JSON file from which the configuration should be loaded
     path: PathBuf,
 }
 
+#[derive(Parser)]
+struct Batch {
+    /// JSON file containing an array of commands (reads from stdin if not provided)
+    path: Option<PathBuf>,
+}
+
 #[derive(Parser)]
 #[clap(author, about, version = build::CLAP_LONG_VERSION)]
 struct Opts {
+    /// Wait until komorebi has finished processing the command before returning
+    #[clap(long, global = true)]
+    wait: bool,
+    /// Show the state changes and window positions that the command would produce without
+    /// touching any windows
+    #[clap(long, global = true)]
+    dry_run: bool,
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
@@ -903,8 +1069,14 @@ enum SubCommand {
     Start(Start),
     /// Stop the komorebi.exe process and restore all hidden windows
     Stop(Stop),
+    /// Restart komorebi.exe, placing windows back into the workspaces they were on
+    Restart(Restart),
     /// Check komorebi configuration and related files for common errors
     Check,
+    /// Generate shell completions for komorebic
+    Completions(Completions),
+    /// Generate a PowerShell module which wraps every komorebic command as a cmdlet
+    PowershellModule,
     /// Show the path to komorebi.json
     #[clap(alias = "config")]
     Configuration,
@@ -919,10 +1091,20 @@ enum SubCommand {
     State,
     /// Show a JSON representation of the current global state
     GlobalState,
+    /// Show the komorebic and komorebi versions and the IPC protocol versions they speak
+    Version,
     /// Launch the komorebi-gui debugging tool
     Gui,
     /// Show a JSON representation of visible windows
     VisibleWindows,
+    /// Show a JSON representation of the window handles known to komorebi
+    KnownHwnds,
+    /// Show a JSON representation of event, command and layout counters and timings
+    Stats,
+    /// Check whether komorebi is receiving and handling events, exiting with an error if it is not
+    CheckHealth,
+    /// Show a compact per-monitor workspace status for status bars
+    BarStatus,
     /// Show information about connected monitors
     #[clap(alias = "monitor-info")]
     MonitorInformation,
@@ -945,6 +1127,11 @@ enum SubCommand {
     UnsubscribePipe(UnsubscribePipe),
     /// Tail komorebi.exe's process logs (cancel with Ctrl-C)
     Log,
+    /// Change the level of komorebi.exe's process logs without restarting
+    #[clap(arg_required_else_help = true)]
+    LogLevel(LogLevel),
+    /// Gather logs, state, monitors and configuration into a redacted file for bug reports
+    Feedback(Feedback),
     /// Quicksave the current resize layout dimensions
     #[clap(alias = "quick-save")]
     QuickSaveResize,
@@ -969,14 +1156,28 @@ enum SubCommand {
     Minimize,
     /// Close the focused window
     Close,
+    /// Close every window on the focused workspace
+    CloseWorkspaceWindows(CloseWorkspaceWindows),
     /// Forcibly focus the window at the cursor with a left mouse click
     ForceFocus,
+    /// Label every visible window with a letter, and focus the window whose letter is typed next
+    Hint,
+    /// Show a list of the containers on every workspace, and focus the one chosen with the arrow keys and enter
+    Switcher,
+    /// Focus the previously focused window, switching to the monitor and workspace that it is on
+    FocusLast,
+    /// Focus the first window of an application, switching to the monitor and workspace that it is on
+    #[clap(arg_required_else_help = true)]
+    Jump(Jump),
     /// Change focus to the window in the specified cycle direction
     #[clap(arg_required_else_help = true)]
     CycleFocus(CycleFocus),
     /// Move the focused window in the specified cycle direction
     #[clap(arg_required_else_help = true)]
     CycleMove(CycleMove),
+    /// Move every container one place along the layout in the specified cycle direction, keeping focus on the focused window
+    #[clap(arg_required_else_help = true)]
+    RotateContainers(RotateContainers),
     /// Stack the focused window in the specified direction
     #[clap(arg_required_else_help = true)]
     Stack(Stack),
@@ -999,6 +1200,9 @@ enum SubCommand {
     /// Resize the focused window or primary column along the specified axis
     #[clap(arg_required_else_help = true)]
     ResizeAxis(ResizeAxis),
+    /// Cycle the width of the focused window through the width presets in the specified cycle direction
+    #[clap(arg_required_else_help = true)]
+    CycleWidth(CycleWidth),
     /// Move the focused window to the specified monitor
     #[clap(arg_required_else_help = true)]
     MoveToMonitor(MoveToMonitor),
@@ -1081,6 +1285,12 @@ enum SubCommand {
     /// Set offsets for a monitor to exclude parts of the work area from tiling
     #[clap(arg_required_else_help = true)]
     MonitorWorkAreaOffset(MonitorWorkAreaOffset),
+    /// Hide the Windows taskbar on the specified monitor and tile over the space it occupied
+    #[clap(arg_required_else_help = true)]
+    HideTaskbar(HideTaskbar),
+    /// Show the Windows taskbar on the specified monitor
+    #[clap(arg_required_else_help = true)]
+    ShowTaskbar(ShowTaskbar),
     /// Set container padding on the focused workspace
     #[clap(arg_required_else_help = true)]
     FocusedWorkspaceContainerPadding(FocusedWorkspaceContainerPadding),
@@ -1106,13 +1316,14 @@ enum SubCommand {
     /// Flip the layout on the focused workspace (BSP only)
     #[clap(arg_required_else_help = true)]
     FlipLayout(FlipLayout),
-    /// Promote the focused window to the top of the tree
+    /// Promote the focused window to the top of the tree, moving it into the primary container
     Promote,
-    /// Promote the user focus to the top of the tree
+    /// Focus the largest container of the layout without rearranging any windows
     PromoteFocus,
     /// Promote the window in the specified direction
     PromoteWindow(PromoteWindow),
-    /// Force the retiling of all managed windows
+    /// Force the retiling of all managed windows, repositioning every one of them even if it looks
+    /// like it is already in place
     Retile,
     /// Set the monitor index preference for a monitor identified using its size
     #[clap(arg_required_else_help = true)]
@@ -1199,6 +1410,13 @@ enum SubCommand {
     ToggleTiling,
     /// Toggle floating mode for the focused window
     ToggleFloat,
+    /// Toggle the visibility of all floating windows on the focused workspace
+    ToggleFloatLayer,
+    /// Set the position and size of the focused floating window
+    #[clap(arg_required_else_help = true)]
+    SetFloatRect(SetFloatRect),
+    /// Center the focused floating window on the work area of the focused monitor
+    CenterFloat(CenterFloat),
     /// Toggle monocle mode for the focused container
     ToggleMonocle,
     /// Toggle native maximization for the focused window
@@ -1212,6 +1430,8 @@ enum SubCommand {
     /// Replace the configuration of a running instance of komorebi from a static configuration file
     #[clap(arg_required_else_help = true)]
     ReplaceConfiguration(ReplaceConfiguration),
+    /// Apply an array of commands under a single lock acquisition with one final re-layout
+    Batch(Batch),
     /// Reload legacy komorebi.ahk or komorebi.ps1 configurations (if they exist)
     ReloadConfiguration,
     /// Enable or disable watching of legacy komorebi.ahk or komorebi.ps1 configurations (if they exist)
@@ -1295,6 +1515,9 @@ enum SubCommand {
     #[clap(arg_required_else_help = true)]
     #[clap(alias = "active-window-border-offset")]
     BorderOffset(BorderOffset),
+    /// Set the radius of rounded border corners
+    #[clap(arg_required_else_help = true)]
+    BorderRadius(BorderRadius),
     /// Set the border style
     #[clap(arg_required_else_help = true)]
     BorderStyle(BorderStyle),
@@ -1368,17 +1591,171 @@ enum SubCommand {
     EnableAutostart(EnableAutostart),
     /// Deletes the komorebi.lnk shortcut in shell:startup to disable autostart
     DisableAutostart,
+    /// Registers a scheduled task to start komorebi without a console window at logon (requires an elevated prompt)
+    EnableScheduledTask(EnableScheduledTask),
+    /// Deletes the scheduled task registered by enable-scheduled-task (requires an elevated prompt)
+    DisableScheduledTask,
+}
+
+// send_message is a helper that ensures komorebi speaks the same IPC protocol version as
+// komorebic before sending a message, so that mismatched builds fail loudly.
+fn send_message(message: &SocketMessage) -> Result<()> {
+    komorebi_client::handshake()?;
+
+    if DRY_RUN.load(Ordering::SeqCst) {
+        if !message.supports_dry_run() {
+            bail!("this command cannot be run with --dry-run");
+        }
+
+        let dry_run = SocketMessage::DryRun(Box::new(message.clone()));
+        let reply = komorebi_client::send_query(&dry_run)?;
+        exit_on_failure(&reply)?;
+        println!("{reply}");
+        return Ok(());
+    }
+
+    if WAIT_FOR_COMMANDS.load(Ordering::SeqCst) {
+        // komorebi closes the connection once it has processed every message sent on it
+        let reply = komorebi_client::send_query(message)?;
+        exit_on_failure(&reply)
+    } else {
+        Ok(komorebi_client::send_message(message)?)
+    }
+}
+
+// exit_on_failure writes the failure to stderr as JSON and exits if komorebi replied that a
+// command failed, so that scripts can branch on the kind of error instead of its message
+fn exit_on_failure(reply: &str) -> Result<()> {
+    if let Some(failure) = CommandFailure::from_reply(reply) {
+        eprintln!("{}", serde_json::to_string(&failure)?);
+        std::process::exit(1);
+    }
+
+    Ok(())
 }
 
 // print_query is a helper that queries komorebi and prints the response.
 // panics on error.
 fn print_query(message: &SocketMessage) {
-    match send_query(message) {
+    if let Err(error) = komorebi_client::handshake() {
+        panic!("{}", error);
+    }
+
+    match komorebi_client::send_query(message) {
         Ok(response) => println!("{response}"),
         Err(error) => panic!("{}", error),
     }
 }
 
+// wait_for_command_socket polls until komorebi accepts connections on its command socket,
+// returning false if this doesn't happen before the timeout
+fn wait_for_command_socket(timeout: Duration) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    while std::time::Instant::now() < deadline {
+        if komorebi_client::send_query(&SocketMessage::Version).is_ok() {
+            return true;
+        }
+
+        std::thread::sleep(Duration::from_millis(250));
+    }
+
+    false
+}
+
+// known_hwnds asks komorebi for the window handles that it knows about, falling back to
+// komorebi.hwnd.json (if it has been enabled) when komorebi is not able to respond
+fn known_hwnds() -> Result<Vec<isize>> {
+    if let Ok(response) = komorebi_client::send_query(&SocketMessage::KnownHwnds) {
+        if let Ok(hwnds) = serde_json::from_str(&response) {
+            return Ok(hwnds);
+        }
+    }
+
+    let hwnd_json = DATA_DIR.join("komorebi.hwnd.json");
+    if !hwnd_json.is_file() {
+        bail!("komorebi did not respond and {} does not exist; set \"known_hwnds_file\": true in your static configuration to be able to restore windows after a crash", hwnd_json.display());
+    }
+
+    let file = File::open(hwnd_json)?;
+    let reader = BufReader::new(file);
+
+    Ok(serde_json::from_reader(reader)?)
+}
+
+fn query_value(message: &SocketMessage) -> serde_json::Value {
+    match komorebi_client::send_query(message) {
+        Ok(response) => serde_json::from_str(&response)
+            .unwrap_or_else(|_| serde_json::Value::String(response.trim().to_string())),
+        Err(error) => serde_json::Value::String(format!("could not query komorebi: {error}")),
+    }
+}
+
+fn scale_factor(hmonitor: isize) -> Option<f32> {
+    let mut dpi_x = u32::default();
+    let mut dpi_y = u32::default();
+
+    unsafe {
+        GetDpiForMonitor(
+            HMONITOR(hmonitor as *mut core::ffi::c_void),
+            MDT_EFFECTIVE_DPI,
+            std::ptr::addr_of_mut!(dpi_x),
+            std::ptr::addr_of_mut!(dpi_y),
+        )
+    }
+    .ok()?;
+
+    #[allow(clippy::cast_precision_loss)]
+    Option::from(dpi_y as f32 / 96.0)
+}
+
+// Window titles can contain file names, URLs and other things that users may not want to share
+// in a public bug report, so they are removed along with the path of the user's home directory
+fn redact_value(value: &mut serde_json::Value, home: &str) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "title" && value.is_string() {
+                    *value = serde_json::Value::String(String::from("<redacted>"));
+                } else {
+                    redact_value(value, home);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => {
+            for value in values {
+                redact_value(value, home);
+            }
+        }
+        serde_json::Value::String(string) => {
+            if !home.is_empty() && string.contains(home) {
+                *string = string.replace(home, "~");
+            }
+        }
+        _ => {}
+    }
+}
+
+fn redact_log_line(line: &str, home: &str) -> String {
+    let mut line = if home.is_empty() {
+        line.to_string()
+    } else {
+        line.replace(home, "~")
+    };
+
+    // Windows are logged as "(hwnd: 123, title: Some Title, exe: app.exe, class: Class)"
+    let mut from = 0;
+    while let Some(start) = line[from..].find("title: ").map(|idx| from + idx + 7) {
+        let Some(end) = line[start..].find(", exe: ").map(|idx| start + idx) else {
+            break;
+        };
+
+        line.replace_range(start..end, "<redacted>");
+        from = start + "<redacted>".len();
+    }
+
+    line
+}
+
 fn startup_dir() -> Result<PathBuf> {
     let startup = dirs::home_dir()
         .expect("unable to obtain user's home folder")
@@ -1397,11 +1774,185 @@ fn startup_dir() -> Result<PathBuf> {
     Ok(startup)
 }
 
+fn pascal_case(name: &str) -> String {
+    name.split(['-', '_'])
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or_else(String::new, |first| {
+                first.to_uppercase().chain(chars).collect::<String>()
+            })
+        })
+        .collect()
+}
+
+fn powershell_function(subcommand: &clap::Command) -> String {
+    let name = subcommand.get_name();
+    let mut params = vec![];
+    let mut body = vec![format!("    $arguments = @('{name}')")];
+    let mut position = 0;
+
+    for arg in subcommand.get_arguments() {
+        let id = arg.get_id().as_str();
+        if arg.is_hide_set() || id == "help" || id == "version" {
+            continue;
+        }
+
+        let param = pascal_case(id);
+        let mut attributes = vec![];
+
+        if arg.is_positional() {
+            if arg.is_required_set() {
+                attributes.push(format!(
+                    "        [Parameter(Mandatory = $true, Position = {position})]"
+                ));
+            } else {
+                attributes.push(format!("        [Parameter(Position = {position})]"));
+            }
+
+            position += 1;
+        } else if arg.is_required_set() {
+            attributes.push(String::from("        [Parameter(Mandatory = $true)]"));
+        }
+
+        let possible_values = arg
+            .get_possible_values()
+            .iter()
+            .map(|value| format!("'{}'", value.get_name().replace('\'', "''")))
+            .collect::<Vec<_>>();
+
+        let is_switch = matches!(arg.get_action(), ArgAction::SetTrue);
+        let is_multiple = arg
+            .get_num_args()
+            .is_some_and(|range| range.max_values() > 1);
+
+        if is_switch {
+            attributes.push(format!("        [switch]${param}"));
+        } else {
+            if !possible_values.is_empty() {
+                attributes.push(format!(
+                    "        [ValidateSet({})]",
+                    possible_values.join(", ")
+                ));
+            }
+
+            if is_multiple {
+                attributes.push(format!("        [string[]]${param}"));
+            } else {
+                attributes.push(format!("        [string]${param}"));
+            }
+        }
+
+        params.push(attributes.join("\n"));
+
+        let flag = arg
+            .get_long()
+            .map(|long| format!("--{long}"))
+            .or_else(|| arg.get_short().map(|short| format!("-{short}")));
+
+        body.push(match (is_switch, flag) {
+            (true, Some(flag)) => format!("    if (${param}) {{ $arguments += '{flag}' }}"),
+            (false, Some(flag)) => format!(
+                "    if ($PSBoundParameters.ContainsKey('{param}')) {{ $arguments += '{flag}'; $arguments += ${param} }}"
+            ),
+            (_, None) => format!(
+                "    if ($PSBoundParameters.ContainsKey('{param}')) {{ $arguments += ${param} }}"
+            ),
+        });
+    }
+
+    body.push(String::from("    & komorebic @arguments"));
+
+    let synopsis = subcommand
+        .get_about()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+
+    format!(
+        r"function Invoke-Komorebic{function} {{
+    <#
+    .SYNOPSIS
+    {synopsis}
+    #>
+    [CmdletBinding()]
+    param(
+{params}
+    )
+
+{body}
+}}
+",
+        function = pascal_case(name),
+        params = params.join(",\n"),
+        body = body.join("\n"),
+    )
+}
+
+fn powershell_module(cli: &clap::Command) -> String {
+    let mut module = vec![format!(
+        "# Generated by 'komorebic powershell-module' for komorebic {}\n",
+        build::PKG_VERSION
+    )];
+
+    for subcommand in cli.get_subcommands() {
+        if subcommand.is_hide_set() {
+            continue;
+        }
+
+        module.push(powershell_function(subcommand));
+    }
+
+    module.push(String::from(
+        "Export-ModuleMember -Function Invoke-Komorebic*\n",
+    ));
+    module.join("\n")
+}
+
 #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
 fn main() -> Result<()> {
     let opts: Opts = Opts::parse();
+    WAIT_FOR_COMMANDS.store(opts.wait, Ordering::SeqCst);
+    DRY_RUN.store(opts.dry_run, Ordering::SeqCst);
 
-    match opts.subcmd {
+    let subcmd = match opts.subcmd {
+        SubCommand::Restart(arg) => {
+            send_message(&SocketMessage::SaveSession)?;
+            send_message(&SocketMessage::Stop)?;
+
+            print!("Waiting for komorebi.exe to stop...");
+            std::io::stdout().flush()?;
+
+            let mut attempts = 0;
+            while is_komorebi_running() && attempts < 10 {
+                std::thread::sleep(Duration::from_millis(500));
+                attempts += 1;
+            }
+
+            if is_komorebi_running() {
+                println!();
+                bail!("komorebi.exe did not stop in time; try running 'komorebic restart' again");
+            }
+
+            println!("Stopped!");
+
+            // Start again in the same way that 'komorebic start' would, adopting the windows in the
+            // session that was saved above
+            SubCommand::Start(Start {
+                ffm: arg.ffm,
+                config: arg.config,
+                await_configuration: arg.await_configuration,
+                tcp_port: arg.tcp_port,
+                tcp_address: arg.tcp_address,
+                whkd: false,
+                ahk: false,
+                bar: false,
+                restore_session: true,
+            })
+        }
+        subcmd => subcmd,
+    };
+
+    match subcmd {
         SubCommand::Docgen => {
             let mut cli = Opts::command();
             let subcommands = cli.get_subcommands_mut();
@@ -1431,6 +1982,14 @@ fn main() -> Result<()> {
                 }
             }
         }
+        SubCommand::Completions(arg) => {
+            let mut cli = Opts::command();
+            let name = cli.get_name().to_string();
+            clap_complete::generate(arg.shell, &mut cli, name, &mut std::io::stdout());
+        }
+        SubCommand::PowershellModule => {
+            print!("{}", powershell_module(&Opts::command()));
+        }
         SubCommand::Quickstart => {
             let local_appdata_dir = data_local_dir().expect("could not find localdata dir");
             let data_dir = local_appdata_dir.join("komorebi");
@@ -1510,6 +2069,61 @@ fn main() -> Result<()> {
                 std::fs::remove_file(shortcut_file)?;
             }
         }
+        SubCommand::EnableScheduledTask(args) => {
+            let mut current_exe = std::env::current_exe().expect("unable to get exec path");
+            current_exe.pop();
+            let komorebi_exe = current_exe.join("komorebi.exe");
+            let komorebi_exe = dunce::simplified(&komorebi_exe);
+
+            let mut task = format!("\"{}\" --headless", komorebi_exe.display());
+
+            if let Some(config) = args.config {
+                let path = resolve_home_path(config)?;
+                task.push_str(&format!(" --config \"{}\"", path.display()));
+            }
+
+            if args.ffm {
+                task.push_str(" --ffm");
+            }
+
+            let username = std::env::var("USERNAME")?;
+
+            // /IT only allows the task to run when the user is logged on, which puts komorebi in
+            // the interactive session instead of the non-interactive one used by services
+            let output = Command::new("schtasks")
+                .args([
+                    "/Create",
+                    "/F",
+                    "/TN",
+                    SCHEDULED_TASK_NAME,
+                    "/SC",
+                    "ONLOGON",
+                ])
+                .args(["/RU", &username, "/IT", "/TR", &task])
+                .output()?;
+
+            if !output.status.success() {
+                bail!(
+                    "could not create scheduled task: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+
+            println!("Scheduled task '{SCHEDULED_TASK_NAME}' created to run: {task}");
+            println!("whkd and komorebi-bar are not started by this task and need to be autostarted separately");
+        }
+        SubCommand::DisableScheduledTask => {
+            let output = Command::new("schtasks")
+                .args(["/Delete", "/F", "/TN", SCHEDULED_TASK_NAME])
+                .output()?;
+
+            if !output.status.success() {
+                bail!(
+                    "could not delete scheduled task: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+        }
         SubCommand::Check => {
             let home_display = HOME_DIR.display();
             if HAS_CUSTOM_CONFIG_HOME.load(Ordering::SeqCst) {
@@ -1626,7 +2240,7 @@ fn main() -> Result<()> {
         }
         SubCommand::Log => {
             let timestamp = Utc::now().format("%Y-%m-%d").to_string();
-            let color_log = std::env::temp_dir().join(format!("komorebi.log.{timestamp}"));
+            let color_log = DATA_DIR.join(format!("komorebi.log.{timestamp}"));
             let file = TailedFile::new(File::open(color_log)?);
             let locked = file.lock();
             #[allow(clippy::significant_drop_in_scrutinee, clippy::lines_filter_map_ok)]
@@ -1634,15 +2248,88 @@ fn main() -> Result<()> {
                 println!("{line}");
             }
         }
+        SubCommand::LogLevel(arg) => {
+            send_message(&SocketMessage::LogLevel(arg.log_level))?;
+        }
+        SubCommand::Feedback(arg) => {
+            let home = dirs::home_dir()
+                .map(|home| home.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            let timestamp = Utc::now().format("%Y-%m-%d").to_string();
+            let plaintext_log = DATA_DIR.join(format!("komorebi_plaintext.log.{timestamp}"));
+
+            let log = std::fs::read_to_string(plaintext_log)
+                .map(|log| {
+                    let lines = log.lines().collect::<Vec<_>>();
+                    lines[lines.len().saturating_sub(arg.lines)..]
+                        .iter()
+                        .map(|line| redact_log_line(line, &home))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_else(|error| vec![format!("could not read log file: {error}")]);
+
+            let monitors = win32_display_data::connected_displays_all()
+                .flatten()
+                .map(|display| {
+                    serde_json::json!({
+                        "name": display.device_name,
+                        "device_path": display.device_path,
+                        "size": Rect::from(display.size),
+                        "work_area_size": Rect::from(display.work_area_size),
+                        "scale_factor": scale_factor(display.hmonitor),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut bundle = serde_json::json!({
+                "komorebic_version": build::CLAP_LONG_VERSION,
+                "komorebi_version": query_value(&SocketMessage::Version),
+                "monitors": monitors,
+                "state": query_value(&SocketMessage::CompactState),
+                "global_state": query_value(&SocketMessage::GlobalState),
+                "config": query_value(&SocketMessage::GenerateStaticConfig),
+                "log": log,
+            });
+
+            redact_value(&mut bundle, &home);
+
+            let output = arg.output.unwrap_or_else(|| {
+                PathBuf::from(format!(
+                    "komorebi-feedback-{}.json",
+                    Utc::now().format("%Y%m%d%H%M%S")
+                ))
+            });
+
+            std::fs::write(&output, serde_json::to_string_pretty(&bundle)?)?;
+
+            println!("Diagnostics have been written to {}", output.display());
+            println!("Window titles and your user directory have been redacted, but please check the file before sharing it");
+        }
         SubCommand::Focus(arg) => {
             send_message(&SocketMessage::FocusWindow(arg.operation_direction))?;
         }
         SubCommand::ForceFocus => {
             send_message(&SocketMessage::ForceFocus)?;
         }
+        SubCommand::Hint => {
+            send_message(&SocketMessage::Hint)?;
+        }
+        SubCommand::Switcher => {
+            send_message(&SocketMessage::Switcher)?;
+        }
+        SubCommand::FocusLast => {
+            send_message(&SocketMessage::FocusLast)?;
+        }
+        SubCommand::Jump(arg) => {
+            send_message(&SocketMessage::JumpToExe(arg.exe, arg.launch))?;
+        }
         SubCommand::Close => {
             send_message(&SocketMessage::Close)?;
         }
+        SubCommand::CloseWorkspaceWindows(arg) => {
+            send_message(&SocketMessage::CloseWorkspaceWindows(arg.force))?;
+        }
         SubCommand::Minimize => {
             send_message(&SocketMessage::Minimize)?;
         }
@@ -1670,6 +2357,9 @@ fn main() -> Result<()> {
         SubCommand::CycleMove(arg) => {
             send_message(&SocketMessage::CycleMoveWindow(arg.cycle_direction))?;
         }
+        SubCommand::RotateContainers(arg) => {
+            send_message(&SocketMessage::RotateContainers(arg.cycle_direction))?;
+        }
         SubCommand::MoveToMonitor(arg) => {
             send_message(&SocketMessage::MoveContainerToMonitorNumber(arg.target))?;
         }
@@ -1697,9 +2387,10 @@ fn main() -> Result<()> {
                 arg.cycle_direction,
             ))?;
         }
-        SubCommand::SendToWorkspace(arg) => {
-            send_message(&SocketMessage::SendContainerToWorkspaceNumber(arg.target))?;
-        }
+        SubCommand::SendToWorkspace(arg) => match arg.target.parse::<usize>() {
+            Ok(idx) => send_message(&SocketMessage::SendContainerToWorkspaceNumber(idx))?,
+            Err(_) => send_message(&SocketMessage::SendContainerToNamedWorkspace(arg.target))?,
+        },
         SubCommand::SendToNamedWorkspace(arg) => {
             send_message(&SocketMessage::SendContainerToNamedWorkspace(arg.workspace))?;
         }
@@ -1750,6 +2441,12 @@ fn main() -> Result<()> {
                 },
             ))?;
         }
+        SubCommand::HideTaskbar(arg) => {
+            send_message(&SocketMessage::HideTaskbar(arg.monitor))?;
+        }
+        SubCommand::ShowTaskbar(arg) => {
+            send_message(&SocketMessage::ShowTaskbar(arg.monitor))?;
+        }
         SubCommand::GlobalWorkAreaOffset(arg) => {
             send_message(&SocketMessage::WorkAreaOffset(Rect {
                 left: arg.left,
@@ -1808,6 +2505,23 @@ fn main() -> Result<()> {
         SubCommand::ToggleTiling => {
             send_message(&SocketMessage::ToggleTiling)?;
         }
+        SubCommand::ToggleFloatLayer => {
+            send_message(&SocketMessage::ToggleFloatLayer)?;
+        }
+        SubCommand::SetFloatRect(arg) => {
+            send_message(&SocketMessage::SetFloatRect(
+                Rect {
+                    left: arg.x,
+                    top: arg.y,
+                    right: arg.width,
+                    bottom: arg.height,
+                },
+                arg.monitor_relative,
+            ))?;
+        }
+        SubCommand::CenterFloat(arg) => {
+            send_message(&SocketMessage::CenterFloat(arg.percentage))?;
+        }
         SubCommand::ToggleFloat => {
             send_message(&SocketMessage::ToggleFloat)?;
         }
@@ -1960,6 +2674,14 @@ fn main() -> Result<()> {
                 flags.push(format!("'--tcp-port={port}'"));
             }
 
+            if let Some(address) = arg.tcp_address {
+                flags.push(format!("'--tcp-address={address}'"));
+            }
+
+            if arg.restore_session {
+                flags.push("'--restore-session'".to_string());
+            }
+
             let script = if flags.is_empty() {
                 format!(
                     "Start-Process '{}' -WindowStyle hidden",
@@ -1987,19 +2709,18 @@ fn main() -> Result<()> {
                 }
 
                 print!("Waiting for komorebi.exe to start...");
-                std::thread::sleep(Duration::from_secs(3));
+                std::io::stdout().flush()?;
 
-                let mut system = sysinfo::System::new_all();
-                system.refresh_processes(ProcessesToUpdate::All);
-
-                if system
-                    .processes_by_name("komorebi.exe".as_ref())
-                    .next()
-                    .is_some()
-                {
+                if wait_for_command_socket(Duration::from_secs(10)) {
                     println!("Started!");
                     running = true;
                 } else {
+                    // Launching again would only create a second instance
+                    if is_komorebi_running() {
+                        println!();
+                        bail!("komorebi.exe is running but is not responding on its command socket; check the logs with 'komorebic log'");
+                    }
+
                     println!("komorebi.exe did not start... Trying again");
                     attempts += 1;
                 }
@@ -2019,7 +2740,7 @@ fn main() -> Result<()> {
                     println!("{}", String::from_utf8(output.stderr)?);
                 }
 
-                return Ok(());
+                bail!("komorebi.exe could not be started");
             }
 
             if arg.whkd {
@@ -2200,26 +2921,29 @@ if (Get-Command Get-CimInstance -ErrorAction SilentlyContinue) {
                 }
             }
 
+            // If komorebi has to be force-quit it won't be able to restore its hidden windows itself
+            let hwnds = known_hwnds().unwrap_or_default();
+
             send_message(&SocketMessage::Stop)?;
-            let mut system = sysinfo::System::new_all();
-            system.refresh_processes(ProcessesToUpdate::All);
 
-            if system.processes_by_name("komorebi.exe".as_ref()).count() >= 1 {
+            // Give komorebi some time to restore all of its windows and exit on its own
+            let mut attempts = 0;
+            while is_komorebi_running() && attempts < 10 {
+                std::thread::sleep(Duration::from_millis(500));
+                attempts += 1;
+            }
+
+            if is_komorebi_running() {
                 println!("komorebi is still running, attempting to force-quit");
 
+                // Instances of komorebi running in other sessions must be left alone
                 let script = r"
-Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
+Get-Process -Name:komorebi -ErrorAction SilentlyContinue | Where-Object SessionId -eq (Get-Process -Id:$PID).SessionId | Stop-Process
                 ";
                 match powershell_script::run(script) {
                     Ok(_) => {
                         println!("{script}");
 
-                        let hwnd_json = DATA_DIR.join("komorebi.hwnd.json");
-
-                        let file = File::open(hwnd_json)?;
-                        let reader = BufReader::new(file);
-                        let hwnds: Vec<isize> = serde_json::from_reader(reader)?;
-
                         for hwnd in hwnds {
                             restore_window(hwnd);
                         }
@@ -2380,12 +3104,49 @@ Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
         SubCommand::GlobalState => {
             print_query(&SocketMessage::GlobalState);
         }
+        SubCommand::Version => {
+            println!(
+                "komorebic v{} (IPC protocol version {PROTOCOL_VERSION})",
+                env!("CARGO_PKG_VERSION")
+            );
+
+            let version = komorebi_client::query_version()?;
+            println!(
+                "komorebi v{} (IPC protocol version {})",
+                version.version, version.protocol_version
+            );
+
+            version.ensure_compatible()?;
+        }
         SubCommand::Gui => {
             Command::new("komorebi-gui").spawn()?;
         }
         SubCommand::VisibleWindows => {
             print_query(&SocketMessage::VisibleWindows);
         }
+        SubCommand::KnownHwnds => {
+            print_query(&SocketMessage::KnownHwnds);
+        }
+        SubCommand::Stats => {
+            print_query(&SocketMessage::Stats);
+        }
+        SubCommand::CheckHealth => {
+            let response = komorebi_client::send_query_with_timeout(
+                &SocketMessage::CheckHealth,
+                Some(Duration::from_secs(5)),
+            )
+            .map_err(|error| anyhow!("komorebi is not responding on komorebi.sock: {error}"))?;
+
+            let health = serde_json::from_str::<Health>(&response)?;
+            println!("{}", serde_json::to_string_pretty(&health)?);
+
+            if !health.is_healthy() {
+                bail!("komorebi is not healthy");
+            }
+        }
+        SubCommand::BarStatus => {
+            print_query(&SocketMessage::BarStatus);
+        }
         SubCommand::MonitorInformation => {
             print_query(&SocketMessage::MonitorInformation);
         }
@@ -2393,13 +3154,7 @@ Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
             print_query(&SocketMessage::Query(arg.state_query));
         }
         SubCommand::RestoreWindows => {
-            let hwnd_json = DATA_DIR.join("komorebi.hwnd.json");
-
-            let file = File::open(hwnd_json)?;
-            let reader = BufReader::new(file);
-            let hwnds: Vec<isize> = serde_json::from_reader(reader)?;
-
-            for hwnd in hwnds {
+            for hwnd in known_hwnds()? {
                 restore_window(hwnd);
             }
         }
@@ -2409,6 +3164,9 @@ Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
         SubCommand::ResizeAxis(arg) => {
             send_message(&SocketMessage::ResizeWindowAxis(arg.axis, arg.sizing))?;
         }
+        SubCommand::CycleWidth(arg) => {
+            send_message(&SocketMessage::CycleWidth(arg.cycle_direction))?;
+        }
         SubCommand::FocusFollowsMouse(arg) => {
             send_message(&SocketMessage::FocusFollowsMouse(
                 arg.implementation,
@@ -2418,6 +3176,15 @@ Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
         SubCommand::ReplaceConfiguration(arg) => {
             send_message(&SocketMessage::ReplaceConfiguration(arg.path))?;
         }
+        SubCommand::Batch(arg) => {
+            let raw = match arg.path {
+                Some(path) => std::fs::read_to_string(resolve_home_path(path)?)?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+
+            let messages: Vec<SocketMessage> = serde_json::from_str(&raw)?;
+            send_message(&SocketMessage::Batch(messages))?;
+        }
         SubCommand::ReloadConfiguration => {
             send_message(&SocketMessage::ReloadConfiguration)?;
         }
@@ -2446,13 +3213,6 @@ Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
             ))?;
         }
         SubCommand::RemoveTitleBar(target) => {
-            match target.identifier {
-                ApplicationIdentifier::Exe => {}
-                _ => {
-                    bail!("this command requires applications to be identified by their exe");
-                }
-            }
-
             send_message(&SocketMessage::RemoveTitleBar(target.identifier, target.id))?;
         }
         SubCommand::ToggleTitleBars => {
@@ -2477,7 +3237,18 @@ Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
             send_message(&SocketMessage::Load(resolve_home_path(arg.path)?))?;
         }
         SubCommand::SubscribeSocket(arg) => {
-            send_message(&SocketMessage::AddSubscriberSocket(arg.socket))?;
+            if arg.filter_state_changes || arg.filter_event_types.is_some() || arg.bar_status {
+                send_message(&SocketMessage::AddSubscriberSocketWithOptions(
+                    arg.socket,
+                    SubscribeOptions {
+                        filter_state_changes: arg.filter_state_changes,
+                        filter_event_types: arg.filter_event_types,
+                        bar_status: arg.bar_status,
+                    },
+                ))?;
+            } else {
+                send_message(&SocketMessage::AddSubscriberSocket(arg.socket))?;
+            }
         }
         SubCommand::UnsubscribeSocket(arg) => {
             send_message(&SocketMessage::RemoveSubscriberSocket(arg.socket))?;
@@ -2511,6 +3282,9 @@ Stop-Process -Name:komorebi -ErrorAction SilentlyContinue
         SubCommand::BorderOffset(arg) => {
             send_message(&SocketMessage::BorderOffset(arg.offset))?;
         }
+        SubCommand::BorderRadius(arg) => {
+            send_message(&SocketMessage::BorderRadius(arg.radius))?;
+        }
         SubCommand::BorderStyle(arg) => {
             send_message(&SocketMessage::BorderStyle(arg.style))?;
         }
@@ -2723,3 +3497,21 @@ fn restore_window(hwnd: isize) {
     show_window(HWND(hwnd as *mut core::ffi::c_void), SW_RESTORE);
     remove_transparency(hwnd);
 }
+
+// komorebi holds this mutex for as long as it is running, and only instances running in the
+// current session are visible in its namespace
+fn is_komorebi_running() -> bool {
+    match unsafe {
+        OpenMutexW(
+            SYNCHRONIZATION_SYNCHRONIZE,
+            false,
+            &HSTRING::from(INSTANCE_MUTEX),
+        )
+    } {
+        Ok(handle) => {
+            let _ = unsafe { CloseHandle(handle) };
+            true
+        }
+        Err(_) => false,
+    }
+}